@@ -0,0 +1,455 @@
+//
+// fecha.rs
+//
+
+use std::cmp::Ordering;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+pub const NOMBRE_MESES: [&str; 12] = ["Enero", "Febrero", "Marzo", "Abril",
+    "Mayo", "Junio", "Julio", "Agosto",
+    "Septiembre", "Octubre", "Noviembre", "Diciembre"];
+
+// una era son 400 años gregorianos: el calendario se repite exactamente cada 146097 días
+const DIAS_POR_ERA: i64 = 146_097;
+// días entre el 01/03/0000 (inicio de la era 0 contando desde marzo) y el 01/01/1970
+const DIAS_HASTA_EPOCH: i64 = 719_468;
+
+/// # Fecha
+///
+/// Fecha civil del calendario gregoriano.<br>
+/// El año podría ser negativo, indicando años antes de Cristo (el año 0 existe y es bisiesto).
+///
+/// El orden es total: se compara primero el año, luego el mes y por último el día.
+///
+/// En JSON se representa como `{ "dia": .., "mes": .., "ano": .. }`.
+/// También se aceptan `day`, `month` y `year` para poder leer archivos viejos de XYZ.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Fecha {
+    #[serde(alias = "day")]
+    pub dia: u8,
+    #[serde(alias = "month")]
+    pub mes: u8,
+    #[serde(alias = "year")]
+    pub ano: i64
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DiaSemana {
+    Lunes, Martes, Miercoles, Jueves, Viernes, Sabado, Domingo
+}
+
+impl DiaSemana {
+    pub const TODOS: [DiaSemana; 7] = [DiaSemana::Lunes, DiaSemana::Martes, DiaSemana::Miercoles,
+        DiaSemana::Jueves, DiaSemana::Viernes, DiaSemana::Sabado, DiaSemana::Domingo];
+
+    /// 0 = lunes, 6 = domingo
+    #[must_use]
+    pub fn indice(self) -> usize {
+        self as usize
+    }
+
+    #[must_use]
+    pub fn es_fin_de_semana(self) -> bool {
+        matches!(self, DiaSemana::Sabado | DiaSemana::Domingo)
+    }
+}
+
+impl Default for Fecha {
+    fn default() -> Self {
+        Fecha { dia: 1, mes: 1, ano: 0 }
+    }
+}
+
+impl Ord for Fecha {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ano.cmp(&other.ano)
+            .then(self.mes.cmp(&other.mes))
+            .then(self.dia.cmp(&other.dia))
+    }
+}
+
+impl PartialOrd for Fecha {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Fecha {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.es_fecha_valida() {
+            write!(f, "{} de {} del {}", self.dia, NOMBRE_MESES[self.mes as usize - 1], self.ano)
+        } else {
+            write!(f, "{}/{}/{}", self.dia, self.mes, self.ano)
+        }
+    }
+}
+
+/// Regla gregoriana completa: divisible por 4, salvo los siglos que no sean divisibles por 400.
+#[must_use]
+pub fn es_ano_bisiesto(ano: i64) -> bool {
+    ano % 4 == 0 && (ano % 100 != 0 || ano % 400 == 0)
+}
+
+#[must_use]
+pub fn dias_del_mes(mes: u8, ano: i64) -> u8 {
+    match mes {
+        4 | 6 | 9 | 11 => 30,
+        2 => if es_ano_bisiesto(ano) { 29 } else { 28 },
+        _ => 31,
+    }
+}
+
+// los valores que recibe ya están acotados por el algoritmo (1..=31, 1..=12)
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub(crate) fn a_u8(valor: i64) -> u8 {
+    valor as u8
+}
+
+impl Fecha {
+
+    // El año podría ser negativo, indicando días antes de Cristo.
+    #[must_use]
+    pub fn new(dia: u8, mes: u8, ano: i64) -> Option<Fecha> {
+        let fecha = Fecha { dia, mes, ano };
+        if fecha.es_fecha_valida() {
+            return Some(fecha);
+        }
+        None
+    }
+
+    #[must_use]
+    pub fn es_fecha_valida(&self) -> bool {
+        // check que el mes sea válido
+        if !(1..=12).contains(&self.mes) { return false }
+
+        // check días del mes
+        if self.dia == 0
+            || self.dia > self.dias_mes_actual()
+        { return false }
+
+        true
+    }
+
+    #[must_use]
+    pub fn es_bisiesto(&self) -> bool {
+        es_ano_bisiesto(self.ano)
+    }
+
+    #[must_use]
+    pub fn dias_mes_actual(&self) -> u8 {
+        dias_del_mes(self.mes, self.ano)
+    }
+
+    //
+    // número de día
+    //
+
+    /// Cantidad de días desde el 01/01/1970 (negativo si la fecha es anterior).
+    ///
+    /// Es el número sobre el que se apoya toda la aritmética de fechas:
+    /// `restar` dos números de día da los días entre dos fechas.
+    #[must_use]
+    pub fn numero_de_dia(&self) -> i64 {
+        // se cuenta el año desde marzo para que el 29 de febrero quede al final
+        let ano = if self.mes <= 2 { self.ano - 1 } else { self.ano };
+        let mes = i64::from(self.mes);
+
+        let era = ano.div_euclid(400);
+        let ano_de_era = ano - era * 400; // [0, 399]
+        let dia_del_ano = (153 * (if mes > 2 { mes - 3 } else { mes + 9 }) + 2) / 5 + i64::from(self.dia) - 1; // [0, 365]
+        let dia_de_era = ano_de_era * 365 + ano_de_era / 4 - ano_de_era / 100 + dia_del_ano; // [0, 146096]
+
+        era * DIAS_POR_ERA + dia_de_era - DIAS_HASTA_EPOCH
+    }
+
+    /// Inversa de `numero_de_dia`.
+    #[must_use]
+    pub fn desde_numero_de_dia(numero_de_dia: i64) -> Fecha {
+        let dias = numero_de_dia + DIAS_HASTA_EPOCH;
+
+        let era = dias.div_euclid(DIAS_POR_ERA);
+        let dia_de_era = dias - era * DIAS_POR_ERA; // [0, 146096]
+        let ano_de_era = (dia_de_era - dia_de_era / 1460 + dia_de_era / 36524 - dia_de_era / 146_096) / 365; // [0, 399]
+        let dia_del_ano = dia_de_era - (365 * ano_de_era + ano_de_era / 4 - ano_de_era / 100); // [0, 365]
+        let mes_desde_marzo = (5 * dia_del_ano + 2) / 153; // [0, 11]
+
+        let dia = dia_del_ano - (153 * mes_desde_marzo + 2) / 5 + 1;
+        let mes = if mes_desde_marzo < 10 { mes_desde_marzo + 3 } else { mes_desde_marzo - 9 };
+        let ano = ano_de_era + era * 400 + i64::from(mes <= 2);
+
+        Fecha { dia: a_u8(dia), mes: a_u8(mes), ano }
+    }
+
+    /// Días que hay desde `self` hasta `otra`. Negativo si `otra` es anterior.
+    #[must_use]
+    pub fn dias_hasta(&self, otra: &Fecha) -> i64 {
+        otra.numero_de_dia() - self.numero_de_dia()
+    }
+
+    #[must_use]
+    pub fn dia_semana(&self) -> DiaSemana {
+        // el 01/01/1970 fue jueves
+        let indice = (self.numero_de_dia() + 3).rem_euclid(7);
+        DiaSemana::TODOS[usize::from(a_u8(indice))]
+    }
+
+    //
+    // aritmética
+    //
+
+    pub fn sumar_dias(&mut self, dias: u32) {
+        *self = Fecha::desde_numero_de_dia(self.numero_de_dia() + i64::from(dias));
+    }
+
+    pub fn restar_dias(&mut self, dias: u32) {
+        *self = Fecha::desde_numero_de_dia(self.numero_de_dia() - i64::from(dias));
+    }
+
+    /// Si el día no existe en el mes resultante se ajusta al último día del mes
+    /// (31/01 + 1 mes = 28/02 o 29/02).
+    pub fn sumar_meses(&mut self, meses: u32) {
+        self.desplazar_meses(i64::from(meses));
+    }
+
+    /// Mismo ajuste que `sumar_meses` (31/03 - 1 mes = 28/02 o 29/02).
+    pub fn restar_meses(&mut self, meses: u32) {
+        self.desplazar_meses(-i64::from(meses));
+    }
+
+    /// El 29/02 pasa a ser 28/02 si el año resultante no es bisiesto.
+    pub fn sumar_anos(&mut self, anos: u32) {
+        self.desplazar_meses(i64::from(anos) * 12);
+    }
+
+    pub fn restar_anos(&mut self, anos: u32) {
+        self.desplazar_meses(-i64::from(anos) * 12);
+    }
+
+    fn desplazar_meses(&mut self, meses: i64) {
+        let total = self.ano * 12 + i64::from(self.mes) - 1 + meses;
+
+        self.ano = total.div_euclid(12);
+        self.mes = a_u8(total.rem_euclid(12) + 1);
+        self.dia = self.dia.min(self.dias_mes_actual());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        // default: 01/01/0000
+        let default_fecha = Fecha::default();
+        assert_eq!(default_fecha.dia, 1);
+        assert_eq!(default_fecha.mes, 1);
+        assert_eq!(default_fecha.ano, 0);
+    }
+
+    #[test]
+    fn test_display() {
+        let valid_fecha = Fecha::default();
+        let invalid_fecha = Fecha { dia: 0, mes: 1, ano: 0 };
+
+        assert_ne!(format!("{valid_fecha}"), format!("{invalid_fecha}"));
+        assert_eq!(format!("{valid_fecha}"), "1 de Enero del 0");
+        assert_eq!(format!("{invalid_fecha}"), "0/1/0");
+    }
+
+    #[test]
+    fn test_new() {
+        // invalida
+        assert!(Fecha::new(0, 0, 0).is_none());
+        assert!(Fecha::new(29, 2, 1900).is_none());
+        assert!(Fecha::new(31, 4, 2024).is_none());
+
+        // valida
+        assert!(Fecha::new(22, 8, 2002).is_some());
+        assert!(Fecha::new(29, 2, 2000).is_some());
+    }
+
+    #[test]
+    fn test_bisiesto() {
+        let Some(fecha) = Fecha::new(1, 1, 0) else { panic!() };
+        assert!(fecha.es_bisiesto());
+
+        let Some(fecha) = Fecha::new(1, 1, 2000) else { panic!() };
+        assert!(fecha.es_bisiesto());
+
+        let Some(fecha) = Fecha::new(1, 1, -4) else { panic!() };
+        assert!(fecha.es_bisiesto());
+
+        let Some(fecha) = Fecha::new(1, 1, 1) else { panic!() };
+        assert!(!fecha.es_bisiesto());
+
+        // regla de los siglos
+        assert!(!es_ano_bisiesto(1900));
+        assert!(!es_ano_bisiesto(2100));
+        assert!(!es_ano_bisiesto(-100));
+        assert!(es_ano_bisiesto(2400));
+    }
+
+    #[test]
+    fn test_restar_dias() {
+        let Some(mut fecha) = Fecha::new(30, 4, 2016) else { panic!() };
+
+        fecha.restar_dias(5000);
+
+        assert_eq!(fecha, Fecha { dia: 22, mes: 8, ano: 2002 });
+    }
+
+    #[test]
+    fn test_sumar_dias() {
+        let Some(mut fecha) = Fecha::new(22, 8, 2002) else { panic!() };
+
+        fecha.sumar_dias(5000);
+
+        assert_eq!(fecha, Fecha { dia: 30, mes: 4, ano: 2016 });
+
+        // cruza un siglo no bisiesto
+        let Some(mut fecha) = Fecha::new(28, 2, 1900) else { panic!() };
+        fecha.sumar_dias(1);
+        assert_eq!(fecha, Fecha { dia: 1, mes: 3, ano: 1900 });
+
+        // cruza el año 0
+        let Some(mut fecha) = Fecha::new(31, 12, -1) else { panic!() };
+        fecha.sumar_dias(1);
+        assert_eq!(fecha, Fecha { dia: 1, mes: 1, ano: 0 });
+    }
+
+    #[test]
+    fn test_dias_mes_actual() {
+        let esperados = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+        for (mes, dias) in (1..=12).zip(esperados) {
+            let Some(fecha) = Fecha::new(22, mes, 2002) else { panic!() };
+            assert_eq!(fecha.dias_mes_actual(), dias);
+        }
+
+        let Some(fecha) = Fecha::new(22, 2, 2004) else { panic!() };
+        assert_eq!(fecha.dias_mes_actual(), 29);
+    }
+
+    #[test]
+    fn test_cmp() {
+        let fecha1 = Fecha { dia: 1, mes: 1, ano: 1};
+        let fecha2 = Fecha { dia: 3, mes: 1, ano: 1};
+        let fecha3 = Fecha { dia: 3, mes: 1, ano: 1};
+
+        assert!(fecha1 < fecha2, "Fecha 1 es anterior, por ende, es menor");
+        assert_eq!(fecha3, fecha2, "Fecha 3 es igual a fecha 2");
+        assert!(fecha3 > fecha1, "Fecha 3 es posterior a fecha1, por ende, es mayor");
+
+        // las copias viejas comparaban el mes sin mirar si el año era el mismo
+        let diciembre_2020 = Fecha { dia: 31, mes: 12, ano: 2020 };
+        let enero_2021 = Fecha { dia: 1, mes: 1, ano: 2021 };
+        assert!(diciembre_2020 < enero_2021);
+        assert_eq!(diciembre_2020.cmp(&enero_2021), Ordering::Less);
+
+        let mut ordenadas = vec![enero_2021, fecha3, diciembre_2020, fecha1];
+        ordenadas.sort();
+        assert_eq!(ordenadas, vec![fecha1, fecha3, diciembre_2020, enero_2021]);
+    }
+
+    #[test]
+    fn test_hash() {
+        let set: HashSet<Fecha> = [
+            Fecha { dia: 1, mes: 1, ano: 2024 },
+            Fecha { dia: 1, mes: 1, ano: 2024 },
+            Fecha { dia: 2, mes: 1, ano: 2024 },
+        ].into_iter().collect();
+
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn test_numero_de_dia() {
+        assert_eq!(Fecha { dia: 1, mes: 1, ano: 1970 }.numero_de_dia(), 0);
+        assert_eq!(Fecha { dia: 31, mes: 12, ano: 1969 }.numero_de_dia(), -1);
+        assert_eq!(Fecha { dia: 1, mes: 1, ano: 2000 }.numero_de_dia(), 10_957);
+
+        // ida y vuelta en un rango amplio, incluyendo años negativos
+        for numero in (-800_000..800_000).step_by(997) {
+            let fecha = Fecha::desde_numero_de_dia(numero);
+            assert!(fecha.es_fecha_valida(), "{fecha:?}");
+            assert_eq!(fecha.numero_de_dia(), numero);
+        }
+    }
+
+    #[test]
+    fn test_dias_hasta() {
+        let Some(desde) = Fecha::new(22, 8, 2002) else { panic!() };
+        let Some(hasta) = Fecha::new(30, 4, 2016) else { panic!() };
+
+        assert_eq!(desde.dias_hasta(&hasta), 5000);
+        assert_eq!(hasta.dias_hasta(&desde), -5000);
+        assert_eq!(desde.dias_hasta(&desde), 0);
+    }
+
+    #[test]
+    fn test_sumar_restar_meses() {
+        let Some(mut fecha) = Fecha::new(31, 1, 2024) else { panic!() };
+        fecha.sumar_meses(1);
+        assert_eq!(fecha, Fecha { dia: 29, mes: 2, ano: 2024 });
+
+        let Some(mut fecha) = Fecha::new(31, 1, 2023) else { panic!() };
+        fecha.sumar_meses(13);
+        assert_eq!(fecha, Fecha { dia: 29, mes: 2, ano: 2024 });
+
+        let Some(mut fecha) = Fecha::new(31, 3, 2023) else { panic!() };
+        fecha.restar_meses(1);
+        assert_eq!(fecha, Fecha { dia: 28, mes: 2, ano: 2023 });
+
+        let Some(mut fecha) = Fecha::new(15, 1, 0) else { panic!() };
+        fecha.restar_meses(1);
+        assert_eq!(fecha, Fecha { dia: 15, mes: 12, ano: -1 });
+
+        let Some(mut fecha) = Fecha::new(15, 6, 2020) else { panic!() };
+        fecha.sumar_meses(0);
+        assert_eq!(fecha, Fecha { dia: 15, mes: 6, ano: 2020 });
+    }
+
+    #[test]
+    fn test_sumar_restar_anos() {
+        let Some(mut fecha) = Fecha::new(29, 2, 2024) else { panic!() };
+        fecha.sumar_anos(1);
+        assert_eq!(fecha, Fecha { dia: 28, mes: 2, ano: 2025 });
+
+        let Some(mut fecha) = Fecha::new(29, 2, 2000) else { panic!() };
+        fecha.sumar_anos(4);
+        assert_eq!(fecha, Fecha { dia: 29, mes: 2, ano: 2004 });
+
+        let Some(mut fecha) = Fecha::new(29, 2, 2000) else { panic!() };
+        fecha.restar_anos(100);
+        assert_eq!(fecha, Fecha { dia: 28, mes: 2, ano: 1900 });
+    }
+
+    #[test]
+    fn test_dia_semana() {
+        assert_eq!(Fecha { dia: 1, mes: 1, ano: 1970 }.dia_semana(), DiaSemana::Jueves);
+        assert_eq!(Fecha { dia: 22, mes: 8, ano: 2002 }.dia_semana(), DiaSemana::Jueves);
+        assert_eq!(Fecha { dia: 29, mes: 2, ano: 2024 }.dia_semana(), DiaSemana::Jueves);
+        assert_eq!(Fecha { dia: 2, mes: 3, ano: 2024 }.dia_semana(), DiaSemana::Sabado);
+        assert_eq!(Fecha { dia: 31, mes: 12, ano: 1969 }.dia_semana(), DiaSemana::Miercoles);
+
+        assert!(DiaSemana::Domingo.es_fin_de_semana());
+        assert!(!DiaSemana::Viernes.es_fin_de_semana());
+        assert_eq!(DiaSemana::Lunes.indice(), 0);
+        assert_eq!(DiaSemana::Domingo.indice(), 6);
+    }
+
+    #[test]
+    fn test_serde() {
+        let fecha = Fecha { dia: 15, mes: 3, ano: 2024 };
+
+        let json = serde_json::to_string(&fecha).unwrap();
+        assert_eq!(json, r#"{"dia":15,"mes":3,"ano":2024}"#);
+        assert_eq!(serde_json::from_str::<Fecha>(&json).unwrap(), fecha);
+
+        // formato que usaba Date en XYZ
+        let json_xyz = r#"{"day":15,"month":3,"year":2024}"#;
+        assert_eq!(serde_json::from_str::<Fecha>(json_xyz).unwrap(), fecha);
+    }
+}
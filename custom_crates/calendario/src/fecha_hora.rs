@@ -0,0 +1,365 @@
+//
+// fecha_hora.rs
+//
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::fecha::{a_u8, Fecha};
+
+pub const MILLIS_POR_SEGUNDO: i64 = 1000;
+pub const MILLIS_POR_MINUTO: i64 = 60 * MILLIS_POR_SEGUNDO;
+pub const MILLIS_POR_HORA: i64 = 60 * MILLIS_POR_MINUTO;
+pub const MILLIS_POR_DIA: i64 = 24 * MILLIS_POR_HORA;
+
+/// Los husos horarios reales van de UTC-12:00 a UTC+14:00
+pub const OFFSET_MAXIMO_MINUTOS: i16 = 14 * 60;
+
+/// # FechaHora
+///
+/// Instante exacto, con precisión de milisegundos.
+///
+/// - `millis_desde_epoch`: milisegundos desde el 01/01/1970 00:00:00.000 UTC (negativo si es anterior)
+/// - `offset_minutos`: huso horario en el que se registró (-180 para Argentina).
+///   Solo cambia cómo se muestra (`fecha()`, `hora()`, `Display`); el instante es el mismo.
+///
+/// Dos `FechaHora` del mismo instante son iguales aunque tengan distinto huso horario,
+/// y el orden es cronológico.
+///
+/// En JSON también se acepta una `Fecha` (`{ "dia": .., "mes": .., "ano": .. }`),
+/// que se toma como la medianoche UTC de ese día. Así siguen cargando los archivos guardados con `Fecha`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(from = "FechaHoraSerializada")]
+pub struct FechaHora {
+    pub millis_desde_epoch: i64,
+    pub offset_minutos: i16
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FechaHoraSerializada {
+    Instante { millis_desde_epoch: i64, #[serde(default)] offset_minutos: i16 },
+    Fecha(Fecha)
+}
+
+impl From<FechaHoraSerializada> for FechaHora {
+    fn from(valor: FechaHoraSerializada) -> Self {
+        match valor {
+            FechaHoraSerializada::Instante { millis_desde_epoch, offset_minutos } => FechaHora { millis_desde_epoch, offset_minutos },
+            FechaHoraSerializada::Fecha(fecha) => FechaHora::from(fecha),
+        }
+    }
+}
+
+impl PartialEq for FechaHora {
+    fn eq(&self, other: &Self) -> bool {
+        self.millis_desde_epoch == other.millis_desde_epoch
+    }
+}
+
+impl Eq for FechaHora {}
+
+impl Hash for FechaHora {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.millis_desde_epoch.hash(state);
+    }
+}
+
+impl Ord for FechaHora {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.millis_desde_epoch.cmp(&other.millis_desde_epoch)
+    }
+}
+
+impl PartialOrd for FechaHora {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `2024-03-15T14:30:05.123-03:00` (ISO-8601; `Z` si está en UTC)
+impl fmt::Display for FechaHora {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fecha = self.fecha();
+        if fecha.ano < 0 { write!(f, "-")? }
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}",
+               fecha.ano.unsigned_abs(), fecha.mes, fecha.dia,
+               self.hora(), self.minuto(), self.segundo(), self.milisegundo())?;
+
+        if self.offset_minutos == 0 {
+            write!(f, "Z")
+        } else {
+            let signo = if self.offset_minutos < 0 { '-' } else { '+' };
+            let offset = self.offset_minutos.unsigned_abs();
+            write!(f, "{signo}{:02}:{:02}", offset / 60, offset % 60)
+        }
+    }
+}
+
+/// Medianoche UTC del día
+impl From<Fecha> for FechaHora {
+    fn from(fecha: Fecha) -> Self {
+        FechaHora::desde_millis(fecha.numero_de_dia() * MILLIS_POR_DIA)
+    }
+}
+
+/// Día del calendario en el huso horario de la `FechaHora`
+impl From<FechaHora> for Fecha {
+    fn from(fecha_hora: FechaHora) -> Self {
+        fecha_hora.fecha()
+    }
+}
+
+impl FechaHora {
+
+    /// Hora local `hora:minuto:segundo.milis` del día `fecha`, en el huso horario `offset_minutos`.
+    ///
+    /// `None` si la fecha o la hora no son válidas, o si el offset supera las 14 horas.
+    #[must_use]
+    pub fn new(fecha: Fecha, hora: u8, minuto: u8, segundo: u8, milis: u16, offset_minutos: i16) -> Option<FechaHora> {
+        if !fecha.es_fecha_valida() || hora > 23 || minuto > 59 || segundo > 59 || milis > 999
+            || offset_minutos.abs() > OFFSET_MAXIMO_MINUTOS
+        { return None }
+
+        let millis_locales = fecha.numero_de_dia() * MILLIS_POR_DIA
+            + i64::from(hora) * MILLIS_POR_HORA
+            + i64::from(minuto) * MILLIS_POR_MINUTO
+            + i64::from(segundo) * MILLIS_POR_SEGUNDO
+            + i64::from(milis);
+
+        Some(FechaHora {
+            millis_desde_epoch: millis_locales - i64::from(offset_minutos) * MILLIS_POR_MINUTO,
+            offset_minutos
+        })
+    }
+
+    /// Instante en UTC
+    #[must_use]
+    pub fn desde_millis(millis_desde_epoch: i64) -> FechaHora {
+        FechaHora { millis_desde_epoch, offset_minutos: 0 }
+    }
+
+    /// Instante actual según el reloj del sistema, en UTC
+    #[must_use]
+    pub fn ahora() -> FechaHora {
+        let millis = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(desde_epoch) => i64::try_from(desde_epoch.as_millis()).unwrap_or(i64::MAX),
+            Err(antes_de_epoch) => -i64::try_from(antes_de_epoch.duration().as_millis()).unwrap_or(i64::MAX),
+        };
+
+        FechaHora::desde_millis(millis)
+    }
+
+    /// El mismo instante, visto desde otro huso horario.
+    /// `None` si el offset supera las 14 horas.
+    #[must_use]
+    pub fn con_offset(self, offset_minutos: i16) -> Option<FechaHora> {
+        if offset_minutos.abs() > OFFSET_MAXIMO_MINUTOS { return None }
+        Some(FechaHora { offset_minutos, ..self })
+    }
+
+    #[must_use]
+    pub fn es_valida(&self) -> bool {
+        self.offset_minutos.abs() <= OFFSET_MAXIMO_MINUTOS
+    }
+
+    #[must_use]
+    pub fn millis(&self) -> i64 {
+        self.millis_desde_epoch
+    }
+
+    // milisegundos desde el epoch, pero contados en el huso horario local
+    fn millis_locales(&self) -> i64 {
+        self.millis_desde_epoch + i64::from(self.offset_minutos) * MILLIS_POR_MINUTO
+    }
+
+    #[must_use]
+    pub fn fecha(&self) -> Fecha {
+        Fecha::desde_numero_de_dia(self.millis_locales().div_euclid(MILLIS_POR_DIA))
+    }
+
+    #[must_use]
+    pub fn hora(&self) -> u8 {
+        a_u8(self.millis_locales().rem_euclid(MILLIS_POR_DIA) / MILLIS_POR_HORA)
+    }
+
+    #[must_use]
+    pub fn minuto(&self) -> u8 {
+        a_u8(self.millis_locales().rem_euclid(MILLIS_POR_HORA) / MILLIS_POR_MINUTO)
+    }
+
+    #[must_use]
+    pub fn segundo(&self) -> u8 {
+        a_u8(self.millis_locales().rem_euclid(MILLIS_POR_MINUTO) / MILLIS_POR_SEGUNDO)
+    }
+
+    #[must_use]
+    pub fn milisegundo(&self) -> u16 {
+        // [0, 999]
+        u16::try_from(self.millis_locales().rem_euclid(MILLIS_POR_SEGUNDO)).unwrap_or_default()
+    }
+
+    /// Milisegundos desde `self` hasta `otra`. Negativo si `otra` es anterior.
+    #[must_use]
+    pub fn millis_hasta(&self, otra: &FechaHora) -> i64 {
+        otra.millis_desde_epoch - self.millis_desde_epoch
+    }
+
+    /// Tiempo transcurrido entre ambas, sin importar cuál es anterior
+    #[must_use]
+    pub fn distancia(&self, otra: &FechaHora) -> Duration {
+        Duration::from_millis(self.millis_hasta(otra).unsigned_abs())
+    }
+}
+
+fn duracion_a_millis(duracion: Duration) -> i64 {
+    i64::try_from(duracion.as_millis()).unwrap_or(i64::MAX)
+}
+
+impl Add<Duration> for FechaHora {
+    type Output = FechaHora;
+
+    fn add(self, duracion: Duration) -> FechaHora {
+        FechaHora { millis_desde_epoch: self.millis_desde_epoch.saturating_add(duracion_a_millis(duracion)), ..self }
+    }
+}
+
+impl Sub<Duration> for FechaHora {
+    type Output = FechaHora;
+
+    fn sub(self, duracion: Duration) -> FechaHora {
+        FechaHora { millis_desde_epoch: self.millis_desde_epoch.saturating_sub(duracion_a_millis(duracion)), ..self }
+    }
+}
+
+impl AddAssign<Duration> for FechaHora {
+    fn add_assign(&mut self, duracion: Duration) {
+        *self = *self + duracion;
+    }
+}
+
+impl SubAssign<Duration> for FechaHora {
+    fn sub_assign(&mut self, duracion: Duration) {
+        *self = *self - duracion;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ARGENTINA: i16 = -180;
+
+    #[test]
+    fn test_new() {
+        let Some(fecha) = Fecha::new(15, 3, 2024) else { panic!() };
+
+        let Some(utc) = FechaHora::new(fecha, 14, 30, 5, 123, 0) else { panic!() };
+        assert_eq!(utc.millis(), 1_710_513_005_123);
+
+        // 14:30 en Argentina son las 17:30 UTC
+        let Some(argentina) = FechaHora::new(fecha, 14, 30, 5, 123, ARGENTINA) else { panic!() };
+        assert_eq!(argentina.millis_hasta(&utc), -3 * MILLIS_POR_HORA);
+
+        assert!(FechaHora::new(fecha, 24, 0, 0, 0, 0).is_none());
+        assert!(FechaHora::new(fecha, 0, 60, 0, 0, 0).is_none());
+        assert!(FechaHora::new(fecha, 0, 0, 0, 1000, 0).is_none());
+        assert!(FechaHora::new(fecha, 0, 0, 0, 0, 15 * 60).is_none());
+        assert!(FechaHora::new(Fecha { dia: 30, mes: 2, ano: 2024 }, 0, 0, 0, 0, 0).is_none());
+    }
+
+    #[test]
+    fn test_componentes() {
+        let Some(fecha) = Fecha::new(31, 12, 2023) else { panic!() };
+        let Some(fecha_hora) = FechaHora::new(fecha, 23, 59, 58, 7, ARGENTINA) else { panic!() };
+
+        assert_eq!(fecha_hora.fecha(), fecha);
+        assert_eq!((fecha_hora.hora(), fecha_hora.minuto(), fecha_hora.segundo(), fecha_hora.milisegundo()), (23, 59, 58, 7));
+
+        // en UTC ya es año nuevo
+        let Some(utc) = fecha_hora.con_offset(0) else { panic!() };
+        assert_eq!(utc, fecha_hora);
+        assert_eq!(utc.fecha(), Fecha { dia: 1, mes: 1, ano: 2024 });
+        assert_eq!(utc.hora(), 2);
+
+        // antes del epoch
+        let antes = FechaHora::desde_millis(-1);
+        assert_eq!(antes.fecha(), Fecha { dia: 31, mes: 12, ano: 1969 });
+        assert_eq!((antes.hora(), antes.minuto(), antes.segundo(), antes.milisegundo()), (23, 59, 59, 999));
+    }
+
+    #[test]
+    fn test_conversiones() {
+        for numero in (-500_000..500_000).step_by(4999) {
+            let fecha = Fecha::desde_numero_de_dia(numero);
+            let fecha_hora = FechaHora::from(fecha);
+            assert_eq!(Fecha::from(fecha_hora), fecha);
+            assert_eq!(fecha_hora.millis() % MILLIS_POR_DIA, 0);
+        }
+
+        for millis in [i64::from(i32::MIN), -1, 0, 1, 1_710_513_005_123] {
+            assert_eq!(FechaHora::desde_millis(millis).millis(), millis);
+        }
+    }
+
+    #[test]
+    fn test_orden() {
+        let Some(fecha) = Fecha::new(15, 3, 2024) else { panic!() };
+        let Some(manana) = FechaHora::new(fecha, 9, 0, 0, 0, ARGENTINA) else { panic!() };
+        let Some(tarde) = FechaHora::new(fecha, 13, 0, 0, 0, 0) else { panic!() };
+
+        // las 09:00 de Argentina son las 12:00 UTC
+        assert!(manana < tarde);
+
+        let mut ventas = vec![tarde, manana, FechaHora::from(fecha)];
+        ventas.sort();
+        assert_eq!(ventas, vec![FechaHora::from(fecha), manana, tarde]);
+    }
+
+    #[test]
+    fn test_aritmetica() {
+        let Some(fecha) = Fecha::new(28, 2, 2024) else { panic!() };
+        let mut fecha_hora = FechaHora::from(fecha);
+
+        fecha_hora += Duration::from_hours(36);
+        assert_eq!(fecha_hora.fecha(), Fecha { dia: 29, mes: 2, ano: 2024 });
+        assert_eq!(fecha_hora.hora(), 12);
+
+        let despues = fecha_hora + Duration::from_millis(1);
+        assert_eq!(fecha_hora.millis_hasta(&despues), 1);
+        assert_eq!(despues.distancia(&fecha_hora), Duration::from_millis(1));
+
+        fecha_hora -= Duration::from_hours(48);
+        assert_eq!(fecha_hora.fecha(), Fecha { dia: 27, mes: 2, ano: 2024 });
+        assert_eq!(fecha_hora - Duration::ZERO, fecha_hora);
+    }
+
+    #[test]
+    fn test_display() {
+        let Some(fecha) = Fecha::new(15, 3, 2024) else { panic!() };
+        let Some(fecha_hora) = FechaHora::new(fecha, 14, 30, 5, 123, ARGENTINA) else { panic!() };
+
+        assert_eq!(fecha_hora.to_string(), "2024-03-15T14:30:05.123-03:00");
+        assert_eq!(FechaHora::desde_millis(0).to_string(), "1970-01-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn test_serde() {
+        let Some(fecha) = Fecha::new(15, 3, 2024) else { panic!() };
+        let Some(fecha_hora) = FechaHora::new(fecha, 14, 30, 5, 123, ARGENTINA) else { panic!() };
+
+        let json = serde_json::to_string(&fecha_hora).unwrap();
+        assert_eq!(json, r#"{"millis_desde_epoch":1710523805123,"offset_minutos":-180}"#);
+
+        let leida: FechaHora = serde_json::from_str(&json).unwrap();
+        assert_eq!(leida, fecha_hora);
+        assert_eq!(leida.offset_minutos, ARGENTINA);
+
+        // archivos viejos, guardados con Fecha
+        let leida: FechaHora = serde_json::from_str(r#"{"day":15,"month":3,"year":2024}"#).unwrap();
+        assert_eq!(leida, FechaHora::from(fecha));
+    }
+}
@@ -0,0 +1,160 @@
+//
+// formato.rs
+//
+
+use crate::fecha::{DiaSemana, Fecha, NOMBRE_MESES};
+
+pub const NOMBRE_MESES_INGLES: [&str; 12] = ["January", "February", "March", "April",
+    "May", "June", "July", "August",
+    "September", "October", "November", "December"];
+
+pub const NOMBRE_DIAS: [&str; 7] = ["Lunes", "Martes", "Miércoles", "Jueves", "Viernes", "Sábado", "Domingo"];
+
+pub const NOMBRE_DIAS_INGLES: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Idioma {
+    #[default]
+    Espanol,
+    Ingles
+}
+
+impl Idioma {
+    /// `mes` va de 1 a 12
+    #[must_use]
+    pub fn nombre_mes(self, mes: u8) -> Option<&'static str> {
+        let nombres = match self {
+            Idioma::Espanol => &NOMBRE_MESES,
+            Idioma::Ingles => &NOMBRE_MESES_INGLES,
+        };
+        nombres.get(usize::from(mes).checked_sub(1)?).copied()
+    }
+
+    #[must_use]
+    pub fn nombre_dia(self, dia: DiaSemana) -> &'static str {
+        match self {
+            Idioma::Espanol => NOMBRE_DIAS[dia.indice()],
+            Idioma::Ingles => NOMBRE_DIAS_INGLES[dia.indice()],
+        }
+    }
+}
+
+/// # FormatoFecha
+///
+/// Forma de escribir una fecha como texto.<br>
+/// Los reportes reciben un `&dyn FormatoFecha` (o un genérico), así cada uno puede elegir cómo se ven sus fechas.
+///
+/// Todos los formatos de este módulo se pueden volver a leer con `Fecha::from_str`.
+pub trait FormatoFecha {
+    fn formatear(&self, fecha: &Fecha) -> String;
+}
+
+/// `2024-03-15`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FormatoIso;
+
+/// `15/03/2024`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FormatoNumerico;
+
+/// - Español: `15 de Marzo del 2024` (`Viernes 15 de Marzo del 2024` con día de la semana)
+/// - Inglés: `March 15, 2024` (`Friday, March 15, 2024` con día de la semana)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FormatoLargo {
+    pub idioma: Idioma,
+    pub con_dia_semana: bool
+}
+
+impl FormatoLargo {
+    #[must_use]
+    pub fn new(idioma: Idioma) -> Self {
+        FormatoLargo { idioma, con_dia_semana: false }
+    }
+
+    #[must_use]
+    pub fn con_dia_semana(self) -> Self {
+        FormatoLargo { con_dia_semana: true, ..self }
+    }
+}
+
+impl FormatoFecha for FormatoIso {
+    fn formatear(&self, fecha: &Fecha) -> String {
+        // los años negativos se escriben con signo, como indica ISO-8601
+        if fecha.ano < 0 {
+            format!("-{:04}-{:02}-{:02}", fecha.ano.unsigned_abs(), fecha.mes, fecha.dia)
+        } else {
+            format!("{:04}-{:02}-{:02}", fecha.ano, fecha.mes, fecha.dia)
+        }
+    }
+}
+
+impl FormatoFecha for FormatoNumerico {
+    fn formatear(&self, fecha: &Fecha) -> String {
+        format!("{:02}/{:02}/{}", fecha.dia, fecha.mes, fecha.ano)
+    }
+}
+
+impl FormatoFecha for FormatoLargo {
+    fn formatear(&self, fecha: &Fecha) -> String {
+        // una fecha inválida no tiene nombre de mes ni día de la semana
+        let Some(nombre_mes) = self.idioma.nombre_mes(fecha.mes).filter(|_| fecha.es_fecha_valida())
+        else { return FormatoNumerico.formatear(fecha) };
+
+        let dia_semana = self.idioma.nombre_dia(fecha.dia_semana());
+
+        match (self.idioma, self.con_dia_semana) {
+            (Idioma::Espanol, false) => format!("{} de {nombre_mes} del {}", fecha.dia, fecha.ano),
+            (Idioma::Espanol, true) => format!("{dia_semana} {} de {nombre_mes} del {}", fecha.dia, fecha.ano),
+            (Idioma::Ingles, false) => format!("{nombre_mes} {}, {}", fecha.dia, fecha.ano),
+            (Idioma::Ingles, true) => format!("{dia_semana}, {nombre_mes} {}, {}", fecha.dia, fecha.ano),
+        }
+    }
+}
+
+impl Fecha {
+    #[must_use]
+    pub fn formatear(&self, formato: &impl FormatoFecha) -> String {
+        formato.formatear(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iso() {
+        assert_eq!(Fecha { dia: 5, mes: 3, ano: 2024 }.formatear(&FormatoIso), "2024-03-05");
+        assert_eq!(Fecha { dia: 15, mes: 3, ano: 44 }.formatear(&FormatoIso), "0044-03-15");
+        assert_eq!(Fecha { dia: 15, mes: 3, ano: -44 }.formatear(&FormatoIso), "-0044-03-15");
+    }
+
+    #[test]
+    fn test_numerico() {
+        assert_eq!(Fecha { dia: 5, mes: 3, ano: 2024 }.formatear(&FormatoNumerico), "05/03/2024");
+    }
+
+    #[test]
+    fn test_largo() {
+        let fecha = Fecha { dia: 15, mes: 3, ano: 2024 };
+
+        assert_eq!(fecha.formatear(&FormatoLargo::default()), "15 de Marzo del 2024");
+        assert_eq!(fecha.formatear(&FormatoLargo::default()), fecha.to_string());
+        assert_eq!(fecha.formatear(&FormatoLargo::new(Idioma::Espanol).con_dia_semana()), "Viernes 15 de Marzo del 2024");
+        assert_eq!(fecha.formatear(&FormatoLargo::new(Idioma::Ingles)), "March 15, 2024");
+        assert_eq!(fecha.formatear(&FormatoLargo::new(Idioma::Ingles).con_dia_semana()), "Friday, March 15, 2024");
+
+        // inválida: cae al formato numérico
+        let invalida = Fecha { dia: 31, mes: 2, ano: 2024 };
+        assert_eq!(invalida.formatear(&FormatoLargo::new(Idioma::Ingles)), "31/02/2024");
+    }
+
+    #[test]
+    fn test_formato_dinamico() {
+        let formatos: [&dyn FormatoFecha; 3] = [&FormatoIso, &FormatoNumerico, &FormatoLargo::default()];
+        let fecha = Fecha { dia: 1, mes: 1, ano: 2000 };
+
+        let textos: Vec<String> = formatos.iter().map(|formato| formato.formatear(&fecha)).collect();
+        assert_eq!(textos, vec!["2000-01-01", "01/01/2000", "1 de Enero del 2000"]);
+    }
+}
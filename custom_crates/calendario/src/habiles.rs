@@ -0,0 +1,370 @@
+//
+// habiles.rs
+//
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::fecha::{a_u8, DiaSemana, Fecha};
+
+const FERIADOS_ARGENTINA: &str = include_str!("../feriados_argentina.json");
+
+/// # Feriado
+///
+/// - `Fijo`: el mismo día todos los años (25/12)
+/// - `Trasladable`: como en Argentina, si cae martes o miércoles pasa al lunes anterior,
+///   y si cae jueves o viernes, al lunes siguiente
+/// - `Pascua`: a una cantidad fija de días del domingo de Pascua (Carnaval, Viernes Santo)
+/// - `Unico`: solo esa fecha (feriados puente, decretos)
+#[derive(Serialize, Deserialize, Clone, PartialEq, PartialOrd, Debug)]
+#[serde(tag = "tipo")]
+pub enum Feriado {
+    Fijo { dia: u8, mes: u8, nombre: String },
+    Trasladable { dia: u8, mes: u8, nombre: String },
+    Pascua { dias_desde_pascua: i64, nombre: String },
+    Unico { fecha: Fecha, nombre: String }
+}
+
+impl Feriado {
+    #[must_use]
+    pub fn nombre(&self) -> &str {
+        match self {
+            Feriado::Fijo { nombre, .. }
+            | Feriado::Trasladable { nombre, .. }
+            | Feriado::Pascua { nombre, .. }
+            | Feriado::Unico { nombre, .. } => nombre,
+        }
+    }
+
+    /// Día en que cae el feriado en el año `ano`.
+    /// `None` si no cae ese año (un `Unico` de otro año, o un 29/02 en un año no bisiesto).
+    #[must_use]
+    pub fn fecha_en(&self, ano: i64) -> Option<Fecha> {
+        match self {
+            Feriado::Fijo { dia, mes, .. } => Fecha::new(*dia, *mes, ano),
+            Feriado::Trasladable { dia, mes, .. } => {
+                let fecha = Fecha::new(*dia, *mes, ano)?;
+                let numero = fecha.numero_de_dia();
+                let trasladado = match fecha.dia_semana() {
+                    DiaSemana::Martes => numero - 1,
+                    DiaSemana::Miercoles => numero - 2,
+                    DiaSemana::Jueves => numero + 4,
+                    DiaSemana::Viernes => numero + 3,
+                    _ => numero,
+                };
+                Some(Fecha::desde_numero_de_dia(trasladado))
+            },
+            Feriado::Pascua { dias_desde_pascua, .. } =>
+                Some(Fecha::desde_numero_de_dia(domingo_de_pascua(ano).numero_de_dia() + dias_desde_pascua)),
+            Feriado::Unico { fecha, .. } => (fecha.ano == ano).then_some(*fecha),
+        }
+    }
+}
+
+/// Domingo de Pascua del calendario gregoriano (algoritmo de Meeus/Jones/Butcher)
+// los nombres de una letra son los del algoritmo publicado, para poder compararlo
+#[allow(clippy::many_single_char_names)]
+#[must_use]
+pub fn domingo_de_pascua(ano: i64) -> Fecha {
+    let a = ano.rem_euclid(19);
+    let b = ano.div_euclid(100);
+    let c = ano.rem_euclid(100);
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15).rem_euclid(30);
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k).rem_euclid(7);
+    let m = (a + 11 * h + 22 * l) / 451;
+    let mes = (h + l - 7 * m + 114) / 31;
+    let dia = (h + l - 7 * m + 114) % 31 + 1;
+
+    Fecha { dia: a_u8(dia), mes: a_u8(mes), ano }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorCalendarioHabil {
+    Archivo(String),
+    Json(String),
+    Toml(String),
+    /// la extensión del archivo no es `.json` ni `.toml`
+    FormatoDesconocido(String)
+}
+
+impl fmt::Display for ErrorCalendarioHabil {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorCalendarioHabil::Archivo(error) => write!(f, "no se pudo leer el archivo de feriados: {error}"),
+            ErrorCalendarioHabil::Json(error) => write!(f, "JSON de feriados inválido: {error}"),
+            ErrorCalendarioHabil::Toml(error) => write!(f, "TOML de feriados inválido: {error}"),
+            ErrorCalendarioHabil::FormatoDesconocido(ruta) => write!(f, "formato de feriados desconocido: '{ruta}' (se espera .json o .toml)"),
+        }
+    }
+}
+
+impl std::error::Error for ErrorCalendarioHabil {}
+
+/// # CalendarioHabil
+///
+/// Qué días se trabaja: todos menos los `fines_de_semana` y los `feriados`.
+///
+/// Se puede cargar de un JSON o TOML con la misma forma que `feriados_argentina.json`:
+/// ```json
+/// { "fines_de_semana": ["Sabado", "Domingo"],
+///   "feriados": [{ "tipo": "Fijo", "dia": 25, "mes": 12, "nombre": "Navidad" }] }
+/// ```
+/// `Default` es el calendario de feriados nacionales de Argentina.
+#[derive(Serialize, Deserialize, Clone, PartialEq, PartialOrd, Debug)]
+pub struct CalendarioHabil {
+    #[serde(default = "fin_de_semana_clasico")]
+    pub fines_de_semana: BTreeSet<DiaSemana>,
+    #[serde(default)]
+    pub feriados: Vec<Feriado>
+}
+
+fn fin_de_semana_clasico() -> BTreeSet<DiaSemana> {
+    BTreeSet::from([DiaSemana::Sabado, DiaSemana::Domingo])
+}
+
+impl Default for CalendarioHabil {
+    fn default() -> Self {
+        CalendarioHabil::argentina()
+    }
+}
+
+impl CalendarioHabil {
+    /// Solo fines de semana, sin feriados
+    #[must_use]
+    pub fn new(fines_de_semana: BTreeSet<DiaSemana>) -> CalendarioHabil {
+        CalendarioHabil { fines_de_semana, feriados: Vec::new() }
+    }
+
+    /// Feriados nacionales de Argentina (los que se repiten todos los años)
+    ///
+    /// # Panics
+    /// Solo si `feriados_argentina.json`, que se incluye al compilar, fuera inválido.
+    #[must_use]
+    pub fn argentina() -> CalendarioHabil {
+        CalendarioHabil::desde_json(FERIADOS_ARGENTINA)
+            .unwrap_or_else(|error| panic!("feriados_argentina.json: {error}"))
+    }
+
+    /// # Errors
+    /// `ErrorCalendarioHabil::Json` si el texto no tiene la forma esperada
+    pub fn desde_json(texto: &str) -> Result<CalendarioHabil, ErrorCalendarioHabil> {
+        serde_json::from_str(texto).map_err(|error| ErrorCalendarioHabil::Json(error.to_string()))
+    }
+
+    /// # Errors
+    /// `ErrorCalendarioHabil::Toml` si el texto no tiene la forma esperada
+    pub fn desde_toml(texto: &str) -> Result<CalendarioHabil, ErrorCalendarioHabil> {
+        toml::from_str(texto).map_err(|error| ErrorCalendarioHabil::Toml(error.to_string()))
+    }
+
+    /// Lee un `.json` o un `.toml` según la extensión
+    ///
+    /// # Errors
+    /// Si no se puede leer el archivo, la extensión no es conocida o el contenido es inválido
+    pub fn cargar(ruta: impl AsRef<Path>) -> Result<CalendarioHabil, ErrorCalendarioHabil> {
+        let ruta = ruta.as_ref();
+        let extension = ruta.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase);
+
+        let leer = || fs::read_to_string(ruta).map_err(|error| ErrorCalendarioHabil::Archivo(error.to_string()));
+        match extension.as_deref() {
+            Some("json") => CalendarioHabil::desde_json(&leer()?),
+            Some("toml") => CalendarioHabil::desde_toml(&leer()?),
+            _ => Err(ErrorCalendarioHabil::FormatoDesconocido(ruta.display().to_string())),
+        }
+    }
+
+    /// Agrega un feriado (por ejemplo, un puente turístico decretado ese año)
+    pub fn agregar_feriado(&mut self, feriado: Feriado) {
+        self.feriados.push(feriado);
+    }
+
+    /// El feriado que cae en `fecha`, si hay alguno
+    #[must_use]
+    pub fn feriado(&self, fecha: &Fecha) -> Option<&Feriado> {
+        // un trasladable de fin de año podría caer en el año siguiente
+        self.feriados.iter().find(|feriado|
+            (fecha.ano - 1..=fecha.ano + 1).any(|ano| feriado.fecha_en(ano) == Some(*fecha))
+        )
+    }
+
+    #[must_use]
+    pub fn es_habil(&self, fecha: &Fecha) -> bool {
+        !self.fines_de_semana.contains(&fecha.dia_semana()) && self.feriado(fecha).is_none()
+    }
+
+    /// Feriados de `ano`, ordenados por fecha
+    #[must_use]
+    pub fn feriados_del_ano(&self, ano: i64) -> Vec<(Fecha, &Feriado)> {
+        let mut feriados: Vec<(Fecha, &Feriado)> = self.feriados.iter()
+            .filter_map(|feriado| feriado.fecha_en(ano).map(|fecha| (fecha, feriado)))
+            .collect();
+        feriados.sort_by_key(|(fecha, _)| *fecha);
+        feriados
+    }
+
+    /// `fecha` si es hábil, o el primer día hábil posterior.
+    /// `None` si todos los días de la semana son fin de semana.
+    #[must_use]
+    pub fn siguiente_habil(&self, fecha: Fecha) -> Option<Fecha> {
+        if self.fines_de_semana.len() >= DiaSemana::TODOS.len() { return None }
+
+        let mut fecha = fecha;
+        while !self.es_habil(&fecha) {
+            fecha.sumar_dias(1);
+        }
+        Some(fecha)
+    }
+
+    /// El día hábil número `dias` después de `fecha` (sin contar `fecha`).
+    /// Con `dias` = 0 devuelve `fecha` tal cual.
+    /// `None` si todos los días de la semana son fin de semana.
+    #[must_use]
+    pub fn sumar_dias_habiles(&self, fecha: Fecha, dias: u32) -> Option<Fecha> {
+        let mut fecha = fecha;
+        for _ in 0..dias {
+            fecha.sumar_dias(1);
+            fecha = self.siguiente_habil(fecha)?;
+        }
+        Some(fecha)
+    }
+
+    /// Días hábiles en `(desde, hasta]`. Negativo si `hasta` es anterior a `desde`.
+    #[must_use]
+    pub fn dias_habiles_entre(&self, desde: &Fecha, hasta: &Fecha) -> i64 {
+        let (inicio, fin, signo) = if desde <= hasta { (desde, hasta, 1) } else { (hasta, desde, -1) };
+
+        let habiles = (inicio.numero_de_dia() + 1..=fin.numero_de_dia())
+            .map(Fecha::desde_numero_de_dia)
+            .filter(|fecha| self.es_habil(fecha))
+            .count();
+
+        signo * i64::try_from(habiles).unwrap_or(i64::MAX)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fecha(dia: u8, mes: u8, ano: i64) -> Fecha {
+        Fecha { dia, mes, ano }
+    }
+
+    #[test]
+    fn test_pascua() {
+        assert_eq!(domingo_de_pascua(2024), fecha(31, 3, 2024));
+        assert_eq!(domingo_de_pascua(2025), fecha(20, 4, 2025));
+        assert_eq!(domingo_de_pascua(2000), fecha(23, 4, 2000));
+        assert_eq!(domingo_de_pascua(1818), fecha(22, 3, 1818));
+        assert_eq!(domingo_de_pascua(2038), fecha(25, 4, 2038));
+
+        for ano in 1583..2500 {
+            assert_eq!(domingo_de_pascua(ano).dia_semana(), DiaSemana::Domingo, "{ano}");
+        }
+    }
+
+    #[test]
+    fn test_feriados_argentina() {
+        let calendario = CalendarioHabil::argentina();
+
+        assert_eq!(calendario.feriado(&fecha(25, 12, 2024)).map(Feriado::nombre), Some("Navidad"));
+        assert_eq!(calendario.feriado(&fecha(29, 3, 2024)).map(Feriado::nombre), Some("Viernes Santo"));
+        assert_eq!(calendario.feriado(&fecha(12, 2, 2024)).map(Feriado::nombre), Some("Carnaval"));
+        assert_eq!(calendario.feriado(&fecha(13, 2, 2024)).map(Feriado::nombre), Some("Carnaval"));
+
+        // 17/08/2024 fue sábado: no se traslada
+        assert!(calendario.feriado(&fecha(17, 8, 2024)).is_some());
+        // 12/10/2023 fue jueves: pasó al lunes 16
+        assert!(calendario.feriado(&fecha(12, 10, 2023)).is_none());
+        assert!(calendario.feriado(&fecha(16, 10, 2023)).is_some());
+        // 20/11/2024 fue miércoles: pasó al lunes 18
+        assert!(calendario.feriado(&fecha(18, 11, 2024)).is_some());
+
+        assert!(!calendario.es_habil(&fecha(1, 1, 2024)));
+        assert!(!calendario.es_habil(&fecha(6, 1, 2024)), "sábado");
+        assert!(calendario.es_habil(&fecha(2, 1, 2024)));
+
+        assert_eq!(calendario.feriados_del_ano(2024).len(), 16);
+        assert_eq!(calendario.feriados_del_ano(2024)[0].0, fecha(1, 1, 2024));
+    }
+
+    #[test]
+    fn test_sumar_dias_habiles() {
+        let calendario = CalendarioHabil::argentina();
+
+        // viernes 22/03/2024: el lunes 25 es hábil, pero 24/03 es domingo y feriado
+        assert_eq!(calendario.sumar_dias_habiles(fecha(22, 3, 2024), 1), Some(fecha(25, 3, 2024)));
+        // jueves 28/03/2024: viernes santo, fin de semana, lunes 1, martes 2 de abril feriado
+        assert_eq!(calendario.sumar_dias_habiles(fecha(28, 3, 2024), 1), Some(fecha(1, 4, 2024)));
+        assert_eq!(calendario.sumar_dias_habiles(fecha(28, 3, 2024), 2), Some(fecha(3, 4, 2024)));
+        assert_eq!(calendario.sumar_dias_habiles(fecha(28, 3, 2024), 0), Some(fecha(28, 3, 2024)));
+
+        let sin_feriados = CalendarioHabil::new(fin_de_semana_clasico());
+        assert_eq!(sin_feriados.sumar_dias_habiles(fecha(1, 1, 2024), 10), Some(fecha(15, 1, 2024)));
+
+        let nunca = CalendarioHabil::new(DiaSemana::TODOS.into_iter().collect());
+        assert_eq!(nunca.sumar_dias_habiles(fecha(1, 1, 2024), 1), None);
+        assert_eq!(nunca.siguiente_habil(fecha(1, 1, 2024)), None);
+    }
+
+    #[test]
+    fn test_dias_habiles_entre() {
+        let calendario = CalendarioHabil::argentina();
+
+        // marzo 2024: 21 días de semana menos el viernes santo (29/03); el 24/03 fue domingo
+        assert_eq!(calendario.dias_habiles_entre(&fecha(29, 2, 2024), &fecha(31, 3, 2024)), 20);
+        assert_eq!(calendario.dias_habiles_entre(&fecha(31, 3, 2024), &fecha(29, 2, 2024)), -20);
+        assert_eq!(calendario.dias_habiles_entre(&fecha(1, 3, 2024), &fecha(1, 3, 2024)), 0);
+
+        for dias in [1, 5, 10, 30] {
+            let desde = fecha(20, 12, 2023);
+            let Some(hasta) = calendario.sumar_dias_habiles(desde, dias) else { panic!() };
+            assert_eq!(calendario.dias_habiles_entre(&desde, &hasta), i64::from(dias));
+        }
+    }
+
+    #[test]
+    fn test_cargar() {
+        let json = r#"{ "fines_de_semana": ["Viernes", "Sabado"],
+                        "feriados": [{ "tipo": "Unico", "fecha": { "dia": 3, "mes": 1, "ano": 2024 }, "nombre": "Puente" }] }"#;
+        let Ok(calendario) = CalendarioHabil::desde_json(json) else { panic!() };
+        assert!(calendario.es_habil(&fecha(7, 1, 2024)), "domingo");
+        assert!(!calendario.es_habil(&fecha(5, 1, 2024)), "viernes");
+        assert!(!calendario.es_habil(&fecha(3, 1, 2024)));
+        assert!(calendario.es_habil(&fecha(2, 1, 2025)));
+
+        let toml = r#"
+            [[feriados]]
+            tipo = "Fijo"
+            dia = 25
+            mes = 12
+            nombre = "Navidad"
+
+            [[feriados]]
+            tipo = "Pascua"
+            dias_desde_pascua = -2
+            nombre = "Viernes Santo"
+        "#;
+        let Ok(calendario) = CalendarioHabil::desde_toml(toml) else { panic!() };
+        assert_eq!(calendario.fines_de_semana, fin_de_semana_clasico());
+        assert!(!calendario.es_habil(&fecha(25, 12, 2024)));
+        assert!(!calendario.es_habil(&fecha(29, 3, 2024)));
+
+        assert!(matches!(CalendarioHabil::desde_json("{ \"feriados\": 3 }"), Err(ErrorCalendarioHabil::Json(_))));
+        assert!(matches!(CalendarioHabil::cargar("feriados.yaml"), Err(ErrorCalendarioHabil::FormatoDesconocido(_))));
+        assert!(matches!(CalendarioHabil::cargar("no_existe.toml"), Err(ErrorCalendarioHabil::Archivo(_))));
+
+        let ruta = std::env::temp_dir().join("calendario_test_cargar.toml");
+        fs::write(&ruta, toml).unwrap();
+        assert_eq!(CalendarioHabil::cargar(&ruta), Ok(calendario));
+        let _ = fs::remove_file(ruta);
+    }
+}
@@ -0,0 +1,28 @@
+// Calendario compartido por todos los ejercicios.
+//
+// Antes cada práctica tenía su propia copia de Fecha (o Date), todas con los mismos errores:
+// la comparación no miraba el año antes que el mes y es_bisiesto ignoraba la regla de los siglos.
+
+#![deny(clippy::pedantic)]
+// los títulos de los docs (`/// # Fecha`) son nombres, no código
+#![allow(clippy::doc_markdown)]
+
+pub mod fecha;
+pub mod fecha_hora;
+pub mod formato;
+pub mod habiles;
+pub mod parseo;
+pub mod rango;
+pub mod recurrencia;
+pub mod reloj;
+pub mod ventana;
+
+pub use fecha::{DiaSemana, Fecha, NOMBRE_MESES};
+pub use fecha_hora::FechaHora;
+pub use habiles::{CalendarioHabil, ErrorCalendarioHabil, Feriado, domingo_de_pascua};
+pub use formato::{FormatoFecha, FormatoIso, FormatoLargo, FormatoNumerico, Idioma};
+pub use parseo::{ComponenteFecha, ErrorParseoFecha};
+pub use rango::RangoFechas;
+pub use recurrencia::{FinRecurrencia, Ocurrencias, Periodo, Recurrencia};
+pub use reloj::{Reloj, RelojCompartido, RelojFijo, RelojSistema};
+pub use ventana::Ventana;
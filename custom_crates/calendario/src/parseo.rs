@@ -0,0 +1,264 @@
+//
+// parseo.rs
+//
+
+use std::fmt;
+use std::str::FromStr;
+use crate::fecha::{DiaSemana, Fecha, NOMBRE_MESES};
+use crate::formato::{NOMBRE_DIAS, NOMBRE_DIAS_INGLES, NOMBRE_MESES_INGLES};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ComponenteFecha {
+    Dia, Mes, Ano
+}
+
+/// # ErrorParseoFecha
+///
+/// - `Vacia`: el texto no tenía nada (o solo espacios)
+/// - `FormatoDesconocido`: no se parece a ninguno de los formatos aceptados
+/// - `ComponenteInvalido`: el formato se reconoció, pero el día, mes o año no se pudo leer o está fuera de rango
+/// - `DiaInexistente`: los tres componentes son válidos por separado, pero el día no existe en ese mes (31/04, 29/02/2023)
+/// - `DiaSemanaNoCoincide`: el texto incluía un día de la semana que no corresponde a la fecha
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ErrorParseoFecha {
+    Vacia,
+    FormatoDesconocido(String),
+    ComponenteInvalido { componente: ComponenteFecha, valor: String },
+    DiaInexistente { dia: u8, mes: u8, ano: i64 },
+    DiaSemanaNoCoincide { leido: DiaSemana, real: DiaSemana }
+}
+
+impl fmt::Display for ComponenteFecha {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComponenteFecha::Dia => write!(f, "día"),
+            ComponenteFecha::Mes => write!(f, "mes"),
+            ComponenteFecha::Ano => write!(f, "año"),
+        }
+    }
+}
+
+impl fmt::Display for ErrorParseoFecha {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorParseoFecha::Vacia => write!(f, "la fecha está vacía"),
+            ErrorParseoFecha::FormatoDesconocido(texto) => write!(f, "formato de fecha desconocido: '{texto}'"),
+            ErrorParseoFecha::ComponenteInvalido { componente, valor } => write!(f, "{componente} inválido: '{valor}'"),
+            ErrorParseoFecha::DiaInexistente { dia, mes, ano } => write!(f, "el día {dia} no existe en {mes}/{ano}"),
+            ErrorParseoFecha::DiaSemanaNoCoincide { leido, real } => write!(f, "la fecha cae {real:?}, no {leido:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ErrorParseoFecha {}
+
+/// Acepta:
+/// - ISO-8601: `2024-03-15` (`-0044-03-15` para años antes de Cristo)
+/// - numérico argentino: `15/03/2024` o `15/3/2024`
+/// - largo en español: `15 de Marzo del 2024`, `viernes 15 de marzo de 2024`
+/// - largo en inglés: `March 15, 2024`, `Friday, March 15, 2024`
+///
+/// Mayúsculas y tildes son indistintas en los nombres.
+impl FromStr for Fecha {
+    type Err = ErrorParseoFecha;
+
+    fn from_str(texto: &str) -> Result<Self, Self::Err> {
+        let texto = texto.trim();
+        if texto.is_empty() { return Err(ErrorParseoFecha::Vacia) }
+
+        if texto.contains('/') {
+            parsear_numerica(texto)
+        } else if texto.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '+') {
+            parsear_iso(texto)
+        } else {
+            parsear_larga(texto)
+        }
+    }
+}
+
+impl TryFrom<&str> for Fecha {
+    type Error = ErrorParseoFecha;
+
+    fn try_from(texto: &str) -> Result<Self, Self::Error> {
+        texto.parse()
+    }
+}
+
+fn parsear_iso(texto: &str) -> Result<Fecha, ErrorParseoFecha> {
+    let (negativo, sin_signo) = match texto.as_bytes().first() {
+        Some(b'-') => (true, &texto[1..]),
+        Some(b'+') => (false, &texto[1..]),
+        _ => (false, texto),
+    };
+
+    let partes: Vec<&str> = sin_signo.split('-').collect();
+    let [ano, mes, dia] = partes[..] else {
+        return Err(ErrorParseoFecha::FormatoDesconocido(texto.to_string()))
+    };
+
+    let ano = leer_ano(ano)?;
+    construir(leer_dia(dia)?, leer_mes(mes)?, if negativo { -ano } else { ano })
+}
+
+fn parsear_numerica(texto: &str) -> Result<Fecha, ErrorParseoFecha> {
+    let partes: Vec<&str> = texto.split('/').map(str::trim).collect();
+    let [dia, mes, ano] = partes[..] else {
+        return Err(ErrorParseoFecha::FormatoDesconocido(texto.to_string()))
+    };
+
+    construir(leer_dia(dia)?, leer_mes(mes)?, leer_ano(ano)?)
+}
+
+fn parsear_larga(texto: &str) -> Result<Fecha, ErrorParseoFecha> {
+    let normalizado = normalizar(texto);
+    let mut palabras: Vec<&str> = normalizado.split_whitespace().collect();
+
+    // el día de la semana es opcional, pero si está tiene que coincidir
+    let dia_semana = palabras.first().and_then(|palabra| buscar_dia_semana(palabra));
+    if dia_semana.is_some() { palabras.remove(0); }
+
+    let (dia, mes, ano) = match palabras[..] {
+        [dia, "de", mes, "de" | "del", ano] => (dia, mes, ano),
+        [mes, dia, ano] if dia.starts_with(|c: char| c.is_ascii_digit()) => (dia, mes, ano),
+        _ => return Err(ErrorParseoFecha::FormatoDesconocido(texto.to_string())),
+    };
+
+    let Some(mes) = buscar_mes(mes) else {
+        return Err(ErrorParseoFecha::ComponenteInvalido { componente: ComponenteFecha::Mes, valor: mes.to_string() })
+    };
+
+    let fecha = construir(leer_dia(dia)?, mes, leer_ano(ano)?)?;
+
+    match dia_semana {
+        Some(leido) if leido != fecha.dia_semana() => Err(ErrorParseoFecha::DiaSemanaNoCoincide { leido, real: fecha.dia_semana() }),
+        _ => Ok(fecha),
+    }
+}
+
+fn construir(dia: u8, mes: u8, ano: i64) -> Result<Fecha, ErrorParseoFecha> {
+    Fecha::new(dia, mes, ano).ok_or(ErrorParseoFecha::DiaInexistente { dia, mes, ano })
+}
+
+fn leer_dia(valor: &str) -> Result<u8, ErrorParseoFecha> {
+    leer_en_rango(valor, 31, ComponenteFecha::Dia)
+}
+
+fn leer_mes(valor: &str) -> Result<u8, ErrorParseoFecha> {
+    leer_en_rango(valor, 12, ComponenteFecha::Mes)
+}
+
+fn leer_en_rango(valor: &str, maximo: u8, componente: ComponenteFecha) -> Result<u8, ErrorParseoFecha> {
+    match valor.parse::<u8>() {
+        Ok(numero) if (1..=maximo).contains(&numero) && valor.chars().all(|c| c.is_ascii_digit()) => Ok(numero),
+        _ => Err(ErrorParseoFecha::ComponenteInvalido { componente, valor: valor.to_string() }),
+    }
+}
+
+fn leer_ano(valor: &str) -> Result<i64, ErrorParseoFecha> {
+    let digitos = valor.strip_prefix('-').unwrap_or(valor);
+    if digitos.is_empty() || !digitos.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ErrorParseoFecha::ComponenteInvalido { componente: ComponenteFecha::Ano, valor: valor.to_string() })
+    }
+
+    valor.parse().map_err(|_| ErrorParseoFecha::ComponenteInvalido { componente: ComponenteFecha::Ano, valor: valor.to_string() })
+}
+
+// minúsculas, sin tildes y sin comas
+fn normalizar(texto: &str) -> String {
+    texto.to_lowercase().chars()
+        .map(|c| match c {
+            'á' => 'a', 'é' => 'e', 'í' => 'i', 'ó' => 'o', 'ú' => 'u',
+            ',' => ' ',
+            otro => otro
+        })
+        .collect()
+}
+
+fn buscar_mes(nombre: &str) -> Option<u8> {
+    if nombre == "setiembre" { return Some(9) }
+
+    NOMBRE_MESES.iter().zip(NOMBRE_MESES_INGLES.iter())
+        .position(|(es, en)| normalizar(es) == nombre || normalizar(en) == nombre)
+        .and_then(|indice| u8::try_from(indice + 1).ok())
+}
+
+fn buscar_dia_semana(nombre: &str) -> Option<DiaSemana> {
+    NOMBRE_DIAS.iter().zip(NOMBRE_DIAS_INGLES.iter())
+        .position(|(es, en)| normalizar(es) == nombre || normalizar(en) == nombre)
+        .map(|indice| DiaSemana::TODOS[indice])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::formato::{FormatoFecha, FormatoIso, FormatoLargo, FormatoNumerico, Idioma};
+
+    fn fecha(dia: u8, mes: u8, ano: i64) -> Fecha {
+        Fecha { dia, mes, ano }
+    }
+
+    #[test]
+    fn test_iso() {
+        assert_eq!("2024-03-15".parse(), Ok(fecha(15, 3, 2024)));
+        assert_eq!("2024-3-5".parse(), Ok(fecha(5, 3, 2024)));
+        assert_eq!("-0044-03-15".parse(), Ok(fecha(15, 3, -44)));
+        assert_eq!(Fecha::try_from("  2000-02-29 "), Ok(fecha(29, 2, 2000)));
+    }
+
+    #[test]
+    fn test_numerica() {
+        assert_eq!("15/03/2024".parse(), Ok(fecha(15, 3, 2024)));
+        assert_eq!("1/1/1970".parse(), Ok(fecha(1, 1, 1970)));
+        assert_eq!("31/12/-1".parse(), Ok(fecha(31, 12, -1)));
+    }
+
+    #[test]
+    fn test_larga() {
+        assert_eq!("15 de Marzo del 2024".parse(), Ok(fecha(15, 3, 2024)));
+        assert_eq!("15 de marzo de 2024".parse(), Ok(fecha(15, 3, 2024)));
+        assert_eq!("Viernes 15 de Marzo del 2024".parse(), Ok(fecha(15, 3, 2024)));
+        assert_eq!("miércoles, 1 de setiembre de 2021".parse(), Ok(fecha(1, 9, 2021)));
+        assert_eq!("March 15, 2024".parse(), Ok(fecha(15, 3, 2024)));
+        assert_eq!("Friday, March 15, 2024".parse(), Ok(fecha(15, 3, 2024)));
+    }
+
+    #[test]
+    fn test_errores() {
+        assert_eq!("   ".parse::<Fecha>(), Err(ErrorParseoFecha::Vacia));
+        assert!(matches!("ayer".parse::<Fecha>(), Err(ErrorParseoFecha::FormatoDesconocido(_))));
+        assert!(matches!("2024-03".parse::<Fecha>(), Err(ErrorParseoFecha::FormatoDesconocido(_))));
+        assert!(matches!("15/03".parse::<Fecha>(), Err(ErrorParseoFecha::FormatoDesconocido(_))));
+
+        assert_eq!("2024-13-01".parse::<Fecha>(),
+                   Err(ErrorParseoFecha::ComponenteInvalido { componente: ComponenteFecha::Mes, valor: "13".to_string() }));
+        assert_eq!("00/03/2024".parse::<Fecha>(),
+                   Err(ErrorParseoFecha::ComponenteInvalido { componente: ComponenteFecha::Dia, valor: "00".to_string() }));
+        assert_eq!("15/03/20x4".parse::<Fecha>(),
+                   Err(ErrorParseoFecha::ComponenteInvalido { componente: ComponenteFecha::Ano, valor: "20x4".to_string() }));
+        assert_eq!("15 de Marzito del 2024".parse::<Fecha>(),
+                   Err(ErrorParseoFecha::ComponenteInvalido { componente: ComponenteFecha::Mes, valor: "marzito".to_string() }));
+
+        assert_eq!("31/04/2024".parse::<Fecha>(), Err(ErrorParseoFecha::DiaInexistente { dia: 31, mes: 4, ano: 2024 }));
+        assert_eq!("1900-02-29".parse::<Fecha>(), Err(ErrorParseoFecha::DiaInexistente { dia: 29, mes: 2, ano: 1900 }));
+
+        assert_eq!("Lunes 15 de Marzo del 2024".parse::<Fecha>(),
+                   Err(ErrorParseoFecha::DiaSemanaNoCoincide { leido: DiaSemana::Lunes, real: DiaSemana::Viernes }));
+    }
+
+    #[test]
+    fn test_ida_y_vuelta() {
+        let formatos: [&dyn FormatoFecha; 6] = [
+            &FormatoIso, &FormatoNumerico,
+            &FormatoLargo::new(Idioma::Espanol), &FormatoLargo::new(Idioma::Espanol).con_dia_semana(),
+            &FormatoLargo::new(Idioma::Ingles), &FormatoLargo::new(Idioma::Ingles).con_dia_semana(),
+        ];
+
+        for numero in (-1_000_000..1_000_000).step_by(7919) {
+            let original = Fecha::desde_numero_de_dia(numero);
+            for formato in formatos {
+                let texto = formato.formatear(&original);
+                assert_eq!(texto.parse(), Ok(original), "{texto}");
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//
+// rango.rs
+//
+
+use serde::{Deserialize, Serialize};
+use crate::fecha::Fecha;
+use crate::recurrencia::{FinRecurrencia, Ocurrencias, Periodo, Recurrencia};
+
+/// # RangoFechas
+///
+/// Intervalo de días `[inicio, fin]`, con ambos extremos incluidos.<br>
+/// Siempre se cumple `inicio <= fin`: un rango tiene al menos un día.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(try_from = "RangoSerializado")]
+pub struct RangoFechas {
+    inicio: Fecha,
+    fin: Fecha
+}
+
+// solo para validar al deserializar
+#[derive(Deserialize)]
+struct RangoSerializado {
+    inicio: Fecha,
+    fin: Fecha
+}
+
+impl TryFrom<RangoSerializado> for RangoFechas {
+    type Error = String;
+
+    fn try_from(valor: RangoSerializado) -> Result<Self, Self::Error> {
+        RangoFechas::new(valor.inicio, valor.fin)
+            .ok_or_else(|| format!("rango inválido: {} - {}", valor.inicio, valor.fin))
+    }
+}
+
+impl RangoFechas {
+    /// `None` si alguna fecha no es válida o si `fin` es anterior a `inicio`
+    #[must_use]
+    pub fn new(inicio: Fecha, fin: Fecha) -> Option<RangoFechas> {
+        if !inicio.es_fecha_valida() || !fin.es_fecha_valida() || fin < inicio { return None }
+        Some(RangoFechas { inicio, fin })
+    }
+
+    /// Un único día
+    #[must_use]
+    pub fn dia(fecha: Fecha) -> Option<RangoFechas> {
+        RangoFechas::new(fecha, fecha)
+    }
+
+    /// `dias` días a partir de `inicio` (incluido). `None` si `dias` es 0.
+    #[must_use]
+    pub fn desde_dias(inicio: Fecha, dias: u32) -> Option<RangoFechas> {
+        let mut fin = inicio;
+        fin.sumar_dias(dias.checked_sub(1)?);
+        RangoFechas::new(inicio, fin)
+    }
+
+    /// `meses` meses a partir de `inicio`: termina el día anterior al mismo día de `meses` meses después
+    /// (una suscripción de 1 mes desde el 15/03 va hasta el 14/04). `None` si `meses` es 0.
+    #[must_use]
+    pub fn desde_meses(inicio: Fecha, meses: u32) -> Option<RangoFechas> {
+        if meses == 0 { return None }
+        let mut fin = Periodo::Meses(meses).aplicar(inicio, 1)?;
+        fin.restar_dias(1);
+        RangoFechas::new(inicio, fin)
+    }
+
+    #[must_use]
+    pub fn inicio(&self) -> Fecha {
+        self.inicio
+    }
+
+    #[must_use]
+    pub fn fin(&self) -> Fecha {
+        self.fin
+    }
+
+    /// Cantidad de días, contando ambos extremos
+    #[must_use]
+    pub fn cantidad_dias(&self) -> i64 {
+        self.inicio.dias_hasta(&self.fin) + 1
+    }
+
+    #[must_use]
+    pub fn contiene(&self, fecha: &Fecha) -> bool {
+        self.inicio <= *fecha && *fecha <= self.fin
+    }
+
+    #[must_use]
+    pub fn contiene_rango(&self, otro: &RangoFechas) -> bool {
+        self.inicio <= otro.inicio && otro.fin <= self.fin
+    }
+
+    /// Si comparten al menos un día
+    #[must_use]
+    pub fn se_superpone(&self, otro: &RangoFechas) -> bool {
+        self.inicio <= otro.fin && otro.inicio <= self.fin
+    }
+
+    /// Días que están en ambos rangos
+    #[must_use]
+    pub fn interseccion(&self, otro: &RangoFechas) -> Option<RangoFechas> {
+        RangoFechas::new(self.inicio.max(otro.inicio), self.fin.min(otro.fin))
+    }
+
+    /// Desde `inicio`, cada `periodo`, sin pasarse de `fin`
+    #[must_use]
+    pub fn cada(&self, periodo: Periodo) -> Ocurrencias {
+        // periodos de 0 darían un iterador infinito sobre el mismo día
+        let periodo = if periodo.es_nulo() { Periodo::Dias(1) } else { periodo };
+
+        Recurrencia { inicio: self.inicio, periodo, fin: FinRecurrencia::Hasta(self.fin) }.ocurrencias()
+    }
+
+    #[must_use]
+    pub fn por_dia(&self) -> Ocurrencias {
+        self.cada(Periodo::Dias(1))
+    }
+
+    #[must_use]
+    pub fn por_semana(&self) -> Ocurrencias {
+        self.cada(Periodo::Semanas(1))
+    }
+
+    #[must_use]
+    pub fn por_mes(&self) -> Ocurrencias {
+        self.cada(Periodo::Meses(1))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fecha(dia: u8, mes: u8, ano: i64) -> Fecha {
+        Fecha { dia, mes, ano }
+    }
+
+    fn rango(inicio: Fecha, fin: Fecha) -> RangoFechas {
+        let Some(rango) = RangoFechas::new(inicio, fin) else { panic!("{inicio:?} - {fin:?}") };
+        rango
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(RangoFechas::new(fecha(2, 1, 2024), fecha(1, 1, 2024)).is_none());
+        assert!(RangoFechas::new(fecha(1, 1, 2024), fecha(31, 2, 2024)).is_none());
+        assert!(RangoFechas::new(fecha(1, 1, 2024), fecha(1, 1, 2024)).is_some());
+
+        assert_eq!(RangoFechas::desde_dias(fecha(30, 12, 2023), 3), Some(rango(fecha(30, 12, 2023), fecha(1, 1, 2024))));
+        assert_eq!(RangoFechas::desde_dias(fecha(30, 12, 2023), 0), None);
+
+        assert_eq!(RangoFechas::desde_meses(fecha(15, 3, 2024), 1), Some(rango(fecha(15, 3, 2024), fecha(14, 4, 2024))));
+        assert_eq!(RangoFechas::desde_meses(fecha(1, 1, 2024), 12), Some(rango(fecha(1, 1, 2024), fecha(31, 12, 2024))));
+        assert_eq!(RangoFechas::desde_meses(fecha(1, 1, 2024), 0), None);
+    }
+
+    #[test]
+    fn test_contiene() {
+        let marzo = rango(fecha(1, 3, 2024), fecha(31, 3, 2024));
+
+        assert!(marzo.contiene(&fecha(1, 3, 2024)));
+        assert!(marzo.contiene(&fecha(31, 3, 2024)));
+        assert!(!marzo.contiene(&fecha(1, 4, 2024)));
+        assert!(!marzo.contiene(&fecha(29, 2, 2024)));
+        assert_eq!(marzo.cantidad_dias(), 31);
+
+        assert!(marzo.contiene_rango(&rango(fecha(10, 3, 2024), fecha(20, 3, 2024))));
+        assert!(marzo.contiene_rango(&marzo));
+        assert!(!marzo.contiene_rango(&rango(fecha(10, 3, 2024), fecha(1, 4, 2024))));
+    }
+
+    #[test]
+    fn test_superposicion() {
+        let marzo = rango(fecha(1, 3, 2024), fecha(31, 3, 2024));
+        let fin_de_marzo = rango(fecha(25, 3, 2024), fecha(5, 4, 2024));
+        let abril = rango(fecha(1, 4, 2024), fecha(30, 4, 2024));
+
+        assert!(marzo.se_superpone(&fin_de_marzo));
+        assert!(fin_de_marzo.se_superpone(&abril));
+        assert!(!marzo.se_superpone(&abril));
+
+        assert_eq!(marzo.interseccion(&fin_de_marzo), Some(rango(fecha(25, 3, 2024), fecha(31, 3, 2024))));
+        assert_eq!(abril.interseccion(&fin_de_marzo), Some(rango(fecha(1, 4, 2024), fecha(5, 4, 2024))));
+        assert_eq!(marzo.interseccion(&abril), None);
+    }
+
+    #[test]
+    fn test_iteracion() {
+        let rango = rango(fecha(30, 1, 2024), fecha(1, 4, 2024));
+
+        assert_eq!(rango.por_dia().count(), 63);
+        assert_eq!(rango.por_dia().nth(1), Some(fecha(31, 1, 2024)));
+        assert_eq!(rango.por_dia().last(), Some(fecha(1, 4, 2024)));
+
+        assert_eq!(rango.por_semana().count(), 9);
+        assert_eq!(rango.por_semana().nth(1), Some(fecha(6, 2, 2024)));
+
+        let meses: Vec<Fecha> = rango.por_mes().collect();
+        assert_eq!(meses, vec![fecha(30, 1, 2024), fecha(29, 2, 2024), fecha(30, 3, 2024)]);
+
+        assert_eq!(rango.cada(Periodo::Dias(0)).count(), 63);
+    }
+
+    #[test]
+    fn test_serde() {
+        let marzo = rango(fecha(1, 3, 2024), fecha(31, 3, 2024));
+        let json = serde_json::to_string(&marzo).unwrap();
+        assert_eq!(serde_json::from_str::<RangoFechas>(&json).unwrap(), marzo);
+
+        let invertido = r#"{"inicio":{"dia":2,"mes":1,"ano":2024},"fin":{"dia":1,"mes":1,"ano":2024}}"#;
+        assert!(serde_json::from_str::<RangoFechas>(invertido).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+//
+// recurrencia.rs
+//
+
+use serde::{Deserialize, Serialize};
+use crate::fecha::Fecha;
+
+/// Cada cuánto se repite algo
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Periodo {
+    Dias(u32), Semanas(u32), Meses(u32), Anos(u32)
+}
+
+impl Periodo {
+    /// `inicio` desplazado `veces` periodos.
+    ///
+    /// Los meses se cuentan siempre desde `inicio`, así un 31/01 mensual
+    /// da 29/02, 31/03, 30/04... y no queda pegado al 29 después de febrero.
+    #[must_use]
+    pub fn aplicar(self, inicio: Fecha, veces: u32) -> Option<Fecha> {
+        let mut fecha = inicio;
+        match self {
+            Periodo::Dias(n) => fecha.sumar_dias(n.checked_mul(veces)?),
+            Periodo::Semanas(n) => fecha.sumar_dias(n.checked_mul(veces)?.checked_mul(7)?),
+            Periodo::Meses(n) => fecha.sumar_meses(n.checked_mul(veces)?),
+            Periodo::Anos(n) => fecha.sumar_anos(n.checked_mul(veces)?),
+        }
+        Some(fecha)
+    }
+
+    pub(crate) fn es_nulo(self) -> bool {
+        matches!(self, Periodo::Dias(0) | Periodo::Semanas(0) | Periodo::Meses(0) | Periodo::Anos(0))
+    }
+}
+
+/// Hasta cuándo se repite
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FinRecurrencia {
+    Nunca,
+    /// inclusive
+    Hasta(Fecha),
+    Veces(u32)
+}
+
+/// # Recurrencia
+///
+/// "Cada N días/semanas/meses/años desde `inicio`, hasta una fecha o una cantidad de veces".<br>
+/// La primera ocurrencia es `inicio`.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(try_from = "RecurrenciaSerializada")]
+pub struct Recurrencia {
+    pub(crate) inicio: Fecha,
+    pub(crate) periodo: Periodo,
+    pub(crate) fin: FinRecurrencia
+}
+
+// solo para validar al deserializar: un periodo de 0 nunca avanzaría
+#[derive(Deserialize)]
+struct RecurrenciaSerializada {
+    inicio: Fecha,
+    periodo: Periodo,
+    fin: FinRecurrencia
+}
+
+impl TryFrom<RecurrenciaSerializada> for Recurrencia {
+    type Error = String;
+
+    fn try_from(valor: RecurrenciaSerializada) -> Result<Self, Self::Error> {
+        Recurrencia::new(valor.inicio, valor.periodo, valor.fin)
+            .ok_or_else(|| format!("recurrencia inválida: {:?} desde {}", valor.periodo, valor.inicio))
+    }
+}
+
+impl Recurrencia {
+    /// `None` si `inicio` no es válida o el periodo es 0
+    #[must_use]
+    pub fn new(inicio: Fecha, periodo: Periodo, fin: FinRecurrencia) -> Option<Recurrencia> {
+        if !inicio.es_fecha_valida() || periodo.es_nulo() { return None }
+        Some(Recurrencia { inicio, periodo, fin })
+    }
+
+    #[must_use]
+    pub fn inicio(&self) -> Fecha {
+        self.inicio
+    }
+
+    #[must_use]
+    pub fn periodo(&self) -> Periodo {
+        self.periodo
+    }
+
+    #[must_use]
+    pub fn fin(&self) -> FinRecurrencia {
+        self.fin
+    }
+
+    /// Ocurrencia número `indice` (la 0 es `inicio`), si la regla llega hasta ella
+    #[must_use]
+    pub fn ocurrencia(&self, indice: u32) -> Option<Fecha> {
+        if let FinRecurrencia::Veces(veces) = self.fin
+            && indice >= veces
+        { return None }
+
+        let fecha = self.periodo.aplicar(self.inicio, indice)?;
+
+        match self.fin {
+            FinRecurrencia::Hasta(limite) if fecha > limite => None,
+            _ => Some(fecha),
+        }
+    }
+
+    /// Todas las ocurrencias en orden. Con `FinRecurrencia::Nunca` no termina.
+    #[must_use]
+    pub fn ocurrencias(&self) -> Ocurrencias {
+        Ocurrencias { recurrencia: *self, indice: 0 }
+    }
+
+    /// Primera ocurrencia en `desde` o después
+    #[must_use]
+    pub fn siguiente(&self, desde: Fecha) -> Option<Fecha> {
+        self.ocurrencias().find(|fecha| *fecha >= desde)
+    }
+
+    /// Las próximas `cantidad` ocurrencias a partir de `desde` (inclusive)
+    #[must_use]
+    pub fn proximas(&self, desde: Fecha, cantidad: usize) -> Vec<Fecha> {
+        self.ocurrencias()
+            .skip_while(|fecha| *fecha < desde)
+            .take(cantidad)
+            .collect()
+    }
+}
+
+/// Iterador de `Recurrencia::ocurrencias`
+#[derive(Clone, Debug)]
+pub struct Ocurrencias {
+    recurrencia: Recurrencia,
+    indice: u32
+}
+
+impl Iterator for Ocurrencias {
+    type Item = Fecha;
+
+    fn next(&mut self) -> Option<Fecha> {
+        let fecha = self.recurrencia.ocurrencia(self.indice)?;
+        self.indice = self.indice.checked_add(1)?;
+        Some(fecha)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fecha(dia: u8, mes: u8, ano: i64) -> Fecha {
+        Fecha { dia, mes, ano }
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(Recurrencia::new(fecha(1, 1, 2024), Periodo::Dias(0), FinRecurrencia::Nunca).is_none());
+        assert!(Recurrencia::new(fecha(30, 2, 2024), Periodo::Dias(1), FinRecurrencia::Nunca).is_none());
+        assert!(Recurrencia::new(fecha(1, 1, 2024), Periodo::Meses(1), FinRecurrencia::Nunca).is_some());
+    }
+
+    #[test]
+    fn test_veces() {
+        let Some(regla) = Recurrencia::new(fecha(28, 12, 2023), Periodo::Dias(3), FinRecurrencia::Veces(3))
+        else { panic!() };
+
+        let fechas: Vec<Fecha> = regla.ocurrencias().collect();
+        assert_eq!(fechas, vec![fecha(28, 12, 2023), fecha(31, 12, 2023), fecha(3, 1, 2024)]);
+        assert_eq!(regla.ocurrencia(3), None);
+    }
+
+    #[test]
+    fn test_hasta() {
+        let Some(regla) = Recurrencia::new(fecha(1, 3, 2024), Periodo::Semanas(2), FinRecurrencia::Hasta(fecha(29, 3, 2024)))
+        else { panic!() };
+
+        let fechas: Vec<Fecha> = regla.ocurrencias().collect();
+        assert_eq!(fechas, vec![fecha(1, 3, 2024), fecha(15, 3, 2024), fecha(29, 3, 2024)]);
+    }
+
+    #[test]
+    fn test_meses_fin_de_mes() {
+        let Some(regla) = Recurrencia::new(fecha(31, 1, 2024), Periodo::Meses(1), FinRecurrencia::Nunca)
+        else { panic!() };
+
+        let fechas: Vec<Fecha> = regla.ocurrencias().take(4).collect();
+        assert_eq!(fechas, vec![fecha(31, 1, 2024), fecha(29, 2, 2024), fecha(31, 3, 2024), fecha(30, 4, 2024)]);
+
+        let Some(anual) = Recurrencia::new(fecha(29, 2, 2024), Periodo::Anos(1), FinRecurrencia::Veces(5))
+        else { panic!() };
+        assert_eq!(anual.ocurrencia(1), Some(fecha(28, 2, 2025)));
+        assert_eq!(anual.ocurrencia(4), Some(fecha(29, 2, 2028)));
+    }
+
+    #[test]
+    fn test_proximas() {
+        let Some(regla) = Recurrencia::new(fecha(10, 1, 2024), Periodo::Meses(1), FinRecurrencia::Veces(6))
+        else { panic!() };
+
+        assert_eq!(regla.siguiente(fecha(11, 3, 2024)), Some(fecha(10, 4, 2024)));
+        assert_eq!(regla.siguiente(fecha(10, 4, 2024)), Some(fecha(10, 4, 2024)));
+        assert_eq!(regla.siguiente(fecha(11, 6, 2024)), None);
+
+        assert_eq!(regla.proximas(fecha(1, 4, 2024), 2), vec![fecha(10, 4, 2024), fecha(10, 5, 2024)]);
+        assert_eq!(regla.proximas(fecha(1, 6, 2024), 5), vec![fecha(10, 6, 2024)]);
+    }
+
+    #[test]
+    fn test_serde() {
+        let Some(regla) = Recurrencia::new(fecha(10, 1, 2024), Periodo::Meses(1), FinRecurrencia::Veces(6))
+        else { panic!() };
+
+        let json = serde_json::to_string(&regla).unwrap();
+        assert_eq!(serde_json::from_str::<Recurrencia>(&json).unwrap(), regla);
+
+        let nula = json.replace(r#""Meses":1"#, r#""Meses":0"#);
+        assert!(serde_json::from_str::<Recurrencia>(&nula).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//
+// reloj.rs
+//
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering as OrdenAtomico};
+use std::time::Duration;
+use crate::fecha::Fecha;
+use crate::fecha_hora::{FechaHora, MILLIS_POR_DIA, OFFSET_MAXIMO_MINUTOS};
+
+/// # Reloj
+///
+/// De dónde sale "ahora".<br>
+/// Los sistemas guardan un `RelojCompartido` y fechan sus operaciones con él,
+/// en lugar de confiar en la fecha que les pasa quien los llama.
+pub trait Reloj: fmt::Debug + Send + Sync {
+    fn ahora(&self) -> FechaHora;
+
+    /// Día del calendario de `ahora()`, en el huso horario del reloj
+    fn hoy(&self) -> Fecha {
+        self.ahora().fecha()
+    }
+}
+
+/// Reloj del sistema operativo, visto desde el huso horario `offset_minutos`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RelojSistema {
+    pub offset_minutos: i16
+}
+
+impl RelojSistema {
+    /// `None` si el offset supera las 14 horas
+    #[must_use]
+    pub fn new(offset_minutos: i16) -> Option<RelojSistema> {
+        if offset_minutos.abs() > OFFSET_MAXIMO_MINUTOS { return None }
+        Some(RelojSistema { offset_minutos })
+    }
+}
+
+impl Reloj for RelojSistema {
+    fn ahora(&self) -> FechaHora {
+        let ahora = FechaHora::ahora();
+        ahora.con_offset(self.offset_minutos).unwrap_or(ahora)
+    }
+}
+
+/// # RelojFijo
+///
+/// Reloj para tests: marca siempre el mismo instante hasta que se lo avanza o se lo fija en otro.
+///
+/// Las copias (`clone()`) comparten la hora,
+/// así el test puede quedarse con una copia y avanzar el reloj que tiene el sistema.
+#[derive(Clone, Debug)]
+pub struct RelojFijo {
+    millis_desde_epoch: Arc<AtomicI64>,
+    offset_minutos: i16
+}
+
+impl RelojFijo {
+    #[must_use]
+    pub fn new(ahora: FechaHora) -> RelojFijo {
+        RelojFijo {
+            millis_desde_epoch: Arc::new(AtomicI64::new(ahora.millis_desde_epoch)),
+            offset_minutos: ahora.offset_minutos
+        }
+    }
+
+    pub fn fijar(&self, ahora: FechaHora) {
+        self.millis_desde_epoch.store(ahora.millis_desde_epoch, OrdenAtomico::SeqCst);
+    }
+
+    pub fn avanzar(&self, duracion: Duration) {
+        self.fijar(self.ahora() + duracion);
+    }
+
+    pub fn avanzar_dias(&self, dias: u32) {
+        self.millis_desde_epoch.fetch_add(i64::from(dias) * MILLIS_POR_DIA, OrdenAtomico::SeqCst);
+    }
+}
+
+/// Medianoche UTC del día
+impl From<Fecha> for RelojFijo {
+    fn from(fecha: Fecha) -> Self {
+        RelojFijo::new(FechaHora::from(fecha))
+    }
+}
+
+impl Reloj for RelojFijo {
+    fn ahora(&self) -> FechaHora {
+        FechaHora {
+            millis_desde_epoch: self.millis_desde_epoch.load(OrdenAtomico::SeqCst),
+            offset_minutos: self.offset_minutos
+        }
+    }
+}
+
+/// # RelojCompartido
+///
+/// Lo que guardan los sistemas: un `Reloj` cualquiera detrás de un `Arc`.
+///
+/// - `Default` es el reloj del sistema en UTC, así los structs que se deserializan
+///   (con `#[serde(skip)]` en el campo) arrancan con la hora real.
+/// - Para `PartialEq`/`PartialOrd` todos los relojes son iguales:
+///   el reloj no es parte de los datos del sistema.
+#[derive(Clone, Debug)]
+pub struct RelojCompartido(Arc<dyn Reloj>);
+
+impl RelojCompartido {
+    pub fn new(reloj: impl Reloj + 'static) -> RelojCompartido {
+        RelojCompartido(Arc::new(reloj))
+    }
+}
+
+impl Reloj for RelojCompartido {
+    fn ahora(&self) -> FechaHora {
+        self.0.ahora()
+    }
+}
+
+impl Default for RelojCompartido {
+    fn default() -> Self {
+        RelojCompartido::new(RelojSistema::default())
+    }
+}
+
+impl PartialEq for RelojCompartido {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl PartialOrd for RelojCompartido {
+    fn partial_cmp(&self, _: &Self) -> Option<Ordering> {
+        Some(Ordering::Equal)
+    }
+}
+
+impl From<RelojSistema> for RelojCompartido {
+    fn from(reloj: RelojSistema) -> Self {
+        RelojCompartido::new(reloj)
+    }
+}
+
+impl From<RelojFijo> for RelojCompartido {
+    fn from(reloj: RelojFijo) -> Self {
+        RelojCompartido::new(reloj)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reloj_sistema() {
+        let antes = FechaHora::ahora();
+        let Some(reloj) = RelojSistema::new(-180) else { panic!() };
+        let ahora = reloj.ahora();
+        let despues = FechaHora::ahora();
+
+        assert!(antes <= ahora && ahora <= despues);
+        assert_eq!(ahora.offset_minutos, -180);
+        assert!(RelojSistema::new(15 * 60).is_none());
+    }
+
+    #[test]
+    fn test_reloj_fijo() {
+        let Some(fecha) = Fecha::new(31, 12, 2023) else { panic!() };
+        let reloj = RelojFijo::from(fecha);
+
+        assert_eq!(reloj.ahora(), FechaHora::from(fecha));
+        assert_eq!(reloj.ahora(), reloj.ahora(), "No debería avanzar solo");
+        assert_eq!(reloj.hoy(), fecha);
+
+        reloj.avanzar(Duration::from_hours(25));
+        assert_eq!(reloj.hoy(), Fecha { dia: 1, mes: 1, ano: 2024 });
+        assert_eq!(reloj.ahora().hora(), 1);
+
+        reloj.avanzar_dias(30);
+        assert_eq!(reloj.hoy(), Fecha { dia: 31, mes: 1, ano: 2024 });
+
+        reloj.fijar(FechaHora::from(fecha));
+        assert_eq!(reloj.hoy(), fecha);
+    }
+
+    #[test]
+    fn test_reloj_compartido() {
+        let reloj = RelojFijo::from(Fecha { dia: 1, mes: 1, ano: 2024 });
+        let compartido = RelojCompartido::from(reloj.clone());
+
+        // el test avanza su copia y el sistema lo ve
+        reloj.avanzar_dias(1);
+        assert_eq!(compartido.hoy(), Fecha { dia: 2, mes: 1, ano: 2024 });
+
+        assert_eq!(compartido, RelojCompartido::default());
+    }
+}
@@ -0,0 +1,162 @@
+//
+// ventana.rs
+//
+
+use serde::{Deserialize, Serialize};
+use crate::fecha::Fecha;
+use crate::rango::RangoFechas;
+use crate::recurrencia::Periodo;
+
+/// # Ventana
+///
+/// Un rango de días cortado en períodos seguidos, para los reportes que cuentan algo por día, semana o mes.<br>
+/// El primer período empieza con el rango y el último termina con él, aunque quede más corto.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(try_from = "VentanaSerializada")]
+pub struct Ventana {
+    rango: RangoFechas,
+    periodo: Periodo
+}
+
+// solo para validar al deserializar: un periodo de 0 nunca avanzaría
+#[derive(Deserialize)]
+struct VentanaSerializada {
+    rango: RangoFechas,
+    periodo: Periodo
+}
+
+impl TryFrom<VentanaSerializada> for Ventana {
+    type Error = String;
+
+    fn try_from(valor: VentanaSerializada) -> Result<Self, Self::Error> {
+        Ventana::new(valor.rango, valor.periodo)
+            .ok_or_else(|| format!("ventana inválida: {:?} desde {}", valor.periodo, valor.rango.inicio()))
+    }
+}
+
+impl Ventana {
+    /// `None` si el periodo es 0
+    #[must_use]
+    pub fn new(rango: RangoFechas, periodo: Periodo) -> Option<Ventana> {
+        if periodo.es_nulo() { return None }
+        Some(Ventana { rango, periodo })
+    }
+
+    #[must_use]
+    pub fn diaria(rango: RangoFechas) -> Ventana {
+        Ventana { rango, periodo: Periodo::Dias(1) }
+    }
+
+    #[must_use]
+    pub fn semanal(rango: RangoFechas) -> Ventana {
+        Ventana { rango, periodo: Periodo::Semanas(1) }
+    }
+
+    #[must_use]
+    pub fn mensual(rango: RangoFechas) -> Ventana {
+        Ventana { rango, periodo: Periodo::Meses(1) }
+    }
+
+    #[must_use]
+    pub fn rango(&self) -> RangoFechas {
+        self.rango
+    }
+
+    #[must_use]
+    pub fn periodo(&self) -> Periodo {
+        self.periodo
+    }
+
+    #[must_use]
+    pub fn contiene(&self, fecha: &Fecha) -> bool {
+        self.rango.contiene(fecha)
+    }
+
+    /// Los períodos en orden. Cada uno termina el día antes de que empiece el siguiente
+    #[must_use]
+    pub fn periodos(&self) -> Vec<RangoFechas> {
+        let inicios: Vec<Fecha> = self.rango.cada(self.periodo).collect();
+        inicios.iter().enumerate().filter_map(|(i, inicio)| {
+            let fin = inicios.get(i + 1).map_or(self.rango.fin(), |siguiente| {
+                let mut fin = *siguiente;
+                fin.restar_dias(1);
+                fin
+            });
+            RangoFechas::new(*inicio, fin)
+        }).collect()
+    }
+
+    /// En qué período cae `fecha`. `None` si está fuera del rango
+    #[must_use]
+    pub fn periodo_de(&self, fecha: &Fecha) -> Option<RangoFechas> {
+        if !self.contiene(fecha) { return None }
+        self.periodos().into_iter().find(|periodo| periodo.contiene(fecha))
+    }
+
+    /// Reparte `elementos` en los períodos, con el orden en que vienen. Los que caen fuera del rango no están.<br>
+    /// Todos los períodos aparecen, aunque no tengan nada.
+    #[must_use]
+    pub fn agrupar<T>(&self, elementos: impl IntoIterator<Item = (Fecha, T)>) -> Vec<(RangoFechas, Vec<T>)> {
+        let mut grupos: Vec<(RangoFechas, Vec<T>)> = self.periodos().into_iter().map(|periodo| (periodo, Vec::new())).collect();
+        for (fecha, elemento) in elementos {
+            if let Some((_, grupo)) = grupos.iter_mut().find(|(periodo, _)| periodo.contiene(&fecha)) {
+                grupo.push(elemento);
+            }
+        }
+        grupos
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fecha(dia: u8, mes: u8, ano: i64) -> Fecha {
+        Fecha { dia, mes, ano }
+    }
+
+    fn rango(inicio: Fecha, fin: Fecha) -> RangoFechas {
+        let Some(rango) = RangoFechas::new(inicio, fin) else { panic!("{inicio:?} - {fin:?}") };
+        rango
+    }
+
+    #[test]
+    fn test_periodos() {
+        let primer_trimestre = rango(fecha(1, 1, 2024), fecha(31, 3, 2024));
+
+        assert_eq!(Ventana::mensual(primer_trimestre).periodos(), vec![
+            rango(fecha(1, 1, 2024), fecha(31, 1, 2024)),
+            rango(fecha(1, 2, 2024), fecha(29, 2, 2024)),
+            rango(fecha(1, 3, 2024), fecha(31, 3, 2024)),
+        ]);
+        assert_eq!(Ventana::diaria(primer_trimestre).periodos().len(), 91);
+
+        // la última semana queda cortada por el fin del rango
+        let semanas = Ventana::semanal(rango(fecha(1, 3, 2024), fecha(10, 3, 2024))).periodos();
+        assert_eq!(semanas, vec![rango(fecha(1, 3, 2024), fecha(7, 3, 2024)), rango(fecha(8, 3, 2024), fecha(10, 3, 2024))]);
+
+        assert_eq!(Ventana::new(primer_trimestre, Periodo::Semanas(0)), None);
+        assert_eq!(Ventana::new(primer_trimestre, Periodo::Meses(3)).map(|ventana| ventana.periodos()), Some(vec![primer_trimestre]));
+    }
+
+    #[test]
+    fn test_agrupar() {
+        let ventana = Ventana::semanal(rango(fecha(1, 3, 2024), fecha(21, 3, 2024)));
+
+        assert_eq!(ventana.periodo_de(&fecha(9, 3, 2024)), Some(rango(fecha(8, 3, 2024), fecha(14, 3, 2024))));
+        assert_eq!(ventana.periodo_de(&fecha(22, 3, 2024)), None);
+
+        let grupos = ventana.agrupar([(fecha(2, 3, 2024), 'a'), (fecha(29, 2, 2024), 'b'), (fecha(21, 3, 2024), 'c'), (fecha(1, 3, 2024), 'd')]);
+        assert_eq!(grupos.iter().map(|(_, grupo)| grupo.clone()).collect::<Vec<_>>(), vec![vec!['a', 'd'], vec![], vec!['c']]);
+    }
+
+    #[test]
+    fn test_serde() {
+        let ventana = Ventana::mensual(rango(fecha(1, 1, 2024), fecha(31, 3, 2024)));
+        let json = serde_json::to_string(&ventana).unwrap();
+        assert_eq!(serde_json::from_str::<Ventana>(&json).unwrap(), ventana);
+
+        let nula = r#"{"rango":{"inicio":{"dia":1,"mes":1,"ano":2024},"fin":{"dia":2,"mes":1,"ano":2024}},"periodo":{"Dias":0}}"#;
+        assert!(serde_json::from_str::<Ventana>(nula).is_err());
+    }
+}
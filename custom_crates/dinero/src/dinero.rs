@@ -0,0 +1,398 @@
+//
+// dinero.rs
+//
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::error::ErrorDinero;
+use crate::moneda::Moneda;
+use crate::redondeo::Redondeo;
+
+/// # Dinero
+///
+/// Un monto exacto: un entero de unidades mínimas de su moneda (centavos, satoshis, weis).
+///
+/// Toda la aritmética es verificada: devuelve `ErrorDinero` en vez de desbordar,
+/// y nunca mezcla monedas. Las operaciones que pueden no ser exactas piden un `Redondeo`.
+///
+/// Se guarda como texto, con todos los decimales de la moneda: `"13548.00 ARS"`, `"0.50000000 BTC"`.
+///
+/// ```
+/// use dinero::{Dinero, Moneda, Redondeo};
+///
+/// let precio = Dinero::desde_texto("900.00", Moneda::ARS).unwrap();
+/// let cantidad = Dinero::desde_texto("0.5", Moneda::BTC).unwrap();
+///
+/// let total = cantidad.por(precio, Redondeo::HaciaCero).unwrap();
+/// assert_eq!(total.to_string(), "450.00 ARS");
+/// assert_eq!(total.dividir_por_precio(precio, Moneda::BTC, Redondeo::HaciaCero), Ok(cantidad));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct Dinero {
+    unidades: i128,
+    moneda: Moneda
+}
+
+/// Solo se comparan montos de la misma moneda: entre monedas distintas, `partial_cmp` es `None`
+impl PartialOrd for Dinero {
+    fn partial_cmp(&self, otro: &Self) -> Option<Ordering> {
+        (self.moneda == otro.moneda).then(|| self.unidades.cmp(&otro.unidades))
+    }
+}
+
+impl Dinero {
+    /// `unidades` unidades mínimas de `moneda`: `Dinero::new(150, Moneda::ARS)` es $1.50
+    #[must_use]
+    pub fn new(unidades: i128, moneda: Moneda) -> Dinero {
+        Dinero { unidades, moneda }
+    }
+
+    #[must_use]
+    pub fn cero(moneda: Moneda) -> Dinero {
+        Dinero::new(0, moneda)
+    }
+
+    /// Una cantidad entera de la moneda: `Dinero::enteros(3, Moneda::BTC)` son 3 BTC
+    #[must_use]
+    pub fn enteros(cantidad: i64, moneda: Moneda) -> Dinero {
+        // i64::MAX * 10^18 entra en un i128
+        Dinero::new(i128::from(cantidad) * moneda.escala(), moneda)
+    }
+
+    #[must_use]
+    pub fn unidades(&self) -> i128 {
+        self.unidades
+    }
+
+    #[must_use]
+    pub fn moneda(&self) -> Moneda {
+        self.moneda
+    }
+
+    /// Un monto escrito en decimal (`"12.5"`, `"-0.00000001"`), sin la moneda.
+    /// Los ceros de más al final se aceptan; cualquier otro decimal de más es un error.
+    ///
+    /// # Errors
+    /// - `ErrorDinero::Formato` si el texto no es un número decimal
+    /// - `ErrorDinero::Precision` si tiene más decimales de los que admite `moneda`
+    /// - `ErrorDinero::Desbordamiento` si no entra en un `i128`
+    pub fn desde_texto(texto: &str, moneda: Moneda) -> Result<Dinero, ErrorDinero> {
+        let (mantisa, decimales) = parsear_decimal(texto)?;
+
+        if decimales > moneda.decimales() {
+            let sobrante = potencia_de_10(decimales - moneda.decimales());
+            if mantisa % sobrante != 0 { return Err(ErrorDinero::Precision { texto: texto.to_string(), moneda }) }
+        }
+
+        escalar(mantisa, decimales, moneda, Redondeo::HaciaCero)
+    }
+
+    /// Como `desde_texto`, pero los decimales de más se redondean
+    ///
+    /// # Errors
+    /// - `ErrorDinero::Formato` si el texto no es un número decimal
+    /// - `ErrorDinero::Desbordamiento` si no entra en un `i128`
+    pub fn desde_texto_redondeando(texto: &str, moneda: Moneda, redondeo: Redondeo) -> Result<Dinero, ErrorDinero> {
+        let (mantisa, decimales) = parsear_decimal(texto)?;
+        escalar(mantisa, decimales, moneda, redondeo)
+    }
+
+    /// El monto más cercano a lo que el flotante representa en decimal (`0.1` es 0.1, no 0.1000000000000000055...),
+    /// redondeado a los decimales de la moneda. Pensado para leer datos de cuando los montos eran flotantes.
+    ///
+    /// # Errors
+    /// - `ErrorDinero::NoFinito` si `valor` es infinito o `NaN`
+    /// - `ErrorDinero::Desbordamiento` si no entra en un `i128`
+    pub fn desde_f64(valor: f64, moneda: Moneda, redondeo: Redondeo) -> Result<Dinero, ErrorDinero> {
+        if !valor.is_finite() { return Err(ErrorDinero::NoFinito) }
+
+        // Display de f64 es el decimal más corto que vuelve al mismo f64, y nunca usa notación científica
+        Dinero::desde_texto_redondeando(&valor.to_string(), moneda, redondeo)
+    }
+
+    /// El monto como flotante, para mostrar o promediar. No sirve para volver a operar.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn a_f64(&self) -> f64 {
+        self.unidades as f64 / self.moneda.escala() as f64
+    }
+
+    #[must_use]
+    pub fn es_cero(&self) -> bool {
+        self.unidades == 0
+    }
+
+    #[must_use]
+    pub fn es_negativo(&self) -> bool {
+        self.unidades < 0
+    }
+
+    #[must_use]
+    pub fn es_positivo(&self) -> bool {
+        self.unidades > 0
+    }
+
+    /// # Errors
+    /// - `ErrorDinero::MonedasDistintas` si `otro` no es de la misma moneda
+    /// - `ErrorDinero::Desbordamiento`
+    pub fn sumar(self, otro: Dinero) -> Result<Dinero, ErrorDinero> {
+        self.misma_moneda(otro)?;
+        self.con_unidades(self.unidades.checked_add(otro.unidades))
+    }
+
+    /// # Errors
+    /// - `ErrorDinero::MonedasDistintas` si `otro` no es de la misma moneda
+    /// - `ErrorDinero::Desbordamiento`
+    pub fn restar(self, otro: Dinero) -> Result<Dinero, ErrorDinero> {
+        self.misma_moneda(otro)?;
+        self.con_unidades(self.unidades.checked_sub(otro.unidades))
+    }
+
+    /// # Errors
+    /// `ErrorDinero::Desbordamiento`
+    pub fn negar(self) -> Result<Dinero, ErrorDinero> {
+        self.con_unidades(self.unidades.checked_neg())
+    }
+
+    /// # Errors
+    /// `ErrorDinero::Desbordamiento`
+    pub fn multiplicar(self, factor: i128) -> Result<Dinero, ErrorDinero> {
+        self.con_unidades(self.unidades.checked_mul(factor))
+    }
+
+    /// `self * numerador / denominador`: porcentajes, repartos, descuentos...
+    ///
+    /// # Errors
+    /// - `ErrorDinero::DivisionPorCero` si `denominador` es 0
+    /// - `ErrorDinero::Desbordamiento`
+    pub fn fraccion(self, numerador: i128, denominador: i128, redondeo: Redondeo) -> Result<Dinero, ErrorDinero> {
+        if denominador == 0 { return Err(ErrorDinero::DivisionPorCero) }
+
+        let producto = self.unidades.checked_mul(numerador).ok_or(ErrorDinero::Desbordamiento)?;
+        self.con_unidades(redondeo.dividir(producto, denominador))
+    }
+
+    /// Cuánto valen `self` unidades de su moneda, si cada unidad entera vale `precio`.
+    /// El resultado está en la moneda de `precio`: 0.5 BTC a $900 son $450.
+    ///
+    /// # Errors
+    /// `ErrorDinero::Desbordamiento`
+    pub fn por(self, precio: Dinero, redondeo: Redondeo) -> Result<Dinero, ErrorDinero> {
+        let producto = self.unidades.checked_mul(precio.unidades).ok_or(ErrorDinero::Desbordamiento)?;
+        precio.con_unidades(redondeo.dividir(producto, self.moneda.escala()))
+    }
+
+    /// Cuántas unidades de `moneda` se consiguen con `self`, si cada unidad entera de `moneda` vale `precio`.
+    /// Es la inversa de `por`: $450 a $900 el BTC son 0.5 BTC.
+    ///
+    /// # Errors
+    /// - `ErrorDinero::MonedasDistintas` si `precio` no está en la moneda de `self`
+    /// - `ErrorDinero::DivisionPorCero` si `precio` es 0
+    /// - `ErrorDinero::Desbordamiento`
+    pub fn dividir_por_precio(self, precio: Dinero, moneda: Moneda, redondeo: Redondeo) -> Result<Dinero, ErrorDinero> {
+        self.misma_moneda(precio)?;
+        if precio.es_cero() { return Err(ErrorDinero::DivisionPorCero) }
+
+        let escalado = self.unidades.checked_mul(moneda.escala()).ok_or(ErrorDinero::Desbordamiento)?;
+        Dinero::cero(moneda).con_unidades(redondeo.dividir(escalado, precio.unidades))
+    }
+
+    /// La suma de todos los montos, que tienen que ser de `moneda`. Sin montos, cero.
+    ///
+    /// # Errors
+    /// - `ErrorDinero::MonedasDistintas` si algún monto no es de `moneda`
+    /// - `ErrorDinero::Desbordamiento`
+    pub fn sumar_todos(moneda: Moneda, montos: impl IntoIterator<Item = Dinero>) -> Result<Dinero, ErrorDinero> {
+        montos.into_iter().try_fold(Dinero::cero(moneda), Dinero::sumar)
+    }
+
+    fn misma_moneda(self, otro: Dinero) -> Result<(), ErrorDinero> {
+        if self.moneda == otro.moneda { Ok(()) }
+        else { Err(ErrorDinero::MonedasDistintas { esperada: self.moneda, encontrada: otro.moneda }) }
+    }
+
+    fn con_unidades(self, unidades: Option<i128>) -> Result<Dinero, ErrorDinero> {
+        unidades.map(|unidades| Dinero::new(unidades, self.moneda)).ok_or(ErrorDinero::Desbordamiento)
+    }
+}
+
+// "-12.345" → (-12345, 3)
+fn parsear_decimal(texto: &str) -> Result<(i128, u32), ErrorDinero> {
+    let error_formato = || ErrorDinero::Formato { texto: texto.to_string() };
+
+    let (negativo, numero) = match texto.strip_prefix('-') {
+        Some(numero) => (true, numero),
+        None => (false, texto)
+    };
+    let (enteros, fraccion) = numero.split_once('.').unwrap_or((numero, ""));
+    let es_numero = |digitos: &str| digitos.bytes().all(|byte| byte.is_ascii_digit());
+
+    if enteros.is_empty() || !es_numero(enteros) || !es_numero(fraccion) || (numero.contains('.') && fraccion.is_empty()) {
+        return Err(error_formato())
+    }
+
+    let mut mantisa: i128 = 0;
+    for byte in enteros.bytes().chain(fraccion.bytes()) {
+        mantisa = mantisa.checked_mul(10)
+            .and_then(|mantisa| mantisa.checked_add(i128::from(byte - b'0')))
+            .ok_or(ErrorDinero::Desbordamiento)?;
+    }
+
+    let decimales = u32::try_from(fraccion.len()).map_err(|_| error_formato())?;
+    Ok((if negativo { -mantisa } else { mantisa }, decimales))
+}
+
+// lleva mantisa * 10^-decimales a unidades mínimas de moneda
+fn escalar(mantisa: i128, decimales: u32, moneda: Moneda, redondeo: Redondeo) -> Result<Dinero, ErrorDinero> {
+    let unidades = if decimales <= moneda.decimales() {
+        mantisa.checked_mul(potencia_de_10(moneda.decimales() - decimales))
+    } else {
+        redondeo.dividir(mantisa, potencia_de_10(decimales - moneda.decimales()))
+    };
+
+    unidades.map(|unidades| Dinero::new(unidades, moneda)).ok_or(ErrorDinero::Desbordamiento)
+}
+
+// 10^exponente, o i128::MAX si no entra: dividir por eso ya redondea igual que por el número real,
+// porque la mantisa de un texto que se pudo leer siempre es menor
+fn potencia_de_10(exponente: u32) -> i128 {
+    10i128.checked_pow(exponente).unwrap_or(i128::MAX)
+}
+
+/// Con todos los decimales de la moneda y su código: `13548.00 ARS`
+impl fmt::Display for Dinero {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let escala = self.moneda.escala().unsigned_abs();
+        let signo = if self.es_negativo() { "-" } else { "" };
+        let absoluto = self.unidades.unsigned_abs();
+        let decimales = self.moneda.decimales() as usize;
+
+        write!(f, "{signo}{}.{:0decimales$} {}", absoluto / escala, absoluto % escala, self.moneda)
+    }
+}
+
+/// Lo que escribe `Display`: un monto, un espacio y el código de la moneda
+impl FromStr for Dinero {
+    type Err = ErrorDinero;
+
+    fn from_str(texto: &str) -> Result<Self, Self::Err> {
+        let Some((monto, codigo)) = texto.split_once(' ') else { return Err(ErrorDinero::Formato { texto: texto.to_string() }) };
+        Dinero::desde_texto(monto, codigo.parse()?)
+    }
+}
+
+impl TryFrom<String> for Dinero {
+    type Error = ErrorDinero;
+
+    fn try_from(texto: String) -> Result<Self, Self::Error> {
+        texto.parse()
+    }
+}
+
+impl From<Dinero> for String {
+    fn from(dinero: Dinero) -> Self {
+        dinero.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ars(texto: &str) -> Dinero {
+        Dinero::desde_texto(texto, Moneda::ARS).unwrap()
+    }
+
+    #[test]
+    fn test_texto() {
+        assert_eq!(ars("12.5"), Dinero::new(1250, Moneda::ARS));
+        assert_eq!(ars("-0.05").unidades(), -5);
+        assert_eq!(ars("7"), Dinero::enteros(7, Moneda::ARS));
+        assert_eq!(ars("12.500"), ars("12.5"), "Los ceros de más no cambian el monto");
+
+        assert_eq!(Dinero::desde_texto("12.345", Moneda::ARS), Err(ErrorDinero::Precision { texto: "12.345".to_string(), moneda: Moneda::ARS }));
+        assert_eq!(Dinero::desde_texto_redondeando("12.345", Moneda::ARS, Redondeo::MitadPar), Ok(ars("12.34")));
+        for texto in ["", "-", "1.", ".5", "1.2.3", "1,5", "+1", " 1", "1e3"] {
+            assert_eq!(Dinero::desde_texto(texto, Moneda::ARS), Err(ErrorDinero::Formato { texto: texto.to_string() }), "'{texto}'");
+        }
+        assert_eq!(Dinero::desde_texto("1000000000000000000000", Moneda::ETH), Err(ErrorDinero::Desbordamiento));
+
+        // Display y FromStr van y vuelven
+        let eth = Dinero::new(-1, Moneda::ETH);
+        assert_eq!(eth.to_string(), "-0.000000000000000001 ETH");
+        assert_eq!(eth.to_string().parse(), Ok(eth));
+        assert_eq!(Dinero::enteros(3, Moneda::BTC).to_string(), "3.00000000 BTC");
+        assert_eq!("12.50".parse::<Dinero>(), Err(ErrorDinero::Formato { texto: "12.50".to_string() }));
+        assert!(matches!("12.50 USD".parse::<Dinero>(), Err(ErrorDinero::MonedaDesconocida { .. })));
+    }
+
+    #[test]
+    fn test_f64() {
+        assert_eq!(Dinero::desde_f64(0.1, Moneda::ARS, Redondeo::HaciaCero), Ok(ars("0.1")));
+        assert_eq!(Dinero::desde_f64(0.1 + 0.2, Moneda::ARS, Redondeo::MitadLejosDeCero), Ok(ars("0.3")));
+        assert_eq!(Dinero::desde_f64(0.1 + 0.2, Moneda::ETH, Redondeo::MitadLejosDeCero).map(|eth| eth.unidades()), Ok(300_000_000_000_000_040));
+        assert_eq!(Dinero::desde_f64(1e-300, Moneda::ARS, Redondeo::HaciaArriba), Ok(ars("0.01")));
+        assert_eq!(Dinero::desde_f64(1e-300, Moneda::ARS, Redondeo::MitadPar), Ok(ars("0")));
+        assert_eq!(Dinero::desde_f64(f64::NAN, Moneda::ARS, Redondeo::HaciaCero), Err(ErrorDinero::NoFinito));
+        assert_eq!(Dinero::desde_f64(1e300, Moneda::ARS, Redondeo::HaciaCero), Err(ErrorDinero::Desbordamiento));
+
+        // lo que con f64 no da
+        let diez_centavos = Dinero::sumar_todos(Moneda::ARS, std::iter::repeat_n(ars("0.1"), 10));
+        assert_eq!(diez_centavos, Ok(ars("1")));
+        assert!((ars("1234.56").a_f64() - 1234.56).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_aritmetica() {
+        assert_eq!(ars("10").sumar(ars("0.5")), Ok(ars("10.5")));
+        assert_eq!(ars("10").restar(ars("10.5")), Ok(ars("-0.5")));
+        assert_eq!(ars("10").sumar(Dinero::enteros(1, Moneda::BTC)),
+                   Err(ErrorDinero::MonedasDistintas { esperada: Moneda::ARS, encontrada: Moneda::BTC }));
+        assert_eq!(Dinero::new(i128::MAX, Moneda::ARS).sumar(ars("0.01")), Err(ErrorDinero::Desbordamiento));
+        assert_eq!(Dinero::new(i128::MIN, Moneda::ARS).negar(), Err(ErrorDinero::Desbordamiento));
+        assert_eq!(ars("1.5").multiplicar(3), Ok(ars("4.5")));
+
+        // 15% de descuento sobre $9.99
+        assert_eq!(ars("9.99").fraccion(85, 100, Redondeo::MitadLejosDeCero), Ok(ars("8.49")));
+        assert_eq!(ars("9.99").fraccion(85, 100, Redondeo::HaciaArriba), Ok(ars("8.50")));
+        assert_eq!(ars("1").fraccion(1, 0, Redondeo::HaciaCero), Err(ErrorDinero::DivisionPorCero));
+
+        // comparar solo entre la misma moneda
+        assert!(ars("1") < ars("1.01"));
+        assert_eq!(ars("1").partial_cmp(&Dinero::enteros(1, Moneda::BTC)), None);
+        assert_eq!(Dinero::sumar_todos(Moneda::ARS, []), Ok(Dinero::cero(Moneda::ARS)));
+    }
+
+    #[test]
+    fn test_conversiones() {
+        let precio_eth = ars("10");
+        let eth = Dinero::desde_texto("0.000000000000000001", Moneda::ETH).unwrap();
+
+        // 1 wei a $10 el ETH no llega a un centavo
+        assert_eq!(eth.por(precio_eth, Redondeo::HaciaCero), Ok(ars("0")));
+        assert_eq!(eth.por(precio_eth, Redondeo::HaciaArriba), Ok(ars("0.01")));
+
+        // $2000 a $3 el BTC
+        let btc = ars("2000").dividir_por_precio(ars("3"), Moneda::BTC, Redondeo::HaciaCero).unwrap();
+        assert_eq!(btc.to_string(), "666.66666666 BTC");
+        assert_eq!(btc.por(ars("3"), Redondeo::HaciaCero), Ok(ars("1999.99")));
+
+        assert_eq!(ars("1").dividir_por_precio(ars("0"), Moneda::BTC, Redondeo::HaciaCero), Err(ErrorDinero::DivisionPorCero));
+        assert!(matches!(ars("1").dividir_por_precio(btc, Moneda::ETH, Redondeo::HaciaCero), Err(ErrorDinero::MonedasDistintas { .. })));
+        assert_eq!(Dinero::new(i128::MAX, Moneda::ETH).por(precio_eth, Redondeo::HaciaCero), Err(ErrorDinero::Desbordamiento));
+    }
+
+    #[test]
+    fn test_serde() {
+        let monto = ars("13548");
+        assert_eq!(serde_json::to_string(&monto).unwrap(), r#""13548.00 ARS""#);
+        assert_eq!(serde_json::from_str::<Dinero>(r#""13548.00 ARS""#).unwrap(), monto);
+        assert_eq!(serde_json::from_str::<Dinero>(r#""0.5 BTC""#).unwrap(), Dinero::new(50_000_000, Moneda::BTC));
+
+        assert!(serde_json::from_str::<Dinero>("13548.0").is_err(), "Un número no es un monto");
+        assert!(serde_json::from_str::<Dinero>(r#""0.001 ARS""#).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+//
+// error.rs
+//
+
+use std::fmt;
+use crate::moneda::Moneda;
+
+/// # ErrorDinero
+///
+/// Una operación con montos que no se pudo hacer de forma exacta.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorDinero {
+    /// El resultado no entra en un `i128` de unidades mínimas
+    Desbordamiento,
+    /// Se operó con montos de monedas distintas
+    MonedasDistintas { esperada: Moneda, encontrada: Moneda },
+    DivisionPorCero,
+    /// El texto no es un monto (`"12.50"`, `"-3"`, `"12.50 ARS"`...)
+    Formato { texto: String },
+    /// El monto tiene más decimales de los que admite la moneda
+    Precision { texto: String, moneda: Moneda },
+    MonedaDesconocida { codigo: String },
+    /// El código no sirve para una moneda (ver `Moneda::registrar`)
+    CodigoInvalido { codigo: String },
+    DemasiadosDecimales { codigo: String, decimales: u32 },
+    /// Se quiso registrar con otros decimales una moneda que ya existe
+    MonedaYaRegistrada { moneda: Moneda },
+    /// Un flotante infinito o `NaN`
+    NoFinito
+}
+
+impl fmt::Display for ErrorDinero {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorDinero::Desbordamiento => write!(f, "el monto es demasiado grande"),
+            ErrorDinero::MonedasDistintas { esperada, encontrada } => write!(f, "se esperaba un monto en {esperada}, no en {encontrada}"),
+            ErrorDinero::DivisionPorCero => write!(f, "división por cero"),
+            ErrorDinero::Formato { texto } => write!(f, "'{texto}' no es un monto"),
+            ErrorDinero::Precision { texto, moneda } =>
+                write!(f, "'{texto}' tiene más de los {} decimales de {moneda}", moneda.decimales()),
+            ErrorDinero::MonedaDesconocida { codigo } => write!(f, "'{codigo}' no es una moneda conocida"),
+            ErrorDinero::CodigoInvalido { codigo } => write!(f, "'{codigo}' no es un código de moneda"),
+            ErrorDinero::DemasiadosDecimales { codigo, decimales } =>
+                write!(f, "{codigo} no puede tener {decimales} decimales, el máximo es {}", crate::moneda::DECIMALES_MAXIMOS),
+            ErrorDinero::MonedaYaRegistrada { moneda } => write!(f, "{moneda} ya existe, con {} decimales", moneda.decimales()),
+            ErrorDinero::NoFinito => write!(f, "el número no es finito"),
+        }
+    }
+}
+
+impl std::error::Error for ErrorDinero {}
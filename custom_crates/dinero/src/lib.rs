@@ -0,0 +1,19 @@
+// Montos de dinero exactos, compartidos por todos los sistemas que manejan plata.
+//
+// Antes cada sistema usaba su propio flotante: `Balance(f64)` en XYZ, `Precio(f64)` en StreamingRust,
+// `f32` en Comercio. Sumar 0.1 diez veces no daba 1, y `Balance` hasta implementaba `Hash` sobre un f64.
+// Ahora un monto es un entero de unidades mínimas (centavos, satoshis, weis) con su moneda.
+
+#![deny(clippy::pedantic)]
+// los títulos de los docs (`/// # Dinero`) son nombres, no código
+#![allow(clippy::doc_markdown)]
+
+pub mod dinero;
+pub mod error;
+pub mod moneda;
+pub mod redondeo;
+
+pub use dinero::Dinero;
+pub use error::ErrorDinero;
+pub use moneda::Moneda;
+pub use redondeo::Redondeo;
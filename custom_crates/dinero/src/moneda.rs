@@ -0,0 +1,199 @@
+//
+// moneda.rs
+//
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::error::ErrorDinero;
+
+/// El código más largo que puede tener una moneda
+pub const LARGO_MAXIMO_CODIGO: usize = 8;
+
+/// Los decimales que puede tener una moneda como mucho: con más, `Dinero::enteros` podría no entrar en un `i128`
+pub const DECIMALES_MAXIMOS: u32 = 18;
+
+// las monedas registradas con `Moneda::registrar`, además de las de siempre
+static REGISTRADAS: RwLock<BTreeMap<Codigo, u32>> = RwLock::new(BTreeMap::new());
+
+type Codigo = [u8; LARGO_MAXIMO_CODIGO];
+
+/// # Moneda
+///
+/// Una moneda, con su cantidad de decimales: un monto de una moneda es un entero de sus unidades mínimas.
+///
+/// Se identifica por su código: letras mayúsculas y dígitos, `"ARS"`, `"BTC"`, `"USDT"`.
+/// `ARS`, `BTC` y `ETH` existen siempre; cualquier otra se registra con `Moneda::registrar`, una vez por proceso,
+/// antes de leer montos en ella.
+///
+/// Se guarda como su código.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct Moneda {
+    // el orden de los campos es el de las monedas: por código
+    codigo: Codigo, // completado con ceros
+    decimales: u32
+}
+
+impl Moneda {
+    /// Peso argentino: 2 decimales (centavos)
+    pub const ARS: Moneda = Moneda::nueva(b"ARS", 2);
+    /// Bitcoin: 8 decimales (satoshis)
+    pub const BTC: Moneda = Moneda::nueva(b"BTC", 8);
+    /// Ether: 18 decimales (weis)
+    pub const ETH: Moneda = Moneda::nueva(b"ETH", 18);
+
+    /// Las que existen sin registrarlas
+    pub const PREDEFINIDAS: [Moneda; 3] = [Moneda::ARS, Moneda::BTC, Moneda::ETH];
+
+    // solo para códigos que ya se sabe que son válidos
+    const fn nueva(codigo: &[u8], decimales: u32) -> Moneda {
+        let mut completo = [0; LARGO_MAXIMO_CODIGO];
+        let mut i = 0;
+        while i < codigo.len() {
+            completo[i] = codigo[i];
+            i += 1;
+        }
+        Moneda { codigo: completo, decimales }
+    }
+
+    /// Agrega una moneda a las conocidas, para que se puedan leer sus montos: `Moneda::registrar("USDT", 6)`.
+    /// Registrar otra vez una que ya existe, con los mismos decimales, no hace nada.
+    ///
+    /// # Errors
+    /// - `ErrorDinero::CodigoInvalido` si el código no son de 1 a `LARGO_MAXIMO_CODIGO` mayúsculas o dígitos
+    /// - `ErrorDinero::DemasiadosDecimales` si tiene más de `DECIMALES_MAXIMOS`
+    /// - `ErrorDinero::MonedaYaRegistrada` si ya existe con otros decimales
+    pub fn registrar(codigo: &str, decimales: u32) -> Result<Moneda, ErrorDinero> {
+        let es_valido = (1..=LARGO_MAXIMO_CODIGO).contains(&codigo.len())
+            && codigo.bytes().all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit());
+        if !es_valido { return Err(ErrorDinero::CodigoInvalido { codigo: codigo.to_string() }) }
+        if decimales > DECIMALES_MAXIMOS { return Err(ErrorDinero::DemasiadosDecimales { codigo: codigo.to_string(), decimales }) }
+
+        let moneda = Moneda::nueva(codigo.as_bytes(), decimales);
+        if let Some(existente) = Moneda::desde_codigo(codigo) {
+            return if existente == moneda { Ok(moneda) } else { Err(ErrorDinero::MonedaYaRegistrada { moneda: existente }) }
+        }
+
+        // un lock envenenado solo puede tener el mapa como estaba antes o después de un insert
+        let mut registradas = REGISTRADAS.write().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let decimales = *registradas.entry(moneda.codigo).or_insert(decimales);
+        // si otro hilo la registró entre medio, con otros decimales
+        if decimales == moneda.decimales { Ok(moneda) } else { Err(ErrorDinero::MonedaYaRegistrada { moneda: Moneda { decimales, ..moneda } }) }
+    }
+
+    /// Las predefinidas y las registradas, por código
+    #[must_use]
+    pub fn conocidas() -> Vec<Moneda> {
+        let registradas = REGISTRADAS.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+        let mut monedas: Vec<Moneda> = Moneda::PREDEFINIDAS.into_iter()
+            .chain(registradas.iter().map(|(&codigo, &decimales)| Moneda { codigo, decimales }))
+            .collect();
+        monedas.sort();
+        monedas
+    }
+
+    #[must_use]
+    pub fn decimales(self) -> u32 {
+        self.decimales
+    }
+
+    /// Cuántas unidades mínimas tiene una unidad entera: 100 para ARS
+    #[must_use]
+    pub fn escala(self) -> i128 {
+        10i128.pow(self.decimales())
+    }
+
+    #[must_use]
+    pub fn codigo(&self) -> &str {
+        let largo = self.codigo.iter().position(|&byte| byte == 0).unwrap_or(LARGO_MAXIMO_CODIGO);
+        // solo se arman desde códigos ASCII
+        std::str::from_utf8(&self.codigo[..largo]).unwrap_or_default()
+    }
+
+    /// `None` si no es una moneda conocida
+    #[must_use]
+    pub fn desde_codigo(codigo: &str) -> Option<Moneda> {
+        if let Some(moneda) = Moneda::PREDEFINIDAS.into_iter().find(|moneda| moneda.codigo() == codigo) { return Some(moneda) }
+        if codigo.len() > LARGO_MAXIMO_CODIGO { return None }
+
+        let completo = Moneda::nueva(codigo.as_bytes(), 0).codigo;
+        let registradas = REGISTRADAS.read().unwrap_or_else(std::sync::PoisonError::into_inner);
+        registradas.get(&completo).map(|&decimales| Moneda { codigo: completo, decimales })
+    }
+}
+
+/// Solo el código: `ARS`
+impl fmt::Debug for Moneda {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.codigo())
+    }
+}
+
+impl fmt::Display for Moneda {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.codigo())
+    }
+}
+
+impl FromStr for Moneda {
+    type Err = ErrorDinero;
+
+    fn from_str(codigo: &str) -> Result<Self, Self::Err> {
+        Moneda::desde_codigo(codigo).ok_or_else(|| ErrorDinero::MonedaDesconocida { codigo: codigo.to_string() })
+    }
+}
+
+impl TryFrom<String> for Moneda {
+    type Error = ErrorDinero;
+
+    fn try_from(codigo: String) -> Result<Self, Self::Error> {
+        codigo.parse()
+    }
+}
+
+impl From<Moneda> for String {
+    fn from(moneda: Moneda) -> Self {
+        moneda.codigo().to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_codigos() {
+        for moneda in Moneda::PREDEFINIDAS {
+            assert_eq!(moneda.codigo().parse(), Ok(moneda));
+            assert_eq!(serde_json::to_string(&moneda).unwrap(), format!("\"{}\"", moneda.codigo()));
+        }
+
+        assert_eq!(Moneda::ETH.escala(), 1_000_000_000_000_000_000);
+        assert_eq!("USD".parse::<Moneda>(), Err(ErrorDinero::MonedaDesconocida { codigo: "USD".to_string() }));
+        assert_eq!(Moneda::desde_codigo("ars"), None);
+        assert!(Moneda::ARS < Moneda::BTC && Moneda::BTC < Moneda::ETH);
+    }
+
+    #[test]
+    fn test_registrar() {
+        let Ok(usdc) = Moneda::registrar("USDC", 6) else { panic!("Debería registrarse") };
+        assert_eq!((usdc.codigo(), usdc.decimales()), ("USDC", 6));
+        assert_eq!("USDC".parse(), Ok(usdc));
+        assert_eq!(serde_json::from_str::<Moneda>(r#""USDC""#).unwrap(), usdc);
+        assert_eq!(Moneda::registrar("USDC", 6), Ok(usdc), "Registrarla otra vez igual no es un error");
+        assert!(Moneda::conocidas().contains(&usdc));
+
+        // un código es de una sola moneda
+        assert_eq!(Moneda::registrar("USDC", 2), Err(ErrorDinero::MonedaYaRegistrada { moneda: usdc }));
+        assert_eq!(Moneda::registrar("BTC", 2), Err(ErrorDinero::MonedaYaRegistrada { moneda: Moneda::BTC }));
+
+        for codigo in ["", "usdc", "USD-C", "DEMASIADOLARGO"] {
+            assert_eq!(Moneda::registrar(codigo, 2), Err(ErrorDinero::CodigoInvalido { codigo: codigo.to_string() }), "'{codigo}'");
+        }
+        assert_eq!(Moneda::registrar("WEI", 19), Err(ErrorDinero::DemasiadosDecimales { codigo: "WEI".to_string(), decimales: 19 }));
+        assert_eq!(Moneda::desde_codigo("WEI"), None);
+    }
+}
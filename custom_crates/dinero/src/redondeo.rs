@@ -0,0 +1,79 @@
+//
+// redondeo.rs
+//
+
+/// # Redondeo
+///
+/// Qué hacer cuando un resultado cae entre dos unidades mínimas.
+/// Toda operación que puede no ser exacta (convertir, dividir, aplicar un porcentaje) pide uno explícitamente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Redondeo {
+    /// Descarta lo que sobra: 2.7 → 2, -2.7 → -2
+    HaciaCero,
+    /// 2.7 → 2, -2.2 → -3
+    HaciaAbajo,
+    /// 2.2 → 3, -2.7 → -2
+    HaciaArriba,
+    /// Al más cercano; en el medio, lejos del cero: 2.5 → 3, -2.5 → -3
+    MitadLejosDeCero,
+    /// Al más cercano; en el medio, al par (redondeo bancario): 2.5 → 2, 3.5 → 4
+    MitadPar
+}
+
+impl Redondeo {
+    /// `numerador / denominador`, redondeado a un entero.
+    /// `None` si el denominador es cero o el resultado no entra en un `i128`.
+    #[must_use]
+    pub fn dividir(self, numerador: i128, denominador: i128) -> Option<i128> {
+        let cociente = numerador.checked_div(denominador)?;
+        let resto = numerador % denominador;
+        if resto == 0 { return Some(cociente) }
+
+        let negativo = (numerador < 0) != (denominador < 0);
+        let alejar = || if negativo { cociente.checked_sub(1) } else { cociente.checked_add(1) };
+
+        // |resto| < |denominador|, así que el doble entra en un u128
+        let doble_resto = resto.unsigned_abs() * 2;
+        let mitad = doble_resto.cmp(&denominador.unsigned_abs());
+
+        match self {
+            Redondeo::HaciaCero => Some(cociente),
+            Redondeo::HaciaAbajo => if negativo { alejar() } else { Some(cociente) },
+            Redondeo::HaciaArriba => if negativo { Some(cociente) } else { alejar() },
+            Redondeo::MitadLejosDeCero => if mitad.is_ge() { alejar() } else { Some(cociente) },
+            Redondeo::MitadPar => if mitad.is_gt() || (mitad.is_eq() && cociente % 2 != 0) { alejar() } else { Some(cociente) },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dividir() {
+        let casos = [
+            // numerador, denominador, cero, abajo, arriba, mitad lejos, mitad par
+            (27, 10, 2, 2, 3, 3, 3),
+            (-27, 10, -2, -3, -2, -3, -3),
+            (22, 10, 2, 2, 3, 2, 2),
+            (25, 10, 2, 2, 3, 3, 2),
+            (35, 10, 3, 3, 4, 4, 4),
+            (-25, 10, -2, -3, -2, -3, -2),
+            (25, -10, -2, -3, -2, -3, -2),
+            (30, 10, 3, 3, 3, 3, 3),
+        ];
+
+        for (numerador, denominador, cero, abajo, arriba, mitad_lejos, mitad_par) in casos {
+            assert_eq!(Redondeo::HaciaCero.dividir(numerador, denominador), Some(cero), "{numerador}/{denominador}");
+            assert_eq!(Redondeo::HaciaAbajo.dividir(numerador, denominador), Some(abajo), "{numerador}/{denominador}");
+            assert_eq!(Redondeo::HaciaArriba.dividir(numerador, denominador), Some(arriba), "{numerador}/{denominador}");
+            assert_eq!(Redondeo::MitadLejosDeCero.dividir(numerador, denominador), Some(mitad_lejos), "{numerador}/{denominador}");
+            assert_eq!(Redondeo::MitadPar.dividir(numerador, denominador), Some(mitad_par), "{numerador}/{denominador}");
+        }
+
+        assert_eq!(Redondeo::HaciaCero.dividir(1, 0), None);
+        assert_eq!(Redondeo::HaciaCero.dividir(i128::MIN, -1), None);
+        assert_eq!(Redondeo::MitadPar.dividir(i128::MAX, i128::MAX - 1), Some(1));
+    }
+}
@@ -0,0 +1,149 @@
+//
+// almacen.rs
+//
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::{Component, Path};
+use std::sync::Arc;
+use serde_json::Value;
+use crate::archivos::AlmacenArchivos;
+use crate::carga::Carga;
+use crate::error::ErrorPersistencia;
+
+/// # Almacen
+///
+/// Dónde se guardan los documentos: cada uno es un valor JSON identificado por una clave
+/// (`"veterinaria_Pepe_atenciones"`, `"xyz"`...).<br>
+/// Los sistemas no lo usan directamente sino a través de un `Documento`, que se encarga de los tipos.
+pub trait Almacen: fmt::Debug + Send + Sync {
+    /// # Errors
+    /// `ErrorPersistencia::NoExiste` si no hay nada guardado con esa clave
+    fn leer(&self, clave: &str) -> Result<Value, ErrorPersistencia>;
+
+    /// `leer`, junto con lo que haya salido mal sin llegar a ser un error.
+    /// Por defecto no hay advertencias: solo un almacén con respaldos puede recuperarse de un documento roto.
+    ///
+    /// # Errors
+    /// Los mismos que `leer`
+    fn leer_con_advertencias(&self, clave: &str) -> Result<Carga<Value>, ErrorPersistencia> {
+        self.leer(clave).map(Carga::limpia)
+    }
+
+    /// Guarda `valor`, reemplazando lo que hubiera con esa clave
+    ///
+    /// # Errors
+    /// Si la clave es inválida o no se pudo guardar
+    fn escribir(&self, clave: &str, valor: &Value) -> Result<(), ErrorPersistencia>;
+
+    fn existe(&self, clave: &str) -> bool;
+
+    /// Eliminar algo que no existe no es un error
+    ///
+    /// # Errors
+    /// Si la clave es inválida o no se pudo eliminar
+    fn eliminar(&self, clave: &str) -> Result<(), ErrorPersistencia>;
+}
+
+/// Una clave es un nombre de archivo relativo: puede tener subcarpetas, pero no salir de la carpeta del almacén
+pub(crate) fn validar_clave(clave: &str) -> Result<(), ErrorPersistencia> {
+    let invalida = |motivo: &str| Err(ErrorPersistencia::Ruta { clave: clave.to_string(), motivo: motivo.to_string() });
+
+    if clave.trim().is_empty() { return invalida("está vacía") }
+    if clave.ends_with('/') || clave.ends_with('\\') { return invalida("termina en una carpeta") }
+
+    let ruta = Path::new(clave);
+    if ruta.has_root() { return invalida("es una ruta absoluta") }
+    if ruta.components().any(|componente| !matches!(componente, Component::Normal(_) | Component::CurDir)) {
+        return invalida("sale de la carpeta del almacén")
+    }
+
+    Ok(())
+}
+
+/// # AlmacenCompartido
+///
+/// Lo que guardan los sistemas: un `Almacen` cualquiera detrás de un `Arc`.
+///
+/// - `Default` son archivos JSON en la carpeta de datos configurada (ver `Configuracion`),
+///   o en la carpeta actual, como hacían todos los sistemas antes.
+/// - Para `PartialEq`/`PartialOrd` todos los almacenes son iguales:
+///   dónde se guarda no es parte de los datos del sistema.
+#[derive(Clone, Debug)]
+pub struct AlmacenCompartido(Arc<dyn Almacen>);
+
+impl AlmacenCompartido {
+    pub fn new(almacen: impl Almacen + 'static) -> AlmacenCompartido {
+        AlmacenCompartido(Arc::new(almacen))
+    }
+}
+
+impl Almacen for AlmacenCompartido {
+    fn leer(&self, clave: &str) -> Result<Value, ErrorPersistencia> {
+        self.0.leer(clave)
+    }
+
+    fn leer_con_advertencias(&self, clave: &str) -> Result<Carga<Value>, ErrorPersistencia> {
+        self.0.leer_con_advertencias(clave)
+    }
+
+    fn escribir(&self, clave: &str, valor: &Value) -> Result<(), ErrorPersistencia> {
+        self.0.escribir(clave, valor)
+    }
+
+    fn existe(&self, clave: &str) -> bool {
+        self.0.existe(clave)
+    }
+
+    fn eliminar(&self, clave: &str) -> Result<(), ErrorPersistencia> {
+        self.0.eliminar(clave)
+    }
+}
+
+impl Default for AlmacenCompartido {
+    fn default() -> Self {
+        AlmacenCompartido::new(AlmacenArchivos::default())
+    }
+}
+
+impl PartialEq for AlmacenCompartido {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl PartialOrd for AlmacenCompartido {
+    fn partial_cmp(&self, _: &Self) -> Option<Ordering> {
+        Some(Ordering::Equal)
+    }
+}
+
+impl From<AlmacenArchivos> for AlmacenCompartido {
+    fn from(almacen: AlmacenArchivos) -> Self {
+        AlmacenCompartido::new(almacen)
+    }
+}
+
+impl From<crate::memoria::AlmacenMemoria> for AlmacenCompartido {
+    fn from(almacen: crate::memoria::AlmacenMemoria) -> Self {
+        AlmacenCompartido::new(almacen)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validar_clave() {
+        assert!(validar_clave("xyz").is_ok());
+        assert!(validar_clave("Pepe's Pet Shop_libros").is_ok());
+        assert!(validar_clave("sistemas/xyz").is_ok());
+        assert!(validar_clave("./xyz").is_ok());
+
+        for clave in ["", "   ", "/xyz", "../xyz", "a/../../xyz", "carpeta/"] {
+            let Err(ErrorPersistencia::Ruta { clave: leida, .. }) = validar_clave(clave) else { panic!("'{clave}' no debería ser válida") };
+            assert_eq!(leida, clave);
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//
+// archivos.rs
+//
+
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde_json::Value;
+use crate::almacen::{validar_clave, Almacen};
+use crate::carga::{Advertencia, Carga};
+use crate::configuracion::Configuracion;
+use crate::error::ErrorPersistencia;
+
+/// Cuántas versiones anteriores de cada documento se guardan si no se indica otra cosa
+pub const RESPALDOS_POR_DEFECTO: usize = 3;
+
+/// # AlmacenArchivos
+///
+/// Un archivo `<clave>.json` por documento, dentro de `carpeta`.
+/// Las carpetas que falten se crean al escribir.<br>
+/// `Default` es la carpeta de la `Configuracion` (`SDL_DATA_DIR` o `sdl_config.json`),
+/// o la carpeta actual si no hay ninguna. Una configuración rota también cae en la carpeta actual:
+/// para enterarse del error, usar `AlmacenArchivos::desde_configuracion`.
+///
+/// Las escrituras nunca dejan un archivo a medio escribir: se escribe `<clave>.json.tmp`,
+/// se fuerza al disco y recién ahí se renombra sobre `<clave>.json`.<br>
+/// Antes de reemplazarlo, la versión anterior pasa a ser `<clave>.json.1.bak`
+/// (y la `.1.bak` pasa a `.2.bak`, etc.), hasta `respaldos` generaciones.
+/// Si el principal está roto, `leer` carga el respaldo válido más reciente y lo avisa.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AlmacenArchivos {
+    carpeta: PathBuf,
+    respaldos: usize
+}
+
+impl Default for AlmacenArchivos {
+    fn default() -> Self {
+        Configuracion::desde_entorno().unwrap_or_default().almacen()
+    }
+}
+
+impl AlmacenArchivos {
+    pub fn new(carpeta: impl Into<PathBuf>) -> AlmacenArchivos {
+        AlmacenArchivos { carpeta: carpeta.into(), respaldos: RESPALDOS_POR_DEFECTO }
+    }
+
+    /// El almacén de `Configuracion::desde_entorno`
+    ///
+    /// # Errors
+    /// Si el archivo de configuración existe pero no se puede leer o no es válido
+    pub fn desde_configuracion() -> Result<AlmacenArchivos, ErrorPersistencia> {
+        Configuracion::desde_entorno().map(|configuracion| configuracion.almacen())
+    }
+
+    /// Cuántas generaciones anteriores guardar. 0 no guarda ninguna.
+    #[must_use]
+    pub fn con_respaldos(mut self, respaldos: usize) -> AlmacenArchivos {
+        self.respaldos = respaldos;
+        self
+    }
+
+    #[must_use]
+    pub fn carpeta(&self) -> &PathBuf {
+        &self.carpeta
+    }
+
+    #[must_use]
+    pub fn respaldos(&self) -> usize {
+        self.respaldos
+    }
+
+    /// Archivo donde se guarda `clave`
+    ///
+    /// # Errors
+    /// `ErrorPersistencia::Ruta` si la clave no es válida
+    pub fn ruta(&self, clave: &str) -> Result<PathBuf, ErrorPersistencia> {
+        validar_clave(clave)?;
+        Ok(self.carpeta.join(format!("{clave}.json")))
+    }
+
+    /// Archivo donde se guarda la `generacion`-ésima versión anterior de `clave` (1 es la más reciente)
+    ///
+    /// # Errors
+    /// `ErrorPersistencia::Ruta` si la clave no es válida
+    pub fn ruta_respaldo(&self, clave: &str, generacion: usize) -> Result<PathBuf, ErrorPersistencia> {
+        validar_clave(clave)?;
+        Ok(self.carpeta.join(format!("{clave}.json.{generacion}.bak")))
+    }
+
+    fn leer_archivo(ruta: &Path, clave: &str) -> Result<Value, ErrorPersistencia> {
+        let texto = match fs::read_to_string(ruta) {
+            Ok(texto) => texto,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Err(ErrorPersistencia::NoExiste { clave: clave.to_string() }),
+            Err(error) => return Err(ErrorPersistencia::io(ruta, &error))
+        };
+
+        serde_json::from_str(&texto)
+            .map_err(|error| ErrorPersistencia::Deserializacion { clave: clave.to_string(), mensaje: error.to_string() })
+    }
+
+    // escribe en un temporal al lado de `ruta` y lo renombra encima:
+    // el rename es atómico, así que `ruta` queda con la versión vieja o con la nueva, nunca a medias
+    fn escribir_atomico(&self, ruta: &Path, clave: &str, texto: &str) -> Result<(), ErrorPersistencia> {
+        if let Some(carpeta) = ruta.parent().filter(|carpeta| !carpeta.as_os_str().is_empty()) {
+            fs::create_dir_all(carpeta).map_err(|error| ErrorPersistencia::io(carpeta, &error))?;
+        }
+
+        let temporal = ruta.with_extension("json.tmp");
+        let error_io = |error: io::Error| ErrorPersistencia::io(&temporal, &error);
+
+        let mut archivo = File::create(&temporal).map_err(error_io)?;
+        archivo.write_all(texto.as_bytes()).map_err(error_io)?;
+        archivo.sync_all().map_err(error_io)?;
+        drop(archivo);
+
+        if let Err(error) = self.rotar_respaldos(ruta, clave) {
+            let _ = fs::remove_file(&temporal);
+            return Err(error)
+        }
+
+        fs::rename(&temporal, ruta).map_err(|error| ErrorPersistencia::io(ruta, &error))?;
+        sincronizar_carpeta(ruta)
+    }
+
+    // .2.bak -> .3.bak, .1.bak -> .2.bak, principal -> .1.bak (copia: el principal sigue en su lugar hasta el rename)
+    fn rotar_respaldos(&self, ruta: &Path, clave: &str) -> Result<(), ErrorPersistencia> {
+        if self.respaldos == 0 || !ruta.is_file() { return Ok(()) }
+
+        for generacion in (1..self.respaldos).rev() {
+            let anterior = self.ruta_respaldo(clave, generacion)?;
+            if !anterior.is_file() { continue }
+
+            let siguiente = self.ruta_respaldo(clave, generacion + 1)?;
+            fs::rename(&anterior, &siguiente).map_err(|error| ErrorPersistencia::io(&anterior, &error))?;
+        }
+
+        let primero = self.ruta_respaldo(clave, 1)?;
+        fs::copy(ruta, &primero).map_err(|error| ErrorPersistencia::io(&primero, &error))?;
+        Ok(())
+    }
+}
+
+// el rename recién es definitivo cuando la carpeta llega al disco.
+// En Windows no se puede abrir una carpeta como archivo, y NTFS ya registra el rename.
+#[cfg(unix)]
+fn sincronizar_carpeta(ruta: &Path) -> Result<(), ErrorPersistencia> {
+    let carpeta = match ruta.parent() {
+        Some(carpeta) if !carpeta.as_os_str().is_empty() => carpeta,
+        _ => Path::new(".")
+    };
+
+    File::open(carpeta).and_then(|carpeta| carpeta.sync_all())
+        .map_err(|error| ErrorPersistencia::io(carpeta, &error))
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::unnecessary_wraps)]
+fn sincronizar_carpeta(_: &Path) -> Result<(), ErrorPersistencia> {
+    Ok(())
+}
+
+impl Almacen for AlmacenArchivos {
+    fn leer(&self, clave: &str) -> Result<Value, ErrorPersistencia> {
+        self.leer_con_advertencias(clave).map(|carga| carga.datos)
+    }
+
+    fn leer_con_advertencias(&self, clave: &str) -> Result<Carga<Value>, ErrorPersistencia> {
+        let ruta = self.ruta(clave)?;
+
+        let error = match AlmacenArchivos::leer_archivo(&ruta, clave) {
+            Ok(valor) => return Ok(Carga::limpia(valor)),
+            Err(error @ ErrorPersistencia::Deserializacion { .. }) => error,
+            Err(error) => return Err(error)
+        };
+
+        // principal roto: el respaldo válido más reciente, si hay alguno
+        for generacion in 1..=self.respaldos {
+            let respaldo = self.ruta_respaldo(clave, generacion)?;
+
+            if let Ok(valor) = AlmacenArchivos::leer_archivo(&respaldo, clave) {
+                let advertencia = Advertencia::RespaldoUsado { clave: clave.to_string(), generacion, ruta: respaldo, error };
+                return Ok(Carga { datos: valor, advertencias: vec![advertencia] })
+            }
+        }
+
+        Err(error)
+    }
+
+    fn escribir(&self, clave: &str, valor: &Value) -> Result<(), ErrorPersistencia> {
+        let ruta = self.ruta(clave)?;
+
+        let texto = serde_json::to_string_pretty(valor)
+            .map_err(|error| ErrorPersistencia::Serializacion { clave: clave.to_string(), mensaje: error.to_string() })?;
+
+        self.escribir_atomico(&ruta, clave, &texto)
+    }
+
+    fn existe(&self, clave: &str) -> bool {
+        self.ruta(clave).is_ok_and(|ruta| ruta.is_file())
+    }
+
+    // se lleva también los respaldos: si no, un documento nuevo con la misma clave podría "recuperar" datos borrados
+    fn eliminar(&self, clave: &str) -> Result<(), ErrorPersistencia> {
+        let mut rutas = vec![self.ruta(clave)?];
+        for generacion in 1..=self.respaldos {
+            rutas.push(self.ruta_respaldo(clave, generacion)?);
+        }
+
+        for ruta in rutas {
+            match fs::remove_file(&ruta) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(ErrorPersistencia::io(&ruta, &error)),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use crate::temporal::CarpetaTemporal;
+    use super::*;
+
+    #[test]
+    fn test_leer_escribir() {
+        let temporal = CarpetaTemporal::new("leer_escribir");
+        let carpeta = temporal.ruta().join("carpeta").join("que_no_existe");
+        let almacen = AlmacenArchivos::new(&carpeta);
+
+        assert!(!almacen.existe("datos"));
+        assert_eq!(almacen.leer("datos"), Err(ErrorPersistencia::NoExiste { clave: "datos".to_string() }));
+
+        let valor = json!({ "usuarios": [1, 2, 3] });
+        assert_eq!(almacen.escribir("datos", &valor), Ok(()));
+        assert!(almacen.existe("datos"));
+        assert!(carpeta.join("datos.json").is_file(), "Las carpetas que faltan se crean");
+        assert!(!carpeta.join("datos.json.tmp").exists(), "El temporal se renombra, no queda dando vueltas");
+        assert_eq!(almacen.leer("datos"), Ok(valor));
+
+        assert_eq!(almacen.eliminar("datos"), Ok(()));
+        assert!(!almacen.existe("datos"));
+        assert_eq!(almacen.eliminar("datos"), Ok(()), "Eliminar algo que no existe no es un error");
+
+        assert_eq!(almacen.escribir("sistemas/xyz", &json!(1)), Ok(()));
+        assert!(carpeta.join("sistemas").join("xyz.json").is_file());
+    }
+
+    #[test]
+    fn test_errores() {
+        let carpeta = CarpetaTemporal::new("errores");
+        let almacen = carpeta.almacen();
+
+        fs::write(carpeta.ruta().join("roto.json"), "{invalid json").unwrap();
+        assert!(matches!(almacen.leer("roto"), Err(ErrorPersistencia::Deserializacion { .. })));
+
+        let Err(ErrorPersistencia::Io { .. }) = carpeta.almacen_inaccesible().escribir("datos", &json!(1))
+        else { panic!("La carpeta es un archivo") };
+
+        assert!(matches!(almacen.escribir("/absoluta", &json!(1)), Err(ErrorPersistencia::Ruta { .. })));
+        assert!(matches!(almacen.leer("../afuera"), Err(ErrorPersistencia::Ruta { .. })));
+        assert!(!almacen.existe(""));
+    }
+
+    #[test]
+    fn test_respaldos() {
+        let temporal = CarpetaTemporal::new("respaldos");
+        let carpeta = temporal.ruta();
+        let almacen = temporal.almacen().con_respaldos(2);
+
+        for version in 1..=4 {
+            assert_eq!(almacen.escribir("datos", &json!({ "version": version })), Ok(()));
+        }
+
+        let leer = |archivo: &str| serde_json::from_str::<Value>(&fs::read_to_string(carpeta.join(archivo)).unwrap()).unwrap();
+        assert_eq!(leer("datos.json"), json!({ "version": 4 }));
+        assert_eq!(leer("datos.json.1.bak"), json!({ "version": 3 }));
+        assert_eq!(leer("datos.json.2.bak"), json!({ "version": 2 }));
+        assert!(!carpeta.join("datos.json.3.bak").exists(), "Solo se guardan 2 generaciones");
+
+        // se corta la luz a mitad de escritura... sin rename atómico, quedaría así
+        fs::write(carpeta.join("datos.json"), "{\"version\": ").unwrap();
+        let Ok(carga) = almacen.leer_con_advertencias("datos") else { panic!("Debería recuperar el respaldo") };
+        assert_eq!(carga.datos, json!({ "version": 3 }));
+        let [Advertencia::RespaldoUsado { generacion: 1, error: ErrorPersistencia::Deserializacion { .. }, .. }] = carga.advertencias.as_slice()
+        else { panic!("Debería avisar que usó el respaldo 1: {:?}", carga.advertencias) };
+
+        // si el respaldo más reciente también está roto, sigue con el siguiente
+        fs::write(carpeta.join("datos.json.1.bak"), "").unwrap();
+        assert_eq!(almacen.leer("datos"), Ok(json!({ "version": 2 })));
+
+        // sin ningún respaldo válido, el error es el del principal
+        fs::write(carpeta.join("datos.json.2.bak"), "[").unwrap();
+        assert!(matches!(almacen.leer("datos"), Err(ErrorPersistencia::Deserializacion { .. })));
+
+        assert_eq!(almacen.eliminar("datos"), Ok(()));
+        assert_eq!(fs::read_dir(carpeta).unwrap().count(), 0, "Eliminar se lleva los respaldos");
+    }
+
+    #[test]
+    fn test_sin_respaldos() {
+        let temporal = CarpetaTemporal::new("sin_respaldos");
+        let carpeta = temporal.ruta();
+        let almacen = temporal.almacen().con_respaldos(0);
+
+        assert_eq!(almacen.escribir("datos", &json!(1)), Ok(()));
+        assert_eq!(almacen.escribir("datos", &json!(2)), Ok(()));
+        assert_eq!(fs::read_dir(carpeta).unwrap().count(), 1);
+
+        fs::write(carpeta.join("datos.json"), "{").unwrap();
+        assert!(matches!(almacen.leer("datos"), Err(ErrorPersistencia::Deserializacion { .. })));
+    }
+}
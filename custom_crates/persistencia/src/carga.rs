@@ -0,0 +1,47 @@
+//
+// carga.rs
+//
+
+use std::fmt;
+use std::path::PathBuf;
+use crate::error::ErrorPersistencia;
+
+/// # Advertencia
+///
+/// Algo salió mal al cargar, pero igual se pudieron recuperar datos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Advertencia {
+    /// El documento principal estaba roto y se cargó un respaldo.
+    /// `generacion` 1 es el respaldo más reciente.
+    RespaldoUsado { clave: String, generacion: usize, ruta: PathBuf, error: ErrorPersistencia }
+}
+
+impl fmt::Display for Advertencia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Advertencia::RespaldoUsado { clave, ruta, error, .. } =>
+                write!(f, "'{clave}' no se pudo leer ({error}), se cargó el respaldo '{}'", ruta.display()),
+        }
+    }
+}
+
+/// # Carga
+///
+/// Lo que se leyó, junto con las advertencias que hubo al leerlo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Carga<T> {
+    pub datos: T,
+    pub advertencias: Vec<Advertencia>
+}
+
+impl<T> Carga<T> {
+    /// Una carga sin advertencias
+    pub fn limpia(datos: T) -> Carga<T> {
+        Carga { datos, advertencias: Vec::new() }
+    }
+
+    #[must_use]
+    pub fn tiene_advertencias(&self) -> bool {
+        !self.advertencias.is_empty()
+    }
+}
@@ -0,0 +1,121 @@
+//
+// configuracion.rs
+//
+
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::archivos::{AlmacenArchivos, RESPALDOS_POR_DEFECTO};
+use crate::error::ErrorPersistencia;
+
+/// Variable de entorno con la carpeta donde guardan sus datos todos los sistemas
+pub const VARIABLE_CARPETA_DATOS: &str = "SDL_DATA_DIR";
+
+/// Archivo de configuración, buscado en la carpeta actual
+pub const ARCHIVO_CONFIGURACION: &str = "sdl_config.json";
+
+fn respaldos_por_defecto() -> usize {
+    RESPALDOS_POR_DEFECTO
+}
+
+/// # Configuracion
+///
+/// Dónde y cómo guardan sus datos los sistemas que usan `AlmacenArchivos::default()`.
+///
+/// Se arma, de menor a mayor prioridad, con:
+/// 1. los valores por defecto: la carpeta actual y `RESPALDOS_POR_DEFECTO` respaldos
+/// 2. `sdl_config.json`, si existe: `{ "carpeta_datos": "datos/", "respaldos": 5 }` (los dos campos son opcionales)
+/// 3. la variable de entorno `SDL_DATA_DIR`, que pisa a `carpeta_datos`
+///
+/// Para elegir la carpeta desde el código, se le pasa el almacén al sistema (`new_con_almacen`, `con_almacen`...).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Configuracion {
+    pub carpeta_datos: PathBuf,
+    #[serde(default = "respaldos_por_defecto")]
+    pub respaldos: usize
+}
+
+impl Default for Configuracion {
+    fn default() -> Self {
+        Configuracion { carpeta_datos: PathBuf::new(), respaldos: RESPALDOS_POR_DEFECTO }
+    }
+}
+
+impl Configuracion {
+    /// La configuración de `sdl_config.json` y `SDL_DATA_DIR`
+    ///
+    /// # Errors
+    /// Si `sdl_config.json` existe pero no se puede leer o no es una configuración válida
+    pub fn desde_entorno() -> Result<Configuracion, ErrorPersistencia> {
+        Configuracion::resolver(Path::new(ARCHIVO_CONFIGURACION), std::env::var_os(VARIABLE_CARPETA_DATOS))
+    }
+
+    /// Lee un archivo de configuración. Un archivo que no existe es la configuración por defecto.
+    ///
+    /// # Errors
+    /// Si el archivo no se puede leer o no es una configuración válida
+    pub fn leer(ruta: &Path) -> Result<Configuracion, ErrorPersistencia> {
+        let texto = match fs::read_to_string(ruta) {
+            Ok(texto) => texto,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Configuracion::default()),
+            Err(error) => return Err(ErrorPersistencia::io(ruta, &error))
+        };
+
+        serde_json::from_str(&texto).map_err(|error| ErrorPersistencia::Deserializacion {
+            clave: ruta.display().to_string(),
+            mensaje: error.to_string()
+        })
+    }
+
+    // separado de desde_entorno para poder probarlo sin tocar el entorno del proceso
+    fn resolver(archivo: &Path, variable: Option<OsString>) -> Result<Configuracion, ErrorPersistencia> {
+        let mut configuracion = Configuracion::leer(archivo)?;
+
+        if let Some(carpeta) = variable.filter(|carpeta| !carpeta.is_empty()) {
+            configuracion.carpeta_datos = PathBuf::from(carpeta);
+        }
+
+        Ok(configuracion)
+    }
+
+    #[must_use]
+    pub fn almacen(&self) -> AlmacenArchivos {
+        AlmacenArchivos::new(&self.carpeta_datos).con_respaldos(self.respaldos)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::temporal::CarpetaTemporal;
+    use super::*;
+
+    #[test]
+    fn test_resolver() {
+        let carpeta = CarpetaTemporal::new("configuracion");
+        let archivo = carpeta.ruta().join(ARCHIVO_CONFIGURACION);
+
+        // sin archivo ni variable: la carpeta actual
+        assert_eq!(Configuracion::resolver(&archivo, None), Ok(Configuracion::default()));
+        assert_eq!(Configuracion::default().almacen(), AlmacenArchivos::new(""));
+
+        fs::write(&archivo, r#"{ "carpeta_datos": "datos/sdl" }"#).unwrap();
+        let Ok(configuracion) = Configuracion::resolver(&archivo, None) else { panic!("Configuración válida") };
+        assert_eq!(configuracion.carpeta_datos, PathBuf::from("datos/sdl"));
+        assert_eq!(configuracion.respaldos, RESPALDOS_POR_DEFECTO);
+
+        // la variable pisa al archivo; vacía es como si no estuviera
+        let Ok(configuracion) = Configuracion::resolver(&archivo, Some("/var/sdl".into())) else { panic!() };
+        assert_eq!(configuracion.almacen().carpeta(), &PathBuf::from("/var/sdl"));
+        let Ok(configuracion) = Configuracion::resolver(&archivo, Some("".into())) else { panic!() };
+        assert_eq!(configuracion.carpeta_datos, PathBuf::from("datos/sdl"));
+
+        fs::write(&archivo, r#"{ "respaldos": 0 }"#).unwrap();
+        assert_eq!(Configuracion::resolver(&archivo, None).map(|configuracion| configuracion.almacen()), Ok(AlmacenArchivos::new("").con_respaldos(0)));
+
+        fs::write(&archivo, r#"{ "carpeta_datos": 5 }"#).unwrap();
+        assert!(matches!(Configuracion::resolver(&archivo, None), Err(ErrorPersistencia::Deserializacion { .. })));
+    }
+}
@@ -0,0 +1,59 @@
+//
+// error.rs
+//
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// # ErrorPersistencia
+///
+/// Todo lo que puede salir mal al leer o guardar un documento, con el contexto suficiente
+/// para saber qué documento era y por qué falló.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorPersistencia {
+    /// No hay nada guardado con esa clave
+    NoExiste { clave: String },
+    /// La clave no sirve para ubicar un documento (vacía, absoluta o con `..`)
+    Ruta { clave: String, motivo: String },
+    /// Falló el sistema de archivos
+    Io { ruta: PathBuf, tipo: io::ErrorKind, mensaje: String },
+    /// Los datos no se pudieron convertir a JSON
+    Serializacion { clave: String, mensaje: String },
+    /// Lo guardado no es JSON válido o no tiene la forma esperada
+    Deserializacion { clave: String, mensaje: String },
+    /// El documento tiene una versión de formato que este programa no sabe leer
+    Version { clave: String, encontrada: u32, esperada: u32 },
+    /// Falló la migración de la versión `desde` a la siguiente
+    Migracion { clave: String, desde: u32, mensaje: String }
+}
+
+impl ErrorPersistencia {
+    pub(crate) fn io(ruta: &Path, error: &io::Error) -> ErrorPersistencia {
+        ErrorPersistencia::Io { ruta: ruta.to_path_buf(), tipo: error.kind(), mensaje: error.to_string() }
+    }
+
+    /// Si el error es solo que todavía no se guardó nada
+    #[must_use]
+    pub fn es_no_existe(&self) -> bool {
+        matches!(self, ErrorPersistencia::NoExiste { .. })
+    }
+}
+
+impl fmt::Display for ErrorPersistencia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorPersistencia::NoExiste { clave } => write!(f, "no existe el documento '{clave}'"),
+            ErrorPersistencia::Ruta { clave, motivo } => write!(f, "clave inválida '{clave}': {motivo}"),
+            ErrorPersistencia::Io { ruta, mensaje, .. } => write!(f, "error de E/S en '{}': {mensaje}", ruta.display()),
+            ErrorPersistencia::Serializacion { clave, mensaje } => write!(f, "no se pudo serializar '{clave}': {mensaje}"),
+            ErrorPersistencia::Deserializacion { clave, mensaje } => write!(f, "no se pudo leer '{clave}': {mensaje}"),
+            ErrorPersistencia::Version { clave, encontrada, esperada } =>
+                write!(f, "'{clave}' está en la versión {encontrada} del formato, se esperaba la {esperada}"),
+            ErrorPersistencia::Migracion { clave, desde, mensaje } =>
+                write!(f, "no se pudo migrar '{clave}' de la versión {desde} a la {}: {mensaje}", desde + 1),
+        }
+    }
+}
+
+impl std::error::Error for ErrorPersistencia {}
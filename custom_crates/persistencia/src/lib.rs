@@ -0,0 +1,29 @@
+// Persistencia compartida por todos los sistemas de la práctica 5.
+//
+// Antes cada sistema guardaba su JSON a su manera, cada uno con su propio error:
+// `ResultSobreescribirArchivo`, `ErrorSobreescribirArchivo`, `FileError`, `ResultArchivoAtenciones`, un bool...
+// Ahora todos guardan `Documento`s en un `Almacen` y fallan con `ErrorPersistencia`.
+
+#![deny(clippy::pedantic)]
+// los títulos de los docs (`/// # Almacen`) son nombres, no código
+#![allow(clippy::doc_markdown)]
+
+pub mod almacen;
+pub mod archivos;
+pub mod carga;
+pub mod configuracion;
+pub mod error;
+pub mod memoria;
+pub mod migraciones;
+pub mod repositorio;
+pub mod temporal;
+
+pub use almacen::{Almacen, AlmacenCompartido};
+pub use archivos::{AlmacenArchivos, RESPALDOS_POR_DEFECTO};
+pub use carga::{Advertencia, Carga};
+pub use configuracion::{Configuracion, ARCHIVO_CONFIGURACION, VARIABLE_CARPETA_DATOS};
+pub use error::ErrorPersistencia;
+pub use memoria::AlmacenMemoria;
+pub use migraciones::{Migracion, Migraciones, VERSION_SIN_SOBRE};
+pub use repositorio::{Documento, Repositorio};
+pub use temporal::CarpetaTemporal;
@@ -0,0 +1,88 @@
+//
+// memoria.rs
+//
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use serde_json::Value;
+use crate::almacen::{validar_clave, Almacen};
+use crate::error::ErrorPersistencia;
+
+/// # AlmacenMemoria
+///
+/// Almacén para tests: no toca el disco, así los tests no se pisan los archivos entre sí.
+///
+/// Las copias (`clone()`) comparten los documentos,
+/// así el test puede quedarse con una copia y revisar lo que guardó el sistema.
+#[derive(Clone, Debug, Default)]
+pub struct AlmacenMemoria {
+    documentos: Arc<Mutex<HashMap<String, Value>>>
+}
+
+impl AlmacenMemoria {
+    #[must_use]
+    pub fn new() -> AlmacenMemoria {
+        AlmacenMemoria::default()
+    }
+
+    // un test que entró en pánico con el lock tomado no debería romper a los demás
+    fn documentos(&self) -> MutexGuard<'_, HashMap<String, Value>> {
+        self.documentos.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Claves guardadas, ordenadas
+    #[must_use]
+    pub fn claves(&self) -> Vec<String> {
+        let mut claves: Vec<String> = self.documentos().keys().cloned().collect();
+        claves.sort();
+        claves
+    }
+}
+
+impl Almacen for AlmacenMemoria {
+    fn leer(&self, clave: &str) -> Result<Value, ErrorPersistencia> {
+        validar_clave(clave)?;
+        self.documentos().get(clave).cloned()
+            .ok_or_else(|| ErrorPersistencia::NoExiste { clave: clave.to_string() })
+    }
+
+    fn escribir(&self, clave: &str, valor: &Value) -> Result<(), ErrorPersistencia> {
+        validar_clave(clave)?;
+        self.documentos().insert(clave.to_string(), valor.clone());
+        Ok(())
+    }
+
+    fn existe(&self, clave: &str) -> bool {
+        self.documentos().contains_key(clave)
+    }
+
+    fn eliminar(&self, clave: &str) -> Result<(), ErrorPersistencia> {
+        validar_clave(clave)?;
+        self.documentos().remove(clave);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn test_memoria() {
+        let almacen = AlmacenMemoria::new();
+        let copia = almacen.clone();
+
+        assert!(almacen.leer("a").is_err_and(|error| error.es_no_existe()));
+
+        assert_eq!(almacen.escribir("b", &json!([1])), Ok(()));
+        assert_eq!(almacen.escribir("a", &json!({ "x": 1 })), Ok(()));
+        assert_eq!(copia.leer("a"), Ok(json!({ "x": 1 })), "Las copias comparten los documentos");
+        assert_eq!(copia.claves(), vec!["a".to_string(), "b".to_string()]);
+
+        assert_eq!(copia.eliminar("a"), Ok(()));
+        assert!(!almacen.existe("a"));
+
+        assert!(matches!(almacen.escribir("../a", &json!(1)), Err(ErrorPersistencia::Ruta { .. })));
+    }
+}
@@ -0,0 +1,197 @@
+//
+// migraciones.rs
+//
+
+use std::collections::BTreeMap;
+use std::fmt;
+use serde_json::{Map, Value};
+use crate::error::ErrorPersistencia;
+
+/// Convierte los datos de un documento de la versión `n` a la `n + 1`
+pub type Migracion = fn(Value) -> Result<Value, String>;
+
+/// Versión de los documentos guardados antes de que existiera el sobre `{ "version", "data" }`
+pub const VERSION_SIN_SOBRE: u32 = 0;
+
+/// # Migraciones
+///
+/// La versión actual del formato de un documento y cómo llegar a ella desde las anteriores.
+///
+/// En disco, los documentos se guardan dentro de un sobre: `{ "version": n, "data": ... }`.
+/// Al leer uno de una versión anterior, se aplican en orden las migraciones registradas
+/// (de `n` a `n + 1`, de `n + 1` a `n + 2`...) hasta llegar a la actual.
+/// Si falta algún paso, o el documento es de una versión más nueva que la actual,
+/// la carga falla con `ErrorPersistencia::Version`: nunca se reemplaza por datos vacíos.
+///
+/// Un documento sin sobre es de la versión `VERSION_SIN_SOBRE`.
+/// `Default` es la versión 1, igual al formato que había antes de los sobres.
+///
+/// ```
+/// use persistencia::Migraciones;
+/// use serde_json::Value;
+///
+/// // v2: `precio` pasó de pesos a centavos
+/// fn precio_en_centavos(mut libro: Value) -> Result<Value, String> {
+///     let precio = libro["precio"].as_f64().ok_or("el libro no tiene precio")?;
+///     libro["precio"] = Value::from((precio * 100.0).round() as i64);
+///     Ok(libro)
+/// }
+///
+/// let migraciones = Migraciones::default().con_migracion(1, precio_en_centavos);
+/// assert_eq!(migraciones.version_actual(), 2);
+/// ```
+#[derive(Clone)]
+pub struct Migraciones {
+    version_actual: u32,
+    pasos: BTreeMap<u32, Migracion>
+}
+
+impl Default for Migraciones {
+    fn default() -> Self {
+        Migraciones::new(1).con_migracion(VERSION_SIN_SOBRE, Ok)
+    }
+}
+
+impl fmt::Debug for Migraciones {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Migraciones")
+            .field("version_actual", &self.version_actual)
+            .field("desde", &self.pasos.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Migraciones {
+    /// Un formato en la versión `version_actual`, todavía sin forma de migrar desde las anteriores
+    #[must_use]
+    pub fn new(version_actual: u32) -> Migraciones {
+        Migraciones { version_actual, pasos: BTreeMap::new() }
+    }
+
+    /// Registra cómo pasar de la versión `desde` a la `desde + 1`.
+    /// Si `desde` es la versión actual, la actual pasa a ser `desde + 1`.
+    #[must_use]
+    pub fn con_migracion(mut self, desde: u32, migracion: Migracion) -> Migraciones {
+        self.pasos.insert(desde, migracion);
+        self.version_actual = self.version_actual.max(desde + 1);
+        self
+    }
+
+    #[must_use]
+    pub fn version_actual(&self) -> u32 {
+        self.version_actual
+    }
+
+    /// Los datos en la versión actual, dentro del sobre
+    #[must_use]
+    pub fn ensobrar(&self, datos: Value) -> Value {
+        let mut sobre = Map::new();
+        sobre.insert("version".to_string(), Value::from(self.version_actual));
+        sobre.insert("data".to_string(), datos);
+        Value::Object(sobre)
+    }
+
+    /// Saca los datos del sobre y los lleva a la versión actual
+    ///
+    /// # Errors
+    /// - `ErrorPersistencia::Version` si el documento es de una versión más nueva o no hay migraciones hasta la actual
+    /// - `ErrorPersistencia::Migracion` si alguna migración falló
+    pub fn abrir(&self, clave: &str, documento: Value) -> Result<Value, ErrorPersistencia> {
+        let (mut version, mut datos) = desensobrar(documento);
+        let error_version = |encontrada| ErrorPersistencia::Version { clave: clave.to_string(), encontrada, esperada: self.version_actual };
+
+        if version > self.version_actual { return Err(error_version(version)) }
+
+        while version < self.version_actual {
+            let Some(migracion) = self.pasos.get(&version) else { return Err(error_version(version)) };
+
+            datos = migracion(datos)
+                .map_err(|mensaje| ErrorPersistencia::Migracion { clave: clave.to_string(), desde: version, mensaje })?;
+            version += 1;
+        }
+
+        Ok(datos)
+    }
+}
+
+// un sobre es un objeto con exactamente `version` (número) y `data`. Cualquier otra cosa es un documento viejo, sin sobre
+fn desensobrar(documento: Value) -> (u32, Value) {
+    let version = match &documento {
+        Value::Object(objeto) if objeto.len() == 2 && objeto.contains_key("data") =>
+            objeto.get("version").and_then(Value::as_u64).and_then(|version| u32::try_from(version).ok()),
+        _ => None
+    };
+
+    match (version, documento) {
+        (Some(version), Value::Object(mut sobre)) => (version, sobre.remove("data").unwrap_or_default()),
+        (_, documento) => (VERSION_SIN_SOBRE, documento)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use super::*;
+
+    fn agregar_telefono(mut cliente: Value) -> Result<Value, String> {
+        let objeto = cliente.as_object_mut().ok_or("el cliente no es un objeto")?;
+        objeto.insert("telefono".to_string(), Value::Null);
+        Ok(cliente)
+    }
+
+    fn separar_nombre(mut cliente: Value) -> Result<Value, String> {
+        let nombre = cliente["nombre"].as_str().ok_or("el cliente no tiene nombre")?.to_string();
+        let (nombre, apellido) = nombre.split_once(' ').unwrap_or((&nombre, ""));
+        cliente["nombre"] = json!(nombre);
+        cliente["apellido"] = json!(apellido);
+        Ok(cliente)
+    }
+
+    #[test]
+    fn test_sobre() {
+        let migraciones = Migraciones::default();
+        assert_eq!(migraciones.version_actual(), 1);
+
+        let sobre = migraciones.ensobrar(json!([1, 2]));
+        assert_eq!(sobre, json!({ "version": 1, "data": [1, 2] }));
+        assert_eq!(migraciones.abrir("a", sobre), Ok(json!([1, 2])));
+
+        // documentos de antes de los sobres
+        assert_eq!(migraciones.abrir("a", json!([1, 2])), Ok(json!([1, 2])));
+        assert_eq!(migraciones.abrir("a", json!({ "version": 1 })), Ok(json!({ "version": 1 })));
+        assert_eq!(migraciones.abrir("a", json!({ "version": "1", "data": 2 })), Ok(json!({ "version": "1", "data": 2 })));
+    }
+
+    #[test]
+    fn test_migrar_paso_a_paso() {
+        let migraciones = Migraciones::default()
+            .con_migracion(1, agregar_telefono)
+            .con_migracion(2, separar_nombre);
+        assert_eq!(migraciones.version_actual(), 3);
+
+        let esperado = json!({ "nombre": "Juan", "apellido": "Pérez", "telefono": null });
+        assert_eq!(migraciones.abrir("c", json!({ "nombre": "Juan Pérez" })), Ok(esperado.clone()));
+        assert_eq!(migraciones.abrir("c", json!({ "version": 1, "data": { "nombre": "Juan Pérez" } })), Ok(esperado.clone()));
+        assert_eq!(migraciones.abrir("c", json!({ "version": 2, "data": { "nombre": "Juan Pérez", "telefono": null } })), Ok(esperado.clone()));
+        assert_eq!(migraciones.abrir("c", json!({ "version": 3, "data": esperado.clone() })), Ok(esperado));
+
+        let Err(ErrorPersistencia::Migracion { clave, desde: 2, .. }) = migraciones.abrir("c", json!({ "version": 2, "data": {} }))
+        else { panic!("No tiene nombre") };
+        assert_eq!(clave, "c");
+    }
+
+    #[test]
+    fn test_sin_camino() {
+        // v3 sin forma de llegar desde la 1
+        let migraciones = Migraciones::default().con_migracion(2, separar_nombre);
+
+        assert_eq!(migraciones.abrir("c", json!({ "version": 1, "data": {} })),
+                   Err(ErrorPersistencia::Version { clave: "c".to_string(), encontrada: 1, esperada: 3 }));
+        assert_eq!(migraciones.abrir("c", json!({ "version": 4, "data": {} })),
+                   Err(ErrorPersistencia::Version { clave: "c".to_string(), encontrada: 4, esperada: 3 }),
+                   "Un documento de una versión más nueva tampoco se puede leer");
+
+        // sin la migración por defecto, un documento sin sobre no se puede leer
+        assert!(matches!(Migraciones::new(1).abrir("c", json!([])), Err(ErrorPersistencia::Version { encontrada: 0, .. })));
+    }
+}
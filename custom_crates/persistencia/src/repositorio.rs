@@ -0,0 +1,222 @@
+//
+// repositorio.rs
+//
+
+use std::fmt;
+use std::marker::PhantomData;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::almacen::{Almacen, AlmacenCompartido};
+use crate::carga::Carga;
+use crate::error::ErrorPersistencia;
+use crate::migraciones::Migraciones;
+
+/// # Repositorio
+///
+/// Dónde un sistema guarda y recupera sus datos de tipo `T`.
+pub trait Repositorio<T> {
+    /// Los datos guardados, con las advertencias de la lectura
+    /// (por ejemplo, que el documento estaba roto y se recuperó un respaldo)
+    ///
+    /// # Errors
+    /// `ErrorPersistencia::NoExiste` si todavía no se guardó nada,
+    /// o el error de lectura o de formato que haya ocurrido
+    fn cargar_con_advertencias(&self) -> Result<Carga<T>, ErrorPersistencia>;
+
+    /// `cargar_con_advertencias`, sin las advertencias
+    ///
+    /// # Errors
+    /// Los mismos que `cargar_con_advertencias`
+    fn cargar(&self) -> Result<T, ErrorPersistencia> {
+        self.cargar_con_advertencias().map(|carga| carga.datos)
+    }
+
+    /// # Errors
+    /// Si `datos` no se pudo serializar o guardar
+    fn guardar(&self, datos: &T) -> Result<(), ErrorPersistencia>;
+
+    fn existe(&self) -> bool;
+
+    /// `cargar`, pero si todavía no se guardó nada devuelve `T::default()`.
+    /// Un documento roto sigue siendo un error: no se pisa con datos vacíos.
+    ///
+    /// # Errors
+    /// Cualquier error de `cargar` salvo `ErrorPersistencia::NoExiste`
+    fn cargar_o_default(&self) -> Result<T, ErrorPersistencia> where T: Default {
+        match self.cargar() {
+            Err(ErrorPersistencia::NoExiste { .. }) => Ok(T::default()),
+            resultado => resultado
+        }
+    }
+}
+
+/// # Documento
+///
+/// Un valor de tipo `T` guardado como JSON bajo `clave` en un `Almacen`,
+/// dentro de un sobre con la versión de su formato (ver `Migraciones`).
+pub struct Documento<T> {
+    almacen: AlmacenCompartido,
+    clave: String,
+    migraciones: Migraciones,
+    tipo: PhantomData<fn() -> T>
+}
+
+impl<T> Documento<T> {
+    pub fn new(almacen: impl Into<AlmacenCompartido>, clave: impl Into<String>) -> Documento<T> {
+        Documento { almacen: almacen.into(), clave: clave.into(), migraciones: Migraciones::default(), tipo: PhantomData }
+    }
+
+    /// Reemplaza la versión del formato y las migraciones desde las anteriores
+    #[must_use]
+    pub fn con_migraciones(mut self, migraciones: Migraciones) -> Documento<T> {
+        self.migraciones = migraciones;
+        self
+    }
+
+    #[must_use]
+    pub fn clave(&self) -> &str {
+        &self.clave
+    }
+
+    /// # Errors
+    /// Si la clave es inválida o no se pudo eliminar
+    pub fn eliminar(&self) -> Result<(), ErrorPersistencia> {
+        self.almacen.eliminar(&self.clave)
+    }
+}
+
+// a mano: `derive` pediría `T: Clone`/`T: Debug` sin necesitarlo
+impl<T> Clone for Documento<T> {
+    fn clone(&self) -> Self {
+        Documento::new(self.almacen.clone(), self.clave.clone()).con_migraciones(self.migraciones.clone())
+    }
+}
+
+impl<T> fmt::Debug for Documento<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Documento")
+            .field("almacen", &self.almacen)
+            .field("clave", &self.clave)
+            .field("migraciones", &self.migraciones)
+            .finish()
+    }
+}
+
+impl<T> Repositorio<T> for Documento<T> where T: Serialize + DeserializeOwned {
+    fn cargar_con_advertencias(&self) -> Result<Carga<T>, ErrorPersistencia> {
+        let Carga { datos: documento, advertencias } = self.almacen.leer_con_advertencias(&self.clave)?;
+        let valor = self.migraciones.abrir(&self.clave, documento)?;
+
+        let datos = serde_json::from_value(valor)
+            .map_err(|error| ErrorPersistencia::Deserializacion { clave: self.clave.clone(), mensaje: error.to_string() })?;
+
+        Ok(Carga { datos, advertencias })
+    }
+
+    fn guardar(&self, datos: &T) -> Result<(), ErrorPersistencia> {
+        let valor = serde_json::to_value(datos)
+            .map_err(|error| ErrorPersistencia::Serializacion { clave: self.clave.clone(), mensaje: error.to_string() })?;
+
+        self.almacen.escribir(&self.clave, &self.migraciones.ensobrar(valor))
+    }
+
+    fn existe(&self) -> bool {
+        self.almacen.existe(&self.clave)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use crate::memoria::AlmacenMemoria;
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+    struct Libro {
+        titulo: String,
+        stock: u32
+    }
+
+    type Libros = BTreeMap<u64, Libro>;
+
+    fn libros() -> Libros {
+        Libros::from([
+            (1, Libro { titulo: "Economía en una lección".to_string(), stock: 1 }),
+            (2, Libro { titulo: "xd".to_string(), stock: 2 })
+        ])
+    }
+
+    #[test]
+    fn test_guardar_cargar() {
+        let almacen = AlmacenMemoria::new();
+        let documento: Documento<Libros> = Documento::new(almacen.clone(), "biblioteca_libros");
+
+        assert!(!documento.existe());
+        assert!(documento.cargar().is_err_and(|error| error.es_no_existe()));
+        assert_eq!(documento.cargar_o_default(), Ok(Libros::new()));
+
+        assert_eq!(documento.guardar(&libros()), Ok(()));
+        assert!(documento.existe());
+        assert_eq!(documento.cargar(), Ok(libros()));
+        assert_eq!(documento.clone().cargar_o_default(), Ok(libros()));
+
+        assert_eq!(documento.eliminar(), Ok(()));
+        assert!(!documento.existe());
+    }
+
+    #[test]
+    fn test_forma_incorrecta() {
+        let almacen = AlmacenMemoria::new();
+        let Ok(()) = almacen.escribir("libros", &json!({ "1": "no es un libro" })) else { panic!() };
+
+        let documento: Documento<Libros> = Documento::new(almacen, "libros");
+        let Err(ErrorPersistencia::Deserializacion { clave, .. }) = documento.cargar() else { panic!("No es un Libros") };
+        assert_eq!(clave, "libros");
+
+        assert!(documento.cargar_o_default().is_err(), "Un documento roto no se reemplaza por uno vacío");
+    }
+
+    // v2: el stock pasó a ser una lista de ejemplares
+    fn stock_a_ejemplares(mut libros: Value) -> Result<Value, String> {
+        for libro in libros.as_object_mut().ok_or("no son libros")?.values_mut() {
+            let stock = libro["stock"].as_u64().ok_or("libro sin stock")?;
+            libro["ejemplares"] = json!((1..=stock).collect::<Vec<u64>>());
+        }
+        Ok(libros)
+    }
+
+    #[test]
+    fn test_versiones() {
+        let almacen = AlmacenMemoria::new();
+        let documento: Documento<Libros> = Documento::new(almacen.clone(), "libros");
+
+        assert_eq!(documento.guardar(&libros()), Ok(()));
+        let Ok(guardado) = almacen.leer("libros") else { panic!() };
+        assert_eq!(guardado["version"], json!(1));
+        assert_eq!(guardado["data"]["1"]["titulo"], json!("Economía en una lección"));
+
+        // un archivo de antes de las versiones
+        let Ok(()) = almacen.escribir("libros", &json!({ "2": { "titulo": "xd", "stock": 2 } })) else { panic!() };
+        assert_eq!(documento.cargar(), Ok(Libros::from([(2, Libro { titulo: "xd".to_string(), stock: 2 })])));
+
+        let v2: Documento<Value> = Documento::new(almacen.clone(), "libros")
+            .con_migraciones(Migraciones::default().con_migracion(1, stock_a_ejemplares));
+        let Ok(migrado) = v2.cargar() else { panic!("Debería migrar de la 0 a la 2") };
+        assert_eq!(migrado["2"]["ejemplares"], json!([1, 2]));
+        assert_eq!(v2.guardar(&migrado), Ok(()));
+
+        // el programa viejo no sabe leer la versión 2: error, no una biblioteca vacía
+        assert_eq!(documento.cargar_o_default(), Err(ErrorPersistencia::Version { clave: "libros".to_string(), encontrada: 2, esperada: 1 }));
+    }
+
+    #[test]
+    fn test_serializacion() {
+        // las claves de un mapa JSON tienen que ser texto
+        let documento: Documento<BTreeMap<Vec<u8>, u8>> = Documento::new(AlmacenMemoria::new(), "mapa");
+        let Err(ErrorPersistencia::Serializacion { clave, .. }) = documento.guardar(&BTreeMap::from([(vec![1], 1)]))
+        else { panic!("No se puede serializar") };
+        assert_eq!(clave, "mapa");
+    }
+}
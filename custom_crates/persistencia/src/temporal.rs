@@ -0,0 +1,63 @@
+//
+// temporal.rs
+//
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::archivos::AlmacenArchivos;
+
+static CARPETAS_CREADAS: AtomicUsize = AtomicUsize::new(0);
+
+/// # CarpetaTemporal
+///
+/// Una carpeta propia dentro de la carpeta temporal del sistema, que se borra al salir de alcance.<br>
+/// Pensada para tests: cada test guarda en su carpeta y no pisa los archivos de los demás
+/// (los tests corren en paralelo) ni deja archivos en el repositorio.
+#[derive(Debug)]
+pub struct CarpetaTemporal {
+    ruta: PathBuf
+}
+
+impl CarpetaTemporal {
+    /// # Panics
+    /// Si no se puede crear la carpeta
+    #[must_use]
+    pub fn new(nombre: &str) -> CarpetaTemporal {
+        let numero = CARPETAS_CREADAS.fetch_add(1, Ordering::Relaxed);
+        let ruta = std::env::temp_dir().join(format!("sdl_{nombre}_{}_{numero}", std::process::id()));
+
+        let _ = fs::remove_dir_all(&ruta);
+        fs::create_dir_all(&ruta).expect("No se pudo crear la carpeta temporal");
+        CarpetaTemporal { ruta }
+    }
+
+    #[must_use]
+    pub fn ruta(&self) -> &Path {
+        &self.ruta
+    }
+
+    /// Un almacén que guarda en esta carpeta
+    #[must_use]
+    pub fn almacen(&self) -> AlmacenArchivos {
+        AlmacenArchivos::new(&self.ruta)
+    }
+
+    /// Un almacén donde todas las escrituras fallan con `ErrorPersistencia::Io`:
+    /// su "carpeta" es un archivo, así que no se puede crear ni escribir nada adentro.
+    ///
+    /// # Panics
+    /// Si no se puede crear el archivo
+    #[must_use]
+    pub fn almacen_inaccesible(&self) -> AlmacenArchivos {
+        let archivo = self.ruta.join("no_es_una_carpeta");
+        fs::write(&archivo, "").expect("No se pudo crear el archivo");
+        AlmacenArchivos::new(archivo)
+    }
+}
+
+impl Drop for CarpetaTemporal {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.ruta);
+    }
+}
@@ -43,18 +43,8 @@ Implemente las estructuras, funciones asociadas y traits necesarios para resolve
  */
 
 use std::collections::HashMap;
-
-//
-// fecha.rs
-//
-
-// epoch: 01/01/1970 00:00:00.0000 (dd/mm/yyyy hh:mm:ss.millis)
-pub struct Fecha {
-    // anteriormente usaba datos para dia, mes, ano (por haberlo importado de ej3/prac3)
-    // pero al necesitar ordenar prefiero usar el sistema clasico de EPOCH
-    // y añadir luego funciones para calcular dia, mes, año si es necesario
-    pub millis_since_epoch: u128,
-}
+use calendario::FechaHora;
+use dinero::{Dinero, Moneda, Redondeo};
 
 //
 // producto.rs
@@ -69,7 +59,7 @@ pub struct Producto {
     pub nombre: String,
     pub apellido: String,
     pub categoria: CategoriaProducto,
-    pub precio: f32,
+    pub precio: Dinero, // en pesos
 }
 
 //
@@ -123,12 +113,21 @@ impl Cliente {
 
 const DESCUENTO_SUSCRIPCION_NEWSLETTER: f32 = 5.0;
 
+// los porcentajes se toman con dos decimales: 12.5% son 1250 centésimos de punto
+const CENTESIMOS_PORCENTAJE: i128 = 10_000;
+
+// el monto sin el porcentaje, redondeado al centavo más cercano
+fn aplicar_descuento(monto: Dinero, porcentaje: f32) -> Option<Dinero> {
+    let centesimos = (f64::from(porcentaje) * 100.0).round() as i128;
+    monto.fraccion(CENTESIMOS_PORCENTAJE - centesimos, CENTESIMOS_PORCENTAJE, Redondeo::MitadLejosDeCero).ok()
+}
+
 pub enum MedioDePago {
     Credito, Debito, Transferencia, Efectivo
 }
 
 pub struct Venta {
-    pub fecha: Fecha,
+    pub fecha: FechaHora,
     pub cliente: u32, // cliente.dni
     pub vendedor: u32, // vendedor.legajo
     pub medio_de_pago: MedioDePago,
@@ -138,7 +137,7 @@ pub struct Venta {
 impl Venta {
 
     // ➢ Crear una venta con: fecha, cliente, vendedor, medio de pago y un listado de productos con sus cantidades.
-    fn new(fecha: Fecha, cliente: u32, vendedor: u32, medio_de_pago: MedioDePago, productos: HashMap<Producto, u16>) -> Venta {
+    fn new(fecha: FechaHora, cliente: u32, vendedor: u32, medio_de_pago: MedioDePago, productos: HashMap<Producto, u16>) -> Venta {
         Venta {
             fecha, cliente, vendedor, medio_de_pago, productos
         }
@@ -151,22 +150,23 @@ impl Venta {
     //     Es decir el sistema tiene una lista de las categorías con el descuento a aplicar.
     //     Además se debe aplicar un porcentaje de descuento general si el cliente tiene suscripción al newsletter.
 
-    fn precio_final(&self, descuentos_porc: &HashMap<CategoriaProducto, f32>, suscrito_newsletter: bool) -> Option<f32> {
+    fn precio_final(&self, descuentos_porc: &HashMap<CategoriaProducto, f32>, suscrito_newsletter: bool) -> Option<Dinero> {
         for val in descuentos_porc.values() {
             if *val < 0.0 || *val > 100.0 { return None }
         }
 
-        let mut precio_final = 0.0;
+        let mut precio_final = Dinero::cero(Moneda::ARS);
         // calcular precios y sumarlos
         for (producto, cant) in &self.productos {
+            let subtotal = producto.precio.multiplicar(i128::from(*cant)).ok()?;
             if let Some(descuento) = descuentos_porc.get(&producto.categoria) {
-                precio_final+= producto.precio * (1.0 - *descuento/100.0) * (*cant as f32)
+                precio_final = precio_final.sumar(aplicar_descuento(subtotal, *descuento)?).ok()?
             } else {
-                precio_final+= producto.precio * (*cant as f32)
+                precio_final = precio_final.sumar(subtotal).ok()?
             }
         }
 
-        if suscrito_newsletter { precio_final*= 1.0 - DESCUENTO_SUSCRIPCION_NEWSLETTER/100.0 }
+        if suscrito_newsletter { precio_final = aplicar_descuento(precio_final, DESCUENTO_SUSCRIPCION_NEWSLETTER)? }
 
         Some(precio_final)
     }
@@ -219,7 +219,7 @@ impl Comercio {
 pub struct InformeVentaIndividual {
     venta: Venta, // fecha, productos, sus cantidades y medio de pago incluido en Venta
     cant_total_productos: u16, // No especifica ser necesario pero por si acaso.
-    monto_total: f64, // obtenible desde la función venta.precio_final() pero no incluido en venta
+    monto_total: Dinero, // obtenible desde la función venta.precio_final() pero no incluido en venta
 }
 
 pub struct HistorialVentas {
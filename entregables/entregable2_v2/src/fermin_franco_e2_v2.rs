@@ -43,19 +43,8 @@ Implemente las estructuras, funciones asociadas y traits necesarios para resolve
  */
 
 use std::collections::{BTreeMap, HashMap};
-
-//
-// fecha.rs
-//
-
-// epoch: 01/01/1970 00:00:00.0000 (dd/mm/yyyy hh:mm:ss.millis)
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Fecha {
-    // anteriormente usaba datos para dia, mes, ano (por haberlo importado de ej3/prac3)
-    // pero al necesitar ordenar prefiero usar el sistema clasico de EPOCH
-    // y añadir luego funciones para calcular dia, mes, año si es necesario
-    pub millis_since_epoch: u128,
-}
+use calendario::FechaHora;
+use dinero::{Dinero, ErrorDinero, Moneda, Redondeo};
 
 //
 // producto.rs
@@ -70,7 +59,7 @@ pub enum CategoriaProducto {
 pub struct Producto {
     pub nombre: String,
     pub categoria: CategoriaProducto,
-    pub precio: f32,
+    pub precio: Dinero, // en pesos
 }
 
 //
@@ -81,7 +70,7 @@ pub struct Producto {
 pub struct Vendedor {
     pub legajo: u32,
     pub antiguedad_anos: u8,
-    pub salario: f64,
+    pub salario: Dinero,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -94,8 +83,8 @@ pub struct Cliente {
 }
 
 impl Vendedor {
-    fn new(legajo: u32, antiguedad_anos: u8, salario: f64) -> Option<Vendedor> {
-        if salario < 0.0 { return None }
+    fn new(legajo: u32, antiguedad_anos: u8, salario: Dinero) -> Option<Vendedor> {
+        if salario.es_negativo() { return None }
 
         Some(Vendedor {
             legajo,
@@ -123,6 +112,15 @@ impl Cliente {
 
 const DESCUENTO_SUSCRIPCION_NEWSLETTER: f32 = 5.0;
 
+// los porcentajes se toman con dos decimales: 12.5% son 1250 centésimos de punto
+const CENTESIMOS_PORCENTAJE: i128 = 10_000;
+
+// el monto sin el porcentaje, redondeado al centavo más cercano
+fn aplicar_descuento(monto: Dinero, porcentaje: f32) -> Result<Dinero, ErrorDinero> {
+    let centesimos = (f64::from(porcentaje) * 100.0).round() as i128;
+    monto.fraccion(CENTESIMOS_PORCENTAJE - centesimos, CENTESIMOS_PORCENTAJE, Redondeo::MitadLejosDeCero)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MedioDePago {
     Credito, Debito, Transferencia, Efectivo
@@ -130,7 +128,7 @@ pub enum MedioDePago {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Venta {
-    pub fecha: Fecha,
+    pub fecha: FechaHora,
     pub cliente: u32, // cliente.dni
     pub vendedor: u32, // vendedor.legajo
     pub medio_de_pago: MedioDePago,
@@ -139,7 +137,7 @@ pub struct Venta {
 
 impl Venta {
     // ➢ Crear una venta con: fecha, cliente, vendedor, medio de pago y un listado de productos con sus cantidades.
-    fn new(fecha: Fecha, cliente: u32, vendedor: u32, medio_de_pago: MedioDePago, productos: Vec<(Producto, u16)>) -> Venta {
+    fn new(fecha: FechaHora, cliente: u32, vendedor: u32, medio_de_pago: MedioDePago, productos: Vec<(Producto, u16)>) -> Venta {
         Venta {
             fecha, cliente, vendedor, medio_de_pago, productos
         }
@@ -147,8 +145,9 @@ impl Venta {
 
     // Solía ser Option<f32> porque un descuento podría ser inválido.
     // En cambio, si un descuento es invalido, se desestima.
-    fn precio_final(&self, descuentos_porc: &HashMap<CategoriaProducto, f32>, suscrito_newsletter: bool) -> f32 {
-        let mut precio_final = 0.0;
+    // Solo falla si el monto no entra en un Dinero, o si un producto no está en pesos.
+    fn precio_final(&self, descuentos_porc: &HashMap<CategoriaProducto, f32>, suscrito_newsletter: bool) -> Result<Dinero, ErrorDinero> {
+        let mut precio_final = Dinero::cero(Moneda::ARS);
         // calcular precios y sumarlos
         for (producto, cant) in &self.productos {
             let descuento = if let Some(descuento) = descuentos_porc.get(&producto.categoria) {
@@ -157,12 +156,13 @@ impl Venta {
             } else { 0.0 };
 
             // aplicar
-            precio_final+= producto.precio * (1.0 - descuento/100.0) * (*cant as f32)
+            let subtotal = producto.precio.multiplicar(i128::from(*cant))?;
+            precio_final = precio_final.sumar(aplicar_descuento(subtotal, descuento)?)?;
         }
 
-        if suscrito_newsletter { precio_final*= 1.0 - DESCUENTO_SUSCRIPCION_NEWSLETTER/100.0 }
+        if suscrito_newsletter { precio_final = aplicar_descuento(precio_final, DESCUENTO_SUSCRIPCION_NEWSLETTER)? }
 
-        precio_final
+        Ok(precio_final)
     }
 }
 
@@ -173,8 +173,8 @@ impl Venta {
 struct Comercio {
     vendedores: HashMap<u32, Vendedor>,
     clientes: BTreeMap<u32, Cliente>,
-    descuentos: HashMap<CategoriaProducto, f32>,
-    ventas: Vec<(Venta, f32)>
+    descuentos: HashMap<CategoriaProducto, f32>, // porcentajes
+    ventas: Vec<(Venta, Dinero)>
 }
 
 #[derive(Default)]
@@ -186,6 +186,7 @@ struct ReporteTotal {
 #[derive(Debug, Clone, PartialEq)]
 enum ErrorRegistrarVenta {
     VendedorInexistente{ legajo_vendedor: u32 },
+    Monto(ErrorDinero),
     ErrorDesconocido(String)
 }
 
@@ -195,7 +196,7 @@ impl Comercio {
     //      Si se brinda None, debe existir en el map de clientes. Si no existe, no se aplicará el descuento.
     //      Si se brinda Some, se reemplazará en el map de clientes. Si existe y corresponde, se aplicará el descuento.
     // Como ninguna parte del enunciado exige lo contrario, se permitirá el anonimato del cliente.
-    fn registrar_venta(&mut self, venta: Venta, cliente: Option<Cliente>) -> Result<&(Venta, f32), ErrorRegistrarVenta> {
+    fn registrar_venta(&mut self, venta: Venta, cliente: Option<Cliente>) -> Result<&(Venta, Dinero), ErrorRegistrarVenta> {
         if let None = self.vendedores.get(&venta.vendedor) {
             return Err(ErrorRegistrarVenta::VendedorInexistente { legajo_vendedor: venta.vendedor });
         }
@@ -208,7 +209,7 @@ impl Comercio {
             cliente.suscrito_newsletter
         } else { false };
 
-        let precio_final = venta.precio_final(&self.descuentos, suscrito_newsletter);
+        let precio_final = venta.precio_final(&self.descuentos, suscrito_newsletter).map_err(ErrorRegistrarVenta::Monto)?;
 
         self.ventas.push((venta, precio_final));
 
@@ -243,9 +244,9 @@ impl Comercio {
 pub struct InformeVentaIndividual {
     // antes utilizaba un clon de Venta pero contiene datos indeseados como Cliente.
     // sería más eficiente usar una estructura específica.
-    fecha: Fecha,
+    fecha: FechaHora,
     productos: Vec<(Producto, u16)>,
-    monto_total: f32, // obtenible desde la función venta.precio_final()
+    monto_total: Dinero, // obtenible desde la función venta.precio_final()
     medio_de_pago: MedioDePago,
 }
 
@@ -317,7 +318,7 @@ impl Comercio {
         // 
         // ventas.sort_by(|a, b| {
         //     // ordenar de mayor a menor (más reciente == mayor)
-        //     b.fecha.millis_since_epoch.cmp(&a.fecha.millis_since_epoch)
+        //     b.fecha.cmp(&a.fecha)
         // });
 
         //
@@ -338,7 +339,7 @@ impl Comercio {
                 let ubicacion_correspondiente = ventas.binary_search_by(
                     // mayor a menor (más reciente = mayor)
                     |key|
-                        venta.fecha.millis_since_epoch.cmp(&key.fecha.millis_since_epoch)
+                        venta.fecha.cmp(&key.fecha)
                 ).unwrap_or_else(|x| x);
         
                 ventas.insert(
@@ -379,10 +380,12 @@ impl Comercio {
 mod tests {
     use super::*;
 
+    fn pesos(pesos: i64) -> Dinero { Dinero::enteros(pesos, Moneda::ARS) }
+
     #[test]
     fn test_new_vendedor() {
-        let vendedor_some = Vendedor::new(10, 5, 170000.0);
-        let vendedor_none = Vendedor::new(10, 5, -170000.0);
+        let vendedor_some = Vendedor::new(10, 5, pesos(170_000));
+        let vendedor_none = Vendedor::new(10, 5, pesos(-170_000));
 
         assert!(vendedor_some.is_some(), "Debería devolver un vendedor");
         assert!(vendedor_none.is_none(), "No debería devolver un vendedor, su salario es negativo");
@@ -408,12 +411,12 @@ mod tests {
     #[test]
     fn test_new_venta() {
         let productos: Vec<(Producto, u16)> = vec![
-            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: 15.0, }, 5),
-            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: 10.0, }, 10),
+            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: pesos(15), }, 5),
+            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: pesos(10), }, 10),
         ];
 
         let venta = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 1,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
@@ -421,7 +424,7 @@ mod tests {
         };
 
         let venta_new = Venta::new(
-            Fecha { millis_since_epoch: 1 }, 1, 2, MedioDePago::Credito, productos
+            FechaHora::desde_millis(1), 1, 2, MedioDePago::Credito, productos
         );
 
         assert_eq!(venta, venta_new, "Ambas ventas deben ser idénticas");
@@ -430,12 +433,12 @@ mod tests {
     #[test]
     fn test_precio_final() {
         let productos: Vec<(Producto, u16)> = vec![
-            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: 15.0, }, 5),
-            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: 10.0, }, 10),
+            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: pesos(15), }, 5),
+            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: pesos(10), }, 10),
         ];
 
         let venta = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 1,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
@@ -444,7 +447,13 @@ mod tests {
 
         let descuentos = HashMap::from([(CategoriaProducto::HomeDeco, 10.0)]);
 
-        assert_eq!(venta.precio_final(&descuentos, true), (15.0*5.0 + 9.0*10.0) * (100.0 - DESCUENTO_SUSCRIPCION_NEWSLETTER)/100.0);
+        // (15*5 + 9*10) * 0.95
+        assert_eq!(venta.precio_final(&descuentos, true), Ok(Dinero::new(15675, Moneda::ARS)));
+
+        // cada línea y el total se redondean al centavo: 3 x $0.35 con 10% son $0.945, que es $0.95
+        let venta = Venta { productos: vec![(Producto { nombre: "asd3".to_string(), categoria: CategoriaProducto::HomeDeco, precio: Dinero::new(35, Moneda::ARS) }, 3)], ..venta };
+        assert_eq!(venta.precio_final(&descuentos, false), Ok(Dinero::new(95, Moneda::ARS)));
+        assert_eq!(venta.precio_final(&descuentos, true), Ok(Dinero::new(90, Moneda::ARS)));
     }
 
     #[test]
@@ -464,12 +473,12 @@ mod tests {
         };
 
         let productos: Vec<(Producto, u16)> = vec![
-            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: 15.0, }, 5),
-            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: 10.0, }, 10),
+            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: pesos(15), }, 5),
+            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: pesos(10), }, 10),
         ];
 
         let venta = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 1,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
@@ -487,7 +496,7 @@ mod tests {
             vendedores: HashMap::from([(2, Vendedor {
                 legajo: 2,
                 antiguedad_anos: 10,
-                salario: 300.0,
+                salario: pesos(300),
             })]),
             clientes: BTreeMap::from([(1, Cliente {
                 dni: 1,
@@ -501,7 +510,7 @@ mod tests {
         };
 
         let result = comercio.registrar_venta(venta.clone(), None);
-        assert_eq!(result, Ok(&(venta, (15.0*5.0 + 9.0*10.0) * (100.0 - DESCUENTO_SUSCRIPCION_NEWSLETTER)/100.0)));
+        assert_eq!(result, Ok(&(venta, Dinero::new(15675, Moneda::ARS))));
     }
 
     #[test]
@@ -509,12 +518,12 @@ mod tests {
         let descuentos = HashMap::from([(CategoriaProducto::HomeDeco, 10.0)]);
 
         let productos: Vec<(Producto, u16)> = vec![
-            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: 15.0, }, 5),
-            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: 10.0, }, 10),
+            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: pesos(15), }, 5),
+            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: pesos(10), }, 10),
         ];
 
         let venta_vend1 = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 0,
             vendedor: 1,
             medio_de_pago: MedioDePago::Credito,
@@ -522,14 +531,14 @@ mod tests {
         };
 
         let venta_vend2 = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 0,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
             productos,
         };
 
-        let precio_ventas = (15.0*5.0 + 9.0*10.0) * (100.0 - DESCUENTO_SUSCRIPCION_NEWSLETTER)/100.0;
+        let precio_ventas = Dinero::new(15675, Moneda::ARS);
 
         let comercio = Comercio {
             vendedores: Default::default(),
@@ -564,12 +573,12 @@ mod tests {
         let descuentos = HashMap::from([(CategoriaProducto::HomeDeco, 10.0), (CategoriaProducto::Cosmetico, -10.0)]);
 
         let productos: Vec<(Producto, u16)> = vec![
-            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: 15.0, }, 5),
-            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: 10.0, }, 10),
+            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: pesos(15), }, 5),
+            (Producto { nombre: "asd2".to_string(), categoria: CategoriaProducto::HomeDeco, precio: pesos(10), }, 10),
         ];
 
         let venta_vend1 = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 0,
             vendedor: 1,
             medio_de_pago: MedioDePago::Credito,
@@ -577,14 +586,14 @@ mod tests {
         };
 
         let venta_vend2 = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 0,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
             productos: productos.clone(),
         };
 
-        let precio_ventas = (15.0*5.0 + 9.0*10.0) * (100.0 - DESCUENTO_SUSCRIPCION_NEWSLETTER)/100.0;
+        let precio_ventas = Dinero::new(15675, Moneda::ARS);
 
         let comercio = Comercio {
 
@@ -592,11 +601,11 @@ mod tests {
                 (2, Vendedor {
                 legajo: 2,
                 antiguedad_anos: 10,
-                salario: 300.0,
+                salario: pesos(300),
             }), (3, Vendedor {
                     legajo: 3,
                     antiguedad_anos: 10,
-                    salario: 400.0
+                    salario: pesos(400)
                 })
 
             ]),
@@ -634,4 +643,30 @@ mod tests {
         assert_eq!(historial3, Err(ErrorHistorialVentas::SinVentasDeCategoria { legajo_vendedor: 3, categoria_condicional: CategoriaProducto::HomeDeco }),
                    "El vendedor 3 no tiene ventas");
     }
+
+    #[test]
+    fn get_historial_ventas_mismo_dia() {
+        let productos: Vec<(Producto, u16)> = vec![
+            (Producto { nombre: "asd1".to_string(), categoria: CategoriaProducto::Cosmetico, precio: pesos(15), }, 1),
+        ];
+
+        // mismo día, distinta hora: el historial tiene que quedar de la más reciente a la más antigua
+        let Some(dia) = calendario::Fecha::new(15, 3, 2024) else { panic!() };
+        let Some(manana) = FechaHora::new(dia, 9, 30, 0, 0, -180) else { panic!() };
+        let Some(tarde) = FechaHora::new(dia, 18, 0, 0, 0, -180) else { panic!() };
+        let Some(mediodia) = FechaHora::new(dia, 12, 0, 0, 1, -180) else { panic!() };
+
+        let venta = |fecha| Venta::new(fecha, 0, 2, MedioDePago::Efectivo, productos.clone());
+
+        let comercio = Comercio {
+            vendedores: HashMap::from([(2, Vendedor { legajo: 2, antiguedad_anos: 1, salario: pesos(300) })]),
+            clientes: BTreeMap::new(),
+            descuentos: HashMap::new(),
+            ventas: vec![(venta(manana), pesos(15)), (venta(tarde), pesos(15)), (venta(mediodia), pesos(15))],
+        };
+
+        let Ok(historial) = comercio.get_historial_ventas(2, CategoriaProducto::Cosmetico) else { panic!("Debería tener ventas") };
+        let fechas: Vec<FechaHora> = historial.ventas.iter().map(|informe| informe.fecha).collect();
+        assert_eq!(fechas, vec![tarde, mediodia, manana]);
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 use crate::structs::cliente::Cliente;
-use crate::structs::fecha::Fecha;
+use calendario::Fecha;
 use crate::structs::prestamo::{EstadoPrestamo, Prestamo};
 use super::libro::Libro;
 
@@ -325,7 +325,7 @@ mod tests {
     use std::collections::BTreeMap;
     use crate::structs::biblioteca::{Biblioteca, ErrorDecrementarStock, ErrorIncrementarStock, ErrorRealizarPrestamo};
     use crate::structs::cliente::Cliente;
-    use crate::structs::fecha::Fecha;
+    use calendario::Fecha;
     use crate::structs::libro::Libro;
     use crate::structs::prestamo::EstadoPrestamo;
 
@@ -1,5 +1,4 @@
 pub mod prestamo;
 pub mod biblioteca;
 pub mod cliente;
-pub mod libro;
-pub mod fecha;
\ No newline at end of file
+pub mod libro;
\ No newline at end of file
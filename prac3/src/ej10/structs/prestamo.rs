@@ -1,4 +1,4 @@
-use super::fecha::Fecha;
+use calendario::Fecha;
 
 /// Para registrar un préstamo se requiere:
 ///     el libro,
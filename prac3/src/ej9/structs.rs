@@ -91,9 +91,4 @@ pub struct Atencion {
 }
 
 // Nota: para la fecha utilice lo implementado en el punto 3.
-#[derive(Debug, PartialEq, Clone, Default)]
-pub struct Fecha {
-    pub dia: u8,
-    pub mes: u8,
-    pub ano: i64
-}
\ No newline at end of file
+pub use calendario::Fecha;
\ No newline at end of file
@@ -43,19 +43,7 @@ Implemente las estructuras, funciones asociadas y traits necesarios para resolve
  */
 
 use std::collections::{BTreeMap, HashMap};
-
-//
-// fecha.rs
-//
-
-// epoch: 01/01/1970 00:00:00.0000 (dd/mm/yyyy hh:mm:ss.millis)
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Fecha {
-    // anteriormente usaba datos para dia, mes, ano (por haberlo importado de ej3/prac3)
-    // pero al necesitar ordenar prefiero usar el sistema clasico de EPOCH
-    // y añadir luego funciones para calcular dia, mes, año si es necesario
-    pub millis_since_epoch: u128,
-}
+use calendario::FechaHora;
 
 //
 // producto.rs
@@ -130,7 +118,7 @@ pub enum MedioDePago {
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Venta {
-    pub fecha: Fecha,
+    pub fecha: FechaHora,
     pub cliente: u32, // cliente.dni
     pub vendedor: u32, // vendedor.legajo
     pub medio_de_pago: MedioDePago,
@@ -139,7 +127,7 @@ pub struct Venta {
 
 impl Venta {
     // ➢ Crear una venta con: fecha, cliente, vendedor, medio de pago y un listado de productos con sus cantidades.
-    fn new(fecha: Fecha, cliente: u32, vendedor: u32, medio_de_pago: MedioDePago, productos: Vec<(Producto, u16)>) -> Venta {
+    fn new(fecha: FechaHora, cliente: u32, vendedor: u32, medio_de_pago: MedioDePago, productos: Vec<(Producto, u16)>) -> Venta {
         Venta {
             fecha, cliente, vendedor, medio_de_pago, productos
         }
@@ -277,7 +265,7 @@ mod tests {
         ];
 
         let venta = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 1,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
@@ -285,7 +273,7 @@ mod tests {
         };
 
         let venta_new = Venta::new(
-            Fecha { millis_since_epoch: 1 }, 1, 2, MedioDePago::Credito, productos
+            FechaHora::desde_millis(1), 1, 2, MedioDePago::Credito, productos
         );
 
         assert_eq!(venta, venta_new, "Ambas ventas deben ser idénticas");
@@ -299,7 +287,7 @@ mod tests {
         ];
 
         let venta = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 1,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
@@ -333,7 +321,7 @@ mod tests {
         ];
 
         let venta = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 1,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
@@ -378,7 +366,7 @@ mod tests {
         ];
 
         let venta_vend1 = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 0,
             vendedor: 1,
             medio_de_pago: MedioDePago::Credito,
@@ -386,7 +374,7 @@ mod tests {
         };
 
         let venta_vend2 = Venta {
-            fecha: Fecha { millis_since_epoch: 1 },
+            fecha: FechaHora::desde_millis(1),
             cliente: 0,
             vendedor: 2,
             medio_de_pago: MedioDePago::Credito,
@@ -59,144 +59,12 @@ N
  */
 //mod structs;
 
-//
-// date.rs
-//
-
-use std::cmp::Ordering;
-use std::cmp::Ordering::{Equal, Greater, Less};
 use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fmt::Formatter;
 use std::hash::{Hash, Hasher};
 use std::ops::{AddAssign, SubAssign};
-
-const NOMBRE_MESES: [&str; 12] = ["Enero", "Febrero", "Marzo", "Abril",
-    "Mayo", "Junio", "Julio", "Agosto",
-    "Septiembre", "Octubre", "Noviembre", "Diciembre"];
-#[derive(Clone, PartialEq, Debug, Copy, Hash)]
-pub struct Date {
-    pub(crate) day: u8,
-    pub(crate) month: u8,
-    pub(crate) year: i64
-}
-
-impl Default for Date {
-    fn default() -> Self {
-        Date { day: 1, month: 1, year: 0 }
-    }
-}
-
-impl PartialOrd for Date {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.day == other.day
-            && self.month == other.month
-            && self.year == other.year
-        { return Some(Equal) }
-
-        if self.year > other.year { return Some(Greater) }
-        if self.month > other.month { return Some(Greater) }
-        if self.day > other.day { return Some(Greater) }
-
-        Some(Less)
-    }
-}
-
-impl fmt::Display for Date {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.is_date_valid() {
-            write!(f, "{} de {} del {}", self.day, NOMBRE_MESES[self.month as usize - 1], self.year)
-        } else {
-            write!(f, "{}/{}/{}", self.day, self.month, self.year)
-        }
-    }
-}
-
-impl Date {
-    // El año podría ser negativo, indicando época antes de Cristo.
-    pub fn new(dia: u8, mes: u8, ano: i64) -> Option<Date> {
-        let fecha = Date { day: dia, month: mes, year: ano };
-        if fecha.is_date_valid() {
-            return Some(fecha);
-        }
-        None
-    }
-
-    pub fn is_date_valid(&self) -> bool {
-        // check que el mes sea válido
-        if !(1..=12).contains(&self.month) { return false }
-
-        // check días del mes
-        if self.day == 0
-            || self.day > self.current_month_days()
-        { return false }
-
-        // el año no puede ser incorrecto...
-        // a no ser que se contabilice la edad del universo
-        // que dudo mucho que pueda importar para este caso
-        true
-    }
-
-    pub fn is_leap_year(&self) -> bool {
-        self.year % 4 == 0
-    }
-
-    pub fn add_days(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            let dias_mes_actual = self.current_month_days();
-            let dias_para_proximo_mes = (dias_mes_actual - self.day + 1) as u32;
-
-            if dias_restantes >= dias_para_proximo_mes {
-                // ir al siguiente mes
-
-                dias_restantes-= dias_para_proximo_mes;
-                self.day = 1;
-                self.month += 1;
-
-                if self.month > 12 {
-                    self.month = 1;
-                    self.year += 1;
-                }
-            } else {
-                self.day += dias_restantes as u8;
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn subtract_days(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            if dias_restantes >= self.day as u32 {
-                // ir al anterior mes
-                dias_restantes-= self.day as u32;
-                self.month -= 1;
-
-                if self.month < 1 {
-                    self.month = 12;
-                    self.year -= 1;
-                }
-
-                // corregir self.dia == 0
-                self.day = self.current_month_days();
-            } else {
-                self.day -= dias_restantes as u8;
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn current_month_days(&self) -> u8 {
-        match self.month {
-            4 | 6 | 9 | 11 => 30,
-            2 => if self.is_leap_year() { 29 } else { 28 },
-            _ => 31,
-        }
-    }
-}
+use calendario::Fecha;
 
 //
 // user.rs
@@ -295,7 +163,7 @@ pub struct BlockchainTransaction {
 impl BlockchainTransaction {
     pub fn new(data: CommonTransactionData, transaction_type: TransactionType, blockchain: &str, hash: Option<BlockchainTransactionHash>, crypto: &str, quote: Quote) -> Result<Self, ErrorNewTransaction> {
         // invalid date
-        if !data.date.is_date_valid() { return Err(ErrorNewTransaction::InvalidDate) }
+        if !data.date.es_fecha_valida() { return Err(ErrorNewTransaction::InvalidDate) }
 
         // invalid amount
         if data.amount < 0.0 { return Err(ErrorNewTransaction::InvalidInputAmount { amount: data.amount }) }
@@ -405,7 +273,7 @@ pub struct Quote {
 // and I prefer transaction types to be hard-coded.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct CommonTransactionData {
-    pub date: Date,
+    pub date: Fecha,
     pub user: u32,
     pub amount: f64,
 }
@@ -469,7 +337,7 @@ impl FiatTransaction {
             _ => return Err(ErrorNewTransaction::InvalidTransactionType { transaction_type })
         }
 
-        if !data.date.is_date_valid() { return Err(ErrorNewTransaction::InvalidDate) }
+        if !data.date.es_fecha_valida() { return Err(ErrorNewTransaction::InvalidDate) }
         if data.amount <= 0.0 { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
 
         // user verifications must be done service-side
@@ -494,7 +362,7 @@ pub struct CryptoTransaction {
 
 impl CryptoTransaction {
     pub fn new(data: CommonTransactionData, transaction_type: TransactionType, currency: &str) -> Result<Self, ErrorNewTransaction> {
-        if !data.date.is_date_valid() { return Err(ErrorNewTransaction::InvalidDate) }
+        if !data.date.es_fecha_valida() { return Err(ErrorNewTransaction::InvalidDate) }
         if data.amount < 0.0 { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
 
         match transaction_type {
@@ -574,7 +442,7 @@ impl XYZ {
 
     // ➢ Ingresar dinero: se recibe un monto en fiat de un usuario
     //  y se acredita al balance de fiat de dicho usuario. Además se crea una transacción del hecho.
-    fn fiat_deposit(&mut self, date: Date, user: u32, fiat_amount: f64) -> Result<FiatTransaction, ErrorFiatDeposit> {
+    fn fiat_deposit(&mut self, date: Fecha, user: u32, fiat_amount: f64) -> Result<FiatTransaction, ErrorFiatDeposit> {
         let data = CommonTransactionData { date, user, amount: fiat_amount };
 
         // date errors are handled by FiatTransaction::new()
@@ -599,7 +467,7 @@ impl XYZ {
     // ➢ Retirar fiat por determinado medio: dado un monto de fiat se le descuenta dicho monto del balance
     // al usuario y se genera una transacción con la siguiente información:
     // fecha, usuario, tipo: retiro fiat, monto y medio (puede ser MercadoPago o Transferencia Bancaria)
-    fn fiat_withdraw(&mut self, date: Date, user: u32, fiat_amount: f64, mean: WithdrawalMean) -> Result<FiatTransaction, ErrorFiatWithdraw> {
+    fn fiat_withdraw(&mut self, date: Fecha, user: u32, fiat_amount: f64, mean: WithdrawalMean) -> Result<FiatTransaction, ErrorFiatWithdraw> {
         let data = CommonTransactionData { date, user, amount: fiat_amount };
 
         match FiatTransaction::new(
@@ -632,7 +500,7 @@ impl XYZ {
     //  de la cripto y desacreditar en el balance de fiat.
     // Luego de ello se registra la transacción con los siguientes datos:
     //      fecha, usuario, criptomoneda, tipo: compra de cripto, monto de cripto y cotización.
-    fn buy_crypto(&mut self, date: Date, user: u32, fiat_amount: f64, crypto_prefix: &str)
+    fn buy_crypto(&mut self, date: Fecha, user: u32, fiat_amount: f64, crypto_prefix: &str)
                   -> Result<CryptoTransaction, ErrorBuySell> {
         // date errors are handled by CryptoTransaction::new()
 
@@ -688,7 +556,7 @@ impl XYZ {
     //  y desacreditar en el balance de la criptomoneda.
     //  Luego de ello se registra la transacción con los siguientes datos:
     //  fecha, usuario, criptomoneda, tipo: venta de cripto, monto de cripto y cotización.
-    fn sell_crypto(&mut self, date: Date, user: u32, crypto_amount: f64, crypto_prefix: &str) ->
+    fn sell_crypto(&mut self, date: Fecha, user: u32, crypto_amount: f64, crypto_prefix: &str) ->
     Result<CryptoTransaction, ErrorBuySell> {
         // date errors are handled by CryptoTransaction::new()
 
@@ -747,7 +615,7 @@ impl XYZ {
     // (esto hágalo retornando el nombre de la blockchain + un número random).
     // Luego se genera una transacción con los siguientes datos:
     // fecha, usuario, tipo: retiro cripto, blockchain, hash, cripto, monto, cotización.
-    fn withdraw_to_blockchain(&mut self, date: Date, user: u32, crypto_amount: f64, blockchain: &str, crypto: &str) -> Result<BlockchainTransaction, ErrorBlockchainWithdraw> {
+    fn withdraw_to_blockchain(&mut self, date: Fecha, user: u32, crypto_amount: f64, blockchain: &str, crypto: &str) -> Result<BlockchainTransaction, ErrorBlockchainWithdraw> {
         // does blockchain exist?
         if !self.blockchains.contains_key(blockchain) {
             return Err(ErrorBlockchainWithdraw::BlockchainNotFound { blockchain: blockchain.to_string() })
@@ -794,7 +662,7 @@ impl XYZ {
     // ➢ Recibir criptomoneda de blockchain: dado un monto de una cripto y una blockchain se le acredita
     // al balancede dicha cripto al usuario el monto. Luego se genera una transacción con los siguientes datos:
     // fecha, usuario, tipo: recepción cripto, blockchain, cripto, monto, cotización.
-    fn deposit_from_blockchain(&mut self, date: Date, user: u32, crypto_amount: f64, blockchain: &str, crypto: &str) -> Result<BlockchainTransaction, ErrorBlockchainDeposit> {
+    fn deposit_from_blockchain(&mut self, date: Fecha, user: u32, crypto_amount: f64, blockchain: &str, crypto: &str) -> Result<BlockchainTransaction, ErrorBlockchainDeposit> {
         // does blockchain exist?
         if !self.blockchains.contains_key(blockchain) {
             return Err(ErrorBlockchainDeposit::BlockchainNotFound { blockchain: blockchain.to_string() })
@@ -847,30 +715,30 @@ mod tests {
 
     #[test]
     fn test_date() {
-        let date = Date::new(1, 1, 2023).unwrap();
+        let date = Fecha::new(1, 1, 2023).unwrap();
         assert_eq!(date.to_string(), "1 de Enero del 2023");
-        assert!(date.is_date_valid());
-        assert!(!Date::new(31, 2, 2023).is_some());
+        assert!(date.es_fecha_valida());
+        assert!(!Fecha::new(31, 2, 2023).is_some());
         
-        let mut date = Date::new(28, 2, 2020).unwrap();
-        assert!(date.is_leap_year());
-        date.add_days(1);
+        let mut date = Fecha::new(28, 2, 2020).unwrap();
+        assert!(date.es_bisiesto());
+        date.sumar_dias(1);
         assert_eq!(date.to_string(), "29 de Febrero del 2020");
-        date.add_days(1);
+        date.sumar_dias(1);
         assert_eq!(date.to_string(), "1 de Marzo del 2020");
-        date.subtract_days(2);
+        date.restar_dias(2);
         assert_eq!(date.to_string(), "28 de Febrero del 2020");
-        date.subtract_days(28);
+        date.restar_dias(28);
         assert_eq!(date.to_string(), "31 de Enero del 2020");
         
         // test partialcmp
-        let date1 = Date::new(1, 1, 2023).unwrap();
-        let date2 = Date::new(2, 1, 2023).unwrap();
+        let date1 = Fecha::new(1, 1, 2023).unwrap();
+        let date2 = Fecha::new(2, 1, 2023).unwrap();
         
         assert!(date1 < date2);
         assert!(date2 > date1);
         
-        let date3 = Date::new(1, 1, 2023).unwrap();
+        let date3 = Fecha::new(1, 1, 2023).unwrap();
         assert!(date1 == date3);
     }
 
@@ -951,7 +819,7 @@ mod tests {
     #[test]
     fn test_fiat_transaction() {
         let data = CommonTransactionData {
-            date: Date::new(1, 1, 2023).unwrap(),
+            date: Fecha::new(1, 1, 2023).unwrap(),
             user: 12345678,
             amount: 100.0
         };
@@ -965,7 +833,7 @@ mod tests {
         assert_eq!(transaction.data.user, 12345678);
         assert_eq!(transaction.data.amount, 100.0);
         assert_eq!(transaction.transaction_type, TransactionType::FiatDeposit);
-        assert!(transaction.data.date.is_date_valid());
+        assert!(transaction.data.date.es_fecha_valida());
     }
 
     #[test]
@@ -1002,7 +870,7 @@ mod tests {
     #[test]
     fn test_crypto_transaction() {
         let data = CommonTransactionData {
-            date: Date::new(1, 1, 2023).unwrap(),
+            date: Fecha::new(1, 1, 2023).unwrap(),
             user: 12345678,
             amount: 0.5
         };
@@ -1016,13 +884,13 @@ mod tests {
         assert_eq!(transaction.data.user, 12345678);
         assert_eq!(transaction.data.amount, 0.5);
         assert_eq!(transaction.currency, "BTC");
-        assert!(transaction.data.date.is_date_valid());
+        assert!(transaction.data.date.es_fecha_valida());
     }
 
     #[test]
     fn test_blockchain_transaction() {
         let data = CommonTransactionData {
-            date: Date::new(1, 1, 2023).unwrap(),
+            date: Fecha::new(1, 1, 2023).unwrap(),
             user: 12345678,
             amount: 0.5
         };
@@ -1039,7 +907,7 @@ mod tests {
         assert_eq!(transaction.crypto, "BTC");
         assert_eq!(transaction.quote.buy, 50000.0);
         assert_eq!(transaction.quote.sell, 49000.0);
-        assert!(transaction.data.date.is_date_valid());
+        assert!(transaction.data.date.es_fecha_valida());
     }
 
     #[test]
@@ -1062,7 +930,7 @@ mod tests {
 
         xyz.users.insert(user.dni, user);
         let data = CommonTransactionData {
-            date: Date::new(1, 1, 2023).unwrap(),
+            date: Fecha::new(1, 1, 2023).unwrap(),
             user: 12345678,
             amount: 100.0
         };
@@ -1074,7 +942,7 @@ mod tests {
         assert_eq!(transaction.data.user, 12345678);
         assert_eq!(transaction.data.amount, 100.0);
         assert_eq!(transaction.transaction_type, TransactionType::FiatDeposit);
-        assert!(transaction.data.date.is_date_valid());
+        assert!(transaction.data.date.es_fecha_valida());
 
         let user = xyz.users.get(&12345678).unwrap();
         assert_eq!(user.fiat_balance.f64(), 1100.0); // 1000 + 100 deposit
@@ -1087,7 +955,7 @@ mod tests {
         assert_eq!(transaction.data.user, 12345678);
         assert_eq!(transaction.data.amount, 100.0);
         assert_eq!(transaction.transaction_type, TransactionType::FiatWithdrawal { mean: WithdrawalMean::BankTansfer });
-        assert!(transaction.data.date.is_date_valid());
+        assert!(transaction.data.date.es_fecha_valida());
 
         let user = xyz.users.get(&12345678).unwrap();
         assert_eq!(user.fiat_balance.f64(), 1000.0); // 1100 - 100 withdraw
@@ -1115,7 +983,7 @@ mod tests {
         xyz.quotes.insert("BTC".to_string(), Quote { buy: 50000.0, sell: 49000.0 });
 
         let data = CommonTransactionData {
-            date: Date::new(1, 1, 2023).unwrap(),
+            date: Fecha::new(1, 1, 2023).unwrap(),
             user: 12345678,
             amount: 1000.0 // fiat amount
         };
@@ -1128,7 +996,7 @@ mod tests {
         assert_eq!(transaction.data.user, 12345678);
         assert_eq!(transaction.data.amount, 1000.0);
         assert_eq!(transaction.currency, "BTC");
-        assert_eq!(transaction.data.date.is_date_valid(), true);
+        assert_eq!(transaction.data.date.es_fecha_valida(), true);
 
         let user = xyz.users.get(&12345678).unwrap();
         assert_eq!(user.fiat_balance.f64(), 0.0); // 1000 - 100 buy
@@ -1142,7 +1010,7 @@ mod tests {
         assert_eq!(transaction.data.user, 12345678);
         assert_eq!(transaction.data.amount, 0.02); // amount in crypto
         assert_eq!(transaction.currency, "BTC");
-        assert_eq!(transaction.data.date.is_date_valid(), true);
+        assert_eq!(transaction.data.date.es_fecha_valida(), true);
 
         let user = xyz.users.get(&12345678).unwrap();
         assert_eq!(user.fiat_balance.f64(), 0.02 * 49000.0); // 900 + (0.002 * 49000) = 900 + 98 = 998
@@ -1175,7 +1043,7 @@ mod tests {
         xyz.blockchains.insert("Bitcoin".to_string(), Blockchain::new("Bitcoin", "BTC", vec!["BTC".to_string()]));
 
         let data = CommonTransactionData {
-            date: Date::new(1, 1, 2023).unwrap(),
+            date: Fecha::new(1, 1, 2023).unwrap(),
             user: 12345678,
             amount: 0.02 // crypto amount
         };
@@ -1190,7 +1058,7 @@ mod tests {
         assert_eq!(transaction.data.amount, 0.02);
         assert_eq!(transaction.blockchain, "Bitcoin");
         assert_eq!(transaction.crypto, "BTC");
-        assert!(transaction.data.date.is_date_valid());
+        assert!(transaction.data.date.es_fecha_valida());
 
         let user = xyz.users.get(&12345678).unwrap();
         assert_eq!(user.crypto_balance.get("BTC").unwrap().f64(), 0.0); // all BTC withdrawn
@@ -1243,7 +1111,7 @@ mod tests {
         assert_eq!(transaction.data.amount, 0.02);
         assert_eq!(transaction.blockchain, "Bitcoin");
         assert_eq!(transaction.crypto, "BTC");
-        assert!(transaction.data.date.is_date_valid());
+        assert!(transaction.data.date.es_fecha_valida());
         
         let user = xyz.users.get(&12345678).unwrap();
         assert_eq!(user.crypto_balance.get("BTC").unwrap().f64(), 0.02); // 0.02 BTC deposited
@@ -2,9 +2,9 @@
 // auto.rs
 //
 
-use std::fs;
 use error_proc_macro::Error;
 use serde::{Deserialize, Serialize};
+use persistencia::{AlmacenCompartido, Documento, ErrorPersistencia, Repositorio};
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Color {
@@ -57,63 +57,62 @@ impl Auto {
 // concesionario.rs
 //
 
+const ARCHIVO_AUTOS: &str = "autos";
+
 pub struct Concesionario {
     pub nombre: String,
     pub direccion: String,
     pub autos: Vec<Auto>,
     pub capacidad: usize,
+    pub almacen: AlmacenCompartido, // dónde se guarda autos.json
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ErrorNewConcesionario {
-    ErrorCapacidadInsuficiente(String)
+    ErrorCapacidadInsuficiente(String),
+    Archivo(ErrorPersistencia)
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ErrorAgregarAuto {
     ConcesionarioLleno { capacidad: usize },
-    ArchivoNoGuardado
+    Archivo(ErrorPersistencia)
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ErrorEliminarAuto {
     ConcesionarioVacio,
     AutoInexistente,
-    ArchivoNoGuardado
+    Archivo(ErrorPersistencia)
 }
 
 impl Concesionario {
     // ➢ new: que pasando los parámetros correspondientes, crea un ConcesionarioAuto y lo retorna.
     pub fn new(nombre: &str, direccion: &str, capacidad: usize) -> Result<Self, ErrorNewConcesionario> {
-        // si el archivo existe, cargarlo
-        let op_read = fs::read_to_string("autos.json");
-        let vec_autos: Vec<Auto> = if let Ok(data) = op_read {
-            let new_autos = serde_json::from_str::<Vec<Auto>>(data.as_str()).unwrap_or_default();
+        Self::new_con_almacen(nombre, direccion, capacidad, AlmacenCompartido::default())
+    }
 
-            if new_autos.len() > capacidad {
-                return Err(ErrorNewConcesionario::ErrorCapacidadInsuficiente("La cantidad de autos cargada desde el archivo es mayor a la capacidad del concesionario".to_string()))
-            }
+    // igual que new, pero los autos se leen y se guardan en `almacen` (por ejemplo, un AlmacenMemoria en los tests)
+    pub fn new_con_almacen(nombre: &str, direccion: &str, capacidad: usize, almacen: impl Into<AlmacenCompartido>) -> Result<Self, ErrorNewConcesionario> {
+        let almacen = almacen.into();
 
-            new_autos
-        } else {
-            Vec::with_capacity(capacidad)
+        // si el archivo existe, cargarlo. si está roto, no se pisa con un concesionario vacío.
+        let mut vec_autos = match Documento::<Vec<Auto>>::new(almacen.clone(), ARCHIVO_AUTOS).cargar_o_default() {
+            Ok(autos) => autos,
+            Err(error) => return Err(ErrorNewConcesionario::Archivo(error))
         };
 
-        Ok(Self { nombre: nombre.to_string(), direccion: direccion.to_string(), autos: vec_autos, capacidad })
+        if vec_autos.len() > capacidad {
+            return Err(ErrorNewConcesionario::ErrorCapacidadInsuficiente("La cantidad de autos cargada desde el archivo es mayor a la capacidad del concesionario".to_string()))
+        }
+        vec_autos.reserve_exact(capacidad - vec_autos.len());
+
+        Ok(Self { nombre: nombre.to_string(), direccion: direccion.to_string(), autos: vec_autos, capacidad, almacen })
     }
 
-    fn reescribir_json_autos(&self) -> bool {
+    fn reescribir_json_autos(&self) -> Result<(), ErrorPersistencia> {
         // c. reescribir el archivo con la información del vector de autos
-        match serde_json::to_string_pretty(&self.autos) {
-            Ok(res) => {
-                if fs::write("autos.json", res).is_err() {
-                    return false
-                }
-                
-                true
-            }
-            Err(_) => { false }
-        }
+        Documento::new(self.almacen.clone(), ARCHIVO_AUTOS).guardar(&self.autos)
     }
 
     // c- Una vez hecho el punto anterior debe hacer que los autos de la concesionaria se
@@ -133,7 +132,7 @@ impl Concesionario {
 
         self.autos.push(auto);
 
-        if !self.reescribir_json_autos() { return Err(ErrorAgregarAuto::ArchivoNoGuardado) }
+        if let Err(error) = self.reescribir_json_autos() { return Err(ErrorAgregarAuto::Archivo(error)) }
 
         Ok(
             self.autos.capacity() - self.autos.len()
@@ -158,8 +157,10 @@ impl Concesionario {
 
         let auto = self.autos.remove(found_index);
 
-        if !self.reescribir_json_autos() { Err(ErrorEliminarAuto::ArchivoNoGuardado) }
-        else { Ok(auto) }
+        match self.reescribir_json_autos() {
+            Ok(()) => Ok(auto),
+            Err(error) => Err(ErrorEliminarAuto::Archivo(error))
+        }
     }
 
     // ➢ buscar_auto(auto): busca un auto y si lo encuentra lo retorna.
@@ -182,6 +183,7 @@ impl Concesionario {
 #[cfg(test)]
 mod tests {
     use Color;
+    use persistencia::{Almacen, AlmacenArchivos, AlmacenMemoria, CarpetaTemporal};
     use super::*;
 
     fn concesionario(capacidad: usize) -> Concesionario {
@@ -189,7 +191,8 @@ mod tests {
             nombre: "asd".to_string(),
             direccion: "dire".to_string(),
             autos: Vec::with_capacity(capacidad),
-            capacidad
+            capacidad,
+            almacen: AlmacenMemoria::new().into()
         }
     }
 
@@ -322,4 +325,57 @@ mod tests {
             Err(err) => { assert_eq!(err, ErrorEliminarAuto::ConcesionarioVacio, "el concesionario está vacío") }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_concesionario_archivo() {
+        let almacen = AlmacenMemoria::new();
+        let auto1 = Auto { marca: "Nissan".to_string(), modelo: "March 1.6".to_string(), ano: 2012, precio: 10.0, color: Color::Negro };
+        let auto2 = Auto { marca: "BMW".to_string(), modelo: "X5".to_string(), ano: 2015, precio: 200.0, color: Color::Rojo };
+
+        let Ok(mut c) = Concesionario::new_con_almacen("asd", "dire", 2, almacen.clone()) else { panic!("No hay archivo: debería arrancar vacío") };
+        assert!(c.autos.is_empty());
+        c.agregar_auto(auto1.clone()).unwrap();
+        c.agregar_auto(auto2.clone()).unwrap();
+        c.eliminar_auto("Nissan", "March 1.6", 2012).unwrap();
+
+        // otro concesionario con el mismo almacén lee lo que guardó el primero
+        let Ok(c) = Concesionario::new_con_almacen("asd", "dire", 2, almacen.clone()) else { panic!("Debería leer el archivo") };
+        assert_eq!(c.autos, vec![auto2.clone()]);
+
+        let Err(ErrorNewConcesionario::ErrorCapacidadInsuficiente(_)) = Concesionario::new_con_almacen("asd", "dire", 0, almacen.clone())
+        else { panic!("El archivo tiene más autos que la capacidad") };
+
+        let Ok(()) = almacen.escribir("autos", &serde_json::json!("no son autos")) else { panic!() };
+        let Err(ErrorNewConcesionario::Archivo(ErrorPersistencia::Deserializacion { .. })) = Concesionario::new_con_almacen("asd", "dire", 2, almacen)
+        else { panic!("El archivo está roto") };
+    }
+
+    #[test]
+    fn test_concesionario_carpeta_datos() {
+        let carpeta = CarpetaTemporal::new("p5e1_carpeta_datos");
+        let datos = carpeta.ruta().join("concesionarios").join("asd");
+
+        let Ok(mut c) = Concesionario::new_con_almacen("asd", "dire", 2, AlmacenArchivos::new(&datos)) else { panic!("No hay archivo: debería arrancar vacío") };
+        let auto1 = Auto { marca: "Nissan".to_string(), modelo: "March 1.6".to_string(), ano: 2012, precio: 10.0, color: Color::Negro };
+        let Ok(_) = c.agregar_auto(auto1.clone()) else { panic!("Debería crear la carpeta") };
+        assert!(datos.join("autos.json").is_file());
+
+        let Ok(c) = Concesionario::new_con_almacen("asd", "dire", 2, AlmacenArchivos::new(&datos)) else { panic!("Debería leer el archivo") };
+        assert_eq!(c.autos, vec![auto1]);
+    }
+
+    #[test]
+    fn test_concesionario_archivo_no_guardado() {
+        let carpeta = CarpetaTemporal::new("p5e1_no_guardado");
+        let mut c = concesionario(2);
+        c.almacen = carpeta.almacen_inaccesible().into();
+
+        let auto1 = Auto { marca: "Nissan".to_string(), modelo: "March 1.6".to_string(), ano: 2012, precio: 10.0, color: Color::Negro };
+        let Err(ErrorAgregarAuto::Archivo(ErrorPersistencia::Io { .. })) = c.agregar_auto(auto1.clone())
+        else { panic!("No se puede escribir en la carpeta") };
+
+        // el auto quedó agregado localmente aunque no se haya podido guardar
+        let Err(ErrorEliminarAuto::Archivo(ErrorPersistencia::Io { .. })) = c.eliminar_auto("Nissan", "March 1.6", 2012)
+        else { panic!("No se puede escribir en la carpeta") };
+    }
+}
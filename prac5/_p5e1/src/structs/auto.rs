@@ -1,5 +1,5 @@
 use error_proc_macro::Error;
-use serde::{Serialize, Serializer};
+use serde::Serialize;
 
 #[derive(Serialize, PartialEq, Clone)]
 pub enum Color {
@@ -1,11 +1,14 @@
-use std::fs;
 use error_proc_macro::Error;
+use persistencia::{Almacen, AlmacenCompartido, Migraciones};
 use crate::structs::auto::Auto;
 
+const ARCHIVO_AUTOS: &str = "autos";
+
 pub struct Concesionario<'a> {
     pub nombre: &'a str,
     pub direccion: &'a str,
-    pub autos: Vec<Auto<'a>>
+    pub autos: Vec<Auto<'a>>,
+    pub almacen: AlmacenCompartido // dónde se guarda autos.json
 }
 
 #[derive(Error, PartialEq)]
@@ -24,7 +27,12 @@ pub enum ErrorEliminarAuto {
 impl<'a> Concesionario<'a> {
     // ➢ new: que pasando los parámetros correspondientes, crea un ConcesionarioAuto y lo retorna.
     pub fn new(nombre: &'a str, direccion: &'a str, capacidad: usize) -> Self {
-        Self { nombre, direccion, autos: Vec::with_capacity(capacidad) }
+        Self::new_con_almacen(nombre, direccion, capacidad, AlmacenCompartido::default())
+    }
+
+    // igual que new, pero los autos se guardan en `almacen` (por ejemplo, un AlmacenMemoria en los tests)
+    pub fn new_con_almacen(nombre: &'a str, direccion: &'a str, capacidad: usize, almacen: impl Into<AlmacenCompartido>) -> Self {
+        Self { nombre, direccion, autos: Vec::with_capacity(capacidad), almacen: almacen.into() }
     }
 
     // c- Una vez hecho el punto anterior debe hacer que los autos de la concesionaria se
@@ -52,7 +60,7 @@ impl<'a> Concesionario<'a> {
     }
 
     // ➢ eliminar_auto(auto): elimina un auto de la lista de autos.
-    pub fn eliminar_auto(&mut self, marca: &'a str, modelo: &'a str, ano: u16) -> Result<Auto, ErrorEliminarAuto> {
+    pub fn eliminar_auto(&mut self, marca: &'a str, modelo: &'a str, ano: u16) -> Result<Auto<'_>, ErrorEliminarAuto> {
         if self.autos.is_empty() { return Err(ErrorEliminarAuto::ConcesionarioVacio) }
 
         let mut found_index = 0;
@@ -74,20 +82,16 @@ impl<'a> Concesionario<'a> {
     }
 
     fn reescribir_json_autos(&self) -> bool {
-        // c. reescribir el archivo con la información del vector de autos
-        match serde_json::to_string_pretty(&self.autos) {
-            Ok(res) => {
-                if fs::write("autos.json", res).is_err() {
-                    return false
-                }
-                true
-            }
+        // c. reescribir el archivo con la información del vector de autos.
+        // los autos no se leen de vuelta (tienen &str), así que no pasan por un Documento: solo se ensobran igual
+        match serde_json::to_value(&self.autos) {
+            Ok(autos) => self.almacen.escribir(ARCHIVO_AUTOS, &Migraciones::default().ensobrar(autos)).is_ok(),
             Err(_) => { false }
         }
     }
 
     // ➢ buscar_auto(auto): busca un auto y si lo encuentra lo retorna.
-    pub fn buscar_auto(&self, marca: &'a str, modelo: &'a str, ano: u16) -> Option<&Auto> {
+    pub fn buscar_auto(&self, marca: &'a str, modelo: &'a str, ano: u16) -> Option<&Auto<'_>> {
         for auto in &self.autos {
             if auto.marca == marca && auto.modelo == modelo && auto.ano == ano {
                 return Some(auto)
@@ -105,6 +109,7 @@ impl<'a> Concesionario<'a> {
 
 #[cfg(test)]
 mod tests {
+    use persistencia::{AlmacenArchivos, AlmacenMemoria, CarpetaTemporal};
     use crate::structs::auto::Color;
     use super::*;
 
@@ -112,7 +117,8 @@ mod tests {
         Concesionario{
             nombre: "asd",
             direccion: "dire",
-            autos: Vec::with_capacity(capacity)
+            autos: Vec::with_capacity(capacity),
+            almacen: AlmacenMemoria::new().into()
         }
     }
 
@@ -163,4 +169,23 @@ mod tests {
         }
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_archivo() {
+        let carpeta = CarpetaTemporal::new("p5e1_structs_archivo");
+        let datos = carpeta.ruta().join("concesionarios");
+        let mut c = Concesionario::new_con_almacen("asd", "dire", 2, AlmacenArchivos::new(&datos));
+
+        let auto1 = Auto { marca: "Nissan", modelo: "March 1.6", ano: 2012, precio: 10.0, color: Color::Negro };
+        assert_eq!(c.agregar_auto(auto1.clone()), Ok(1));
+        let Ok(guardado) = AlmacenArchivos::new(&datos).leer(ARCHIVO_AUTOS) else { panic!("Debería estar en la carpeta de datos") };
+        assert_eq!(guardado["data"][0]["marca"], serde_json::json!("Nissan"));
+
+        assert!(c.eliminar_auto(auto1.marca, auto1.modelo, auto1.ano).is_ok());
+        let Ok(guardado) = AlmacenArchivos::new(&datos).leer(ARCHIVO_AUTOS) else { panic!() };
+        assert_eq!(guardado["data"], serde_json::json!([]));
+
+        // si no se puede escribir, el auto queda agregado igual
+        c.almacen = carpeta.almacen_inaccesible().into();
+        assert_eq!(c.agregar_auto(auto1), Err(ErrorAgregarAuto::ArchivoNoGuardado));
+    }
+}
@@ -1,17 +1,16 @@
-use std::fs::File;
-use std::io::Write;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use persistencia::{AlmacenCompartido, Documento, ErrorPersistencia, Repositorio};
 
 //
 // cancion.rs
 //
 
-#[derive(Serialize, PartialEq, Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
 pub enum Genero {
     Rock, Pop, Rap, Jazz, Otros
 }
 
-#[derive(Serialize, PartialEq, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct Cancion {
     pub titulo: String,
     pub artista: String,
@@ -28,40 +27,41 @@ impl Cancion {
 // playlist.rs
 //
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct Playlist {
     pub nombre: String,
-    pub canciones: Vec<Cancion>
+    pub canciones: Vec<Cancion>,
+    #[serde(skip)]
+    pub almacen: AlmacenCompartido // dónde se guarda <nombre>.json
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ErrorAgregarCancion {
-    Archivos(ErrorSobreescribirArchivo)
+    Archivos(ErrorPersistencia)
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ErrorMoverCancion {
     PosicionFueraDeLimites,
     CancionNoEncontrada,
-    Archivos(ErrorSobreescribirArchivo)
+    Archivos(ErrorPersistencia)
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ErrorEliminarCancion {
     CancionNoExiste,
-    Archivos(ErrorSobreescribirArchivo)
-}
-
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum ErrorSobreescribirArchivo {
-    CrearArchivo,
-    SerializarPlaylist,
-    EscribirArchivo
+    Archivos(ErrorPersistencia)
 }
 
 impl Playlist {
     pub fn new(nombre: &str) -> Self {
-        Self { nombre: nombre.to_string(), canciones: Vec::new() }
+        Self { nombre: nombre.to_string(), canciones: Vec::new(), almacen: AlmacenCompartido::default() }
+    }
+
+    // reemplaza dónde se guarda la playlist
+    pub fn con_almacen(mut self, almacen: impl Into<AlmacenCompartido>) -> Self {
+        self.almacen = almacen.into();
+        self
     }
 
     // ➔ agregar canción.
@@ -140,13 +140,13 @@ impl Playlist {
     }
 
     // ➔ modificar título de la playlist.
-    pub fn modificar_titulo(&mut self, titulo: &str) -> Result<(), ErrorSobreescribirArchivo> {
+    pub fn modificar_titulo(&mut self, titulo: &str) -> Result<(), ErrorPersistencia> {
         self.nombre = titulo.to_string();
         self.sobreescribir_archivo()
     }
 
     // ➔ eliminar todas las canciones.
-    pub fn clear(&mut self) -> Result<(), ErrorSobreescribirArchivo> {
+    pub fn clear(&mut self) -> Result<(), ErrorPersistencia> {
         self.canciones.clear();
         self.sobreescribir_archivo()
     }
@@ -154,27 +154,16 @@ impl Playlist {
     //      b- Una vez obtenido dicho coverage, las canciones de la playlist deben ser guardadas en un archivo en formato JSON,
     //          por lo tanto las operaciones que agreguen, quiten o modifiquen la playlist deben estar respaldadas sobre dicho archivo.
 
-    fn sobreescribir_archivo(&self) -> Result<(), ErrorSobreescribirArchivo> {
-        let mut file = match File::create(format!("{}.json", self.nombre)) {
-            Ok(res) => { res }
-            Err(_) => { return Err(ErrorSobreescribirArchivo::CrearArchivo) }
-        };
-
-        let json_data = match serde_json::to_string_pretty(self) {
-            Ok(res) => { res }
-            Err(_) => { return Err(ErrorSobreescribirArchivo::SerializarPlaylist) }
-        };
-
-        match file.write_all(json_data.as_bytes()) {
-            Ok(_) => { Ok(()) }
-            Err(_) => { Err(ErrorSobreescribirArchivo::EscribirArchivo) }
-        }
+    // el almacén escribe a un temporal y lo renombra: un corte a mitad de escritura no rompe el archivo
+    fn sobreescribir_archivo(&self) -> Result<(), ErrorPersistencia> {
+        Documento::new(self.almacen.clone(), self.nombre.as_str()).guardar(self)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use std::cmp::PartialEq;
+    use persistencia::{AlmacenMemoria, CarpetaTemporal};
     use super::*;
 
     impl PartialEq<Cancion> for &Cancion {
@@ -187,7 +176,8 @@ mod tests {
 
     #[test]
     fn test() {
-        let mut mi_playlist = Playlist::new("platuka");
+        let carpeta = CarpetaTemporal::new("p5e2");
+        let mut mi_playlist = Playlist::new("platuka").con_almacen(carpeta.almacen());
 
         let mi_cancion_1 = Cancion::new("Un siglo sin tí", "Chayanne", Genero::Pop);
         let mi_cancion_2 = Cancion::new("Mi abuela", "Molotov", Genero::Rap);
@@ -251,4 +241,20 @@ mod tests {
         
         
     }
+
+    #[test]
+    fn test_almacen() {
+        let almacen = AlmacenMemoria::new();
+        let mut mi_playlist = Playlist::new("platuka_memoria").con_almacen(almacen.clone());
+
+        mi_playlist.agregar_cancion(Cancion::new("Mi abuela", "Molotov", Genero::Rap)).expect("No debería fallar");
+        assert_eq!(almacen.claves(), vec!["platuka_memoria".to_string()]);
+
+        let guardada: Playlist = Documento::new(almacen.clone(), "platuka_memoria").cargar().expect("Debería estar guardada");
+        assert_eq!(guardada.canciones, mi_playlist.canciones);
+
+        // el nombre es la clave: un nombre que no sirve de clave no se puede guardar
+        let res = mi_playlist.modificar_titulo("../platuka");
+        assert!(matches!(res, Err(ErrorPersistencia::Ruta { .. })));
+    }
 }
\ No newline at end of file
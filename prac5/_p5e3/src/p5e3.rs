@@ -1,21 +1,8 @@
 
-//
-// fecha.rs
-//
-
 use std::collections::VecDeque;
-use std::{fmt, fs, io};
-use std::fs::File;
-use std::io::Read;
 use serde::{Deserialize, Serialize};
-
-const NOMBRE_MESES: [&str; 12] = ["Enero", "Febrero", "Marzo", "Abril",
-    "Mayo", "Junio", "Julio", "Agosto",
-    "Septiembre", "Octubre", "Noviembre", "Diciembre"];
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
-pub struct Fecha {
-    pub(crate) time_since_epoch: u64,
-}
+use calendario::Fecha;
+use persistencia::{AlmacenCompartido, Documento, ErrorPersistencia, Repositorio};
 
 //
 // atencion.rs
@@ -81,8 +68,6 @@ impl Atencion {
 // veterinaria.rs
 //
 
-const BASE_FOLDER: &str = "";
-
 // De la veterinaria se conoce
 // - nombre
 // - la dirección
@@ -93,20 +78,8 @@ pub struct Veterinaria {
     pub direccion: String,
     pub id: u64,
     pub cola: VecDeque<Mascota>,
-    pub atenciones: Vec<Atencion>
-}
-
-//
-// results ArchivoAtenciones
-//
-
-#[derive(Debug, PartialEq)]
-pub enum ResultArchivoAtenciones {
-    Read{ atenciones: Vec<Atencion> },
-    Written{ json: String },
-    IOError,
-    SerializeError,
-    DeserializeError
+    pub atenciones: Vec<Atencion>,
+    pub almacen: AlmacenCompartido // dónde se guarda el archivo de atenciones
 }
 
 //
@@ -117,14 +90,14 @@ pub enum ResultArchivoAtenciones {
 pub enum ErrorNewVeterinaria {
     VecColaCapacidad0,
     VecAtencionesCapacidad0,
-    ArchivoAtenciones(ResultArchivoAtenciones)
+    ArchivoAtenciones(ErrorPersistencia)
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ResultAgregarMascota {
     Exito,
     ColaLlena{ capacity: usize },
-    ArchivoAtenciones(ResultArchivoAtenciones)
+    ArchivoAtenciones(ErrorPersistencia)
 }
 
 #[derive(Debug, PartialEq)]
@@ -132,13 +105,13 @@ pub enum ResultRemoverMascota {
     Exito (Mascota),
     ColaVacia,
     MascotaInexistente { nombre_mascota: String, nombre_dueno: String },
-    ArchivoAtenciones(ResultArchivoAtenciones),
+    ArchivoAtenciones(ErrorPersistencia),
 }
 
 #[derive(Debug, PartialEq)]
 pub enum ResultAgregarAtencion {
     Exito,
-    ArchivoAtenciones(ResultArchivoAtenciones)
+    ArchivoAtenciones(ErrorPersistencia)
 }
 
 #[derive(Debug, PartialEq)]
@@ -146,7 +119,7 @@ pub enum ResultRemoverAtencion {
     Exito(Atencion),
     VectorVacio,
     AtencionInexistente,
-    ArchivoAtenciones(ResultArchivoAtenciones)
+    ArchivoAtenciones(ErrorPersistencia)
 }
 
 
@@ -164,6 +137,19 @@ impl Veterinaria {
         cola: Option<VecDeque<Mascota>>,
         atenciones: Option<Vec<Atencion>>
     ) -> Result<Self, ErrorNewVeterinaria> {
+        Self::new_con_almacen(nombre, direccion, id, cola, atenciones, AlmacenCompartido::default())
+    }
+
+    // igual que new, pero las atenciones se leen y se guardan en `almacen`
+    pub fn new_con_almacen(
+        nombre: &str,
+        direccion: &str,
+        id: u64,
+        cola: Option<VecDeque<Mascota>>,
+        atenciones: Option<Vec<Atencion>>,
+        almacen: impl Into<AlmacenCompartido>
+    ) -> Result<Self, ErrorNewVeterinaria> {
+        let almacen = almacen.into();
         let cola = if let Some(cola) = cola {
             if cola.capacity() == 0 { return Err(ErrorNewVeterinaria::VecColaCapacidad0) }
             cola
@@ -172,20 +158,23 @@ impl Veterinaria {
         // si atenciones some(val) -> crear un archivo que contenga val
         // si atenciones none -> intentar abrir el archivo y colocar su información en self.atenciones
 
+        let archivo = archivo_atenciones(&almacen, nombre);
         let atenciones = if let Some(atenciones) = atenciones {
             if atenciones.capacity() == 0 { return Err(ErrorNewVeterinaria::VecAtencionesCapacidad0)  }
-            sobreescribir_archivo_atenciones(nombre, &atenciones);
+            if let Err(error) = archivo.guardar(&atenciones) {
+                return Err(ErrorNewVeterinaria::ArchivoAtenciones(error))
+            }
             atenciones
         } else {
-            // leer o crear
-            match leer_archivo_atenciones(nombre) {
-                ResultArchivoAtenciones::Read { atenciones } => { atenciones },
-                error => return Err(ErrorNewVeterinaria::ArchivoAtenciones(error))
+            // leer o crear: si no hay archivo, no hay atenciones. si está roto, es un error.
+            match archivo.cargar_o_default() {
+                Ok(atenciones) => { atenciones },
+                Err(error) => return Err(ErrorNewVeterinaria::ArchivoAtenciones(error))
             }
         };
 
         Ok(
-            Self { nombre: nombre.to_string(), direccion: direccion.to_string(), id, cola, atenciones }
+            Self { nombre: nombre.to_string(), direccion: direccion.to_string(), id, cola, atenciones, almacen }
         )
     }
 
@@ -225,9 +214,9 @@ impl Veterinaria {
     pub fn registrar_atencion(&mut self, atencion: Atencion) -> ResultAgregarAtencion {
         self.atenciones.push(atencion);
 
-        match sobreescribir_archivo_atenciones(&self.nombre, &self.atenciones) {
-            ResultArchivoAtenciones::Written { .. } => { ResultAgregarAtencion::Exito },
-            x => ResultAgregarAtencion::ArchivoAtenciones(x)
+        match self.sobreescribir_archivo_atenciones() {
+            Ok(()) => { ResultAgregarAtencion::Exito },
+            Err(error) => ResultAgregarAtencion::ArchivoAtenciones(error)
         }
     }
 
@@ -289,9 +278,9 @@ impl Veterinaria {
 
             let atencion = self.atenciones.remove(index);
 
-            match sobreescribir_archivo_atenciones(&self.nombre, &self.atenciones) {
-                ResultArchivoAtenciones::Written { .. } => { ResultRemoverAtencion::Exito(atencion) },
-                x => ResultRemoverAtencion::ArchivoAtenciones(x)
+            match self.sobreescribir_archivo_atenciones() {
+                Ok(()) => { ResultRemoverAtencion::Exito(atencion) },
+                Err(error) => ResultRemoverAtencion::ArchivoAtenciones(error)
             }
         } else {
             ResultRemoverAtencion::AtencionInexistente
@@ -303,67 +292,31 @@ impl Veterinaria {
 //  atenciones file
 //
 
-fn archivo_atenciones_filepath(nombre_vet: &str) -> String {
-    format!("{}veterinaria_{}_atenciones.json", BASE_FOLDER, nombre_vet)
+fn archivo_atenciones(almacen: &AlmacenCompartido, nombre_vet: &str) -> Documento<Vec<Atencion>> {
+    Documento::new(almacen.clone(), format!("veterinaria_{nombre_vet}_atenciones"))
 }
 
-//
-
-fn sobreescribir_archivo_atenciones(nombre_vet: &str, atenciones: &Vec<Atencion>) -> ResultArchivoAtenciones {
-    // crear y/o escribir
+impl Veterinaria {
     // presupongo que la imposibilidad de persistir como archivo es un error semi-catastrófico
-    // semi-catastrófico == no runtime panic, pero se aborta la creación de la veterinaria
-    let json_data = match serde_json::to_string_pretty(atenciones) {
-        Ok(data) => { data }
-        Err(error) => { return ResultArchivoAtenciones::SerializeError }
-    };
-
-    // escribir
-    match fs::write(archivo_atenciones_filepath(nombre_vet), json_data.clone()) {
-        Ok(_) => {  },
-        Err(error) => {
-            return ResultArchivoAtenciones::IOError
-        }
-    };
-
-    // éxito
-    ResultArchivoAtenciones::Written { json: json_data }
-}
-
-//
+    // semi-catastrófico == no runtime panic, pero se informa a quien llamó
+    fn sobreescribir_archivo_atenciones(&self) -> Result<(), ErrorPersistencia> {
+        archivo_atenciones(&self.almacen, &self.nombre).guardar(&self.atenciones)
+    }
 
-fn leer_archivo_atenciones(nombre_vet: &str) -> ResultArchivoAtenciones {
-    // leer
-    let mut file = match File::open(archivo_atenciones_filepath(nombre_vet)) {
-        Ok(file) => { file }
-        Err(error) => { return ResultArchivoAtenciones::IOError }
-    };
-
-    let mut contents = String::new();
-    match file.read_to_string(&mut contents) {
-        Ok(_) => {},
-        Err(error) => return ResultArchivoAtenciones::IOError
-    };
-
-    let json_value: serde_json::Value = match serde_json::from_str(&contents) {
-        Ok(value) => value,
-        Err(error) => return ResultArchivoAtenciones::DeserializeError,
-    };
-
-    let atenciones: Vec<Atencion> = match serde_json::from_value(json_value) {
-        Ok(atenciones) => atenciones,
-        Err(error) => return ResultArchivoAtenciones::DeserializeError,
-    };
-
-    ResultArchivoAtenciones::Read { atenciones }
+    // descarta los cambios locales y vuelve a leer las atenciones guardadas
+    pub fn releer_archivo_atenciones(&mut self) -> Result<(), ErrorPersistencia> {
+        self.atenciones = archivo_atenciones(&self.almacen, &self.nombre).cargar_o_default()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::VecDeque;
+    use persistencia::{AlmacenMemoria, CarpetaTemporal};
     use super::*;
 
-    fn veterinaria_de_pepe(capacidad: usize) -> Veterinaria {
+    fn veterinaria_de_pepe(capacidad: usize, almacen: impl Into<AlmacenCompartido>) -> Veterinaria {
         // Crear dueños
         let dueno1 = Dueno {
             nombre: "Juan Pérez".to_string(),
@@ -402,9 +355,9 @@ mod tests {
         };
 
         // Crear fechas para atenciones
-        let fecha1 = Fecha { time_since_epoch: 15 };
-        let fecha2 = Fecha { time_since_epoch: 20 };
-        let fecha3 = Fecha { time_since_epoch: 30 };
+        let fecha1 = Fecha { dia: 15, mes: 10, ano: 2023 };
+        let fecha2 = Fecha { dia: 20, mes: 10, ano: 2023 };
+        let fecha3 = Fecha { dia: 30, mes: 10, ano: 2023 };
 
         // Crear atenciones
         let atencion1 = Atencion {
@@ -432,12 +385,13 @@ mod tests {
         vec_cola.push_back(mascota3);
 
         // Crear la veterinaria con cola y atenciones
-        match Veterinaria::new (
+        match Veterinaria::new_con_almacen(
             "Pepe's Pet Shop",
             "Calle Principal 100",
             1,
             Some(vec_cola),
             Some(vec![atencion1, atencion2, atencion3]),
+            almacen
         ) {
             Ok(vet) => { vet }
             Err(err) => { panic!("error: {:?}", err) }
@@ -478,7 +432,7 @@ mod tests {
 
     #[test]
     fn test_atencion() {
-        let mut veterinaria = veterinaria_de_pepe(100);
+        let mut veterinaria = veterinaria_de_pepe(100, AlmacenMemoria::new());
 
         // Buscar
 
@@ -489,8 +443,8 @@ mod tests {
         atencion.modificar_diagnostico_atencion("jijodebu en los jijolines jijox".to_string());
         assert_eq!(atencion.diagnostico, "jijodebu en los jijolines jijox");
 
-        atencion.modificar_fecha_atencion(Fecha { time_since_epoch: 10 });
-        assert_eq!(atencion.proxima_visita, Fecha { time_since_epoch: 10 });
+        atencion.modificar_fecha_atencion(Fecha { dia: 10, mes: 11, ano: 2023 });
+        assert_eq!(atencion.proxima_visita, Fecha { dia: 10, mes: 11, ano: 2023 });
 
         // registrar
 
@@ -503,7 +457,7 @@ mod tests {
             },
             diagnostico: String::from("Resfriado leve"),
             tratamiento: String::from("Antibióticos"),
-            proxima_visita: Fecha { time_since_epoch: 100 },
+            proxima_visita: Fecha { dia: 8, mes: 2, ano: 2024 },
         };
 
         let res = veterinaria.registrar_atencion(atencion1.clone());
@@ -531,7 +485,7 @@ mod tests {
 
     #[test]
     fn test_agregar_eliminar() {
-        let mut veterinaria = veterinaria_de_pepe(10);
+        let mut veterinaria = veterinaria_de_pepe(10, AlmacenMemoria::new());
 
         let dueno1 = Dueno {
             nombre: "Enrique Ibañez".to_string(),
@@ -572,14 +526,16 @@ mod tests {
 
     #[test]
     fn test_json() {
-        veterinaria_de_pepe(10); // creará el .json
+        let carpeta = CarpetaTemporal::new("p5e3_json");
+        veterinaria_de_pepe(10, carpeta.almacen()); // creará el .json
 
-        match Veterinaria::new( // cargará el .json
+        match Veterinaria::new_con_almacen( // cargará el .json
                                 "Pepe's Pet Shop",
                                 "asd",
                                 1,
                                 None,
-                                None
+                                None,
+                                carpeta.almacen()
         ) {
             Ok(vet) => {
                 assert_eq!(vet.atenciones.len(), 3, "deberían ser 3 según el archivo");
@@ -591,4 +547,37 @@ mod tests {
             Err(err) => { panic!("error new veterinaria: {:?}", err) }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_almacen() {
+        let carpeta = CarpetaTemporal::new("p5e3_almacen");
+        let almacen = AlmacenMemoria::new();
+        let atencion = Atencion { diagnostico: "Resfriado leve".to_string(), ..Default::default() };
+
+        let Ok(mut veterinaria) = Veterinaria::new_con_almacen("Memoria", "asd", 1, None, None, almacen.clone())
+        else { panic!("Sin archivo, debería arrancar sin atenciones") };
+        assert!(veterinaria.atenciones.is_empty());
+
+        assert_eq!(veterinaria.registrar_atencion(atencion.clone()), ResultAgregarAtencion::Exito);
+        assert_eq!(almacen.claves(), vec!["veterinaria_Memoria_atenciones".to_string()]);
+
+        let Ok(mut otra) = Veterinaria::new_con_almacen("Memoria", "asd", 1, None, None, almacen.clone())
+        else { panic!("Debería leer el archivo") };
+        assert_eq!(otra.atenciones, vec![atencion.clone()]);
+
+        veterinaria.atenciones.clear();
+        assert_eq!(veterinaria.releer_archivo_atenciones(), Ok(()));
+        assert_eq!(veterinaria.atenciones, otra.atenciones);
+
+        // no se puede guardar: la atención queda registrada localmente, pero se informa el error
+        otra.almacen = carpeta.almacen_inaccesible().into();
+        let ResultAgregarAtencion::ArchivoAtenciones(ErrorPersistencia::Io { .. }) = otra.registrar_atencion(atencion.clone())
+        else { panic!("No se puede escribir en la carpeta") };
+        let ResultRemoverAtencion::ArchivoAtenciones(ErrorPersistencia::Io { .. }) = otra.eliminar_atencion("", "", "Resfriado leve")
+        else { panic!("No se puede escribir en la carpeta") };
+
+        let Err(ErrorNewVeterinaria::ArchivoAtenciones(ErrorPersistencia::Io { .. })) =
+            Veterinaria::new_con_almacen("Memoria", "asd", 1, None, Some(vec![atencion]), carpeta.almacen_inaccesible())
+        else { panic!("No se puede escribir en la carpeta") };
+    }
+}
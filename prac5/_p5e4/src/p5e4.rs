@@ -1,253 +1,11 @@
 
 
 
-//
-// fecha.rs
-//
-
-use std::cmp::Ordering;
-use std::cmp::Ordering::{Equal, Greater, Less};
 use std::collections::BTreeMap;
-use std::{fmt, fs, io};
 use std::collections::btree_map::Entry::Vacant;
-use std::error::Error;
-use std::fs::File;
-use std::io::Read;
 use serde::{Deserialize, Serialize};
-use serde::de::DeserializeOwned;
-use serde_json::Value;
-
-const NOMBRE_MESES: [&str; 12] = ["Enero", "Febrero", "Marzo", "Abril",
-    "Mayo", "Junio", "Julio", "Agosto",
-    "Septiembre", "Octubre", "Noviembre", "Diciembre"];
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
-pub struct Fecha {
-    pub(crate) dia: u8,
-    pub(crate) mes: u8,
-    pub(crate) ano: i64
-}
-
-impl Default for Fecha {
-    fn default() -> Self {
-        Fecha { dia: 1, mes: 1, ano: 0 }
-    }
-}
-
-impl PartialOrd for Fecha {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.dia == other.dia
-            && self.mes == other.mes
-            && self.ano == other.ano
-        { return Some(Equal) }
-
-        if self.ano > other.ano { return Some(Greater) }
-        if self.mes > other.mes { return Some(Greater) }
-        if self.dia > other.dia { return Some(Greater) }
-
-        Some(Less)
-    }
-}
-
-impl fmt::Display for Fecha {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.es_fecha_valida() {
-            write!(f, "{} de {} del {}", self.dia, NOMBRE_MESES[self.mes as usize - 1], self.ano)
-        } else {
-            write!(f, "{}/{}/{}", self.dia, self.mes, self.ano)
-        }
-    }
-}
-
-impl Fecha {
-
-    // El año podría ser negativo, indicando días antes de Cristo.
-    pub fn new(dia: u8, mes: u8, ano: i64) -> Option<Fecha> {
-        let fecha = Fecha { dia, mes, ano };
-        if fecha.es_fecha_valida() {
-            return Some(fecha);
-        }
-        None
-    }
-
-    pub fn es_fecha_valida(&self) -> bool {
-        // check que el mes sea válido
-        if !(1..=12).contains(&self.mes) { return false }
-
-        // check días del mes
-        if self.dia == 0
-            || self.dia > self.dias_mes_actual()
-        { return false }
-
-        // el año no puede ser incorrecto...
-        // a no ser que se contabilice la edad del universo
-        // que dudo mucho que pueda importar para este caso
-        true
-    }
-
-    pub fn es_bisiesto(&self) -> bool {
-        self.ano % 4 == 0
-    }
-
-    pub fn sumar_dias(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            let dias_mes_actual = self.dias_mes_actual();
-            let dias_para_proximo_mes = u32::from(dias_mes_actual - self.dia + 1);
-
-            if dias_restantes >= dias_para_proximo_mes {
-                // ir al siguiente mes
-
-                dias_restantes-= dias_para_proximo_mes;
-                self.dia = 1;
-                self.mes += 1;
-
-                if self.mes > 12 {
-                    self.mes = 1;
-                    self.ano+= 1;
-                }
-            } else {
-                self.dia += u8::try_from(dias_restantes).unwrap_or(0);
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn restar_dias(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            if dias_restantes >= u32::from(self.dia) {
-                // ir al anterior mes
-                dias_restantes-= u32::from(self.dia);
-                self.mes-= 1;
-
-                if self.mes < 1 {
-                    self.mes = 12;
-                    self.ano-= 1;
-                }
-
-                // corregir self.dia == 0
-                self.dia = self.dias_mes_actual();
-            } else {
-                self.dia-= u8::try_from(dias_restantes).unwrap_or(0);
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn dias_mes_actual(&self) -> u8 {
-        match self.mes {
-            4 | 6 | 9 | 11 => 30,
-            2 => if self.es_bisiesto() { 29 } else { 28 },
-            _ => 31,
-        }
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_default() {
-        // default: 01/01/0000
-        let default_fecha = Fecha::default();
-        assert_eq!(default_fecha.dia, 1);
-        assert_eq!(default_fecha.mes, 1);
-        assert_eq!(default_fecha.ano, 0);
-    }
-
-    #[test]
-    fn test_display() {
-        let valid_fecha = Fecha::default();
-        let invalid_fecha = Fecha { dia: 0, mes: 1, ano: 0 };
-
-        // write!(f, "{} de {} del {}", self.dia, NOMBRE_MESES[self.mes as usize - 1], self.ano)
-
-        assert_ne!(format!("{valid_fecha}"), format!("{}", invalid_fecha));
-        assert_eq!(format!("{valid_fecha}"), format!("{} de {} del {}", valid_fecha.dia, NOMBRE_MESES[valid_fecha.mes as usize - 1], valid_fecha.ano));
-        assert_eq!(format!("{invalid_fecha}"), format!("{}/{}/{}", invalid_fecha.dia, invalid_fecha.mes, invalid_fecha.ano));
-    }
-
-    #[test]
-    fn test_new() {
-        // invalida
-        let fecha = Fecha::new(0, 0, 0);
-        assert!(fecha.is_none());
-
-        // valida
-        let fecha = Fecha::new(22, 08, 2002);
-        assert!(fecha.is_some());
-    }
-
-    #[test]
-    fn test_bisiesto() {
-        let Some(fecha) = Fecha::new(1, 1, 0) else { panic!() };
-        assert!(fecha.es_bisiesto());
-
-        let Some(fecha) = Fecha::new(1, 1, 2000) else { panic!() };
-        assert!(fecha.es_bisiesto());
-
-        let Some(fecha) = Fecha::new(1, 1, -4) else { panic!() };
-        assert!(fecha.es_bisiesto());
-
-        let Some(fecha) = Fecha::new(1, 1, 1) else { panic!() };
-        assert!(!fecha.es_bisiesto());
-    }
-
-    #[test]
-    fn test_restar_dias() {
-        let Some(mut fecha) = Fecha::new(30, 04, 2016) else { panic!() };
-
-        fecha.restar_dias(5000);
-
-        assert_eq!(fecha.dia, 22);
-        assert_eq!(fecha.mes, 08);
-        assert_eq!(fecha.ano, 2002);
-    }
-
-    #[test]
-    fn test_sumar_dias() {
-        let Some(mut fecha) = Fecha::new(22, 08, 2002) else { panic!() };
-
-        fecha.sumar_dias(5000);
-
-        assert_eq!(fecha.dia, 30);
-        assert_eq!(fecha.mes, 04);
-        assert_eq!(fecha.ano, 2016);
-    }
-
-    #[test]
-    fn test_dias_mes_actual() {
-        let Some(fecha) = Fecha::new(22, 01, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 02, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 28);
-        let Some(fecha) = Fecha::new(22, 02, 2004) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 29);
-        let Some(fecha) = Fecha::new(22, 03, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 04, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 05, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 06, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 07, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 08, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 09, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 10, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 11, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 12, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-    }
-}
+use calendario::{CalendarioHabil, Fecha, FechaHora, RangoFechas, Reloj, RelojCompartido, Ventana};
+use persistencia::{AlmacenCompartido, Documento, ErrorPersistencia, Repositorio};
 
 //
 // libro.rs
@@ -295,13 +53,17 @@ impl Libro {
 /// Para registrar un préstamo se requiere:
 ///     el libro,
 ///     el cliente,
+///     el momento en que se realizó,
 ///     la fecha de vencimiento del préstamo,
 ///     la fecha de devolución
 ///     y el estado (devuelto o en préstamo)
 #[derive(Serialize, Deserialize, Clone, PartialEq, PartialOrd, Debug)]
+#[allow(clippy::struct_field_names)] // `fecha` a secas no dice si es la del préstamo o la de devolución
 pub struct Prestamo {
     pub isbn: u64, // isbn
     pub cliente: u32, // id
+    #[serde(default)] // los archivos viejos no lo guardaban
+    pub fecha_prestamo: FechaHora,
     pub vencimiento: Fecha,
     pub estado: EstadoPrestamo
 }
@@ -313,8 +75,8 @@ pub enum EstadoPrestamo {
 
 impl Prestamo {
 
-    pub fn new(isbn: u64, cliente: u32, vencimiento: Fecha, estado: EstadoPrestamo) -> Prestamo {
-        Prestamo { isbn, cliente, vencimiento, estado }
+    pub fn new(isbn: u64, cliente: u32, fecha_prestamo: FechaHora, vencimiento: Fecha, estado: EstadoPrestamo) -> Prestamo {
+        Prestamo { isbn, cliente, fecha_prestamo, vencimiento, estado }
     }
 
 }
@@ -345,154 +107,51 @@ impl Cliente {
 // biblioteca_fm.rs
 //
 
-#[derive(Debug, Clone, PartialEq)]
-enum DataBiblioteca<'a> {
-    Libros (&'a Libros),
-    Clientes (&'a Clientes),
-}
-
-impl<'a> DataBiblioteca<'a> {
-    fn is_libros(&self) -> bool {
-        matches!(self,DataBiblioteca::Libros(_))
-    }
-
-    fn is_prestamos(&self) -> bool {
-        !self.is_libros()
-    }
-
-    fn unwrap_libros(self) -> &'a Libros {
-        match self {
-            DataBiblioteca::Libros(data) => { data }
-            DataBiblioteca::Clientes(_) => { panic!("Self matches DataBiblioteca::Clientes, but DataBiblioteca::Libros was required.") },
-        }
-    }
-
-    fn unwrap_prestamos(self) -> &'a Clientes {
-        match self {
-            DataBiblioteca::Libros(_) => { panic!("Self matches DataBiblioteca::Libros, but DataBiblioteca::Clientes was required.") },
-            DataBiblioteca::Clientes(data) => { data },
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub enum ResultSobreescribirArchivo {
-    Success,
-    IOError, //io::Error == ()
-    SerializationError,
-}
-
-#[derive(Debug, Clone)]
-pub enum ErrorLeerArchivo {
-    IOError,
-    DeserializationError,
-}
-
-impl PartialEq for ResultSobreescribirArchivo {
-    fn eq(&self, other: &Self) -> bool {
-        std::mem::discriminant(self) == std::mem::discriminant(other)
-    }
-}
-
-impl PartialEq for ErrorLeerArchivo {
-    fn eq(&self, other: &Self) -> bool {
-        std::mem::discriminant(self) == std::mem::discriminant(other)
-    }
-}
-
-const BASE_FOLDER: &str = "";
-const FILE_NAME_FORMAT: &str = "{}_{}.json"; // {biblioteca.nombre}_{libros/prestamos}.json
 const LIBROS_FILENAME: &str = "libros";
 const CLIENTES_FILENAME: &str = "clientes";
-fn archivo_filepath(library_name: &str, file_kind: &str) -> String {
-    format!("{BASE_FOLDER}{library_name}_{file_kind}.json")
-}
-
-//
-// IO part
-//
-
-fn sobreescribir_archivo(file_abs_path: String, data: &DataBiblioteca) -> ResultSobreescribirArchivo {
-    let json_string = match data {
-        DataBiblioteca::Libros(data) => { serde_json::to_string_pretty(data) },
-        DataBiblioteca::Clientes(data) => { serde_json::to_string_pretty(data) }
-    };
-
-    let text = match json_string {
-        Ok(text) => { text }
-        Err(error) => { return ResultSobreescribirArchivo::SerializationError }
-    };
 
-    match fs::write(file_abs_path, text) {
-        Err(error) => ResultSobreescribirArchivo::IOError,
-        _ => ResultSobreescribirArchivo::Success
-    }
+// {biblioteca.nombre}_{libros/clientes}. el almacén le agrega el .json
+fn clave_archivo(library_name: &str, file_kind: &str) -> String {
+    format!("{library_name}_{file_kind}")
 }
 
-fn leer_archivo(filepath: String) -> Result<Value, ErrorLeerArchivo> {
-    let mut file = match File::open(filepath) {
-        Ok(file) => { file }
-        Err(error) => { return Err(ErrorLeerArchivo::IOError) }
-    };
+pub trait BibliotecaFileManagement {
+    fn sobreescribir_archivo_libros(&self) -> Result<(), ErrorPersistencia>;
+    fn sobreescribir_archivo_clientes(&self) -> Result<(), ErrorPersistencia>;
+    fn leer_archivo_libros(&self) -> Result<Libros, ErrorPersistencia>;
+    fn leer_archivo_clientes(&self) -> Result<Clientes, ErrorPersistencia>;
+    fn actualizar_variable_libros(&mut self) -> Option<ErrorPersistencia>;
+    fn actualizar_variable_clientes(&mut self) -> Option<ErrorPersistencia>;
+}
 
-    let mut contents = String::new();
-    match file.read_to_string(&mut contents) {
-        Ok(_) => {},
-        Err(error) => return Err(ErrorLeerArchivo::IOError)
+impl Biblioteca {
+    fn archivo_libros(&self) -> Documento<Libros> {
+        Documento::new(self.almacen.clone(), clave_archivo(&self.nombre, LIBROS_FILENAME))
     }
 
-    match serde_json::from_str(&contents) {
-        Err(error) => Err(ErrorLeerArchivo::DeserializationError),
-        Ok(value) => Ok(value),
+    fn archivo_clientes(&self) -> Documento<Clientes> {
+        Documento::new(self.almacen.clone(), clave_archivo(&self.nombre, CLIENTES_FILENAME))
     }
 }
 
-fn leer_archivo_parsed<T>(value: Value) -> Result<T, ErrorLeerArchivo> where T: DeserializeOwned {
-    let data: T = match serde_json::from_value::<T>(value) {
-        Ok(data) => { data }
-        Err(error) => { return Err(ErrorLeerArchivo::DeserializationError) }
-    };
-
-    Ok(data)
-}
-
-//
-// Implementation / Serialization-Deserialization part
-//
-
-pub trait BibliotecaFileManagement {
-    fn sobreescribir_archivo_libros(&self) -> ResultSobreescribirArchivo;
-    fn sobreescribir_archivo_clientes(&self) -> ResultSobreescribirArchivo;
-    fn leer_archivo_libros(&self) -> Result<Libros, ErrorLeerArchivo>;
-    fn leer_archivo_clientes(&self) -> Result<Clientes, ErrorLeerArchivo>;
-    fn actualizar_variable_libros(&mut self) -> Option<ErrorLeerArchivo>;
-    fn actualizar_variable_clientes(&mut self) -> Option<ErrorLeerArchivo>;
-}
-
 impl BibliotecaFileManagement for Biblioteca {
-    fn sobreescribir_archivo_libros(&self) -> ResultSobreescribirArchivo {
-        let data = DataBiblioteca::Libros(&self.libros);
-        sobreescribir_archivo(archivo_filepath(&self.nombre, LIBROS_FILENAME), &data)
+    fn sobreescribir_archivo_libros(&self) -> Result<(), ErrorPersistencia> {
+        self.archivo_libros().guardar(&self.libros)
     }
 
-    fn sobreescribir_archivo_clientes(&self) -> ResultSobreescribirArchivo {
-        let data = DataBiblioteca::Clientes(&self.clientes);
-        sobreescribir_archivo(archivo_filepath(&self.nombre, CLIENTES_FILENAME), &data)
+    fn sobreescribir_archivo_clientes(&self) -> Result<(), ErrorPersistencia> {
+        self.archivo_clientes().guardar(&self.clientes)
     }
 
-    fn leer_archivo_libros(&self) -> Result<Libros, ErrorLeerArchivo> {
-        let lectura = leer_archivo(archivo_filepath(&self.nombre, LIBROS_FILENAME))?;
-        let libros: Libros = leer_archivo_parsed(lectura)?;
-        Ok(libros)
+    fn leer_archivo_libros(&self) -> Result<Libros, ErrorPersistencia> {
+        self.archivo_libros().cargar()
     }
 
-    fn leer_archivo_clientes(&self) -> Result<Clientes, ErrorLeerArchivo> {
-        let lectura = leer_archivo(archivo_filepath(&self.nombre, CLIENTES_FILENAME))?;
-        let clientes: Clientes = leer_archivo_parsed(lectura)?;
-        Ok(clientes)
+    fn leer_archivo_clientes(&self) -> Result<Clientes, ErrorPersistencia> {
+        self.archivo_clientes().cargar()
     }
 
-    fn actualizar_variable_libros(&mut self) -> Option<ErrorLeerArchivo> {
+    fn actualizar_variable_libros(&mut self) -> Option<ErrorPersistencia> {
         let libros = match self.leer_archivo_libros() {
             Ok(libros) => libros,
             Err(error) => return Some(error)
@@ -502,7 +161,7 @@ impl BibliotecaFileManagement for Biblioteca {
         None
     }
 
-    fn actualizar_variable_clientes(&mut self) -> Option<ErrorLeerArchivo> {
+    fn actualizar_variable_clientes(&mut self) -> Option<ErrorPersistencia> {
         let clientes = match self.leer_archivo_clientes() {
             Ok(clientes) => clientes,
             Err(error) => return Some(error)
@@ -515,12 +174,9 @@ impl BibliotecaFileManagement for Biblioteca {
 
 #[cfg(test)]
 mod tests_fm {
+    use persistencia::{Almacen, AlmacenArchivos, AlmacenMemoria, CarpetaTemporal};
     use super::*;
 
-    const TEST_FAKE_PATH: &str = "Z:/CalleFalsa123/AvenidaSiempreviva742/";
-    const TEST_LIBROS_FILE_NAME: &str = "libros_testfile.json";
-    const TEST_CLIENTES_FILE_NAME: &str = "clientes_testfile.json";
-
     fn libro_economia_1() -> Libro {
         Libro::new(
             1,
@@ -573,6 +229,7 @@ mod tests_fm {
         Prestamo::new(
             1,
             1,
+            FechaHora::default(),
             Fecha { dia: 22, mes: 8, ano: 2002 },
             EstadoPrestamo::Prestando
         )
@@ -581,6 +238,7 @@ mod tests_fm {
         Prestamo::new(
             2,
             2,
+            FechaHora::default(),
             Fecha { dia: 23, mes: 6, ano: 2025 },
             EstadoPrestamo::Prestando
         )
@@ -589,6 +247,7 @@ mod tests_fm {
         Prestamo::new(
             3,
             2,
+            FechaHora::default(),
             Fecha { dia: 1, mes: 1, ano: 2025 },
             EstadoPrestamo::Devuelto( Fecha { dia: 19, mes: 11, ano: 2023 } )
         )
@@ -600,191 +259,107 @@ mod tests_fm {
         ])
     }
 
-    fn escribir_archivo_libros() -> ResultSobreescribirArchivo {
-        let test_libros = testdata_libros();
-        let test_data = DataBiblioteca::Libros(&test_libros);
-        sobreescribir_archivo(format!("{BASE_FOLDER}{TEST_LIBROS_FILE_NAME}"), &test_data)
-    }
-    fn escribir_archivo_clientes() -> ResultSobreescribirArchivo {
-        let test_clientes = testdata_clientes();
-        let test_data = DataBiblioteca::Clientes(&test_clientes);
-        sobreescribir_archivo(format!("{BASE_FOLDER}{TEST_CLIENTES_FILE_NAME}"), &test_data)
+    fn biblioteca_test(almacen: impl Into<AlmacenCompartido>) -> Biblioteca {
+        Biblioteca {
+            nombre: "testfile".to_string(),
+            libros: testdata_libros(),
+            clientes: testdata_clientes(),
+            almacen: almacen.into(),
+            ..Default::default()
+        }
     }
 
     #[test]
     fn test_escribir_archivo() {
-        // libros
+        let carpeta = CarpetaTemporal::new("p5e4_escribir");
+        let biblioteca_fail = biblioteca_test(carpeta.almacen_inaccesible());
+        let biblioteca_good = biblioteca_test(AlmacenMemoria::new());
 
-        let test_libros = testdata_libros();
-        let test_libros_data = DataBiblioteca::Libros(&test_libros);
+        // libros
 
-        let result_libros_fail = sobreescribir_archivo(format!("{TEST_FAKE_PATH}{TEST_LIBROS_FILE_NAME}"), &test_libros_data);
-        let result_libros_good = escribir_archivo_libros();
+        let Err(ErrorPersistencia::Io { .. }) = biblioteca_fail.sobreescribir_archivo_libros()
+        else { panic!("Shouldn't be success. Path is a file, not a folder!") };
 
-        if let ResultSobreescribirArchivo::Success = result_libros_fail { panic!("Shouldn't be success. Fake path does not exist!") }
-
-        match result_libros_good {
-            ResultSobreescribirArchivo::Success => {}
-            _ => { panic!("Shouldn't throw error") }
-        }
+        assert_eq!(biblioteca_good.sobreescribir_archivo_libros(), Ok(()), "Shouldn't throw error");
 
         // clientes
 
-        let test_clientes = testdata_libros();
-        let test_clientes_data = DataBiblioteca::Libros(&test_clientes);
-
-        let result_clientes_fail = sobreescribir_archivo(format!("{TEST_FAKE_PATH}{TEST_LIBROS_FILE_NAME}"), &test_libros_data);
-        let result_clientes_good = escribir_archivo_clientes();
+        let Err(ErrorPersistencia::Io { .. }) = biblioteca_fail.sobreescribir_archivo_clientes()
+        else { panic!("Shouldn't be success. Path is a file, not a folder!") };
 
-        if let ResultSobreescribirArchivo::Success = result_clientes_fail { panic!("Shouldn't be success. Fake path does not exist!") }
-
-        match result_clientes_good {
-            ResultSobreescribirArchivo::Success => {}
-            _ => { panic!("Shouldn't throw error") }
-        }
+        assert_eq!(biblioteca_good.sobreescribir_archivo_clientes(), Ok(()), "Shouldn't throw error");
     }
 
     #[test]
     fn test_leer_archivo() {
-        // inicializar ambos libros con funciones ya testeadas
-        escribir_archivo_libros();
-        escribir_archivo_clientes();
+        let almacen = AlmacenMemoria::new();
+        let biblioteca = biblioteca_test(almacen.clone());
 
-        // libros
+        let Err(ErrorPersistencia::NoExiste { clave }) = biblioteca.leer_archivo_libros()
+        else { panic!("Todavía no se escribió") };
+        assert_eq!(clave, "testfile_libros");
 
-        let Ok(value_libros_good) = leer_archivo(format!("{BASE_FOLDER}{TEST_LIBROS_FILE_NAME}"))
-        else { panic!("Shouldn't throw an error") };
+        // inicializar ambos archivos con funciones ya testeadas
+        biblioteca.sobreescribir_archivo_libros().expect("Shouldn't throw an error");
+        biblioteca.sobreescribir_archivo_clientes().expect("Shouldn't throw an error");
 
-        let Err(value_libros_fail) = leer_archivo(format!("{TEST_FAKE_PATH}{TEST_LIBROS_FILE_NAME}"))
-        else { panic!("Should throw error.") };
+        assert_eq!(biblioteca.leer_archivo_libros(), Ok(testdata_libros()));
+        assert_eq!(biblioteca.leer_archivo_clientes(), Ok(testdata_clientes()));
 
-        let result_libros_good: Libros = match leer_archivo_parsed::<Libros>(value_libros_good.clone()) {
-            Ok(res) => { res }
-            _ => { panic!("Shouln't throw an error") }
-        };
-
-        assert!(leer_archivo_parsed::<Clientes>(value_libros_good).is_err(), "Should throw an error");
-
-        // clientes
+        // el archivo de libros no tiene la forma de Clientes
+        let libros_como_clientes: Documento<Clientes> = Documento::new(almacen, clave_archivo("testfile", LIBROS_FILENAME));
+        let Err(ErrorPersistencia::Deserializacion { .. }) = libros_como_clientes.cargar()
+        else { panic!("Should throw an error") };
 
-        let Ok(value_clientes_good) = leer_archivo(format!("{BASE_FOLDER}{TEST_CLIENTES_FILE_NAME}"))
-        else { panic!("Shouldn't throw an error") };
-
-        let Err(value_clientes_fail) = leer_archivo(format!("{TEST_FAKE_PATH}{TEST_CLIENTES_FILE_NAME}"))
+        // en una carpeta que no existe, tampoco existe el archivo
+        let carpeta = CarpetaTemporal::new("p5e4_leer");
+        let Err(ErrorPersistencia::NoExiste { .. }) = biblioteca_test(AlmacenArchivos::new(carpeta.ruta().join("no_existe"))).leer_archivo_libros()
         else { panic!("Should throw error.") };
-
-        let result_clientes_good: Clientes = match leer_archivo_parsed::<Clientes>(value_clientes_good.clone()) {
-            Ok(res) => { res }
-            _ => { panic!("Shouln't throw an error") }
-        };
-
-        assert!(leer_archivo_parsed::<Libros>(value_clientes_good).is_err(), "Should throw an error");
     }
 
     #[test]
-    fn test_archivo_filepath() {
+    fn test_clave_archivo() {
         let library_name = "test_library";
 
-        assert_eq!(
-            archivo_filepath(library_name, TEST_LIBROS_FILE_NAME),
-            format!("{BASE_FOLDER}{library_name}_{TEST_LIBROS_FILE_NAME}.json")
-        );
-        assert_eq!(
-            archivo_filepath(library_name, TEST_CLIENTES_FILE_NAME),
-            format!("{BASE_FOLDER}{library_name}_{TEST_CLIENTES_FILE_NAME}.json")
-        );
-    }
-
-    #[test]
-    fn test_data_biblioteca_methods() {
-        let test_libros = testdata_libros();
-        let test_clientes = testdata_clientes();
-
-        let data_libros = DataBiblioteca::Libros(&test_libros);
-        let data_clientes = DataBiblioteca::Clientes(&test_clientes);
-
-        // Test is_libros()
-        assert!(data_libros.is_libros());
-        assert!(!data_clientes.is_libros());
-
-        // Test is_prestamos()
-        assert!(data_clientes.is_prestamos());
-        assert!(!data_libros.is_prestamos());
-
-        // Test unwrap_libros()
-        assert_eq!(data_libros.clone().unwrap_libros(), &test_libros);
-
-        // Test unwrap_prestamos()
-        assert_eq!(data_clientes.clone().unwrap_prestamos(), &test_clientes);
-
-        // Test unwrap panics
-        let data_libros_clone = data_libros.clone();
-        let data_clientes_clone = data_clientes.clone();
-
-        std::panic::catch_unwind(move || {
-            data_libros_clone.unwrap_prestamos();
-        }).expect_err("Should panic when unwrapping libros as prestamos");
-
-        std::panic::catch_unwind(move || {
-            data_clientes_clone.unwrap_libros();
-        }).expect_err("Should panic when unwrapping prestamos as libros");
-    }
-
-    #[test]
-    fn test_error_comparisons() {
-        // Test ResultSobreescribirArchivo equality
-        let success1 = ResultSobreescribirArchivo::Success;
-        let success2 = ResultSobreescribirArchivo::Success;
-        let io_error = ResultSobreescribirArchivo::IOError;
-
-        assert_eq!(success1, success2);
-        assert_ne!(success1, io_error);
-
-        // Test ErrorLeerArchivo equality
-        let io_error1 = ErrorLeerArchivo::IOError;
-        let io_error2 = ErrorLeerArchivo::IOError;
-
-        assert_eq!(io_error1, io_error2);
+        assert_eq!(clave_archivo(library_name, LIBROS_FILENAME), "test_library_libros");
+        assert_eq!(clave_archivo(library_name, CLIENTES_FILENAME), "test_library_clientes");
     }
 
     #[test]
     fn test_integration_read_write() {
-        // Write test data
-        let write_result_libros = escribir_archivo_libros();
-        assert!(matches!(write_result_libros, ResultSobreescribirArchivo::Success));
-
-        let write_result_clientes = escribir_archivo_clientes();
-        assert!(matches!(write_result_clientes, ResultSobreescribirArchivo::Success));
-
-        // Read and verify libros
-        let Ok(libros_value) = leer_archivo(format!("{BASE_FOLDER}{TEST_LIBROS_FILE_NAME}")) else {
-            panic!("Failed to read libros file");
-        };
-        let read_libros: Libros = leer_archivo_parsed(libros_value)
-            .expect("Failed to parse libros data");
+        let almacen = AlmacenMemoria::new();
 
-        assert_eq!(read_libros, testdata_libros());
+        // Write test data
+        let biblioteca = Biblioteca::new_con_almacen("testfile".to_string(), "test address".to_string(), Some(testdata_libros()), Some(testdata_clientes()), almacen.clone())
+            .expect("Shouldn't throw an error");
+        assert_eq!(almacen.claves(), vec!["testfile_clientes".to_string(), "testfile_libros".to_string()]);
 
-        // Read and verify clientes
-        let Ok(clientes_value) = leer_archivo(format!("{BASE_FOLDER}{TEST_CLIENTES_FILE_NAME}")) else {
-            panic!("Failed to read clientes file");
-        };
-        let read_clientes: Clientes = leer_archivo_parsed(clientes_value)
-            .expect("Failed to parse clientes data");
+        // Read and verify: otra biblioteca con el mismo nombre y almacén lee lo mismo
+        let leida = Biblioteca::new_con_almacen("testfile".to_string(), "test address".to_string(), None, None, almacen.clone())
+            .expect("Should read both files");
+        assert_eq!(leida.libros, testdata_libros());
+        assert_eq!(leida.clientes, testdata_clientes());
+        assert_eq!(leida, biblioteca);
 
-        assert_eq!(read_clientes, testdata_clientes());
+        // un archivo de una versión que este programa no conoce: error, no una biblioteca vacía
+        let Ok(()) = almacen.escribir("testfile_libros", &serde_json::json!({ "version": 99, "data": {} })) else { panic!() };
+        let Err(ErrorPersistencia::Version { encontrada: 99, .. }) =
+            Biblioteca::new_con_almacen("testfile".to_string(), "test address".to_string(), None, None, almacen)
+        else { panic!("Should fail loudly") };
     }
 
     #[test]
     fn test_biblioteca_file_management() {
         // First ensure we have test data in files
 
-        let mut biblioteca = Biblioteca::new(
+        let carpeta = CarpetaTemporal::new("p5e4_file_management");
+        let mut biblioteca = Biblioteca::new_con_almacen(
             "testlibrary".to_string(),
             "test address".to_string(),
             Some(testdata_libros()),
-            Some(testdata_clientes())
-        );
+            Some(testdata_clientes()),
+            carpeta.almacen()
+        ).expect("Shouldn't throw an error");
 
         // Test leer_archivo_libros()
         let result_libros = biblioteca.leer_archivo_libros();
@@ -810,41 +385,28 @@ mod tests_fm {
 
         // libros
         std::fs::write(
-            format!("{BASE_FOLDER}testlibrary_libros.json"),
+            carpeta.ruta().join("testlibrary_libros.json"),
             "{invalid json"
         ).expect("Failed to write invalid JSON");
 
         let result_invalid = biblioteca.actualizar_variable_libros();
-        assert!(result_invalid.is_some(), "Should return error when parsing invalid JSON");
+        assert!(matches!(result_invalid, Some(ErrorPersistencia::Deserializacion { .. })), "Should return error when parsing invalid JSON");
 
         // Clean up test file
-        std::fs::remove_file(format!("{BASE_FOLDER}testlibrary_libros.json"))
+        std::fs::remove_file(carpeta.ruta().join("testlibrary_libros.json"))
             .expect("Failed to clean up test file");
 
-        std::fs::write(
-            format!("{BASE_FOLDER}testlibrary_libros.json"),
-            "{invalid json"
-        ).expect("Failed to write invalid JSON");
-
-        let result_invalid = biblioteca.actualizar_variable_libros();
-        assert!(result_invalid.is_some(), "Should return error when parsing invalid JSON");
-
-        // Clean up test file
-        std::fs::remove_file(format!("{BASE_FOLDER}testlibrary_libros.json"))
-            .expect("Failed to clean up test file");
+        let result_missing = biblioteca.actualizar_variable_libros();
+        assert!(matches!(result_missing, Some(ErrorPersistencia::NoExiste { .. })), "Should return error when the file is missing");
 
         // clientes
         std::fs::write(
-            format!("{BASE_FOLDER}testlibrary_clientes.json"),
+            carpeta.ruta().join("testlibrary_clientes.json"),
             "{invalid json"
         ).expect("Failed to write invalid JSON");
 
         let result_invalid = biblioteca.actualizar_variable_clientes();
         assert!(result_invalid.is_some(), "Should return error when parsing invalid JSON");
-
-        // Clean up test file
-        std::fs::remove_file(format!("{BASE_FOLDER}testlibrary_clientes.json"))
-            .expect("Failed to clean up test file");
     }
 }
 
@@ -859,13 +421,22 @@ const MAX_PRESTAMOS_ACTIVOS: usize = 5;
 /// `nombre: String` - Nombre de la biblioteca<br>
 /// `direccion: String` - Dirección física de la biblioteca<br>
 /// `libros: BTreeMap<u64, Libro>` - Libros de la biblioteca.<br>
-/// `prestamos: BTreeMap<u32, (Cliente, Vec<Prestamo>)>` -> `BTreeMap<ID del cliente, (Cliente, Vec<Prestamo>)>`
+/// `prestamos: BTreeMap<u32, (Cliente, Vec<Prestamo>)>` -> `BTreeMap<ID del cliente, (Cliente, Vec<Prestamo>)>`<br>
+/// `reloj: RelojCompartido` - De dónde sale la fecha de hoy. Por defecto, el reloj del sistema<br>
+/// `calendario_habil: CalendarioHabil` - Qué días abre la biblioteca. Por defecto, fines de semana y feriados de Argentina<br>
+/// `almacen: AlmacenCompartido` - Dónde se guardan los libros y los clientes. Por defecto, archivos JSON en la carpeta actual
 #[derive(Serialize, Deserialize, Default, Clone, PartialEq, PartialOrd, Debug)]
 pub struct Biblioteca {
     pub nombre: String,
     pub direccion: String,
     pub libros: BTreeMap<u64, Libro>,
-    pub clientes: BTreeMap<u32, (Cliente, Vec<Prestamo>)> // <ID cliente, (Cliente, Vec<Préstamo>)>
+    pub clientes: BTreeMap<u32, (Cliente, Vec<Prestamo>)>, // <ID cliente, (Cliente, Vec<Préstamo>)>
+    #[serde(skip)]
+    reloj: RelojCompartido,
+    #[serde(skip)]
+    calendario_habil: CalendarioHabil,
+    #[serde(skip)]
+    almacen: AlmacenCompartido
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -880,7 +451,9 @@ pub enum ErrorIncrementarStock {
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum ErrorRealizarPrestamo {
-    PrestamosMaximosAlcanzados, StockInsuficiente, ClienteInexistente, LibroNoExiste
+    PrestamosMaximosAlcanzados, StockInsuficiente, ClienteInexistente, LibroNoExiste,
+    /// el calendario de la biblioteca no tiene ningún día hábil
+    SinDiasHabiles
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
@@ -897,13 +470,13 @@ pub enum ErrorDevolverLibro {
 
 #[derive(Debug)]
 pub enum ResultRegistrarLibro {
-    Exito{ resultado_fm: ResultSobreescribirArchivo },
+    Exito{ resultado_fm: Result<(), ErrorPersistencia> },
     LibroYaExiste,
 }
 
 #[derive(Debug)]
 pub enum ResultRegistrarCliente {
-    Exito{ resultado_fm: ResultSobreescribirArchivo },
+    Exito{ resultado_fm: Result<(), ErrorPersistencia> },
     ClienteYaExiste,
 }
 
@@ -911,7 +484,7 @@ pub enum ResultRegistrarCliente {
 // specifically, the functions that make use if the File Management system.
 pub enum DoubleError<T> {
     LocalError(T),
-    RemoteError(ResultSobreescribirArchivo),
+    RemoteError(ErrorPersistencia),
 }
 
 impl<T> DoubleError<T> {
@@ -930,7 +503,7 @@ impl<T> From<T> for DoubleError<T> {
 }
 
 impl<T> DoubleError<T> {
-    fn from(value: ResultSobreescribirArchivo) -> Self {
+    fn from(value: ErrorPersistencia) -> Self {
         Self::RemoteError(value)
     }
 }
@@ -948,34 +521,62 @@ impl Biblioteca {
     /// - `direccion` - Dirección de la biblioteca
     /// - `libros` - Opcional: Lista de libros de la biblioteca.
     /// - `prestamos` - Opcional: Lista de préstamos de la biblioteca<br>
-    ///   * Si `libros` o `prestamos` son None, intentará leer la información individualmente de disco. De no poder, creará un conjunto vacío.
+    ///   * Si `libros` o `prestamos` son None, intentará leer la información individualmente de disco. Si todavía no hay archivo, creará un conjunto vacío.
     ///   * Si `libros` o `prestamos` son Some(data), creará un nuevo archivo que contenga data.
     ///
     /// #### Devuelve:
-    /// `Biblioteca` - Nueva instancia de Biblioteca
-    pub fn new(nombre: String, direccion: String, libros: Option<Libros>, clientes: Option<Clientes>) -> Biblioteca {
+    /// `Ok(Biblioteca)` - Nueva instancia de Biblioteca<br>
+    /// `Err(ErrorPersistencia)` - Si un archivo existe pero no se pudo leer (roto, o de una versión sin migración),
+    /// o si no se pudo guardar. Nunca se reemplazan datos ilegibles por un conjunto vacío.
+    pub fn new(nombre: String, direccion: String, libros: Option<Libros>, clientes: Option<Clientes>) -> Result<Biblioteca, ErrorPersistencia> {
+        Biblioteca::new_con_almacen(nombre, direccion, libros, clientes, AlmacenCompartido::default())
+    }
+
+    /// ### `fn new_con_almacen(nombre, direccion, libros, clientes, almacen) -> Result<Biblioteca, ErrorPersistencia>`
+    /// Igual que `new`, pero los archivos se leen y se escriben en `almacen`
+    /// (por ejemplo, un `AlmacenMemoria` en los tests)
+    pub fn new_con_almacen(nombre: String, direccion: String, libros: Option<Libros>, clientes: Option<Clientes>, almacen: impl Into<AlmacenCompartido>) -> Result<Biblioteca, ErrorPersistencia> {
         let mut biblioteca = Biblioteca {
             nombre,
             direccion,
             libros: Libros::new(),
-            clientes: Clientes::new()
+            clientes: Clientes::new(),
+            reloj: RelojCompartido::default(),
+            calendario_habil: CalendarioHabil::default(),
+            almacen: almacen.into()
         };
 
         if let Some(data) = libros {
             biblioteca.libros = data;
-            biblioteca.sobreescribir_archivo_libros();
+            biblioteca.sobreescribir_archivo_libros()?;
         } else {
-            biblioteca.libros = biblioteca.leer_archivo_libros().unwrap_or_default();
+            biblioteca.libros = biblioteca.archivo_libros().cargar_o_default()?;
         }
 
         if let Some(data) = clientes {
             biblioteca.clientes = data;
-            biblioteca.sobreescribir_archivo_clientes();
+            biblioteca.sobreescribir_archivo_clientes()?;
         } else {
-            biblioteca.clientes = biblioteca.leer_archivo_clientes().unwrap_or_default();
+            biblioteca.clientes = biblioteca.archivo_clientes().cargar_o_default()?;
         }
 
-        biblioteca
+        Ok(biblioteca)
+    }
+
+    /// ### `fn con_reloj(reloj) -> Biblioteca`
+    /// Reemplaza el reloj del sistema por otro (por ejemplo, un `RelojFijo` en los tests)
+    #[must_use]
+    pub fn con_reloj(mut self, reloj: impl Into<RelojCompartido>) -> Biblioteca {
+        self.reloj = reloj.into();
+        self
+    }
+
+    /// ### `fn con_calendario_habil(calendario) -> Biblioteca`
+    /// Reemplaza el calendario de días hábiles (por ejemplo, uno cargado con `CalendarioHabil::cargar`)
+    #[must_use]
+    pub fn con_calendario_habil(mut self, calendario_habil: CalendarioHabil) -> Biblioteca {
+        self.calendario_habil = calendario_habil;
+        self
     }
 
     pub fn registrar_libro(&mut self, libro: Libro) -> ResultRegistrarLibro {
@@ -1026,9 +627,8 @@ impl Biblioteca {
             None => return Err(ErrorDecrementarStock::LibroNoExiste.into()),
         };
 
-        match self.sobreescribir_archivo_libros() {
-            ResultSobreescribirArchivo::Success => (),
-            x => return Err(DoubleError::RemoteError(x))
+        if let Err(error) = self.sobreescribir_archivo_libros() {
+            return Err(DoubleError::RemoteError(error))
         }
 
         Ok(nuevo_stock)
@@ -1056,9 +656,8 @@ impl Biblioteca {
             None => return Err(ErrorIncrementarStock::LibroNoExiste.into())
         };
 
-        match self.sobreescribir_archivo_libros() {
-            ResultSobreescribirArchivo::Success => (),
-            x => return Err(DoubleError::from(x))
+        if let Err(error) = self.sobreescribir_archivo_libros() {
+            return Err(DoubleError::from(error))
         }
 
         Ok(nuevo_stock)
@@ -1124,7 +723,7 @@ impl Biblioteca {
         datos_cliente.1.retain(|p| p.isbn != isbn);
 
         // realizar préstamo
-        let prestamo = Prestamo::new(isbn, id_cliente, vencimiento, EstadoPrestamo::Prestando);
+        let prestamo = Prestamo::new(isbn, id_cliente, self.reloj.ahora(), vencimiento, EstadoPrestamo::Prestando);
         datos_cliente.1.push(prestamo);
 
         // reducir stock
@@ -1132,14 +731,12 @@ impl Biblioteca {
             libro.stock-= 1;
         }
 
-        match self.sobreescribir_archivo_libros() {
-            ResultSobreescribirArchivo::Success => (),
-            x => return Err(DoubleError::from(x))
+        if let Err(error) = self.sobreescribir_archivo_libros() {
+            return Err(DoubleError::from(error))
         }
 
-        match self.sobreescribir_archivo_clientes() {
-            ResultSobreescribirArchivo::Success => (),
-            x => return Err(DoubleError::from(x))
+        if let Err(error) = self.sobreescribir_archivo_clientes() {
+            return Err(DoubleError::from(error))
         }
 
         Ok(cant_libros_no_devueltos + 1)
@@ -1246,18 +843,97 @@ impl Biblioteca {
             libro.stock
         } else { 0 };
 
-        match self.sobreescribir_archivo_libros() {
-            ResultSobreescribirArchivo::Success => (),
-            x => return Err(DoubleError::from(x))
+        if let Err(error) = self.sobreescribir_archivo_libros() {
+            return Err(DoubleError::from(error))
         }
 
-        match self.sobreescribir_archivo_clientes() {
-            ResultSobreescribirArchivo::Success => (),
-            x => return Err(DoubleError::from(x))
+        if let Err(error) = self.sobreescribir_archivo_clientes() {
+            return Err(DoubleError::from(error))
         }
 
         Ok(stock_libro)
     }
+
+    //
+    // mismas operaciones, pero la fecha de hoy la da el reloj de la biblioteca.
+    // las versiones con fecha explícita quedan para cargar operaciones pasadas.
+    //
+
+    /// ### `fn prestamos_por_vencer_hoy(dias) -> Vec<&Prestamo>`
+    /// `prestamos_por_vencer` desde la fecha de hoy según el reloj de la biblioteca
+    pub fn prestamos_por_vencer_hoy(&self, dias: u32) -> Vec<&Prestamo> {
+        self.prestamos_por_vencer(self.reloj.hoy(), dias)
+    }
+
+    /// ### `fn prestamos_vencidos_hoy() -> Vec<&Prestamo>`
+    /// `prestamos_vencidos` a la fecha de hoy según el reloj de la biblioteca
+    pub fn prestamos_vencidos_hoy(&self) -> Vec<&Prestamo> {
+        self.prestamos_vencidos(self.reloj.hoy())
+    }
+
+    /// ### `fn devolver_libro_hoy(isbn, id_cliente) -> Result<u32, ErrorDevolverLibro>`
+    /// `devolver_libro` con la fecha de hoy según el reloj de la biblioteca
+    pub fn devolver_libro_hoy(&mut self, isbn: u64, id_cliente: u32) -> Result<u32, DoubleError<ErrorDevolverLibro>> {
+        self.devolver_libro(isbn, id_cliente, self.reloj.hoy())
+    }
+
+    //
+    // vencimientos en días hábiles, según el calendario de la biblioteca
+    //
+
+    /// ### `fn realizar_prestamo_dias_habiles(id_cliente, isbn, dias_habiles) -> Result(usize, ErrorRealizarPrestamo)`
+    /// `realizar_prestamo` con vencimiento a `dias_habiles` días hábiles de hoy
+    ///
+    /// #### Devuelve:<br>
+    /// `usize` - Cantidad de préstamos del cliente, incluyendo el recién realizado<br>
+    /// `ErrorRealizarPrestamo::SinDiasHabiles` - El calendario no tiene días hábiles
+    pub fn realizar_prestamo_dias_habiles(&mut self, id_cliente: u32, isbn: u64, dias_habiles: u32) -> Result<usize, DoubleError<ErrorRealizarPrestamo>> {
+        let Some(vencimiento) = self.calendario_habil.sumar_dias_habiles(self.reloj.hoy(), dias_habiles)
+        else { return Err(ErrorRealizarPrestamo::SinDiasHabiles.into()) };
+
+        self.realizar_prestamo(id_cliente, isbn, vencimiento)
+    }
+
+    /// ### `fn prestamos_por_vencer_dias_habiles(fecha_hoy, dias_habiles) -> Vec<&Prestamo>`
+    /// Como `prestamos_por_vencer`, pero contando solo días hábiles.
+    /// Un préstamo que vence el sábado cuenta como "por vencer" el viernes con `dias_habiles = 0`,
+    /// porque hasta el lunes no se puede devolver.
+    ///
+    /// #### Recibe:<br>
+    /// `fecha_hoy` - Fecha del día de hoy<br>
+    /// `dias_habiles` - Días hábiles en los que vencerán los préstamos devueltos<br>
+    pub fn prestamos_por_vencer_dias_habiles(&self, fecha_hoy: Fecha, dias_habiles: u32) -> Vec<&Prestamo> {
+        // el último día que cubre la consulta: el día antes del siguiente hábil
+        let Some(mut fecha_limite) = self.calendario_habil.sumar_dias_habiles(fecha_hoy, dias_habiles + 1)
+        else { return Vec::new() };
+        fecha_limite.restar_dias(1);
+
+        self.clientes.values()
+            .flat_map(|(_, prestamos)| prestamos)
+            .filter(|prestamo| prestamo.estado == EstadoPrestamo::Prestando && prestamo.vencimiento <= fecha_limite)
+            .collect()
+    }
+
+    /// ### `fn prestamos_por_periodo(ventana) -> Vec<(RangoFechas, BTreeMap<u64, u32>)>`
+    /// Cuenta cuántas veces se prestó cada libro en cada período de la ventana, según el día en que se prestó.<br>
+    /// Cuenta también los préstamos ya devueltos.
+    ///
+    /// #### Recibe:<br>
+    /// `ventana` - Los días a contar y cada cuánto cortarlos (por día, semana, mes...)<br>
+    ///
+    /// #### Devuelve:<br>
+    /// `Vec<(RangoFechas, BTreeMap<u64, u32>)>` - Cada período, en orden, con los préstamos de cada ISBN. Los libros que no se prestaron no están
+    pub fn prestamos_por_periodo(&self, ventana: &Ventana) -> Vec<(RangoFechas, BTreeMap<u64, u32>)> {
+        let prestamos = self.clientes.values()
+            .flat_map(|(_, prestamos)| prestamos)
+            .map(|prestamo| (prestamo.fecha_prestamo.fecha(), prestamo.isbn));
+
+        ventana.agrupar(prestamos).into_iter().map(|(periodo, isbns)| {
+            let mut cantidades: BTreeMap<u64, u32> = BTreeMap::new();
+            for isbn in isbns { *cantidades.entry(isbn).or_default() += 1 }
+            (periodo, cantidades)
+        }).collect()
+    }
 }
 
 //
@@ -1266,10 +942,13 @@ impl Biblioteca {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+    use calendario::{DiaSemana, RelojFijo};
+    use persistencia::AlmacenMemoria;
     use super::*;
 
     fn biblioteca_de_pepe() -> Biblioteca {
-        Biblioteca::new(
+        Biblioteca::new_con_almacen(
             "biblio de pepe".to_string(),
             "donde queda".to_string(),
             Some(BTreeMap::from(
@@ -1279,7 +958,8 @@ mod tests {
                     (4, libro_asd_4()),
                     (5, libro_estadistica_5()),
                     (u64::from(u32::MAX), libro_algo_u32max())])),
-            None)
+            None,
+            AlmacenMemoria::new()).expect("No debería fallar en memoria")
     }
     fn cliente_pepe() -> Cliente {
         Cliente::new(
@@ -1446,7 +1126,10 @@ mod tests {
             nombre: "asd".to_string(),
             direccion: "asd".to_string(),
             libros: BTreeMap::default(),
-            clientes: BTreeMap::default()
+            clientes: BTreeMap::default(),
+            reloj: RelojCompartido::default(),
+            calendario_habil: CalendarioHabil::default(),
+            almacen: AlmacenMemoria::new().into()
         };
 
         // init realizar prestamos
@@ -1461,8 +1144,8 @@ mod tests {
         match biblioteca.registrar_cliente(cliente_pepe()) {
             ResultRegistrarCliente::Exito { resultado_fm } => {
                 match resultado_fm {
-                    ResultSobreescribirArchivo::Success => {}
-                    x => panic!("No deberían haber errores. {x:?}")
+                    Ok(()) => {}
+                    Err(x) => panic!("No deberían haber errores. {x:?}")
                 }
             }
             ResultRegistrarCliente::ClienteYaExiste => { panic!("Cliente no existe") }
@@ -1471,8 +1154,8 @@ mod tests {
         match biblioteca.registrar_cliente(cliente_manuel()) {
             ResultRegistrarCliente::Exito { resultado_fm } => {
                 match resultado_fm {
-                    ResultSobreescribirArchivo::Success => {}
-                    x => panic!("No deberían haber errores. {x:?}")
+                    Ok(()) => {}
+                    Err(x) => panic!("No deberían haber errores. {x:?}")
                 }
             }
             ResultRegistrarCliente::ClienteYaExiste => { panic!("Cliente no existe") }
@@ -1484,8 +1167,10 @@ mod tests {
         let fecha5 = Fecha{ dia: 1, mes: 1, ano: 1 };
         let fecha3 = Fecha{ dia: 2, mes: 1, ano: 1 };
 
+        let antes = FechaHora::ahora();
         let Ok(p5) = biblioteca.realizar_prestamo(id_pepe, 5, fecha5) else { panic!("Deberia ser exitoso") };
         let Ok(p3) = biblioteca.realizar_prestamo(id_manuel, 3, fecha3) else { panic!("Deberia ser exitoso") };
+        let despues = FechaHora::ahora();
 
         let Some(cant_prestamos_pepe) = biblioteca.cantidad_prestamos_cliente(id_pepe) else { panic!() };
         let Some(cant_prestamos_manuel) = biblioteca.cantidad_prestamos_cliente(id_manuel) else { panic!() };
@@ -1530,7 +1215,9 @@ mod tests {
         assert!(!buscar_prestamo5.is_err(), "Error buscar_prestamo(): {:?}", buscar_prestamo5.unwrap_err());
         assert!(!buscar_prestamo3.is_err(), "Error buscar_prestamo(): {:?}", buscar_prestamo3.unwrap_err());
 
-        assert_eq!(buscar_prestamo5.unwrap().isbn, 5, "El préstamo encontrado debería ser sobre el libro #5");
+        assert_eq!(buscar_prestamo5.as_ref().unwrap().isbn, 5, "El préstamo encontrado debería ser sobre el libro #5");
+        let fecha_prestamo5 = buscar_prestamo5.unwrap().fecha_prestamo;
+        assert!(antes <= fecha_prestamo5 && fecha_prestamo5 <= despues, "El préstamo debería registrar el momento en que se realizó");
         assert_eq!(buscar_prestamo3.unwrap().isbn, 3, "El préstamo encontrado debería ser sobre el libro #3");
 
         // init-check devolver prestamos
@@ -1663,7 +1350,10 @@ mod tests {
             nombre: "asd".to_string(),
             direccion: "asd".to_string(),
             libros: BTreeMap::default(),
-            clientes: BTreeMap::default()
+            clientes: BTreeMap::default(),
+            reloj: RelojCompartido::default(),
+            calendario_habil: CalendarioHabil::default(),
+            almacen: AlmacenMemoria::new().into()
         };
 
         biblioteca.registrar_cliente(cliente_manuel());
@@ -1686,13 +1376,134 @@ mod tests {
         assert_eq!(p_venc.len(), 0);
     }
 
+    #[test]
+    fn test_operaciones_con_reloj() {
+        let reloj = RelojFijo::from(Fecha { dia: 1, mes: 1, ano: 0 });
+        let mut biblioteca = Biblioteca {
+            nombre: "asd".to_string(),
+            direccion: "asd".to_string(),
+            libros: BTreeMap::default(),
+            clientes: BTreeMap::default(),
+            reloj: RelojCompartido::default(),
+            calendario_habil: CalendarioHabil::default(),
+            almacen: AlmacenMemoria::new().into()
+        }.con_reloj(reloj.clone());
+
+        biblioteca.registrar_cliente(cliente_manuel());
+        let id_manuel = cliente_manuel().id;
+
+        biblioteca.registrar_libro(libro_economia_1());
+        biblioteca.registrar_libro(libro_xd_2());
+
+        let vencimiento = Fecha { dia: 3, mes: 1, ano: 0 };
+        let Ok(_) = biblioteca.realizar_prestamo(id_manuel, 1, vencimiento) else { panic!("No debe haber error") };
+        let Ok(_) = biblioteca.realizar_prestamo(id_manuel, 2, vencimiento) else { panic!("No debe haber error") };
+
+        let Ok(prestamo) = biblioteca.buscar_prestamo(1, id_manuel) else { panic!("Debería existir") };
+        assert_eq!(prestamo.fecha_prestamo, reloj.ahora(), "El préstamo se fecha con el reloj de la biblioteca");
+
+        assert_eq!(biblioteca.prestamos_por_vencer_hoy(1).len(), 0);
+        assert_eq!(biblioteca.prestamos_por_vencer_hoy(2).len(), 2);
+        assert_eq!(biblioteca.prestamos_vencidos_hoy().len(), 0);
+
+        reloj.avanzar_dias(1);
+        let Ok(_) = biblioteca.devolver_libro_hoy(1, id_manuel) else { panic!("No debe haber error") };
+        let Ok(prestamo) = biblioteca.buscar_prestamo(1, id_manuel) else { panic!("Debería existir") };
+        assert_eq!(prestamo.estado, EstadoPrestamo::Devuelto(Fecha { dia: 2, mes: 1, ano: 0 }));
+
+        reloj.avanzar_dias(2);
+        let vencidos = biblioteca.prestamos_vencidos_hoy();
+        assert_eq!(vencidos.len(), 1);
+        assert_eq!(vencidos[0].isbn, 2);
+    }
+
+    #[test]
+    fn test_prestamos_dias_habiles() {
+        // jueves santo de 2024: viene el viernes santo, el fin de semana y el 2 de abril
+        let reloj = RelojFijo::from(Fecha { dia: 28, mes: 3, ano: 2024 });
+        let mut biblioteca = Biblioteca {
+            nombre: "asd".to_string(),
+            direccion: "asd".to_string(),
+            libros: BTreeMap::default(),
+            clientes: BTreeMap::default(),
+            reloj: RelojCompartido::default(),
+            calendario_habil: CalendarioHabil::default(),
+            almacen: AlmacenMemoria::new().into()
+        }.con_reloj(reloj.clone());
+
+        biblioteca.registrar_cliente(cliente_manuel());
+        let id_manuel = cliente_manuel().id;
+
+        biblioteca.registrar_libro(libro_economia_1());
+        biblioteca.registrar_libro(libro_xd_2());
+
+        let Ok(_) = biblioteca.realizar_prestamo_dias_habiles(id_manuel, 1, 10) else { panic!("No debe haber error") };
+        let Ok(prestamo) = biblioteca.buscar_prestamo(1, id_manuel) else { panic!("Debería existir") };
+        assert_eq!(prestamo.vencimiento, Fecha { dia: 15, mes: 4, ano: 2024 });
+
+        // vence el sábado: el viernes 12/04 ya está por vencer, aunque no quede ningún día hábil
+        let Ok(_) = biblioteca.realizar_prestamo(id_manuel, 2, Fecha { dia: 13, mes: 4, ano: 2024 }) else { panic!("No debe haber error") };
+        let viernes = Fecha { dia: 12, mes: 4, ano: 2024 };
+        assert_eq!(biblioteca.prestamos_por_vencer_dias_habiles(viernes, 0).len(), 1);
+        assert_eq!(biblioteca.prestamos_por_vencer_dias_habiles(viernes, 1).len(), 2);
+        assert_eq!(biblioteca.prestamos_por_vencer(viernes, 1).len(), 1, "En días corridos el lunes queda afuera");
+
+        let sin_dias_habiles = CalendarioHabil::new(BTreeSet::from(DiaSemana::TODOS));
+        let mut biblioteca = biblioteca.con_calendario_habil(sin_dias_habiles);
+        let Err(DoubleError::LocalError(error)) = biblioteca.realizar_prestamo_dias_habiles(id_manuel, 1, 1) else { panic!("Debería fallar") };
+        assert_eq!(error, ErrorRealizarPrestamo::SinDiasHabiles);
+    }
+
+    #[test]
+    fn test_prestamos_por_periodo() {
+        let reloj = RelojFijo::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let mut biblioteca = Biblioteca {
+            nombre: "asd".to_string(),
+            direccion: "asd".to_string(),
+            libros: BTreeMap::default(),
+            clientes: BTreeMap::default(),
+            reloj: RelojCompartido::default(),
+            calendario_habil: CalendarioHabil::default(),
+            almacen: AlmacenMemoria::new().into()
+        }.con_reloj(reloj.clone());
+
+        biblioteca.registrar_cliente(cliente_manuel());
+        biblioteca.registrar_cliente(cliente_pepe());
+        biblioteca.registrar_libro(libro_xd_2());
+        biblioteca.registrar_libro(libro_harrypotter_3());
+        let vencimiento = Fecha { dia: 30, mes: 4, ano: 2024 };
+
+        // dos préstamos la primera semana, uno ya devuelto; uno la segunda
+        let Ok(_) = biblioteca.realizar_prestamo(cliente_manuel().id, 2, vencimiento) else { panic!("No debe haber error") };
+        let Ok(_) = biblioteca.devolver_libro_hoy(2, cliente_manuel().id) else { panic!("No debe haber error") };
+        reloj.avanzar_dias(2);
+        let Ok(_) = biblioteca.realizar_prestamo(cliente_pepe().id, 2, vencimiento) else { panic!("No debe haber error") };
+        reloj.avanzar_dias(7);
+        let Ok(_) = biblioteca.realizar_prestamo(cliente_pepe().id, 3, vencimiento) else { panic!("No debe haber error") };
+
+        let Some(marzo) = RangoFechas::new(Fecha { dia: 1, mes: 3, ano: 2024 }, Fecha { dia: 21, mes: 3, ano: 2024 }) else { panic!("Debería ser un rango") };
+        let semanas = biblioteca.prestamos_por_periodo(&Ventana::semanal(marzo));
+        assert_eq!(semanas.iter().map(|(_, cantidades)| cantidades.clone()).collect::<Vec<_>>(), vec![
+            BTreeMap::from([(2, 2)]),
+            BTreeMap::from([(3, 1)]),
+            BTreeMap::new(),
+        ]);
+        assert_eq!(semanas[1].0.inicio(), Fecha { dia: 8, mes: 3, ano: 2024 });
+
+        let meses = biblioteca.prestamos_por_periodo(&Ventana::mensual(marzo));
+        assert_eq!(meses, vec![(marzo, BTreeMap::from([(2, 2), (3, 1)]))]);
+    }
+
     #[test]
     fn test_registrar_cliente() {
         let mut biblioteca = Biblioteca {
             nombre: "asd".to_string(),
             direccion: "asd".to_string(),
             libros: BTreeMap::default(),
-            clientes: BTreeMap::default()
+            clientes: BTreeMap::default(),
+            reloj: RelojCompartido::default(),
+            calendario_habil: CalendarioHabil::default(),
+            almacen: AlmacenMemoria::new().into()
         };
 
         let r1 = biblioteca.registrar_cliente(cliente_pepe());
@@ -1708,8 +1519,8 @@ mod tests {
             ResultRegistrarCliente::ClienteYaExiste => panic!("El cliente no debería existir")
         };
 
-        assert_eq!(r1, ResultSobreescribirArchivo::Success, "Debería ser exito");
-        assert_eq!(r2, ResultSobreescribirArchivo::Success, "Debería ser exito");
+        assert_eq!(r1, Ok(()), "Debería ser exito");
+        assert_eq!(r2, Ok(()), "Debería ser exito");
 
         let r1 = biblioteca.registrar_cliente(cliente_pepe());
         let r2 = biblioteca.registrar_cliente(cliente_manuel());
@@ -1732,7 +1543,7 @@ mod tests {
         assert!(double_error.is_local(), "Es un error local");
 
         // don't mind the error's content. it's being forced to create an example
-        let double_error: DoubleError<ErrorBuscarPrestamo> = DoubleError::from(ResultSobreescribirArchivo::Success); // just an example
+        let double_error: DoubleError<ErrorBuscarPrestamo> = DoubleError::from(ErrorPersistencia::NoExiste { clave: "asd".to_string() }); // just an example
 
         assert!(double_error.is_remote(), "Es un error remoto");
     }
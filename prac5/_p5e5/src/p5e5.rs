@@ -1,263 +1,12 @@
 
 
 
-//
-// fecha.rs
-//
-
-use std::cmp::Ordering;
-use std::cmp::Ordering::{Equal, Greater, Less};
 use std::collections::{BTreeMap, HashMap};
-use std::{fmt, fs};
-use std::fs::File;
-use std::io::Read;
 use std::mem::{discriminant, Discriminant};
 use serde::{Deserialize, Serialize};
-use serde::de::DeserializeOwned;
-use serde_json::Value;
-
-const NOMBRE_MESES: [&str; 12] = ["Enero", "Febrero", "Marzo", "Abril",
-    "Mayo", "Junio", "Julio", "Agosto",
-    "Septiembre", "Octubre", "Noviembre", "Diciembre"];
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
-pub struct Fecha {
-    pub(crate) dia: u8,
-    pub(crate) mes: u8,
-    pub(crate) ano: i64
-}
-
-impl Default for Fecha {
-    fn default() -> Self {
-        Fecha { dia: 1, mes: 1, ano: 0 }
-    }
-}
-
-impl PartialOrd for Fecha {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.dia == other.dia
-            && self.mes == other.mes
-            && self.ano == other.ano
-        { return Some(Equal) }
-
-        if self.ano > other.ano { return Some(Greater) }
-        if self.mes > other.mes { return Some(Greater) }
-        if self.dia > other.dia { return Some(Greater) }
-
-        Some(Less)
-    }
-}
-
-impl fmt::Display for Fecha {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.es_fecha_valida() {
-            write!(f, "{} de {} del {}", self.dia, NOMBRE_MESES[self.mes as usize - 1], self.ano)
-        } else {
-            write!(f, "{}/{}/{}", self.dia, self.mes, self.ano)
-        }
-    }
-}
-
-impl Fecha {
-
-    // El año podría ser negativo, indicando días antes de Cristo.
-    pub fn new(dia: u8, mes: u8, ano: i64) -> Option<Fecha> {
-        let fecha = Fecha { dia, mes, ano };
-        if fecha.es_fecha_valida() {
-            return Some(fecha);
-        }
-        None
-    }
-
-    pub fn es_fecha_valida(&self) -> bool {
-        // check que el mes sea válido
-        if !(1..=12).contains(&self.mes) { return false }
-
-        // check días del mes
-        if self.dia == 0
-            || self.dia > self.dias_mes_actual()
-        { return false }
-
-        // el año no puede ser incorrecto...
-        // a no ser que se contabilice la edad del universo
-        // que dudo mucho que pueda importar para este caso
-        true
-    }
-
-    pub fn es_bisiesto(&self) -> bool {
-        self.ano % 4 == 0
-    }
-
-    pub fn sumar_dias(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            let dias_mes_actual = self.dias_mes_actual();
-            let dias_para_proximo_mes = u32::from(dias_mes_actual - self.dia + 1);
-
-            if dias_restantes >= dias_para_proximo_mes {
-                // ir al siguiente mes
-
-                dias_restantes-= dias_para_proximo_mes;
-                self.dia = 1;
-                self.mes += 1;
-
-                if self.mes > 12 {
-                    self.mes = 1;
-                    self.ano+= 1;
-                }
-            } else {
-                self.dia += u8::try_from(dias_restantes).unwrap_or(0);
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn restar_dias(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            if dias_restantes >= u32::from(self.dia) {
-                // ir al anterior mes
-                dias_restantes-= u32::from(self.dia);
-                self.mes-= 1;
-
-                if self.mes < 1 {
-                    self.mes = 12;
-                    self.ano-= 1;
-                }
-
-                // corregir self.dia == 0
-                self.dia = self.dias_mes_actual();
-            } else {
-                self.dia-= u8::try_from(dias_restantes).unwrap_or(0);
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn dias_mes_actual(&self) -> u8 {
-        match self.mes {
-            4 | 6 | 9 | 11 => 30,
-            2 => if self.es_bisiesto() { 29 } else { 28 },
-            _ => 31,
-        }
-    }
-}
-
-#[cfg(test)]
-mod test_fecha {
-    use super::*;
-
-    #[test]
-    fn test_default() {
-        // default: 01/01/0000
-        let default_fecha = Fecha::default();
-        assert_eq!(default_fecha.dia, 1);
-        assert_eq!(default_fecha.mes, 1);
-        assert_eq!(default_fecha.ano, 0);
-    }
-
-    #[test]
-    fn test_display() {
-        let valid_fecha = Fecha::default();
-        let invalid_fecha = Fecha { dia: 0, mes: 1, ano: 0 };
-
-        // write!(f, "{} de {} del {}", self.dia, NOMBRE_MESES[self.mes as usize - 1], self.ano)
-
-        assert_ne!(format!("{valid_fecha}"), format!("{}", invalid_fecha));
-        assert_eq!(format!("{valid_fecha}"), format!("{} de {} del {}", valid_fecha.dia, NOMBRE_MESES[valid_fecha.mes as usize - 1], valid_fecha.ano));
-        assert_eq!(format!("{invalid_fecha}"), format!("{}/{}/{}", invalid_fecha.dia, invalid_fecha.mes, invalid_fecha.ano));
-    }
-
-    #[test]
-    fn test_new() {
-        // invalida
-        let fecha = Fecha::new(0, 0, 0);
-        assert!(fecha.is_none());
-
-        // valida
-        let fecha = Fecha::new(22, 08, 2002);
-        assert!(fecha.is_some());
-    }
-
-    #[test]
-    fn test_bisiesto() {
-        let Some(fecha) = Fecha::new(1, 1, 0) else { panic!() };
-        assert!(fecha.es_bisiesto());
-
-        let Some(fecha) = Fecha::new(1, 1, 2000) else { panic!() };
-        assert!(fecha.es_bisiesto());
-
-        let Some(fecha) = Fecha::new(1, 1, -4) else { panic!() };
-        assert!(fecha.es_bisiesto());
-
-        let Some(fecha) = Fecha::new(1, 1, 1) else { panic!() };
-        assert!(!fecha.es_bisiesto());
-    }
-
-    #[test]
-    fn test_restar_dias() {
-        let Some(mut fecha) = Fecha::new(30, 04, 2016) else { panic!() };
-
-        fecha.restar_dias(5000);
-
-        assert_eq!(fecha.dia, 22);
-        assert_eq!(fecha.mes, 08);
-        assert_eq!(fecha.ano, 2002);
-    }
-
-    #[test]
-    fn test_sumar_dias() {
-        let Some(mut fecha) = Fecha::new(22, 08, 2002) else { panic!() };
-
-        fecha.sumar_dias(5000);
-
-        assert_eq!(fecha.dia, 30);
-        assert_eq!(fecha.mes, 04);
-        assert_eq!(fecha.ano, 2016);
-    }
-
-    #[test]
-    fn test_dias_mes_actual() {
-        let Some(fecha) = Fecha::new(22, 01, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 02, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 28);
-        let Some(fecha) = Fecha::new(22, 02, 2004) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 29);
-        let Some(fecha) = Fecha::new(22, 03, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 04, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 05, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 06, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 07, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 08, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 09, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 10, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-        let Some(fecha) = Fecha::new(22, 11, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 30);
-        let Some(fecha) = Fecha::new(22, 12, 2002) else { panic!() };
-        assert_eq!(fecha.dias_mes_actual(), 31);
-    }
-
-    #[test]
-    fn test_cmp() {
-        let fecha1 = Fecha { dia: 1, mes: 1, ano: 1};
-        let fecha2 = Fecha { dia: 3, mes: 1, ano: 1};
-        let fecha3 = Fecha { dia: 3, mes: 1, ano: 1};
-
-        assert!(fecha1 < fecha2, "Fecha 1 es anterior, por ende, es menor");
-        assert_eq!(fecha3, fecha2, "Fecha 3 es igual a fecha 2");
-        assert!(fecha3 > fecha1, "Fecha 3 es posterior a fecha1, por ende, es mayor");
-    }
-}
+use calendario::{Fecha, FinRecurrencia, Periodo, RangoFechas, Recurrencia, Ventana};
+use persistencia::{AlmacenCompartido, Documento, ErrorPersistencia, Migraciones, Repositorio};
+use dinero::{Dinero, Moneda, Redondeo};
 
 //
 // suscripcion.rs
@@ -283,30 +32,6 @@ pub enum MedioDePago {
     Cripto(String)
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Precio(f64);
-impl PartialEq for Precio {
-    fn eq(&self, other: &Self) -> bool {
-        if self.0.is_nan() || self.0.is_infinite() { return false }
-        self.0 == other.0
-    }
-}
-impl From<f64> for Precio {
-    fn from(value: f64) -> Self { Precio(value) }
-}
-impl Precio {
-    pub fn f64(&self) -> f64 { self.0 }
-}
-
-pub trait F64Precio {
-    fn as_precio(self) -> Precio;
-}
-impl F64Precio for f64 {
-    fn as_precio(self) -> Precio {
-        Precio::from(self)
-    }
-}
-
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum TipoSuscripcion {
     Basic, Classic, Super
@@ -316,17 +41,17 @@ pub enum TipoSuscripcion {
 pub struct Suscripcion {
     pub id: u16, // (ID del usuario, número de suscripcion)
     pub tipo: TipoSuscripcion,
-    pub costo_mensual: Precio,
+    pub costo_mensual: Dinero, // en pesos
     pub fecha_inicio: Fecha
 }
 
 impl TipoSuscripcion {
-    pub fn costo_mensual(self) -> Precio {
-        Precio::from(match self {
-            TipoSuscripcion::Basic => 1200.0,
-            TipoSuscripcion::Classic => 2200.0,
-            TipoSuscripcion::Super => 5800.0
-        })
+    pub fn costo_mensual(self) -> Dinero {
+        Dinero::enteros(match self {
+            TipoSuscripcion::Basic => 1200,
+            TipoSuscripcion::Classic => 2200,
+            TipoSuscripcion::Super => 5800
+        }, Moneda::ARS)
     }
 }
 
@@ -358,6 +83,26 @@ impl Suscripcion {
         self.costo_mensual = self.tipo.costo_mensual();
         Ok(self.tipo)
     }
+
+    // se cobra todos los meses el mismo día que empezó (o el último del mes, si ese día no existe)
+    pub fn cobros(&self) -> Option<Recurrencia> {
+        Recurrencia::new(self.fecha_inicio, Periodo::Meses(1), FinRecurrencia::Nunca)
+    }
+
+    pub fn proximos_cobros(&self, desde: Fecha, cantidad: usize) -> Vec<Fecha> {
+        self.cobros().map(|cobros| cobros.proximas(desde, cantidad)).unwrap_or_default()
+    }
+
+    // el mes ya pagado que incluye a `fecha`: desde el último cobro hasta el día anterior al siguiente
+    pub fn periodo_vigente(&self, fecha: Fecha) -> Option<RangoFechas> {
+        let cobros = self.cobros()?;
+        let cobros_hechos = cobros.ocurrencias().take_while(|cobro| *cobro <= fecha).count();
+        let ultimo = u32::try_from(cobros_hechos.checked_sub(1)?).ok()?;
+
+        let mut fin = cobros.ocurrencia(ultimo + 1)?;
+        fin.restar_dias(1);
+        RangoFechas::new(cobros.ocurrencia(ultimo)?, fin)
+    }
 }
 
 #[cfg(test)]
@@ -386,6 +131,24 @@ mod tests_suscripcion {
         assert_eq!(suscripcion_1, suscripcion_2);
     }
 
+    #[test]
+    fn test_cobros() {
+        let suscripcion = Suscripcion::new(0, TipoSuscripcion::Basic, Fecha { dia: 31, mes: 1, ano: 2024 });
+
+        assert_eq!(suscripcion.proximos_cobros(Fecha { dia: 1, mes: 2, ano: 2024 }, 3), vec![
+            Fecha { dia: 29, mes: 2, ano: 2024 },
+            Fecha { dia: 31, mes: 3, ano: 2024 },
+            Fecha { dia: 30, mes: 4, ano: 2024 },
+        ]);
+
+        let Some(periodo) = suscripcion.periodo_vigente(Fecha { dia: 15, mes: 3, ano: 2024 }) else { panic!("Debería estar vigente") };
+        assert_eq!(periodo.inicio(), Fecha { dia: 29, mes: 2, ano: 2024 });
+        assert_eq!(periodo.fin(), Fecha { dia: 30, mes: 3, ano: 2024 });
+
+        // antes de empezar no hay periodo
+        assert!(suscripcion.periodo_vigente(Fecha { dia: 30, mes: 1, ano: 2024 }).is_none());
+    }
+
     #[test]
     fn test_precio_mejorar_degradar() {
         let mut suscripcion_basic = Suscripcion::new(
@@ -404,9 +167,9 @@ mod tests_suscripcion {
         // TipoSuscripcion::Classic => 2200.0,
         // TipoSuscripcion::Super => 5800.0
 
-        assert_eq!(suscripcion_basic.tipo.costo_mensual(), Precio::from(1200.0));
-        assert_eq!(suscripcion_classic.tipo.costo_mensual(), Precio::from(2200.0));
-        assert_eq!(suscripcion_super.tipo.costo_mensual(), Precio::from(5800.0));
+        assert_eq!(suscripcion_basic.tipo.costo_mensual(), Dinero::enteros(1200, Moneda::ARS));
+        assert_eq!(suscripcion_classic.tipo.costo_mensual(), Dinero::enteros(2200, Moneda::ARS));
+        assert_eq!(suscripcion_super.tipo.costo_mensual(), Dinero::enteros(5800, Moneda::ARS));
 
         assert_eq!(suscripcion_basic.degradar(), Err(ErrorDegradarSuscripcion::SuscripcionMinima));
         assert_eq!(suscripcion_super.mejorar(), Err(ErrorMejorarSuscripcion::SuscripcionMaxima));
@@ -477,7 +240,7 @@ mod test_usuario {
         let sus = Suscripcion {
             id: 0,
             tipo: TipoSuscripcion::Basic,
-            costo_mensual: Precio::from(0.0),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
 
@@ -493,7 +256,7 @@ mod test_usuario {
         let sus = Suscripcion {
             id: 0,
             tipo: TipoSuscripcion::Basic,
-            costo_mensual: Precio::from(0.0),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
 
@@ -512,115 +275,53 @@ mod test_usuario {
 // streamingrust_fm.rs
 //
 
-const BASE_FOLDER: &str = "";
 const DEFAULT_FILE_NAME: &str = "streamingrust_usuarios";
 
-pub trait UsuariosFile {
-    fn unwrap_or_leer(self, file_name: &str) -> Usuarios;
-}
-
-impl UsuariosFile for Option<Usuarios> {
-    fn unwrap_or_leer(self, file_name: &str) -> Usuarios {
-        if let Some(usuarios) = self {
-            usuarios
-        } else {
-            let Ok(value) = leer_archivo(format!("{BASE_FOLDER}{file_name}"))
-            else { return Usuarios::new(); };
-
-            leer_archivo_parsed::<Usuarios>(value).unwrap_or_default()
-        }
-    }
-}
-
-#[derive(Debug, Clone)]
-pub enum ErrorSobreescribirArchivo {
-    IOError, //io::Error == ()
-    SerializationError,
-}
-
-#[derive(Debug, Clone)]
-pub enum ErrorLeerArchivo {
-    IOError,
-    DeserializationError,
-}
-
-impl PartialEq for ErrorSobreescribirArchivo {
-    fn eq(&self, other: &Self) -> bool {
-        discriminant(self) == std::mem::discriminant(other)
-    }
+pub trait StreamingRustFileManagement {
+    fn sobreescribir_archivo_usuarios(&self) -> Result<(), ErrorPersistencia>;
+    fn leer_archivo_usuarios(&self) -> Result<Usuarios, ErrorPersistencia>;
+    fn actualizar_variable_usuarios(&mut self) -> Result<(), ErrorPersistencia>;
 }
 
-impl PartialEq for ErrorLeerArchivo {
-    fn eq(&self, other: &Self) -> bool {
-        discriminant(self) == std::mem::discriminant(other)
+impl StreamingRust {
+    // el almacén le agrega el .json a file_name
+    fn archivo_usuarios(&self) -> Documento<Usuarios> {
+        Documento::new(self.almacen.clone(), self.file_name.as_str()).con_migraciones(migraciones_usuarios())
     }
 }
 
-fn archivo_filepath(file_name: &str) -> String {
-    format!("{BASE_FOLDER}{file_name}.json")
-}
-
-//
-// IO part
-//
-
-fn sobreescribir_archivo(file_abs_path: String, data: &Usuarios) -> Result<(), ErrorSobreescribirArchivo> {
-    let json_string = serde_json::to_string_pretty(data);
-
-    let Ok(text) = json_string else { return Err(ErrorSobreescribirArchivo::SerializationError); };
-
-    match fs::write(file_abs_path, text) {
-        Err(_) => Err(ErrorSobreescribirArchivo::IOError),
-        _ => Ok(())
-    }
+// v2: costo_mensual pasó de f64 a Dinero
+fn migraciones_usuarios() -> Migraciones {
+    Migraciones::default().con_migracion(1, costo_mensual_en_pesos)
 }
 
-fn leer_archivo(filepath: String) -> Result<Value, ErrorLeerArchivo> {
-    let Ok(mut file) = File::open(filepath) else { return Err(ErrorLeerArchivo::IOError) };
+// cada costo pasa a los centavos más cercanos
+fn costo_mensual_en_pesos(mut usuarios: serde_json::Value) -> Result<serde_json::Value, String> {
+    let usuarios_objeto = usuarios.as_object_mut().ok_or("los usuarios no son un objeto")?;
 
-    let mut contents = String::new();
-    match file.read_to_string(&mut contents) {
-        Ok(_) => {},
-        Err(_) => return Err(ErrorLeerArchivo::IOError)
-    }
+    for usuario in usuarios_objeto.values_mut() {
+        let historial = usuario["historial_suscripciones"].as_object_mut().ok_or("el usuario no tiene historial")?;
 
-    match serde_json::from_str(&contents) {
-        Err(_) => Err(ErrorLeerArchivo::DeserializationError),
-        Ok(value) => Ok(value),
+        for suscripcion in historial.values_mut() {
+            let costo = suscripcion["costo_mensual"].as_f64().ok_or("la suscripción no tiene costo")?;
+            let costo = Dinero::desde_f64(costo, Moneda::ARS, Redondeo::MitadLejosDeCero).map_err(|error| error.to_string())?;
+            suscripcion["costo_mensual"] = serde_json::Value::from(costo.to_string());
+        }
     }
-}
 
-fn leer_archivo_parsed<T>(value: Value) -> Result<T, ErrorLeerArchivo> where T: DeserializeOwned {
-    let data: T = match serde_json::from_value::<T>(value) {
-        Ok(data) => { data }
-        Err(_) => { return Err(ErrorLeerArchivo::DeserializationError) }
-    };
-    Ok(data)
-}
-
-//
-// Implementation / Serialization-Deserialization part
-//
-
-pub trait StreamingRustFileManagement {
-    fn sobreescribir_archivo_usuarios(&self, file_name: &str) -> Result<(), ErrorSobreescribirArchivo>;
-    fn leer_archivo_usuarios(&self) -> Result<Usuarios, ErrorLeerArchivo>;
-    fn actualizar_variable_usuarios(&mut self) -> Result<(), ErrorLeerArchivo>;
+    Ok(usuarios)
 }
 
 impl StreamingRustFileManagement for StreamingRust {
-    fn sobreescribir_archivo_usuarios(&self, file_name: &str) -> Result<(), ErrorSobreescribirArchivo> {
-        let data = &self.usuarios;
-        sobreescribir_archivo(archivo_filepath(file_name), data)
+    fn sobreescribir_archivo_usuarios(&self) -> Result<(), ErrorPersistencia> {
+        self.archivo_usuarios().guardar(&self.usuarios)
     }
 
-    fn leer_archivo_usuarios(&self) -> Result<Usuarios, ErrorLeerArchivo> {
-        let lectura = leer_archivo(archivo_filepath(self.file_name.as_str()))?;
-        let usuarios: Usuarios = leer_archivo_parsed(lectura)?;
-        Ok(usuarios)
+    fn leer_archivo_usuarios(&self) -> Result<Usuarios, ErrorPersistencia> {
+        self.archivo_usuarios().cargar()
     }
 
-    fn actualizar_variable_usuarios(&mut self) -> Result<(), ErrorLeerArchivo> {
+    fn actualizar_variable_usuarios(&mut self) -> Result<(), ErrorPersistencia> {
         let usuarios = self.leer_archivo_usuarios()?;
         self.usuarios = usuarios;
         Ok(())
@@ -630,34 +331,34 @@ impl StreamingRustFileManagement for StreamingRust {
 #[cfg(test)]
 mod test_fm {
     use std::collections::HashMap;
+    use persistencia::{Almacen, AlmacenMemoria, CarpetaTemporal};
     use super::*;
 
-    const TEST_FAKE_PATH: &str = "Z:/CalleFalsa123/AvenidaSiempreviva742/";
-    const TEST_FILE_NAME: &str = "streamingrust_testfile.json";
+    const TEST_FILE_NAME: &str = "streamingrust_testfile_fm";
 
     fn usuarios_mock() -> Usuarios {
         let sus_1 = Suscripcion {
             id: 0,
             tipo: TipoSuscripcion::Basic,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
         let sus_2 = Suscripcion {
             id: 1,
             tipo: TipoSuscripcion::Classic,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
         let sus_3 = Suscripcion {
             id: 2,
             tipo: TipoSuscripcion::Super,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
         let sus_4 = Suscripcion {
             id: 3,
             tipo: TipoSuscripcion::Classic,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
 
@@ -698,91 +399,72 @@ mod test_fm {
         ])
     }
 
-    fn escribir_archivo_usuarios(file_name: &str) -> Result<(), ErrorSobreescribirArchivo> {
-        let test_usuarios = usuarios_mock();
-        sobreescribir_archivo(format!("{BASE_FOLDER}{file_name}"), &test_usuarios)
+    fn streamingrust_test(almacen: impl Into<AlmacenCompartido>) -> StreamingRust {
+        StreamingRust { usuarios: usuarios_mock(), file_name: TEST_FILE_NAME.to_string(), almacen: almacen.into() }
     }
 
     #[test]
     fn test_sobreescribir_archivo() {
-        let test_data = usuarios_mock();
-
-        let result_fail = sobreescribir_archivo(format!("{TEST_FAKE_PATH}{TEST_FILE_NAME}"), &test_data);
-        let result_good = sobreescribir_archivo(format!("{BASE_FOLDER}{TEST_FILE_NAME}"), &test_data);
-
-        if let Ok(()) = result_fail { panic!("Shouldn't be success. Fake path does not exist!") }
+        let carpeta = CarpetaTemporal::new("p5e5_sobreescribir");
+        let result_fail = streamingrust_test(carpeta.almacen_inaccesible()).sobreescribir_archivo_usuarios();
+        let result_good = streamingrust_test(AlmacenMemoria::new()).sobreescribir_archivo_usuarios();
 
-        match result_good {
-            Ok(()) => {}
-            _ => { panic!("Shouldn't throw error") }
-        }
+        let Err(ErrorPersistencia::Io { .. }) = result_fail else { panic!("Shouldn't be success. Path is a file, not a folder!") };
+        assert_eq!(result_good, Ok(()), "Shouldn't throw error");
     }
 
     #[test]
     fn test_leer_archivo() {
-        // inicializar ambos libros con funciones ya testeadas
-        escribir_archivo_usuarios(TEST_FILE_NAME).expect("Shouldn't throw error");
-
-
-        let Ok(value_good) = leer_archivo(format!("{BASE_FOLDER}{TEST_FILE_NAME}"))
-        else { panic!("Shouldn't throw an error") };
-
-        let Err(value_fail) = leer_archivo(format!("{TEST_FAKE_PATH}{TEST_FILE_NAME}"))
-        else { panic!("Should throw error.") };
+        let sr = streamingrust_test(AlmacenMemoria::new());
 
-        let result_good: Usuarios = match leer_archivo_parsed::<Usuarios>(value_good.clone()) {
-            Ok(res) => { res }
-            _ => { panic!("Shouln't throw an error") }
-        };
-    }
+        let Err(ErrorPersistencia::NoExiste { clave }) = sr.leer_archivo_usuarios()
+        else { panic!("Todavía no se escribió") };
+        assert_eq!(clave, TEST_FILE_NAME);
 
-    #[test]
-    fn test_archivo_filepath() {
-        assert_eq!(
-            archivo_filepath(TEST_FILE_NAME),
-            format!("{BASE_FOLDER}{TEST_FILE_NAME}.json")
-        );
+        sr.sobreescribir_archivo_usuarios().expect("Shouldn't throw error");
+        assert_eq!(sr.leer_archivo_usuarios(), Ok(usuarios_mock()));
     }
 
     #[test]
-    fn test_error_comparisons() {
-        // Test ResultSobreescribirArchivo equality
-        let io_error1 = ErrorSobreescribirArchivo::IOError;
-        let io_error2 = ErrorSobreescribirArchivo::IOError;
+    fn test_integration_read_write() {
+        let almacen = AlmacenMemoria::new();
 
-        assert_eq!(io_error1, io_error2);
+        // Write test data
+        let Ok(_) = StreamingRust::new_con_almacen(TEST_FILE_NAME, Some(usuarios_mock()), almacen.clone())
+        else { panic!("Shouldn't throw an error") };
+        assert_eq!(almacen.claves(), vec![TEST_FILE_NAME.to_string()]);
 
-        // Test ErrorLeerArchivo equality
-        let io_error1 = ErrorLeerArchivo::IOError;
-        let io_error2 = ErrorLeerArchivo::IOError;
+        // Read and verify
+        let Ok(read_data) = StreamingRust::new_con_almacen(TEST_FILE_NAME, None, almacen)
+        else { panic!("Failed to read usuarios file") };
 
-        assert_eq!(io_error1, io_error2);
+        assert_eq!(read_data.usuarios, usuarios_mock());
     }
 
     #[test]
-    fn test_integration_read_write() {
-        // Write test data
-        let write_result_libros = escribir_archivo_usuarios(TEST_FILE_NAME);
-        assert!(write_result_libros.is_ok());
-
-        // Read and verify libros
-        let Ok(value) = leer_archivo(format!("{BASE_FOLDER}{TEST_FILE_NAME}")) else {
-            panic!("Failed to read libros file");
-        };
-
-        let read_data: Usuarios = leer_archivo_parsed(value)
-            .expect("Failed to parse libros data");
+    fn test_migracion_costo_mensual() {
+        // un archivo v1: costos en f64
+        let almacen = AlmacenMemoria::new();
+        let Ok(mut v1) = serde_json::to_value(usuarios_mock()) else { panic!() };
+        v1["0"]["historial_suscripciones"]["0"]["costo_mensual"] = serde_json::json!(1200.005);
+        v1["1"]["historial_suscripciones"]["1"]["costo_mensual"] = serde_json::json!(0.0);
+        v1["1"]["historial_suscripciones"]["2"]["costo_mensual"] = serde_json::json!(0.0);
+        v1["2"]["historial_suscripciones"]["3"]["costo_mensual"] = serde_json::json!(0.0);
+        let Ok(()) = almacen.escribir(TEST_FILE_NAME, &serde_json::json!({ "version": 1, "data": v1 })) else { panic!() };
 
-        assert_eq!(read_data, usuarios_mock());
+        let Ok(sr) = StreamingRust::new_con_almacen(TEST_FILE_NAME, None, almacen) else { panic!("Debería migrar") };
+        assert_eq!(sr.usuarios[&0].historial_suscripciones[&0].costo_mensual, Dinero::new(120_001, Moneda::ARS));
+        assert_eq!(sr.usuarios[&1].historial_suscripciones[&1].costo_mensual, Dinero::cero(Moneda::ARS));
     }
 
     #[test]
     fn test_file_management() {
         // First ensure we have test data in files
-        let Ok(mut sr) = StreamingRust::new(TEST_FILE_NAME, Some(usuarios_mock()))
+        let carpeta = CarpetaTemporal::new("p5e5_file_management");
+        let Ok(mut sr) = StreamingRust::new_con_almacen(TEST_FILE_NAME, Some(usuarios_mock()), carpeta.almacen())
         else { panic!("Shouldn't throw an error") };
 
-        // Test leer_archivo_libros()
+        // Test leer_archivo_usuarios()
         let result_leer = sr.leer_archivo_usuarios();
         assert!(result_leer.is_ok(), "Should successfully read usuarios file");
         let usuarios = result_leer.unwrap();
@@ -790,20 +472,24 @@ mod test_fm {
 
         // Test actualizar_variable_usuarios()
         assert!(sr.actualizar_variable_usuarios().is_ok(),
-                "Should successfully update libros");
+                "Should successfully update usuarios");
         assert_eq!(sr.usuarios, usuarios_mock(),
-                   "Updated libros should match test data");
+                   "Updated usuarios should match test data");
 
         // Test updating with invalid JSON data
         // Write invalid JSON to test files
 
         std::fs::write(
-            format!("{BASE_FOLDER}{TEST_FILE_NAME}.json"),
+            carpeta.ruta().join(format!("{TEST_FILE_NAME}.json")),
             "{invalid json"
         ).expect("Failed to write invalid JSON");
 
         let result_invalid = sr.actualizar_variable_usuarios();
-        assert!(result_invalid.is_err(), "Should return error when parsing invalid JSON");
+        assert!(matches!(result_invalid, Err(ErrorPersistencia::Deserializacion { .. })), "Should return error when parsing invalid JSON");
+
+        // un archivo roto no se pisa con usuarios vacíos
+        let result_new = StreamingRust::new_con_almacen(TEST_FILE_NAME, None, carpeta.almacen());
+        assert!(matches!(result_new, Err(ErrorPersistencia::Deserializacion { .. })), "Should return error when parsing invalid JSON");
     }
 }
 
@@ -813,10 +499,18 @@ mod test_fm {
 
 type Usuarios = BTreeMap<u64, Usuario>;
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct StreamingRust {
     pub usuarios: Usuarios,
     pub file_name: String,
+    #[serde(skip)]
+    pub almacen: AlmacenCompartido, // dónde se guarda file_name
+}
+
+impl Default for StreamingRust {
+    fn default() -> Self {
+        StreamingRust { usuarios: Usuarios::default(), file_name: DEFAULT_FILE_NAME.to_string(), almacen: AlmacenCompartido::default() }
+    }
 }
 impl PartialEq for StreamingRust {
     fn eq(&self, other: &Self) -> bool {
@@ -836,7 +530,7 @@ impl PartialEq for StreamingRust {
 #[derive(Debug, Clone, PartialEq)]
 pub enum ErrorRegistrarUsuario {
     UsuarioYaExiste,
-    Archivo(ErrorSobreescribirArchivo)
+    Archivo(ErrorPersistencia)
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -844,7 +538,7 @@ pub enum ErrorMejorarSuscripcion {
     UsuarioInexistente,
     SuscripcionMaxima,
     SinSuscripcion,
-    Archivo(ErrorSobreescribirArchivo)
+    Archivo(ErrorPersistencia)
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -852,13 +546,25 @@ pub enum ErrorDegradarSuscripcion {
     UsuarioInexistente,
     SuscripcionMinima,
     SinSuscripcion,
-    Archivo(ErrorSobreescribirArchivo)
+    Archivo(ErrorPersistencia)
 }
 
 impl StreamingRust {
-    pub fn new(file_name: &str, usuarios: Option<Usuarios>) -> Result<StreamingRust, ErrorSobreescribirArchivo> {
-        let sr = StreamingRust { usuarios: usuarios.unwrap_or_leer(file_name), file_name: file_name.to_string() };
-        sr.sobreescribir_archivo_usuarios(file_name)?;
+    pub fn new(file_name: &str, usuarios: Option<Usuarios>) -> Result<StreamingRust, ErrorPersistencia> {
+        StreamingRust::new_con_almacen(file_name, usuarios, AlmacenCompartido::default())
+    }
+
+    // igual que new, pero los usuarios se leen y se guardan en `almacen`
+    pub fn new_con_almacen(file_name: &str, usuarios: Option<Usuarios>, almacen: impl Into<AlmacenCompartido>) -> Result<StreamingRust, ErrorPersistencia> {
+        let mut sr = StreamingRust { usuarios: Usuarios::new(), file_name: file_name.to_string(), almacen: almacen.into() };
+
+        // sin usuarios, leer los del archivo (o ninguno si todavía no existe)
+        sr.usuarios = match usuarios {
+            Some(usuarios) => usuarios,
+            None => sr.archivo_usuarios().cargar_o_default()?
+        };
+
+        sr.sobreescribir_archivo_usuarios()?;
         Ok(sr)
     }
 
@@ -868,7 +574,7 @@ impl StreamingRust {
         }
 
         self.usuarios.insert(user.id, user);
-        match self.sobreescribir_archivo_usuarios(DEFAULT_FILE_NAME) {
+        match self.sobreescribir_archivo_usuarios() {
             Ok(()) => { Ok(()) }
             Err(error) => { Err(ErrorRegistrarUsuario::Archivo(error)) }
         }
@@ -883,7 +589,7 @@ impl StreamingRust {
 
         match sus.mejorar() {
             Ok(nue_sus) => {
-                match self.sobreescribir_archivo_usuarios(DEFAULT_FILE_NAME) {
+                match self.sobreescribir_archivo_usuarios() {
                     Ok(()) => { Ok(nue_sus) }
                     Err(error) => { Err(ErrorMejorarSuscripcion::Archivo(error)) }
                 }
@@ -901,7 +607,7 @@ impl StreamingRust {
 
         match sus.degradar() {
             Ok(nue_sus) => {
-                match self.sobreescribir_archivo_usuarios(DEFAULT_FILE_NAME) {
+                match self.sobreescribir_archivo_usuarios() {
                     Ok(()) => { Ok(nue_sus) }
                     Err(error) => {
                         // problema: el cambio está hecho localmente, no remotamente.
@@ -971,25 +677,56 @@ impl StreamingRust {
             .max_by_key(|(_, c)| *c)
             .map(|(ts, _)| ts).copied()
     }
+
+    // las suscripciones contratadas en cada período de la ventana, según su fecha de inicio.
+    // cuenta todo el historial, activas o no: cada período dice qué se contrató entonces
+    pub fn contabilizar_tipos_de_suscripcion_por_periodo(&self, ventana: &Ventana) -> Vec<(RangoFechas, HashMap<TipoSuscripcion, u32>)> {
+        let suscripciones = self.usuarios.values()
+            .flat_map(|usuario| usuario.historial_suscripciones.values())
+            .map(|suscripcion| (suscripcion.fecha_inicio, suscripcion.tipo));
+
+        ventana.agrupar(suscripciones).into_iter().map(|(periodo, tipos)| {
+            let mut contabilizador_ts: HashMap<TipoSuscripcion, u32> = HashMap::new();
+            for tipo in tipos { *contabilizador_ts.entry(tipo).or_default() += 1 }
+            (periodo, contabilizador_ts)
+        }).collect()
+    }
+
+    // lo mismo, por medio de pago. Solo se guarda el medio de pago actual del usuario,
+    // así que es el que se cuenta para todas sus suscripciones
+    pub fn contabilizar_medios_de_pago_por_periodo(&self, ventana: &Ventana) -> Vec<(RangoFechas, HashMap<Discriminant<MedioDePago>, u32>)> {
+        let suscripciones = self.usuarios.values()
+            .flat_map(|usuario| usuario.historial_suscripciones.values()
+                .map(|suscripcion| (suscripcion.fecha_inicio, discriminant(&usuario.medio_de_pago))));
+
+        ventana.agrupar(suscripciones).into_iter().map(|(periodo, medios)| {
+            let mut contabilizador_mdp: HashMap<Discriminant<MedioDePago>, u32> = HashMap::new();
+            for medio in medios { *contabilizador_mdp.entry(medio).or_default() += 1 }
+            (periodo, contabilizador_mdp)
+        }).collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::collections::{HashMap};
-    use std::fs;
     use std::mem::discriminant;
+    use persistencia::AlmacenMemoria;
     use super::*;
 
     const TEST_FILE_NAME: &str = "streamingrust_testfile";
 
     #[test]
     fn test_new() {
-        let sr1 = StreamingRust::new(TEST_FILE_NAME, None).expect("Shouldn't be an error");
+        let almacen = AlmacenMemoria::new();
+        let sr1 = StreamingRust::new_con_almacen(TEST_FILE_NAME, None, almacen.clone()).expect("Shouldn't be an error");
         let sr2 = StreamingRust::default();
 
         assert_eq!(sr1, sr2);
-        let sr1 = StreamingRust::new(TEST_FILE_NAME, Some(Usuarios::default())).expect("Shouldn't be an error");
+        assert_eq!(sr1.usuarios, sr2.usuarios);
+        let sr1 = StreamingRust::new_con_almacen(TEST_FILE_NAME, Some(Usuarios::default()), almacen.clone()).expect("Shouldn't be an error");
         assert_eq!(sr1, sr2);
+        assert_eq!(almacen.claves(), vec![TEST_FILE_NAME.to_string()]);
     }
 
     // basic, classic(none), super(none), classic(none)
@@ -999,32 +736,29 @@ mod tests {
     // efectivo, mpago(none), mpago(none), debito(none)
     // res mdp(false) -> efectivo
     // res mdp(true) -> mpago
-    fn streamingrust_mock(delete_file: bool) -> StreamingRust {
-        if delete_file {
-            delete_streamingrust_mock_json();
-        }
+    fn streamingrust_mock() -> StreamingRust {
         let sus_1 = Suscripcion {
             id: 0,
             tipo: TipoSuscripcion::Basic,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
         let sus_2 = Suscripcion {
             id: 1,
             tipo: TipoSuscripcion::Classic,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
         let sus_3 = Suscripcion {
             id: 2,
             tipo: TipoSuscripcion::Super,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
         let sus_4 = Suscripcion {
             id: 3,
             tipo: TipoSuscripcion::Classic,
-            costo_mensual: 0.0.as_precio(),
+            costo_mensual: Dinero::cero(Moneda::ARS),
             fecha_inicio: Fecha::default(),
         };
 
@@ -1057,7 +791,7 @@ mod tests {
             historial_suscripciones: HashMap::default(),
         };
 
-        StreamingRust::new(
+        StreamingRust::new_con_almacen(
             TEST_FILE_NAME,
             Some(Usuarios::from([
                 (user_1.id, user_1),
@@ -1065,18 +799,13 @@ mod tests {
                 (user_3.id, user_3),
                 (user_4.id, user_4)
             ])),
+            AlmacenMemoria::new()
         ).expect("Shouldn't throw an error.")
     }
 
-    fn delete_streamingrust_mock_json() -> bool {
-        fs::remove_file(
-            format!("{BASE_FOLDER}{TEST_FILE_NAME}")
-        ).is_ok()
-    }
-
     #[test]
     fn test_estadistica_sus_mdp() {
-        let sr = streamingrust_mock(false);
+        let sr = streamingrust_mock();
 
         // basic, classic(none), super(none), classic(none)
         // res tipo(false) -> basic
@@ -1093,9 +822,34 @@ mod tests {
         assert_eq!(sr.medio_de_pago_mas_utilizado(true), Some(discriminant(&MedioDePago::MercadoPago(0))));
     }
 
+    #[test]
+    fn test_estadistica_por_periodo() {
+        let mut sr = streamingrust_mock();
+        // las del mock empiezan en Fecha::default(). Se mueven a marzo: una la primera semana y dos la segunda
+        let inicios = [(0, Fecha { dia: 1, mes: 3, ano: 2024 }), (1, Fecha { dia: 8, mes: 3, ano: 2024 }), (2, Fecha { dia: 14, mes: 3, ano: 2024 })];
+        for (id_usuario, fecha_inicio) in inicios {
+            let Some(usuario) = sr.usuarios.get_mut(&id_usuario) else { panic!("Debería existir") };
+            usuario.historial_suscripciones.values_mut().for_each(|suscripcion| suscripcion.fecha_inicio = fecha_inicio);
+        }
+
+        let Some(marzo) = RangoFechas::new(Fecha { dia: 1, mes: 3, ano: 2024 }, Fecha { dia: 21, mes: 3, ano: 2024 }) else { panic!("Debería ser un rango") };
+        let ventana = Ventana::semanal(marzo);
+
+        let tipos = sr.contabilizar_tipos_de_suscripcion_por_periodo(&ventana);
+        assert_eq!(tipos.iter().map(|(_, contabilizador)| contabilizador.clone()).collect::<Vec<_>>(), vec![
+            HashMap::from([(TipoSuscripcion::Basic, 1)]),
+            HashMap::from([(TipoSuscripcion::Classic, 2), (TipoSuscripcion::Super, 1)]),
+            HashMap::new(),
+        ]);
+
+        let medios = sr.contabilizar_medios_de_pago_por_periodo(&ventana);
+        assert_eq!(medios[1].1, HashMap::from([(discriminant(&MedioDePago::MercadoPago(0)), 2), (discriminant(&MedioDePago::Debito(0)), 1)]));
+        assert_eq!(medios.iter().map(|(periodo, _)| *periodo).collect::<Vec<_>>(), ventana.periodos());
+    }
+
     #[test]
     fn test_registrar_usuario() {
-        let mut sr = streamingrust_mock(true);
+        let mut sr = streamingrust_mock();
 
         let user_1 = Usuario {
             id: 0,
@@ -1122,7 +876,7 @@ mod tests {
 
     #[test]
     fn test_mejorar_degradar_suscripcion() {
-        let mut sr = streamingrust_mock(true);
+        let mut sr = streamingrust_mock();
 
         // user id 0 tiene suscripcion BASIC
         let res = sr.degradar_suscripcion(0);
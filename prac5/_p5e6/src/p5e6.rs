@@ -1,308 +1,82 @@
 
 
 
-//
-// date.rs
-//
-
-use std::cmp::Ordering;
-use std::cmp::Ordering::{Equal, Greater, Less};
-use std::collections::{BTreeMap, HashMap};
-use std::{fmt, fs};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 use std::fmt::Formatter;
-use std::fs::File;
 use std::hash::{Hash, Hasher};
-use std::io::Read;
-use std::ops::{AddAssign, SubAssign};
+use std::mem::discriminant;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
-
-const NOMBRE_MESES: [&str; 12] = ["Enero", "Febrero", "Marzo", "Abril",
-    "Mayo", "Junio", "Julio", "Agosto",
-    "Septiembre", "Octubre", "Noviembre", "Diciembre"];
-#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Copy, Hash)]
-pub struct Date {
-    pub(crate) day: u8,
-    pub(crate) month: u8,
-    pub(crate) year: i64
-}
-
-impl Default for Date {
-    fn default() -> Self {
-        Date { day: 1, month: 1, year: 0 }
-    }
-}
-
-impl PartialOrd for Date {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.day == other.day
-            && self.month == other.month
-            && self.year == other.year
-        { return Some(Equal) }
-
-        if self.year > other.year { return Some(Greater) }
-        if self.month > other.month { return Some(Greater) }
-        if self.day > other.day { return Some(Greater) }
-
-        Some(Less)
-    }
-}
-
-impl fmt::Display for Date {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if self.is_date_valid() {
-            write!(f, "{} de {} del {}", self.day, NOMBRE_MESES[self.month as usize - 1], self.year)
-        } else {
-            write!(f, "{}/{}/{}", self.day, self.month, self.year)
-        }
-    }
-}
-
-impl Date {
-    // El año podría ser negativo, indicando época antes de Cristo.
-    pub fn new(dia: u8, mes: u8, ano: i64) -> Option<Date> {
-        let fecha = Date { day: dia, month: mes, year: ano };
-        if fecha.is_date_valid() {
-            return Some(fecha);
-        }
-        None
-    }
-
-    pub fn is_date_valid(&self) -> bool {
-        // check que el mes sea válido
-        if !(1..=12).contains(&self.month) { return false }
-
-        // check días del mes
-        if self.day == 0
-            || self.day > self.current_month_days()
-        { return false }
-
-        // el año no puede ser incorrecto...
-        // a no ser que se contabilice la edad del universo
-        // que dudo mucho que pueda importar para este caso
-        true
-    }
-
-    pub fn is_leap_year(&self) -> bool {
-        self.year % 4 == 0
-    }
-
-    pub fn add_days(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            let dias_mes_actual = self.current_month_days();
-            let dias_para_proximo_mes = u32::from(dias_mes_actual - self.day + 1);
-
-            if dias_restantes >= dias_para_proximo_mes {
-                // ir al siguiente mes
-
-                dias_restantes-= dias_para_proximo_mes;
-                self.day = 1;
-                self.month += 1;
-
-                if self.month > 12 {
-                    self.month = 1;
-                    self.year += 1;
-                }
-            } else {
-                self.day += u8::try_from(dias_restantes).unwrap();
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn subtract_days(&mut self, dias: u32) {
-        let mut dias_restantes = dias;
-
-        while dias_restantes > 0 {
-            if dias_restantes >= u32::from(self.day) {
-                // ir al anterior mes
-                dias_restantes-= u32::from(self.day);
-                self.month -= 1;
-
-                if self.month < 1 {
-                    self.month = 12;
-                    self.year -= 1;
-                }
-
-                // corregir self.dia == 0
-                self.day = self.current_month_days();
-            } else {
-                self.day -= u8::try_from(dias_restantes).unwrap();
-                dias_restantes = 0;
-            }
-        }
-    }
-
-    pub fn current_month_days(&self) -> u8 {
-        match self.month {
-            4 | 6 | 9 | 11 => 30,
-            2 => if self.is_leap_year() { 29 } else { 28 },
-            _ => 31,
-        }
-    }
-}
-
-#[cfg(test)]
-mod test_date {
-    use super::*;
-
-    #[test]
-    fn test_default() {
-        // default: 01/01/0000
-        let default_fecha = Date::default();
-        assert_eq!(default_fecha.day, 1);
-        assert_eq!(default_fecha.month, 1);
-        assert_eq!(default_fecha.year, 0);
-    }
-
-    #[test]
-    fn test_display() {
-        let valid_fecha = Date::default();
-        let invalid_fecha = Date { day: 0, month: 1, year: 0 };
-
-        // write!(f, "{} de {} del {}", self.dia, NOMBRE_MESES[self.mes as usize - 1], self.ano)
-
-        assert_ne!(format!("{valid_fecha}"), format!("{}", invalid_fecha));
-        assert_eq!(format!("{valid_fecha}"), format!("{} de {} del {}", valid_fecha.day, NOMBRE_MESES[valid_fecha.month as usize - 1], valid_fecha.year));
-        assert_eq!(format!("{invalid_fecha}"), format!("{}/{}/{}", invalid_fecha.day, invalid_fecha.month, invalid_fecha.year));
-    }
-
-    #[test]
-    fn test_new() {
-        // invalida
-        let fecha = Date::new(0, 0, 0);
-        assert!(fecha.is_none());
-
-        // valida
-        let fecha = Date::new(22, 08, 2002);
-        assert!(fecha.is_some());
-    }
-
-    #[test]
-    fn test_bisiesto() {
-        let Some(fecha) = Date::new(1, 1, 0) else { panic!() };
-        assert!(fecha.is_leap_year());
-
-        let Some(fecha) = Date::new(1, 1, 2000) else { panic!() };
-        assert!(fecha.is_leap_year());
-
-        let Some(fecha) = Date::new(1, 1, -4) else { panic!() };
-        assert!(fecha.is_leap_year());
-
-        let Some(fecha) = Date::new(1, 1, 1) else { panic!() };
-        assert!(!fecha.is_leap_year());
-    }
-
-    #[test]
-    fn test_restar_dias() {
-        let Some(mut fecha) = Date::new(30, 04, 2016) else { panic!() };
-
-        fecha.subtract_days(5000);
-
-        assert_eq!(fecha.day, 22);
-        assert_eq!(fecha.month, 08);
-        assert_eq!(fecha.year, 2002);
-    }
-
-    #[test]
-    fn test_sumar_dias() {
-        let Some(mut fecha) = Date::new(22, 08, 2002) else { panic!() };
-
-        fecha.add_days(5000);
-
-        assert_eq!(fecha.day, 30);
-        assert_eq!(fecha.month, 04);
-        assert_eq!(fecha.year, 2016);
-    }
-
-    #[test]
-    fn test_current_month_days() {
-        let Some(fecha) = Date::new(22, 01, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 31);
-        let Some(fecha) = Date::new(22, 02, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 28);
-        let Some(fecha) = Date::new(22, 02, 2004) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 29);
-        let Some(fecha) = Date::new(22, 03, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 31);
-        let Some(fecha) = Date::new(22, 04, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 30);
-        let Some(fecha) = Date::new(22, 05, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 31);
-        let Some(fecha) = Date::new(22, 06, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 30);
-        let Some(fecha) = Date::new(22, 07, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 31);
-        let Some(fecha) = Date::new(22, 08, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 31);
-        let Some(fecha) = Date::new(22, 09, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 30);
-        let Some(fecha) = Date::new(22, 10, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 31);
-        let Some(fecha) = Date::new(22, 11, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 30);
-        let Some(fecha) = Date::new(22, 12, 2002) else { panic!() };
-        assert_eq!(fecha.current_month_days(), 31);
-    }
-
-    #[test]
-    fn test_cmp() {
-        let fecha1 = Date { day: 1, month: 1, year: 1};
-        let fecha2 = Date { day: 3, month: 1, year: 1};
-        let fecha3 = Date { day: 3, month: 1, year: 1};
-
-        assert!(fecha1 < fecha2, "Fecha 1 es anterior, por ende, es menor");
-        assert_eq!(fecha3, fecha2, "Fecha 3 es igual a fecha 2");
-        assert!(fecha3 > fecha1, "Fecha 3 es posterior a fecha1, por ende, es mayor");
-    }
-}
+use sha2::{Digest, Sha256};
+use calendario::{Fecha, FechaHora, FormatoIso, RangoFechas, Reloj, RelojCompartido, Ventana};
+use dinero::{Dinero, ErrorDinero, Moneda, Redondeo};
+use persistencia::{AlmacenCompartido, Documento, ErrorPersistencia, Migraciones, Repositorio};
 
 //
 // monetary_structs.rs
 //
 
-#[derive(Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BlockchainTransactionHash(String);
 impl BlockchainTransactionHash {
-    fn new(prefix: &str) -> BlockchainTransactionHash {
-        BlockchainTransactionHash(format!("{}-{}", prefix, rand::random::<u32>()))
+    // SHA-256 over what the transaction says and its chain's nonce, after the blockchain's name.
+    // a transaction gets its nonce when it's sent, see SimulatedChain::send; until then it's hashed with 0
+    fn of(transaction: &BlockchainTransaction, nonce: u64) -> BlockchainTransactionHash {
+        let payload = (&transaction.data, &transaction.blockchain, transaction.crypto, &transaction.quote, transaction.fee, nonce);
+        // these always serialize
+        let digest = Sha256::digest(serde_json::to_vec(&payload).unwrap_or_default());
+        BlockchainTransactionHash(format!("{}-{:x}", transaction.blockchain, digest))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct BlockchainTransaction {
     pub data: CommonTransactionData,
     pub blockchain: String,
     pub hash: BlockchainTransactionHash,
-    pub crypto: String,
-    pub quote: Quote
+    pub crypto: Moneda,
+    pub quote: Quote,
+    pub fee: Dinero, // in the crypto, on top of the amount
 }
 
 impl BlockchainTransaction {
-    pub fn new(data: CommonTransactionData, blockchain: &str, hash: Option<BlockchainTransactionHash>, crypto: &str, quote: Quote) -> Result<Self, ErrorNewTransaction> {
+    pub fn new(data: CommonTransactionData, blockchain: &str, hash: Option<BlockchainTransactionHash>, crypto: Moneda, quote: Quote) -> Result<Self, ErrorNewTransaction> {
         // invalid date
-        if !data.date.is_date_valid() { return Err(ErrorNewTransaction::InvalidDate) }
+        if !data.date.es_valida() { return Err(ErrorNewTransaction::InvalidDate) }
 
         // invalid amount
-        if data.amount < 0.0 { return Err(ErrorNewTransaction::InvalidInputAmount { amount: data.amount }) }
+        if data.amount.es_negativo() { return Err(ErrorNewTransaction::InvalidInputAmount { amount: data.amount }) }
+        if data.amount.moneda() != crypto { return Err(ErrorNewTransaction::InvalidCurrency { amount: data.amount }) }
 
         // invalid transaction type
         if data.transaction_type != TransactionType::BlockchainWithdrawal
             && data.transaction_type != TransactionType::BlockchainDeposit
         { return Err(ErrorNewTransaction::InvalidTransactionType { transaction_type: data.transaction_type }) }
 
-        // unwrap or create
-        let hash = if let Some(val) = hash { val }
-        else { BlockchainTransactionHash::new(&blockchain) };
-
-        Ok(Self {
+        let mut transaction = Self {
             data,
             blockchain: blockchain.to_string(),
-            hash,
-            crypto: crypto.to_string(),
-            quote
-        })
+            hash: BlockchainTransactionHash(String::new()),
+            crypto,
+            quote,
+            fee: Dinero::cero(crypto)
+        };
+
+        // unwrap or create
+        transaction.hash = if let Some(val) = hash { val }
+        else { BlockchainTransactionHash::of(&transaction, 0) };
+
+        Ok(transaction)
+    }
+
+    pub fn with_fee(mut self, fee: Dinero) -> Self {
+        self.fee = fee;
+        self
     }
 }
 
@@ -311,11 +85,26 @@ pub struct Blockchain {
     pub name: String,
     pub prefix: String,
     pub supported_cryptos: Vec<String>,
+    pub chain: SimulatedChain, // see chain.rs
 }
 
 impl Blockchain {
     fn new(name: &str, prefix: &str, supported_cryptos: Vec<String>) -> Self {
-        Blockchain { name: name.to_string(), prefix: prefix.to_string(), supported_cryptos }
+        Blockchain { name: name.to_string(), prefix: prefix.to_string(), supported_cryptos, chain: SimulatedChain::default() }
+    }
+
+    // deposits are credited once they have this many confirmations. 0 credits them as soon as they're sent
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.chain.confirmations = confirmations;
+        self
+    }
+
+    // `listed_as` is the name the error gives it: XYZ lists blockchains by their prefix
+    fn check_supports(&self, crypto: Moneda, listed_as: &str) -> Result<(), ErrorNewTransaction> {
+        if !self.supported_cryptos.iter().any(|supported| supported == crypto.codigo()) {
+            return Err(ErrorNewTransaction::CryptoNotSupportedByBlockchain { crypto: crypto.codigo().to_string(), blockchain: listed_as.to_string() })
+        }
+        Ok(())
     }
 
     // ➢ Retirar criptomoneda a blockchain: dado un monto de una cripto y una blockchain
@@ -325,10 +114,8 @@ impl Blockchain {
     // Luego se genera una transacción con los siguientes datos:
     // fecha, usuario, tipo: retiro cripto, blockchain, hash, cripto, monto, cotización.
 
-    fn withdraw(&self, data: CommonTransactionData, crypto: &str, quote: Quote) -> Result<BlockchainTransaction, ErrorNewTransaction> {
-        if !self.supported_cryptos.contains(&crypto.to_string()) {
-            return Err(ErrorNewTransaction::CryptoNotSupportedByBlockchain { crypto: crypto.to_string(), blockchain: self.name.clone() })
-        }
+    fn withdraw(&self, data: CommonTransactionData, crypto: Moneda, quote: Quote) -> Result<BlockchainTransaction, ErrorNewTransaction> {
+        self.check_supports(crypto, &self.name)?;
 
         // all other checks are made by BlockchainTransaction::new()
         BlockchainTransaction::new(
@@ -355,10 +142,19 @@ pub enum ErrorNewCryptocurrency {
 // but I want to enforce compile-time names for values
 // as it's not intuitive that .0 is the BUY value and .1 the SELL value
 // quote must be copied,
+// prices of one whole unit of the crypto, in pesos
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Quote {
-    pub buy: f64,
-    pub sell: f64
+    pub buy: Dinero,
+    pub sell: Dinero,
+    pub date: FechaHora // when it was published
+}
+
+impl Quote {
+    // halfway between buy and sell, what the history's candles are made of
+    pub fn mid(&self) -> Result<Dinero, ErrorDinero> {
+        self.buy.sumar(self.sell)?.fraccion(1, 2, Redondeo::MitadPar)
+    }
 }
 
 // CommonTransactionData
@@ -368,9 +164,9 @@ pub struct Quote {
 // and I prefer transaction types to be hard-coded.
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 pub struct CommonTransactionData {
-    pub date: Date,
+    pub date: FechaHora,
     pub user: u32,
-    pub amount: f64,
+    pub amount: Dinero, // pesos for fiat operations and buys, the crypto for everything else
     pub transaction_type: TransactionType,
 }
 
@@ -381,7 +177,8 @@ pub struct CommonTransactionData {
 #[derive(Debug, Clone, PartialEq)]
 pub enum ErrorNewTransaction {
     InvalidDate,
-    InvalidInputAmount{ amount: f64 },
+    InvalidInputAmount{ amount: Dinero },
+    InvalidCurrency { amount: Dinero }, // the amount isn't in the currency the operation is made in
     InvalidTransactionType { transaction_type: TransactionType },
     BlockchainNotDeclared,
     CryptoNotSupportedByBlockchain { crypto: String, blockchain: String },
@@ -400,7 +197,9 @@ pub enum TransactionType {
     BlockchainDeposit,
     BlockchainWithdrawal,
     CryptoBuy,
-    CryptoSell
+    CryptoSell,
+    InternalTransfer,
+    CryptoSwap,
 }
 
 impl fmt::Display for TransactionType {
@@ -412,14 +211,16 @@ impl fmt::Display for TransactionType {
             TransactionType::BlockchainWithdrawal => write!(f, "Blockchain Withdrawal"),
             TransactionType::CryptoBuy => write!(f, "Crypto Buy"),
             TransactionType::CryptoSell => write!(f, "Crypto Sell"),
+            TransactionType::InternalTransfer => write!(f, "Internal Transfer"),
+            TransactionType::CryptoSwap => write!(f, "Crypto Swap"),
         }
     }
 }
 
-#[derive(PartialEq)]
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct FiatTransaction {
     pub data: CommonTransactionData,
+    pub fee: Dinero, // on top of the amount
 }
 
 // all FIAT transfers will be treated as Argentine Peso transfers
@@ -431,49 +232,177 @@ impl FiatTransaction {
             _ => return Err(ErrorNewTransaction::InvalidTransactionType { transaction_type: data.transaction_type })
         }
 
-        if !data.date.is_date_valid() { return Err(ErrorNewTransaction::InvalidDate) }
-        if data.amount <= 0.0 { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
+        if !data.date.es_valida() { return Err(ErrorNewTransaction::InvalidDate) }
+        if !data.amount.es_positivo() { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
+        if data.amount.moneda() != FIAT { return Err(ErrorNewTransaction::InvalidCurrency { amount: data.amount }) }
 
         // user verifications must be done service-side
 
         Ok(FiatTransaction {
-            data
+            data, fee: Dinero::cero(FIAT)
         })
     }
+
+    pub fn with_fee(mut self, fee: Dinero) -> Self {
+        self.fee = fee;
+        self
+    }
 }
 
 //
 // Crypto Transaction
 //
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct CryptoTransaction {
     pub data: CommonTransactionData,
-    pub currency: String,
+    pub currency: Moneda,
+    pub quote: Option<Quote>, // the one the operation was made at. None for operations older than v6
+    pub fee: Dinero, // taken from what the user got: pesos for sells and buys from XYZ, the crypto for buys in the book
 }
 
 // all FIAT transfers will all be treated as Argentine Peso transfers
 
 impl CryptoTransaction {
-    pub fn new(data: CommonTransactionData, currency: &str) -> Result<Self, ErrorNewTransaction> {
-        if !data.date.is_date_valid() { return Err(ErrorNewTransaction::InvalidDate) }
-        if data.amount < 0.0 { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
-
-        match data.transaction_type {
-            TransactionType::CryptoBuy | TransactionType::CryptoSell => (),
+    pub fn new(data: CommonTransactionData, currency: Moneda) -> Result<Self, ErrorNewTransaction> {
+        if !data.date.es_valida() { return Err(ErrorNewTransaction::InvalidDate) }
+        if data.amount.es_negativo() { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
+
+        // buys are paid in pesos, sells are made in the crypto
+        let amount_currency = match data.transaction_type {
+            TransactionType::CryptoBuy => FIAT,
+            TransactionType::CryptoSell => currency,
             _ => { return Err(ErrorNewTransaction::InvalidTransactionType { transaction_type: data.transaction_type }) }
-        }
+        };
+        if data.amount.moneda() != amount_currency { return Err(ErrorNewTransaction::InvalidCurrency { amount: data.amount }) }
 
         // blockchain, currency, user_from, user_to verifications must be done service-side
 
         Ok(CryptoTransaction {
-            data, currency: currency.to_string()
+            data, currency, quote: None, fee: Dinero::cero(FIAT)
         })
     }
+
+    pub fn with_quote(mut self, quote: Quote) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+
+    pub fn with_fee(mut self, fee: Dinero) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    // what the operation was worth in pesos. A sell from before v6 has no quote to tell
+    pub fn fiat_value(&self) -> Result<Option<Dinero>, ErrorDinero> {
+        match (self.data.transaction_type, &self.quote) {
+            (TransactionType::CryptoBuy, _) => Ok(Some(self.data.amount)),
+            (_, Some(quote)) => self.data.amount.por(quote.sell, Redondeo::HaciaCero).map(Some),
+            (_, None) => Ok(None),
+        }
+    }
+}
+
+//
+// Internal Transfer
+//
+
+// who gets a transfer: a user id, or the email they registered with
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recipient {
+    Id(u32),
+    Email(String),
+}
+
+// pesos or crypto moved from one XYZ account to another, off-chain. data.user is who sent it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InternalTransfer {
+    pub data: CommonTransactionData,
+    pub recipient: u32,
+    pub memo: Option<String>,
+    pub quote: Option<Quote>, // the crypto's when it was sent. None for pesos, unquoted cryptos and transfers older than v13
+}
+
+impl InternalTransfer {
+    pub fn new(data: CommonTransactionData, recipient: u32, memo: Option<&str>) -> Result<Self, ErrorNewTransaction> {
+        if data.transaction_type != TransactionType::InternalTransfer {
+            return Err(ErrorNewTransaction::InvalidTransactionType { transaction_type: data.transaction_type })
+        }
+        if !data.date.es_valida() { return Err(ErrorNewTransaction::InvalidDate) }
+        if !data.amount.es_positivo() { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
+
+        // both users' verifications must be done service-side
+
+        Ok(InternalTransfer { data, recipient, memo: memo.map(str::to_string), quote: None })
+    }
+
+    pub fn with_quote(mut self, quote: Quote) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+}
+
+//
+// Crypto Swap
+//
+
+// one conversion in a swap's route, at one of XYZ's quotes: selling `from` for the currency it's quoted in,
+// or buying `to` with it. price is what a whole unit of the crypto sold or bought costs
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SwapLeg {
+    pub from: Moneda,
+    pub to: Moneda,
+    pub side: OrderSide,
+    pub price: Dinero,
+}
+
+impl SwapLeg {
+    // rounded down, like buys and sells
+    pub fn convert(&self, amount: Dinero) -> Result<Dinero, ErrorDinero> {
+        match self.side {
+            OrderSide::Sell => amount.por(self.price, Redondeo::HaciaCero),
+            OrderSide::Buy => amount.dividir_por_precio(self.price, self.to, Redondeo::HaciaCero),
+        }
+    }
+}
+
+// one currency traded for another in a single operation, whatever route it took. data.amount is what was given
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SwapTransaction {
+    pub data: CommonTransactionData,
+    pub to: Moneda,
+    pub route: Vec<SwapLeg>, // in order: the first leg takes data.amount, the last one gives what was received plus the fee
+    pub received: Dinero,
+    pub fee: Dinero, // in `to`, taken from what the user got
+    pub value: Option<Dinero>, // in pesos, see SwapPreview
+}
+
+impl SwapTransaction {
+    pub fn new(data: CommonTransactionData, to: Moneda) -> Result<Self, ErrorNewTransaction> {
+        if data.transaction_type != TransactionType::CryptoSwap {
+            return Err(ErrorNewTransaction::InvalidTransactionType { transaction_type: data.transaction_type })
+        }
+        if !data.date.es_valida() { return Err(ErrorNewTransaction::InvalidDate) }
+        if !data.amount.es_positivo() { return Err(ErrorNewTransaction::InvalidInputAmount{ amount: data.amount }) }
+        if data.amount.moneda() == to { return Err(ErrorNewTransaction::InvalidCurrency { amount: data.amount }) }
+
+        // the route and what it gives are up to XYZ's quotes, see XYZ::swap_preview
+
+        Ok(SwapTransaction { data, to, route: Vec::new(), received: Dinero::cero(to), fee: Dinero::cero(to), value: None })
+    }
+
+    pub fn with_preview(mut self, preview: &SwapPreview) -> Self {
+        self.route.clone_from(&preview.route);
+        self.received = preview.received;
+        self.fee = preview.fee;
+        self.value = preview.value;
+        self
+    }
 }
 
 #[cfg(test)]
 mod test_monetary_structs {
+    use calendario::Fecha;
     use super::*;
 
     #[test]
@@ -490,19 +419,29 @@ mod test_monetary_structs {
         let blockchain = Blockchain::new("Ethereum", "ETH", vec!["ETH".to_string(), "USDT".to_string()]);
 
         let data = CommonTransactionData {
-            date: Date::new(2, 10, 1).unwrap(),
+            date: Fecha::new(2, 10, 1).unwrap().into(),
             user: 1,
-            amount: 100.0,
+            amount: Dinero::enteros(100, Moneda::ETH),
             transaction_type: TransactionType::BlockchainWithdrawal
         };
 
-        let quote = Quote { buy: 2000.0, sell: 1900.0 };
+        let quote = Quote { buy: Dinero::enteros(2000, Moneda::ARS), sell: Dinero::enteros(1900, Moneda::ARS), date: data.date };
 
-        let transaction = blockchain.withdraw(data, "ETH", quote.clone()).unwrap();
+        let transaction = blockchain.withdraw(data, Moneda::ETH, quote.clone()).unwrap();
 
         assert_eq!(transaction.blockchain, "Ethereum");
-        assert_eq!(transaction.crypto, "ETH");
+        assert_eq!(transaction.crypto, Moneda::ETH);
         assert_eq!(transaction.quote, quote);
+
+        // any crypto it lists, once it's a known currency
+        let Ok(usdt) = Moneda::registrar("USDT", 6) else { panic!("Should be registered") };
+        let usdt_data = CommonTransactionData { amount: Dinero::enteros(100, usdt), ..data };
+        assert_eq!(blockchain.withdraw(usdt_data, usdt, quote.clone()).map(|transaction| transaction.crypto), Ok(usdt));
+
+        // the amount must be in the crypto being withdrawn
+        let data = CommonTransactionData { amount: Dinero::enteros(100, Moneda::BTC), ..data };
+        assert_eq!(blockchain.withdraw(data, Moneda::ETH, quote.clone()), Err(ErrorNewTransaction::InvalidCurrency { amount: data.amount }));
+        assert!(matches!(blockchain.withdraw(data, Moneda::BTC, quote), Err(ErrorNewTransaction::CryptoNotSupportedByBlockchain { .. })));
     }
 
     #[test]
@@ -530,6 +469,14 @@ mod test_monetary_structs {
         let crypto_sell = TransactionType::CryptoSell;
         let crypto_sell_str = format!("{}", crypto_sell);
         assert_eq!(crypto_sell_str, "Crypto Sell");
+
+        let internal_transfer = TransactionType::InternalTransfer;
+        let internal_transfer_str = format!("{internal_transfer}");
+        assert_eq!(internal_transfer_str, "Internal Transfer");
+
+        let crypto_swap = TransactionType::CryptoSwap;
+        let crypto_swap_str = format!("{crypto_swap}");
+        assert_eq!(crypto_swap_str, "Crypto Swap");
     }
 }
 
@@ -537,210 +484,2992 @@ mod test_monetary_structs {
 // user.rs
 //
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone, Copy)]
-pub struct Balance(pub f64);
+// user
 
-impl Balance {
-    pub fn new(balance: f64) -> Self {
-        Balance(balance)
-    }
-    pub fn add_assign_f64(&mut self, val: f64) {
-        self.0+= val;
-    }
-    pub fn sub_assign_f64(&mut self, val: f64) {
-        self.0-= val;
-    }
-    pub fn f64(self) -> f64 { self.0 }
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct User {
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub dni: Option<String>, // digits only. None for users from before v9
+    pub id: u32, // primary key
+    pub fiat_balance: Dinero, // pesos
+    pub crypto_balance: HashMap<Moneda, Dinero>,
+    pub kyc: Kyc, // what the user is allowed to do, see kyc.rs
+    pub tier: UserTier, // sets the withdrawal limits, see aml.rs
 }
 
-impl Default for Balance {
-    fn default() -> Self {
-        Balance::from(0.0)
+impl Hash for User {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.first_name.hash(state);
+        self.last_name.hash(state);
+        self.email.hash(state);
+        self.id.hash(state);
+        self.fiat_balance.hash(state);
     }
 }
 
-impl Hash for Balance {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.to_bits().hash(state);
-    }
+// what registrar_usuario needs: the id is XYZ's to give, and balances start at zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewUser {
+    pub first_name: String,
+    pub last_name: String,
+    pub email: String,
+    pub dni: String,
 }
-impl AddAssign for Balance {
-    fn add_assign(&mut self, rhs: Self) {
-        self.0 += rhs.0;
-    }
+
+// what actualizar_datos changes: only what is set. The DNI can't change, KYC verified it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UserDataUpdate {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
 }
-impl SubAssign for Balance {
-    fn sub_assign(&mut self, rhs: Self) {
-        self.0 -= rhs.0;
-    }
+
+// where a closing account's balances go when they're not zero
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payout {
+    pub mean: WithdrawalMean, // for the pesos
+    pub blockchains: BTreeMap<Moneda, String>, // the one each crypto goes out on
+}
+
+// closed accounts are kept, balances at zero, so their history still adds up
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ClosedAccount {
+    pub user: User,
+    pub date: FechaHora,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserDataError {
+    InvalidName { name: String },
+    InvalidEmail { email: String },
+    InvalidDni { dni: String },
+    EmailInUse { email: String, user_id: u32 },
+    DniInUse { dni: String, user_id: u32 },
+    UserNotFound { user_id: u32 },
+    NoIdsLeft,
+    File(ErrorPersistencia),
+}
+
+fn valid_name(name: &str) -> Result<String, UserDataError> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() { return Err(UserDataError::InvalidName { name: name.to_string() }) }
+    Ok(trimmed.to_string())
+}
+
+// something@domain.tld, without spaces. Emails are compared in lowercase
+fn valid_email(email: &str) -> Result<String, UserDataError> {
+    let email = email.trim().to_lowercase();
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty()
+            && !domain.contains('@')
+            && domain.split('.').count() >= 2
+            && domain.split('.').all(|label| !label.is_empty())
+            && !email.chars().any(char::is_whitespace),
+        None => false,
+    };
+    if valid { Ok(email) } else { Err(UserDataError::InvalidEmail { email }) }
 }
-impl From<f64> for Balance {
-    fn from(value: f64) -> Self {
-        Balance(value)
+
+// 7 or 8 digits, dots allowed: 12.345.678 is kept as 12345678
+fn valid_dni(dni: &str) -> Result<String, UserDataError> {
+    let digits: String = dni.trim().chars().filter(|character| *character != '.').collect();
+    if (7..=8).contains(&digits.len()) && digits.chars().all(|character| character.is_ascii_digit()) {
+        Ok(digits)
+    } else {
+        Err(UserDataError::InvalidDni { dni: dni.to_string() })
     }
 }
 
-pub trait AsBalance {
-    fn as_balance(&self) -> Balance;
+//
+// kyc.rs
+//
+
+// ➢ Solo los usuarios validados pueden comprar, vender o retirar.
+// Unverified -> Pending (documents submitted) -> Verified or Rejected.
+// a rejected user may submit again; a verified user may be suspended and reinstated
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KycState {
+    #[default]
+    Unverified,
+    Pending,
+    Verified,
+    Rejected,
+    Suspended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KycOperation {
+    Deposit,
+    Withdrawal,
+    Trade, // buys, sells and orders
 }
-impl AsBalance for f64 {
-    fn as_balance(&self) -> Balance {
-        Balance(self.clone())
+
+impl KycState {
+    // until they're verified users may only deposit. Suspended users may do nothing
+    pub fn allows(self, operation: KycOperation) -> bool {
+        match self {
+            KycState::Verified => true,
+            KycState::Unverified | KycState::Pending | KycState::Rejected => operation == KycOperation::Deposit,
+            KycState::Suspended => false,
+        }
     }
 }
 
-// user
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentKind {
+    Dni,
+    Passport,
+    ProofOfAddress,
+    Selfie,
+}
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-pub struct User {
-    pub first_name: String,
-    pub last_name: String,
-    pub email: String,
-    pub id: u32, // primary key
-    pub fiat_balance: Balance,
-    pub crypto_balance: HashMap<String, Balance>
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KycDocument {
+    pub kind: DocumentKind,
+    pub reference: String, // where the reviewer finds it: a number, a file name...
+    pub submitted: FechaHora,
 }
 
-impl Hash for User {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.first_name.hash(state);
-        self.last_name.hash(state);
-        self.email.hash(state);
-        self.id.hash(state);
-        self.fiat_balance.hash(state);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KycReview {
+    Approve, // Pending -> Verified
+    Reject, // Pending -> Rejected
+    Suspend, // Verified -> Suspended
+    Reinstate, // Suspended -> Verified
+}
+
+// every change of state, with the reviewer's note. Submissions have none
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KycTransition {
+    pub date: FechaHora,
+    pub from: KycState,
+    pub to: KycState,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KycError {
+    InvalidTransition { from: KycState, to: KycState },
+    NoDocuments,
+}
+
+// documents are kept even after a rejection: the next review sees every one of them
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Kyc {
+    state: KycState,
+    documents: Vec<KycDocument>,
+    history: Vec<KycTransition>, // oldest first
+}
+
+impl Kyc {
+    pub fn state(&self) -> KycState {
+        self.state
+    }
+
+    pub fn documents(&self) -> &[KycDocument] {
+        &self.documents
+    }
+
+    pub fn history(&self) -> &[KycTransition] {
+        &self.history
+    }
+
+    pub fn submit(&mut self, date: FechaHora, documents: Vec<KycDocument>) -> Result<(), KycError> {
+        if !matches!(self.state, KycState::Unverified | KycState::Rejected) {
+            return Err(KycError::InvalidTransition { from: self.state, to: KycState::Pending })
+        }
+        if documents.is_empty() { return Err(KycError::NoDocuments) }
+
+        self.documents.extend(documents);
+        self.change(date, KycState::Pending, None);
+        Ok(())
+    }
+
+    pub fn review(&mut self, date: FechaHora, review: KycReview, note: &str) -> Result<(), KycError> {
+        let (from, to) = match review {
+            KycReview::Approve => (KycState::Pending, KycState::Verified),
+            KycReview::Reject => (KycState::Pending, KycState::Rejected),
+            KycReview::Suspend => (KycState::Verified, KycState::Suspended),
+            KycReview::Reinstate => (KycState::Suspended, KycState::Verified),
+        };
+        if self.state != from { return Err(KycError::InvalidTransition { from: self.state, to }) }
+
+        self.change(date, to, Some(note.to_string()));
+        Ok(())
+    }
+
+    fn change(&mut self, date: FechaHora, to: KycState, note: Option<String>) {
+        self.history.push(KycTransition { date, from: self.state, to, note });
+        self.state = to;
+    }
+}
+
+// the error every operation returns when the user's KYC state doesn't allow it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsuarioNoValidado {
+    pub user_id: u32,
+    pub state: KycState,
+    pub operation: KycOperation,
+}
+
+impl User {
+    pub fn check_kyc(&self, operation: KycOperation) -> Result<(), UsuarioNoValidado> {
+        if self.kyc.state().allows(operation) { return Ok(()) }
+        Err(UsuarioNoValidado { user_id: self.id, state: self.kyc.state(), operation })
+    }
+}
+
+//
+// ledger.rs
+//
+
+// every operation XYZ ever made, in the order it was made.
+// crypto_transactions is still kept for the crypto stats, the ledger is what statements are built from
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LedgerRecord {
+    Fiat(FiatTransaction),
+    Crypto(CryptoTransaction),
+    Blockchain(BlockchainTransaction),
+    Transfer(InternalTransfer),
+    Swap(SwapTransaction),
+}
+
+impl LedgerRecord {
+    pub fn data(&self) -> &CommonTransactionData {
+        match self {
+            LedgerRecord::Fiat(transaction) => &transaction.data,
+            LedgerRecord::Crypto(transaction) => &transaction.data,
+            LedgerRecord::Blockchain(transaction) => &transaction.data,
+            LedgerRecord::Transfer(transfer) => &transfer.data,
+            LedgerRecord::Swap(swap) => &swap.data,
+        }
+    }
+
+    // fiat operations have no crypto. A swap's is the crypto given, or the one got for pesos
+    pub fn crypto(&self) -> Option<Moneda> {
+        match self {
+            LedgerRecord::Fiat(_) => None,
+            LedgerRecord::Crypto(transaction) => Some(transaction.currency),
+            LedgerRecord::Blockchain(transaction) => Some(transaction.crypto),
+            LedgerRecord::Transfer(transfer) => Some(transfer.data.amount.moneda()).filter(|currency| *currency != FIAT),
+            LedgerRecord::Swap(swap) => Some(swap.data.amount.moneda()).filter(|currency| *currency != FIAT).or(Some(swap.to)),
+        }
+    }
+
+    // the crypto a swap gave the user. Every other operation has only one
+    pub fn swapped_to(&self) -> Option<Moneda> {
+        match self {
+            LedgerRecord::Swap(swap) => Some(swap.to).filter(|currency| *currency != FIAT),
+            _ => None,
+        }
+    }
+
+    // the user on the other side of a transfer. Every other operation has only one
+    pub fn recipient(&self) -> Option<u32> {
+        match self {
+            LedgerRecord::Transfer(transfer) => Some(transfer.recipient),
+            _ => None,
+        }
+    }
+
+    // what the operation was worth in pesos. Blockchain operations and crypto transfers go at their quote's sell price,
+    // if they have one. Swaps were valued when they were made
+    pub fn fiat_value(&self) -> Result<Option<Dinero>, ErrorDinero> {
+        match self {
+            LedgerRecord::Fiat(transaction) => Ok(Some(transaction.data.amount)),
+            LedgerRecord::Crypto(transaction) => transaction.fiat_value(),
+            LedgerRecord::Blockchain(transaction) => transaction.data.amount.por(transaction.quote.sell, Redondeo::HaciaCero).map(Some),
+            LedgerRecord::Transfer(transfer) => match &transfer.quote {
+                _ if transfer.data.amount.moneda() == FIAT => Ok(Some(transfer.data.amount)),
+                Some(quote) => transfer.data.amount.por(quote.sell, Redondeo::HaciaCero).map(Some),
+                None => Ok(None),
+            },
+            LedgerRecord::Swap(swap) => Ok(swap.value),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    pub id: u64, // position in the ledger, never reused
+    pub record: LedgerRecord,
+}
+
+// append-only: entries can be added and queried, never changed or removed
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Ledger {
+    entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    pub fn record(&mut self, record: LedgerRecord) -> &LedgerEntry {
+        let id = self.entries.len() as u64;
+        self.entries.push(LedgerEntry { id, record });
+        &self.entries[self.entries.len() - 1]
+    }
+
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // entries matching every filter set in query, oldest first
+    pub fn query(&self, query: &LedgerQuery) -> Vec<&LedgerEntry> {
+        self.entries.iter().filter(|entry| query.matches(entry)).collect()
+    }
+}
+
+// LedgerQuery
+// filters for Ledger::query. An unset filter matches everything,
+// so LedgerQuery::default() returns the whole ledger
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LedgerQuery {
+    user: Option<u32>,
+    transaction_type: Option<TransactionType>,
+    crypto: Option<Moneda>,
+    from: Option<FechaHora>,
+    until: Option<FechaHora>,
+}
+
+impl LedgerQuery {
+    // transfers are in both users' histories
+    pub fn for_user(mut self, user_id: u32) -> Self {
+        self.user = Some(user_id);
+        self
+    }
+
+    // only the kind of operation is compared:
+    // FiatWithdrawal { mean: MercadoPago } also matches bank transfers
+    pub fn of_type(mut self, transaction_type: TransactionType) -> Self {
+        self.transaction_type = Some(transaction_type);
+        self
+    }
+
+    // a swap matches either of its cryptos
+    pub fn with_crypto(mut self, crypto: Moneda) -> Self {
+        self.crypto = Some(crypto);
+        self
+    }
+
+    // from is included, until is not
+    pub fn between(mut self, from: FechaHora, until: FechaHora) -> Self {
+        self.from = Some(from);
+        self.until = Some(until);
+        self
+    }
+
+    // every day of the range, from the first day's midnight up to the midnight after the last one (UTC)
+    pub fn during(self, range: &RangoFechas) -> Self {
+        let mut after_last_day = range.fin();
+        after_last_day.sumar_dias(1);
+        self.between(FechaHora::from(range.inicio()), FechaHora::from(after_last_day))
+    }
+
+    pub fn matches(&self, entry: &LedgerEntry) -> bool {
+        let data = entry.record.data();
+
+        self.user.is_none_or(|user| data.user == user || entry.record.recipient() == Some(user))
+            && self.transaction_type.is_none_or(|transaction_type| discriminant(&data.transaction_type) == discriminant(&transaction_type))
+            && self.crypto.is_none_or(|crypto| entry.record.crypto() == Some(crypto) || entry.record.swapped_to() == Some(crypto))
+            && self.from.is_none_or(|from| data.date >= from)
+            && self.until.is_none_or(|until| data.date < until)
+    }
+}
+
+//
+// journal.rs
+//
+
+// pesos are the only fiat
+pub const FIAT: Moneda = Moneda::ARS;
+
+// Account
+// users' accounts are what XYZ owes them; the house accounts are XYZ's side:
+//  FiatFloat: pesos XYZ holds in the bank
+//  HotWallet: a crypto XYZ holds on its blockchains
+//  HouseTrading: XYZ's own position in a currency, which moves when users buy from or sell to XYZ
+//  FeeRevenue: fees charged, per currency
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Account {
+    UserFiat(u32),
+    UserCrypto { user: u32, crypto: Moneda },
+    FiatFloat,
+    HotWallet(Moneda),
+    HouseTrading(Moneda),
+    FeeRevenue(Moneda),
+}
+
+impl Account {
+    pub fn currency(&self) -> Moneda {
+        match self {
+            Account::UserFiat(_) | Account::FiatFloat => FIAT,
+            Account::UserCrypto { crypto: currency, .. } | Account::HotWallet(currency)
+            | Account::HouseTrading(currency) | Account::FeeRevenue(currency) => *currency,
+        }
+    }
+
+    // what XYZ holds grows with debits. What it owes (users' money), its own position and its revenue grow with credits
+    pub fn grows_with_debits(&self) -> bool {
+        matches!(self, Account::FiatFloat | Account::HotWallet(_))
+    }
+
+    // the accounts that users' balances are a copy of
+    pub fn is_user_account(&self) -> bool {
+        matches!(self, Account::UserFiat(_) | Account::UserCrypto { .. })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Debit,
+    Credit,
+}
+
+// JournalLine
+// the amount is never negative: the side says which way it goes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalLine {
+    pub account: Account,
+    pub side: Side,
+    pub amount: Dinero,
+}
+
+impl JournalLine {
+    pub fn debit(account: Account, amount: Dinero) -> Self {
+        JournalLine { account, side: Side::Debit, amount }
+    }
+
+    pub fn credit(account: Account, amount: Dinero) -> Self {
+        JournalLine { account, side: Side::Credit, amount }
+    }
+
+    // in the account's currency, and not negative
+    pub fn is_valid(&self) -> bool {
+        self.amount.moneda() == self.account.currency() && !self.amount.es_negativo()
+    }
+
+    // the account's balance after this line
+    pub fn apply_to(&self, balance: Dinero) -> Result<Dinero, ErrorDinero> {
+        if (self.side == Side::Debit) == self.account.grows_with_debits() {
+            balance.sumar(self.amount)
+        } else {
+            balance.restar(self.amount)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub id: u64,
+    pub date: FechaHora,
+    pub ledger_id: Option<u64>, // the operation that posted it. None for opening balances
+    pub lines: Vec<JournalLine>,
+}
+
+impl JournalEntry {
+    // per currency, debits minus credits. A balanced entry has none
+    pub fn imbalances(&self) -> Result<BTreeMap<Moneda, Dinero>, ErrorDinero> {
+        let mut sums: BTreeMap<Moneda, Dinero> = BTreeMap::new();
+        for line in &self.lines {
+            let sum = sums.entry(line.amount.moneda()).or_insert(Dinero::cero(line.amount.moneda()));
+            *sum = match line.side {
+                Side::Debit => sum.sumar(line.amount)?,
+                Side::Credit => sum.restar(line.amount)?,
+            };
+        }
+
+        sums.retain(|_, sum| !sum.es_cero());
+        Ok(sums)
+    }
+}
+
+// append-only, like the ledger
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct Journal {
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    // lines must balance per currency. XYZ only posts balanced entries; verificar_integridad checks the saved ones
+    pub fn post(&mut self, date: FechaHora, ledger_id: Option<u64>, lines: Vec<JournalLine>) -> &JournalEntry {
+        let id = self.entries.len() as u64;
+        self.entries.push(JournalEntry { id, date, ledger_id, lines });
+        &self.entries[self.entries.len() - 1]
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    pub fn balance(&self, account: &Account) -> Result<Dinero, ErrorDinero> {
+        self.entries.iter()
+            .flat_map(|entry| &entry.lines)
+            .filter(|line| &line.account == account)
+            .try_fold(Dinero::cero(account.currency()), |balance, line| line.apply_to(balance))
+    }
+
+    // every account that was ever posted to
+    pub fn balances(&self) -> Result<BTreeMap<&Account, Dinero>, ErrorDinero> {
+        let mut balances: BTreeMap<&Account, Dinero> = BTreeMap::new();
+        for line in self.entries.iter().flat_map(|entry| &entry.lines) {
+            let balance = balances.entry(&line.account).or_insert(Dinero::cero(line.account.currency()));
+            *balance = line.apply_to(*balance)?;
+        }
+        Ok(balances)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityError {
+    InvalidLine { entry: u64, line: usize }, // see JournalLine::is_valid
+    UnbalancedEntry { entry: u64, difference: Dinero },
+    BalanceDrift { account: Account, cached: Dinero, journal: Dinero },
+    Money(ErrorDinero), // the journal can't even be added up
+}
+
+//
+// orderbook.rs
+//
+
+// every crypto trades against pesos: a book per crypto, prices in pesos per whole unit
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    pub fn opposite(self) -> Self {
+        match self {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderKind {
+    Limit { price: Dinero }, // whatever isn't filled right away rests in the book
+    Market, // takes whatever the book has at any price. Whatever isn't filled right away is dropped
+}
+
+// Order
+// only limit orders rest in a book, so a resting order always has a price.
+// amount and remaining are in the book's crypto
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Order {
+    pub id: u64,
+    pub date: FechaHora,
+    pub user: u32,
+    pub side: OrderSide,
+    pub price: Dinero,
+    pub amount: Dinero,
+    pub remaining: Dinero,
+}
+
+impl Order {
+    pub fn crypto(&self) -> Moneda {
+        self.amount.moneda()
+    }
+
+    // what the order holds until it's filled or cancelled:
+    // a buy, the pesos for the rest of it at its price (rounded up); a sell, the rest of the crypto
+    pub fn reserved(&self) -> Result<Dinero, ErrorDinero> {
+        match self.side {
+            OrderSide::Buy => self.remaining.por(self.price, Redondeo::HaciaArriba),
+            OrderSide::Sell => Ok(self.remaining),
+        }
+    }
+
+    // whether an order at `price` on the opposite side can trade with this one
+    fn crosses(&self, price: Dinero) -> bool {
+        match self.side {
+            OrderSide::Buy => price <= self.price,
+            OrderSide::Sell => price >= self.price,
+        }
+    }
+
+    // same, for an order of any kind. A market order trades with anything
+    fn crossed_by(&self, kind: OrderKind) -> bool {
+        match kind {
+            OrderKind::Limit { price } => self.crosses(price),
+            OrderKind::Market => true,
+        }
+    }
+}
+
+// price-time priority: best price first, and the oldest first at the same price
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct OrderBook {
+    bids: Vec<Order>, // highest first
+    asks: Vec<Order>, // lowest first
+}
+
+impl OrderBook {
+    pub fn bids(&self) -> &[Order] {
+        &self.bids
+    }
+
+    pub fn asks(&self) -> &[Order] {
+        &self.asks
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.bids.iter().chain(&self.asks)
+    }
+
+    fn side_mut(&mut self, side: OrderSide) -> &mut Vec<Order> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+
+    pub fn best(&self, side: OrderSide) -> Option<&Order> {
+        match side {
+            OrderSide::Buy => self.bids.first(),
+            OrderSide::Sell => self.asks.first(),
+        }
+    }
+
+    pub fn side(&self, side: OrderSide) -> &[Order] {
+        match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        }
+    }
+
+    // behind every order at the same price or better
+    fn insert(&mut self, order: Order) {
+        let position = match order.side {
+            OrderSide::Buy => self.bids.partition_point(|resting| resting.price >= order.price),
+            OrderSide::Sell => self.asks.partition_point(|resting| resting.price <= order.price),
+        };
+        self.side_mut(order.side).insert(position, order);
+    }
+
+    fn remove(&mut self, order_id: u64) -> Option<Order> {
+        for orders in [&mut self.bids, &mut self.asks] {
+            if let Some(position) = orders.iter().position(|order| order.id == order_id) {
+                return Some(orders.remove(position));
+            }
+        }
+        None
+    }
+
+    // fills the best order of a side; it leaves the book once there's nothing left of it
+    fn fill_best(&mut self, side: OrderSide, amount: Dinero) -> Result<(), ErrorDinero> {
+        let orders = self.side_mut(side);
+        let Some(best) = orders.first_mut() else { return Ok(()) };
+        best.remaining = best.remaining.restar(amount)?;
+        if !best.remaining.es_positivo() { orders.remove(0); }
+        Ok(())
+    }
+}
+
+// the books and the ids of their orders, which never repeat
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct OrderBooks {
+    next_id: u64,
+    books: BTreeMap<Moneda, OrderBook>,
+}
+
+impl OrderBooks {
+    pub fn book(&self, crypto: Moneda) -> Option<&OrderBook> {
+        self.books.get(&crypto)
+    }
+
+    pub fn orders(&self) -> impl Iterator<Item = &Order> {
+        self.books.values().flat_map(OrderBook::orders)
+    }
+
+    // every open order of the user, in every book
+    fn cancel_all(&mut self, user_id: u32) {
+        for book in self.books.values_mut() {
+            book.bids.retain(|order| order.user != user_id);
+            book.asks.retain(|order| order.user != user_id);
+        }
+    }
+
+    // what a user's open orders hold of a currency
+    pub fn held(&self, user_id: u32, currency: Moneda) -> Result<Dinero, ErrorDinero> {
+        let reserved = self.orders()
+            .filter(|order| order.user == user_id)
+            .filter(|order| currency == match order.side { OrderSide::Buy => FIAT, OrderSide::Sell => order.crypto() })
+            .map(Order::reserved)
+            .collect::<Result<Vec<_>, _>>()?;
+        Dinero::sumar_todos(currency, reserved)
+    }
+}
+
+// Trade
+// the resting order sets the price. fiat is what the buyer paid the seller;
+// each side pays its fee out of what it gets
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub buy_order: u64,
+    pub sell_order: u64,
+    pub buyer: u32,
+    pub seller: u32,
+    pub price: Dinero,
+    pub amount: Dinero,
+    pub fiat: Dinero,
+    pub buyer_fee: Dinero, // in the crypto, out of what the buyer gets
+    pub seller_fee: Dinero, // in pesos, out of what the seller gets
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderPlacement {
+    pub order_id: u64,
+    pub trades: Vec<Trade>,
+    pub resting: Option<Order>, // what's left in the book, if anything
+}
+
+//
+// quotes.rs
+//
+
+// Candle
+// the open, high, low and close of a crypto's mid price during a day (UTC)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Candle {
+    pub day: Fecha,
+    pub open: Dinero,
+    pub high: Dinero,
+    pub low: Dinero,
+    pub close: Dinero,
+}
+
+impl Candle {
+    fn new(day: Fecha, price: Dinero) -> Self {
+        Candle { day, open: price, high: price, low: price, close: price }
+    }
+}
+
+// a day's quotes must be recorded in order: the last one recorded is the close.
+// the candles are a list and not a map because a Fecha can't be a JSON key
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct QuoteHistory {
+    candles: BTreeMap<Moneda, Vec<Candle>>, // oldest day first
+}
+
+impl QuoteHistory {
+    pub fn record(&mut self, crypto: Moneda, quote: &Quote) -> Result<(), ErrorDinero> {
+        let price = quote.mid()?;
+        let day = quote.date.fecha();
+        let candles = self.candles.entry(crypto).or_default();
+
+        match candles.binary_search_by(|candle| candle.day.cmp(&day)) {
+            Ok(position) => {
+                let candle = &mut candles[position];
+                if price > candle.high { candle.high = price }
+                if price < candle.low { candle.low = price }
+                candle.close = price;
+            }
+            Err(position) => candles.insert(position, Candle::new(day, price)),
+        }
+        Ok(())
+    }
+
+    pub fn candles(&self, crypto: Moneda) -> &[Candle] {
+        self.candles.get(&crypto).map_or(&[], Vec::as_slice)
+    }
+
+    // the close of the last day recorded on or before `day`
+    pub fn close_on(&self, crypto: Moneda, day: Fecha) -> Option<Dinero> {
+        self.candles(crypto).iter().take_while(|candle| candle.day <= day).last().map(|candle| candle.close)
+    }
+}
+
+// PriceFeed
+// where quotes come from. Every call returns the quotes published up to `until`
+// that weren't returned before, oldest first
+pub trait PriceFeed {
+    fn quotes_until(&mut self, until: FechaHora) -> Vec<(Moneda, Quote)>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedError {
+    Io { path: String, error: String },
+    Line { line: usize, error: String }, // counted from 1
+}
+
+// ReplayFeed
+// plays back quotes saved as CSV: fecha,cripto,compra,venta
+// fecha is milliseconds since the epoch or a day (taken as its midnight UTC),
+// compra and venta are in pesos. Blank lines, # comments and a header are skipped
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayFeed {
+    quotes: Vec<(Moneda, Quote)>, // oldest first
+    next: usize,
+}
+
+impl ReplayFeed {
+    pub fn from_csv(csv: &str) -> Result<Self, FeedError> {
+        let mut quotes = Vec::new();
+        for (index, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || (index == 0 && line.starts_with("fecha")) { continue }
+
+            let error = |error: String| FeedError::Line { line: index + 1, error };
+            let [date, crypto, buy, sell] = line.split(',').map(str::trim).collect::<Vec<_>>()[..] else {
+                return Err(error(format!("expected 4 columns: {line}")))
+            };
+
+            let date = match date.parse::<i64>() {
+                Ok(millis) => FechaHora::desde_millis(millis),
+                Err(_) => FechaHora::from(date.parse::<Fecha>().map_err(|e| error(e.to_string()))?),
+            };
+            let crypto: Moneda = crypto.parse().map_err(|e: ErrorDinero| error(e.to_string()))?;
+            let buy = Dinero::desde_texto(buy, FIAT).map_err(|e| error(e.to_string()))?;
+            let sell = Dinero::desde_texto(sell, FIAT).map_err(|e| error(e.to_string()))?;
+
+            quotes.push((crypto, Quote { buy, sell, date }));
+        }
+
+        // stable: same-date quotes keep the file's order
+        quotes.sort_by_key(|(_, quote)| quote.date);
+        Ok(ReplayFeed { quotes, next: 0 })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, FeedError> {
+        let csv = std::fs::read_to_string(path)
+            .map_err(|error| FeedError::Io { path: path.to_string(), error: error.to_string() })?;
+        Self::from_csv(&csv)
+    }
+}
+
+impl PriceFeed for ReplayFeed {
+    fn quotes_until(&mut self, until: FechaHora) -> Vec<(Moneda, Quote)> {
+        let pending = &self.quotes[self.next..];
+        let published = pending.partition_point(|(_, quote)| quote.date <= until);
+        self.next += published;
+        pending[..published].to_vec()
+    }
+}
+
+// RandomWalkFeed
+// a simulated market: every `step` each crypto's buy and sell move together by up to
+// `volatility` hundredths of a percent, up or down. The same seed walks the same way every time.
+// the starting quotes are published at `start`, unchanged
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomWalkFeed {
+    state: u64,
+    next: FechaHora,
+    step: Duration,
+    volatility: u32,
+    quotes: BTreeMap<Moneda, Quote>, // ordered, so cryptos take their random numbers in the same order
+    started: bool,
+}
+
+impl RandomWalkFeed {
+    pub fn new(seed: u64, start: FechaHora, step: Duration, quotes: &Quotes) -> Self {
+        RandomWalkFeed {
+            state: seed,
+            next: start,
+            step: if step.is_zero() { Duration::from_millis(1) } else { step }, // or it would never get to `until`
+            volatility: 100,
+            quotes: quotes.iter().map(|(crypto, quote)| (*crypto, quote.clone())).collect(),
+            started: false,
+        }
+    }
+
+    // at most 99.99%: a price can't fall to zero
+    pub fn with_volatility(mut self, hundredths_of_percent: u32) -> Self {
+        self.volatility = hundredths_of_percent.min(9_999);
+        self
+    }
+
+    // splitmix64
+    fn next_random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // a price that would round to zero or overflow stays where it was
+    fn walk(&mut self, quote: &Quote, date: FechaHora) -> Quote {
+        let volatility = u64::from(self.volatility);
+        let change = i128::from(self.next_random() % (2 * volatility + 1)) - i128::from(volatility);
+        let moved = |price: Dinero| price.fraccion(10_000 + change, 10_000, Redondeo::MitadPar)
+            .ok().filter(Dinero::es_positivo).unwrap_or(price);
+        Quote { buy: moved(quote.buy), sell: moved(quote.sell), date }
+    }
+}
+
+impl PriceFeed for RandomWalkFeed {
+    fn quotes_until(&mut self, until: FechaHora) -> Vec<(Moneda, Quote)> {
+        let mut published = Vec::new();
+        while self.next <= until {
+            let date = self.next;
+            let cryptos: Vec<Moneda> = self.quotes.keys().copied().collect();
+            for crypto in cryptos {
+                let current = self.quotes[&crypto].clone();
+                let quote = if self.started { self.walk(&current, date) } else { Quote { date, ..current } };
+                self.quotes.insert(crypto, quote.clone());
+                published.push((crypto, quote));
+            }
+            self.started = true;
+            self.next += self.step;
+        }
+        published
+    }
+}
+
+//
+// fees.rs
+//
+
+// fee rates are in hundredths of a percent: 25 is 0.25%
+pub const FULL_RATE: u32 = 10_000;
+
+// a user's fee tier is picked by what they traded during the last TRAILING_VOLUME_DAYS days, in pesos
+pub const TRAILING_VOLUME_DAYS: u64 = 30;
+
+// the order that was already in the book makes the market, the one that trades with it takes.
+// buys from and sells to XYZ itself are always takers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradingFee {
+    Flat(u32),
+    MakerTaker { maker: u32, taker: u32 },
+}
+
+impl TradingFee {
+    pub fn rate(self, liquidity: Liquidity) -> u32 {
+        match (self, liquidity) {
+            (TradingFee::Flat(rate), _) | (TradingFee::MakerTaker { maker: rate, .. }, Liquidity::Maker)
+            | (TradingFee::MakerTaker { taker: rate, .. }, Liquidity::Taker) => rate,
+        }
+    }
+}
+
+// the fee for users that traded at least min_volume (in pesos)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FeeTier {
+    pub min_volume: Dinero,
+    pub fee: TradingFee,
+}
+
+// FeeSchedule
+// what XYZ charges. Anything not in the schedule is free, so the default charges nothing.
+// trading fees are per pair (every crypto trades against pesos); withdrawal fees are fixed,
+// per blockchain and crypto, or per withdrawal mean
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FeeSchedule {
+    trading: BTreeMap<Moneda, Vec<FeeTier>>, // lowest min_volume first
+    blockchain_withdrawal: BTreeMap<String, BTreeMap<Moneda, Dinero>>,
+    fiat_withdrawal: HashMap<WithdrawalMean, Dinero>,
+}
+
+impl FeeSchedule {
+    // rates are capped at 100%
+    pub fn with_trading(mut self, crypto: Moneda, mut tiers: Vec<FeeTier>) -> Self {
+        let cap = |rate: u32| rate.min(FULL_RATE);
+        for tier in &mut tiers {
+            tier.fee = match tier.fee {
+                TradingFee::Flat(rate) => TradingFee::Flat(cap(rate)),
+                TradingFee::MakerTaker { maker, taker } => TradingFee::MakerTaker { maker: cap(maker), taker: cap(taker) },
+            };
+        }
+        tiers.sort_by_key(|tier| tier.min_volume.unidades());
+        self.trading.insert(crypto, tiers);
+        self
+    }
+
+    // the fee's currency is the crypto it's charged on
+    pub fn with_blockchain_withdrawal(mut self, blockchain: &str, fee: Dinero) -> Self {
+        self.blockchain_withdrawal.entry(blockchain.to_string()).or_default().insert(fee.moneda(), fee);
+        self
+    }
+
+    pub fn with_fiat_withdrawal(mut self, mean: WithdrawalMean, fee: Dinero) -> Self {
+        self.fiat_withdrawal.insert(mean, fee);
+        self
+    }
+
+    // the highest tier the volume reaches. No tier, no fee
+    pub fn trading_rate(&self, crypto: Moneda, volume: Dinero, liquidity: Liquidity) -> u32 {
+        self.trading.get(&crypto)
+            .and_then(|tiers| tiers.iter().rev().find(|tier| tier.min_volume <= volume))
+            .map_or(0, |tier| tier.fee.rate(liquidity))
+    }
+
+    pub fn blockchain_withdrawal_fee(&self, blockchain: &str, crypto: Moneda) -> Dinero {
+        self.blockchain_withdrawal.get(blockchain)
+            .and_then(|fees| fees.get(&crypto)).copied()
+            .unwrap_or(Dinero::cero(crypto))
+    }
+
+    pub fn fiat_withdrawal_fee(&self, mean: WithdrawalMean) -> Dinero {
+        self.fiat_withdrawal.get(&mean).copied().unwrap_or(Dinero::cero(FIAT))
+    }
+}
+
+// rounded up: XYZ never charges less than its rate
+pub fn fee_at(amount: Dinero, rate: u32) -> Result<Dinero, ErrorDinero> {
+    amount.fraccion(i128::from(rate), i128::from(FULL_RATE), Redondeo::HaciaArriba)
+}
+
+//
+// aml.rs
+//
+
+// a withdrawal counts against the monthly limit for MONTHLY_LIMIT_DAYS days
+pub const MONTHLY_LIMIT_DAYS: u64 = 30;
+
+// withdrawal limits are per tier. Users start as Standard, see set_tier
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum UserTier {
+    #[default]
+    Standard,
+    Premium,
+    Corporate,
+}
+
+// how much, in pesos, a tier may withdraw during the last day and during the last MONTHLY_LIMIT_DAYS days.
+// crypto counts at its quote's sell price. A limit that isn't set doesn't apply
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct WithdrawalLimits {
+    pub daily: Option<Dinero>,
+    pub monthly: Option<Dinero>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitPeriod {
+    Daily,
+    Monthly,
+}
+
+impl LimitPeriod {
+    pub fn hours(self) -> u64 {
+        match self {
+            LimitPeriod::Daily => 24,
+            LimitPeriod::Monthly => 24 * MONTHLY_LIMIT_DAYS,
+        }
+    }
+}
+
+// the withdrawal isn't made: withdrawn is what the period would add up to with it
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitExceeded {
+    pub period: LimitPeriod,
+    pub limit: Dinero,
+    pub withdrawn: Dinero,
+}
+
+// more than `withdrawals` withdrawals within `hours`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VelocityRule {
+    pub withdrawals: usize,
+    pub hours: u64,
+}
+
+// `deposits` or more fiat deposits within `hours`, each of them under `threshold` by no more than `margin`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct StructuringRule {
+    pub threshold: Dinero,
+    pub margin: Dinero,
+    pub deposits: usize,
+    pub hours: u64,
+}
+
+// withdrawing, within `hours` of depositing, at least `rate` of what was deposited.
+// like fee rates, in hundredths of a percent
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TurnaroundRule {
+    pub hours: u64,
+    pub rate: u32,
+}
+
+// AmlRules
+// what XYZ checks on deposits and withdrawals. Like the fee schedule, the default checks nothing.
+// limits are enforced; the other rules only raise alerts, see Compliance
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AmlRules {
+    limits: BTreeMap<UserTier, WithdrawalLimits>,
+    velocity: Option<VelocityRule>,
+    structuring: Option<StructuringRule>,
+    turnaround: Option<TurnaroundRule>,
+}
+
+impl AmlRules {
+    pub fn with_limits(mut self, tier: UserTier, limits: WithdrawalLimits) -> Self {
+        self.limits.insert(tier, limits);
+        self
+    }
+
+    pub fn with_velocity(mut self, rule: VelocityRule) -> Self {
+        self.velocity = Some(rule);
+        self
+    }
+
+    pub fn with_structuring(mut self, rule: StructuringRule) -> Self {
+        self.structuring = Some(rule);
+        self
+    }
+
+    pub fn with_turnaround(mut self, rule: TurnaroundRule) -> Self {
+        self.turnaround = Some(rule);
+        self
+    }
+
+    pub fn limits(&self, tier: UserTier) -> WithdrawalLimits {
+        self.limits.get(&tier).copied().unwrap_or_default()
+    }
+
+    pub fn velocity(&self) -> Option<VelocityRule> {
+        self.velocity
+    }
+
+    pub fn structuring(&self) -> Option<StructuringRule> {
+        self.structuring
+    }
+
+    pub fn turnaround(&self) -> Option<TurnaroundRule> {
+        self.turnaround
+    }
+}
+
+// what tripped the rule, as it was when it did
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AlertKind {
+    Velocity { withdrawals: usize, hours: u64 },
+    Structuring { deposits: usize, hours: u64 },
+    RapidTurnaround { deposited: Dinero, withdrawn: Dinero, hours: u64 },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertStatus {
+    Open,
+    Dismissed, // nothing wrong
+    Confirmed, // to be reported
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertReview {
+    Dismiss,
+    Confirm,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoldStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+// who reviewed is up to the note
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComplianceReview {
+    pub date: FechaHora,
+    pub note: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub id: u64, // position in the alerts, never reused
+    pub date: FechaHora,
+    pub user_id: u32,
+    pub kind: AlertKind,
+    pub status: AlertStatus,
+    pub review: Option<ComplianceReview>,
+}
+
+// HeldWithdrawal
+// a withdrawal, or a transfer to another user, waiting for an operator, exactly as it would have been posted.
+// while it's pending, what it debits is held from the user's balance
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HeldWithdrawal {
+    pub id: u64, // position in the holds, never reused
+    pub record: LedgerRecord,
+    pub lines: Vec<JournalLine>,
+    pub value: Dinero, // in pesos, what it counts against the limits
+    pub alerts: Vec<u64>, // why it's held: the user's open alerts when it was asked for
+    pub status: HoldStatus,
+    pub review: Option<ComplianceReview>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReviewError {
+    AlertNotFound { alert_id: u64 },
+    AlertAlreadyReviewed { alert_id: u64, status: AlertStatus },
+    HoldNotFound { hold_id: u64 },
+    HoldAlreadyReviewed { hold_id: u64, status: HoldStatus },
+}
+
+// Compliance
+// the alerts raised and the withdrawals held. Neither is ever removed: once reviewed they're kept, with the review.
+// a withdrawal is held when its user has open alerts, whatever raised them
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Compliance {
+    alerts: Vec<Alert>,
+    holds: Vec<HeldWithdrawal>,
+}
+
+impl Compliance {
+    pub fn alerts(&self) -> &[Alert] {
+        &self.alerts
+    }
+
+    pub fn holds(&self) -> &[HeldWithdrawal] {
+        &self.holds
+    }
+
+    pub fn open_alerts(&self, user_id: u32) -> impl Iterator<Item = &Alert> {
+        self.alerts.iter().filter(move |alert| alert.user_id == user_id && alert.status == AlertStatus::Open)
+    }
+
+    pub fn pending(&self, user_id: u32) -> impl Iterator<Item = &HeldWithdrawal> {
+        self.holds.iter().filter(move |hold| hold.record.data().user == user_id && hold.status == HoldStatus::Pending)
+    }
+
+    // what a user's pending withdrawals hold of a currency, fees included
+    pub fn held(&self, user_id: u32, currency: Moneda) -> Result<Dinero, ErrorDinero> {
+        let debits = self.pending(user_id)
+            .flat_map(|hold| &hold.lines)
+            .filter(|line| line.side == Side::Debit && line.account.is_user_account() && line.account.currency() == currency)
+            .map(|line| line.amount);
+        Dinero::sumar_todos(currency, debits)
+    }
+
+    // unless the user already has an open alert for the same rule
+    fn raise(&mut self, date: FechaHora, user_id: u32, kind: AlertKind) {
+        if self.open_alerts(user_id).any(|alert| discriminant(&alert.kind) == discriminant(&kind)) { return }
+        let id = self.alerts.len() as u64;
+        self.alerts.push(Alert { id, date, user_id, kind, status: AlertStatus::Open, review: None });
+    }
+
+    // holds the withdrawal if its user has open alerts. Returns the hold's id
+    fn hold(&mut self, record: LedgerRecord, lines: Vec<JournalLine>, value: Dinero) -> Option<u64> {
+        let alerts: Vec<u64> = self.open_alerts(record.data().user).map(|alert| alert.id).collect();
+        if alerts.is_empty() { return None }
+
+        let id = self.holds.len() as u64;
+        self.holds.push(HeldWithdrawal { id, record, lines, value, alerts, status: HoldStatus::Pending, review: None });
+        Some(id)
+    }
+
+    pub fn review_alert(&mut self, date: FechaHora, alert_id: u64, review: AlertReview, note: &str) -> Result<(), ReviewError> {
+        let Some(alert) = usize::try_from(alert_id).ok().and_then(|index| self.alerts.get_mut(index)) else {
+            return Err(ReviewError::AlertNotFound { alert_id })
+        };
+        if alert.status != AlertStatus::Open { return Err(ReviewError::AlertAlreadyReviewed { alert_id, status: alert.status }) }
+
+        alert.status = match review {
+            AlertReview::Dismiss => AlertStatus::Dismissed,
+            AlertReview::Confirm => AlertStatus::Confirmed,
+        };
+        alert.review = Some(ComplianceReview { date, note: note.to_string() });
+        Ok(())
+    }
+
+    pub fn pending_hold(&self, hold_id: u64) -> Result<&HeldWithdrawal, ReviewError> {
+        let Some(hold) = usize::try_from(hold_id).ok().and_then(|index| self.holds.get(index)) else {
+            return Err(ReviewError::HoldNotFound { hold_id })
+        };
+        if hold.status != HoldStatus::Pending { return Err(ReviewError::HoldAlreadyReviewed { hold_id, status: hold.status }) }
+        Ok(hold)
+    }
+
+    // releases what the hold held. Its alerts that are still open go with it:
+    // dismissed if the withdrawal was approved, confirmed if it was rejected
+    fn resolve(&mut self, date: FechaHora, hold_id: u64, approved: bool, note: &str) -> Result<(), ReviewError> {
+        let alerts = self.pending_hold(hold_id)?.alerts.clone();
+        let review = if approved { AlertReview::Dismiss } else { AlertReview::Confirm };
+        for alert_id in alerts {
+            match self.review_alert(date, alert_id, review, note) {
+                Ok(()) | Err(ReviewError::AlertAlreadyReviewed { .. }) => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        let Some(hold) = usize::try_from(hold_id).ok().and_then(|index| self.holds.get_mut(index)) else {
+            return Err(ReviewError::HoldNotFound { hold_id })
+        };
+        hold.status = if approved { HoldStatus::Approved } else { HoldStatus::Rejected };
+        hold.review = Some(ComplianceReview { date, note: note.to_string() });
+        Ok(())
+    }
+}
+
+//
+// chain.rs
+//
+
+// a block and the transactions mined into it, oldest sent first.
+// its hash is SHA-256 over its height, date, the previous block's hash and its transactions' hashes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Block {
+    pub height: u64, // the first block is 1
+    pub date: FechaHora,
+    pub previous: Option<String>, // None for the first block
+    pub hash: String,
+    pub transactions: Vec<BlockchainTransaction>,
+}
+
+// SimulatedChain
+// a blockchain that runs inside XYZ, so nothing needs a network. Sent transactions wait in the mempool
+// until the next block is mined, one block per tick. Nothing is random: the same transactions,
+// sent in the same order and mined at the same dates, always end up with the same hashes.
+// a transaction has one confirmation once it's in a block, and one more for every block after it
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SimulatedChain {
+    confirmations: u64, // what a deposit needs to be credited, see Blockchain::with_confirmations
+    nonce: u64, // transactions ever sent
+    mempool: Vec<BlockchainTransaction>,
+    blocks: Vec<Block>,
+    unconfirmed_deposits: Vec<BlockchainTransactionHash>, // sent to XYZ and not credited yet
+}
+
+impl SimulatedChain {
+    pub fn confirmations_required(&self) -> u64 {
+        self.confirmations
+    }
+
+    pub fn height(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn mempool(&self) -> &[BlockchainTransaction] {
+        &self.mempool
+    }
+
+    pub fn unconfirmed_deposits(&self) -> &[BlockchainTransactionHash] {
+        &self.unconfirmed_deposits
+    }
+
+    // in the mempool or in a block
+    pub fn transaction(&self, hash: &BlockchainTransactionHash) -> Option<&BlockchainTransaction> {
+        self.mempool.iter().chain(self.blocks.iter().flat_map(|block| &block.transactions))
+            .find(|transaction| &transaction.hash == hash)
+    }
+
+    // None if the chain never saw the transaction, 0 while it's in the mempool
+    pub fn confirmations(&self, hash: &BlockchainTransactionHash) -> Option<u64> {
+        if self.mempool.iter().any(|transaction| &transaction.hash == hash) { return Some(0) }
+        self.blocks.iter()
+            .find(|block| block.transactions.iter().any(|transaction| &transaction.hash == hash))
+            .map(|block| self.height() - block.height + 1)
+    }
+
+    // hashes the transaction with the next nonce and queues it. Deposits wait for their confirmations,
+    // unless the chain requires none. Returns the transaction as it was sent
+    fn send(&mut self, mut transaction: BlockchainTransaction) -> BlockchainTransaction {
+        self.nonce += 1;
+        transaction.hash = BlockchainTransactionHash::of(&transaction, self.nonce);
+        if transaction.data.transaction_type == TransactionType::BlockchainDeposit && self.confirmations > 0 {
+            self.unconfirmed_deposits.push(transaction.hash.clone());
+        }
+        self.mempool.push(transaction.clone());
+        transaction
+    }
+
+    // every transaction in the mempool goes into a new block, even if there's none
+    fn mine(&mut self, date: FechaHora) -> &Block {
+        let height = self.height() + 1;
+        let previous = self.blocks.last().map(|block| block.hash.clone());
+        let transactions = std::mem::take(&mut self.mempool);
+
+        let hashes: Vec<&str> = transactions.iter().map(|transaction| transaction.hash.as_str()).collect();
+        // these always serialize
+        let digest = Sha256::digest(serde_json::to_vec(&(height, date, &previous, hashes)).unwrap_or_default());
+
+        self.blocks.push(Block { height, date, previous, hash: format!("{digest:x}"), transactions });
+        &self.blocks[self.blocks.len() - 1]
+    }
+
+    // the deposits that have their confirmations now, in the order they were sent. They're no longer unconfirmed
+    fn confirmed_deposits(&mut self) -> Vec<BlockchainTransaction> {
+        let (confirmed, unconfirmed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.unconfirmed_deposits).into_iter()
+            .partition(|hash| self.confirmations(hash).is_some_and(|confirmations| confirmations >= self.confirmations));
+        self.unconfirmed_deposits = unconfirmed;
+
+        confirmed.iter().filter_map(|hash| self.transaction(hash)).cloned().collect()
+    }
+}
+
+//
+// portfolio.rs
+//
+
+// how what's sold is costed: the oldest acquisitions still held go first,
+// or everything held costs the same, its average
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostMethod {
+    Fifo,
+    WeightedAverage,
+}
+
+// what's left of an acquisition, and what that part cost in pesos
+#[derive(Debug, Clone, PartialEq)]
+struct Lot {
+    acquired: FechaHora, // averaged, when the first of it was
+    amount: Dinero,
+    cost: Dinero,
+}
+
+// the lots of a crypto a user still holds, oldest first.
+// averaged, every acquisition goes into one lot, so taking from it takes at the average cost
+#[derive(Debug, Clone, PartialEq)]
+struct CostBasis {
+    method: CostMethod,
+    lots: Vec<Lot>,
+}
+
+impl CostBasis {
+    fn new(method: CostMethod) -> Self {
+        CostBasis { method, lots: Vec::new() }
+    }
+
+    fn acquire(&mut self, date: FechaHora, amount: Dinero, cost: Dinero) -> Result<(), ErrorDinero> {
+        match (self.method, self.lots.first_mut()) {
+            (CostMethod::WeightedAverage, Some(lot)) => {
+                lot.amount = lot.amount.sumar(amount)?;
+                lot.cost = lot.cost.sumar(cost)?;
+            }
+            _ => self.lots.push(Lot { acquired: date, amount, cost }),
+        }
+        Ok(())
+    }
+
+    // takes `amount` out of the lots and returns what was taken from each. Whatever wasn't held isn't there.
+    // a lot taken in part keeps the rest of its cost, so nothing is lost to rounding
+    fn dispose(&mut self, mut amount: Dinero) -> Result<Vec<Lot>, ErrorDinero> {
+        let mut taken = Vec::new();
+        while amount.es_positivo() && let Some(lot) = self.lots.first_mut() {
+            if lot.amount <= amount {
+                amount = amount.restar(lot.amount)?;
+                taken.push(self.lots.remove(0));
+            } else {
+                let cost = lot.cost.fraccion(amount.unidades(), lot.amount.unidades(), Redondeo::MitadPar)?;
+                lot.amount = lot.amount.restar(amount)?;
+                lot.cost = lot.cost.restar(cost)?;
+                taken.push(Lot { acquired: lot.acquired, amount, cost });
+                amount = Dinero::cero(amount.moneda());
+            }
+        }
+        Ok(taken)
+    }
+
+    fn cost(&self) -> Result<Dinero, ErrorDinero> {
+        Dinero::sumar_todos(FIAT, self.lots.iter().map(|lot| lot.cost))
+    }
+}
+
+// what `part` is of `total`, in hundredths of a percent. None if there's no total
+fn allocation(part: Dinero, total: Dinero) -> Option<u32> {
+    if !total.es_positivo() { return None }
+    Redondeo::MitadPar.dividir(part.unidades().checked_mul(i128::from(FULL_RATE))?, total.unidades())
+        .and_then(|share| u32::try_from(share).ok())
+}
+
+// a crypto the user holds, or held. Prices, values and what follows from them
+// are None when there was no price for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub crypto: Moneda,
+    pub holding: Dinero,
+    pub price: Option<Dinero>, // pesos per unit
+    pub value: Option<Dinero>,
+    pub cost_basis: Dinero, // what the holding cost
+    pub realized: Dinero, // what sales and swaps made over what was sold had cost
+    pub unrealized: Option<Dinero>, // value over cost basis
+    pub allocation: Option<u32>, // share of the portfolio's value, in hundredths of a percent
+}
+
+// what a user had at a date, what it was worth and what it made. In pesos.
+// crypto that came in without pesos costs what it was quoted at when it arrived, or what was swapped for it,
+// except the opening balances and transfers from other users, which cost nothing: nobody knows what was paid for them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Portfolio {
+    pub user_id: u32,
+    pub date: FechaHora,
+    pub method: CostMethod,
+    pub fiat: Dinero,
+    pub fiat_allocation: Option<u32>,
+    pub positions: Vec<Position>, // by crypto
+    pub value: Dinero, // the pesos plus every position with a value
+    pub realized: Dinero,
+    pub unrealized: Dinero, // of the positions with a value
+}
+
+// what a crypto the user held is up to, replayed from the journal
+#[derive(Debug, Clone, PartialEq)]
+struct Held {
+    holding: Dinero,
+    basis: CostBasis,
+    realized: Dinero,
+}
+
+// a user's journal lines, replayed up to a date
+#[derive(Debug, Clone, PartialEq)]
+struct Replay {
+    fiat: Dinero,
+    held: BTreeMap<Moneda, Held>,
+    disposals: Vec<Disposal>, // oldest first
+}
+
+//
+// taxes.rs
+//
+
+// what the user got for crypto they no longer hold: pesos, the crypto's worth when it left XYZ
+// or was sent to another user, or what it was swapped for, at the swap's value
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisposalKind {
+    Sale,
+    Withdrawal,
+    Swap,
+    Transfer,
+}
+
+// the part of a disposal that came from one acquisition. A disposal that took from
+// several lots is several of these, each with its own cost and holding period
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Disposal {
+    pub date: FechaHora,
+    pub crypto: Moneda,
+    pub kind: DisposalKind,
+    pub amount: Dinero,
+    pub acquired: FechaHora,
+    pub holding_days: i64,
+    pub cost: Dinero,
+    pub proceeds: Dinero, // net of fees
+    pub gain: Dinero,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AssetTotal {
+    pub crypto: Moneda,
+    pub amount: Dinero,
+    pub cost: Dinero,
+    pub proceeds: Dinero,
+    pub gain: Dinero,
+}
+
+// something held at the end of the year. Pesos have no price; a crypto with no quote has no value
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct YearEndHolding {
+    pub currency: Moneda,
+    pub amount: Dinero,
+    pub price: Option<Dinero>,
+    pub value: Option<Dinero>,
+}
+
+// TaxReport
+// what a user has to declare for a year (ganancias), and what they held on Dec 31 (bienes personales).
+// lots are costed first in, first out, the way the tax is. Amounts are in pesos
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TaxReport {
+    pub user_id: u32,
+    pub year: i64,
+    pub disposals: Vec<Disposal>, // oldest first
+    pub totals: Vec<AssetTotal>, // by crypto
+    pub gain: Dinero,
+    pub holdings: Vec<YearEndHolding>, // the pesos first, then by crypto
+    pub holdings_value: Dinero,
+}
+
+impl TaxReport {
+    // the three tables one after the other, each under a # line saying what it is
+    pub fn to_csv(&self) -> String {
+        let date = |date: FechaHora| date.fecha().formatear(&FormatoIso);
+        let mut lines = vec![format!("# operaciones del {}", self.year), "fecha,cripto,tipo,cantidad,adquirida,dias,costo,ingreso,ganancia".to_string()];
+        for disposal in &self.disposals {
+            let kind = match disposal.kind { DisposalKind::Sale => "venta", DisposalKind::Withdrawal => "retiro", DisposalKind::Swap => "permuta", DisposalKind::Transfer => "transferencia" };
+            lines.push(format!("{},{},{kind},{},{},{},{},{},{}", date(disposal.date), disposal.crypto, csv_amount(disposal.amount),
+                               date(disposal.acquired), disposal.holding_days, csv_amount(disposal.cost), csv_amount(disposal.proceeds), csv_amount(disposal.gain)));
+        }
+
+        lines.extend([String::new(), "# totales".to_string(), "cripto,cantidad,costo,ingreso,ganancia".to_string()]);
+        for total in &self.totals {
+            lines.push(format!("{},{},{},{},{}", total.crypto, csv_amount(total.amount), csv_amount(total.cost), csv_amount(total.proceeds), csv_amount(total.gain)));
+        }
+        lines.push(format!("total,,,,{}", csv_amount(self.gain)));
+
+        lines.extend([String::new(), format!("# bienes personales al {}-12-31", self.year), "moneda,cantidad,cotizacion,valor".to_string()]);
+        for holding in &self.holdings {
+            lines.push(format!("{},{},{},{}", holding.currency, csv_amount(holding.amount),
+                               holding.price.map(csv_amount).unwrap_or_default(), holding.value.map(csv_amount).unwrap_or_default()));
+        }
+        lines.push(format!("total,,,{}", csv_amount(self.holdings_value)));
+        lines.join("\n") + "\n"
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+// just the number: the currency has its own column
+fn csv_amount(amount: Dinero) -> String {
+    let text = amount.to_string();
+    text.split(' ').next().unwrap_or_default().to_string()
+}
+
+//
+// analytics.rs
+//
+
+// what a market ranking puts first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketMetric {
+    Trades,
+    Volume,
+    Traders,
+}
+
+// what users bought and sold of a crypto, swaps included. Volumes are in pesos, so every crypto can be compared.
+// a sell from before v6 has no quote: it's counted, but adds no volume
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetStats {
+    pub crypto: Moneda,
+    pub buys: usize,
+    pub sells: usize,
+    pub bought: Dinero,
+    pub sold: Dinero,
+    pub volume: Dinero, // bought and sold
+    pub traders: usize, // different users that bought or sold it
+}
+
+impl AssetStats {
+    pub fn trades(&self) -> usize {
+        self.buys + self.sells
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketPeriod {
+    pub period: RangoFechas,
+    pub ranking: Vec<AssetStats>,
+}
+
+// a crypto bought or sold, as the market analytics see it
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MarketOperation {
+    date: FechaHora,
+    user: u32,
+    crypto: Moneda,
+    side: OrderSide,
+    value: Option<Dinero>, // in pesos
+}
+
+impl MarketOperation {
+    // a buy or a sell is one operation. A swap is a sell of what was given and a buy of what was got, each at
+    // the swap's value: the pesos side of a swap from or to pesos isn't a crypto, so it's left out
+    fn from_record(record: &LedgerRecord) -> Result<Vec<MarketOperation>, ErrorDinero> {
+        match record {
+            LedgerRecord::Crypto(transaction) => {
+                let side = match transaction.data.transaction_type {
+                    TransactionType::CryptoBuy => OrderSide::Buy,
+                    TransactionType::CryptoSell => OrderSide::Sell,
+                    _ => return Ok(Vec::new()),
+                };
+                let value = transaction.fiat_value()?;
+                Ok(vec![MarketOperation { date: transaction.data.date, user: transaction.data.user, crypto: transaction.currency, side, value }])
+            }
+            LedgerRecord::Swap(swap) => Ok([(swap.data.amount.moneda(), OrderSide::Sell), (swap.to, OrderSide::Buy)].into_iter()
+                .filter(|(crypto, _)| *crypto != FIAT)
+                .map(|(crypto, side)| MarketOperation { date: swap.data.date, user: swap.data.user, crypto, side, value: swap.value })
+                .collect()),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+// the stats of every crypto in `operations`, the highest `metric` first. Ties go by crypto
+fn market_ranking(operations: impl IntoIterator<Item = MarketOperation>, metric: MarketMetric) -> Result<Vec<AssetStats>, ErrorDinero> {
+    let mut stats: BTreeMap<Moneda, (AssetStats, BTreeSet<u32>)> = BTreeMap::new();
+    for operation in operations {
+        let zero = Dinero::cero(FIAT);
+        let (asset, traders) = stats.entry(operation.crypto).or_insert_with(|| {
+            (AssetStats { crypto: operation.crypto, buys: 0, sells: 0, bought: zero, sold: zero, volume: zero, traders: 0 }, BTreeSet::new())
+        });
+        let value = operation.value.unwrap_or(zero);
+        match operation.side {
+            OrderSide::Buy => { asset.buys += 1; asset.bought = asset.bought.sumar(value)? }
+            OrderSide::Sell => { asset.sells += 1; asset.sold = asset.sold.sumar(value)? }
+        }
+        asset.volume = asset.volume.sumar(value)?;
+        traders.insert(operation.user);
+    }
+
+    let mut ranking: Vec<AssetStats> = stats.into_values()
+        .map(|(asset, traders)| AssetStats { traders: traders.len(), ..asset })
+        .collect();
+    // stable: ties keep the crypto order
+    match metric {
+        MarketMetric::Trades => ranking.sort_by_key(|asset| std::cmp::Reverse(asset.trades())),
+        MarketMetric::Volume => ranking.sort_by_key(|asset| std::cmp::Reverse(asset.volume.unidades())),
+        MarketMetric::Traders => ranking.sort_by_key(|asset| std::cmp::Reverse(asset.traders)),
+    }
+    Ok(ranking)
+}
+
+//
+// swaps.rs
+//
+
+// cryptos quoted in other cryptos, by the crypto quoted: a Quote whose buy and sell are in another crypto.
+// pesos quotes are kept in XYZ.quotes
+pub type PairQuotes = BTreeMap<Moneda, Vec<Quote>>; // one quote per currency it's quoted in
+
+// every conversion the quotes allow from one currency straight to another: selling the crypto quoted
+// at its sell price, or buying it at its buy price. Quotes that aren't positive are left out
+fn swap_legs(quotes: &Quotes, pair_quotes: &PairQuotes, from: Moneda, to: Moneda) -> Vec<SwapLeg> {
+    let pairs = pair_quotes.iter().flat_map(|(crypto, quotes)| quotes.iter().map(|quote| (*crypto, quote)));
+    let mut legs = Vec::new();
+    for (crypto, quote) in quotes.iter().map(|(crypto, quote)| (*crypto, quote)).chain(pairs) {
+        let quoted_in = quote.sell.moneda();
+        if crypto == from && quoted_in == to && quote.sell.es_positivo() {
+            legs.push(SwapLeg { from, to, side: OrderSide::Sell, price: quote.sell });
+        }
+        if crypto == to && quoted_in == from && quote.buy.es_positivo() {
+            legs.push(SwapLeg { from, to, side: OrderSide::Buy, price: quote.buy });
+        }
+    }
+    legs
+}
+
+// the routes from one currency to another: straight first, then through each of `through`, in order
+fn swap_routes(quotes: &Quotes, pair_quotes: &PairQuotes, through: &[Moneda], from: Moneda, to: Moneda) -> Vec<Vec<SwapLeg>> {
+    let mut routes: Vec<Vec<SwapLeg>> = swap_legs(quotes, pair_quotes, from, to).into_iter().map(|leg| vec![leg]).collect();
+    for via in through.iter().copied().filter(|via| *via != from && *via != to) {
+        for first in swap_legs(quotes, pair_quotes, from, via) {
+            for second in swap_legs(quotes, pair_quotes, via, to) {
+                routes.push(vec![first, second]);
+            }
+        }
+    }
+    routes
+}
+
+// what a swap would give along a route, before it's made
+#[derive(Debug, Clone, PartialEq)]
+pub struct SwapPreview {
+    pub amount: Dinero, // what's given
+    pub route: Vec<SwapLeg>,
+    pub gross: Dinero, // what the route gives, before the fee
+    pub fee: Dinero,
+    pub received: Dinero,
+    pub rate: Dinero, // what's received per whole unit given, fee included
+    // in pesos: the pesos it went through, or the amount at its pesos sell price. None if it has neither
+    pub value: Option<Dinero>,
+}
+
+impl SwapPreview {
+    fn new(amount: Dinero, route: Vec<SwapLeg>, fee_rate: u32, quotes: &Quotes) -> Result<Self, ErrorDinero> {
+        let mut value = Some(amount).filter(|amount| amount.moneda() == FIAT);
+        let mut gross = amount;
+        for leg in &route {
+            gross = leg.convert(gross)?;
+            if value.is_none() && gross.moneda() == FIAT { value = Some(gross) }
+        }
+        if value.is_none() && let Some(quote) = quotes.get(&amount.moneda()) {
+            value = Some(amount.por(quote.sell, Redondeo::HaciaCero)?);
+        }
+
+        let fee = fee_at(gross, fee_rate)?;
+        let received = gross.restar(fee)?;
+        let rate = received.fraccion(amount.moneda().escala(), amount.unidades(), Redondeo::HaciaCero)?;
+        Ok(SwapPreview { amount, route, gross, fee, received, rate, value })
+    }
+}
+
+//
+// xyz.rs
+//
+
+type Users = BTreeMap<u32, User>;
+type Blockchains = BTreeMap<String, Blockchain>;
+type Quotes = HashMap<Moneda, Quote>;
+type CryptoTransactionHistory = BTreeMap<Moneda, Vec<CryptoTransaction>>;
+
+// v2: added the ledger
+// v3: added the journal
+// v4: amounts are Dinero instead of f64
+// v5: added the order books
+// v6: quotes are dated, kept in the quote history, and snapshotted on buys and sells
+// v7: added the fee schedule, and every transaction says what fee it paid
+// v8: users have a KYC state
+// v9: users have a DNI, ids are given by XYZ, and closed accounts are kept apart
+// v10: users have a tier, and withdrawals are checked against AML rules
+// v11: every blockchain runs a simulated chain
+// v12: cryptos can be quoted in other cryptos
+// v13: transfers keep the quote they were sent at
+// v14: swaps can go through other currencies than pesos
+// each step reads and writes the file as its versions saved it, never today's types: those may have changed since
+fn xyz_migrations() -> Migraciones {
+    Migraciones::default()
+        .con_migracion(1, ledger_from_crypto_transactions)
+        .con_migracion(2, journal_from_balances)
+        .con_migracion(3, amounts_to_money)
+        .con_migracion(4, empty_order_books)
+        .con_migracion(5, dated_quotes)
+        .con_migracion(6, free_of_fees)
+        .con_migracion(7, grandfathered_kyc)
+        .con_migracion(8, account_lifecycle)
+        .con_migracion(9, no_aml_rules)
+        .con_migracion(10, idle_chains)
+        .con_migracion(11, no_pair_quotes)
+        .con_migracion(12, unquoted_transfers)
+        .con_migracion(13, swaps_through_pesos)
+}
+
+// before v2 only buys and sells were kept, so that's all the ledger can start with.
+// they're sorted by date; same-date operations keep their per-crypto order
+fn ledger_from_crypto_transactions(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    let history: BTreeMap<String, Vec<serde_json::Value>> = serde_json::from_value(xyz["crypto_transactions"].clone())
+        .map_err(|error| format!("crypto_transactions: {error}"))?;
+
+    // sorted by the date's instant; the dates are still read as FechaHora to get it
+    let mut transactions = Vec::new();
+    for transaction in history.into_values().flatten() {
+        let date: FechaHora = serde_json::from_value(transaction["data"]["date"].clone())
+            .map_err(|error| format!("crypto_transactions: {error}"))?;
+        transactions.push((date, transaction));
+    }
+    transactions.sort_by_key(|(date, _)| *date);
+
+    let ledger: Vec<serde_json::Value> = transactions.into_iter().enumerate()
+        .map(|(id, (_, transaction))| serde_json::json!({ "id": id, "record": { "Crypto": transaction } }))
+        .collect();
+
+    xyz["ledger"] = serde_json::Value::from(ledger);
+    Ok(xyz)
+}
+
+// the history before v3 can't be trusted to add up to the balances (fiat wasn't even kept before v2),
+// so every balance is brought in as it is, as an opening entry dated at the epoch.
+// v3 lines were a signed amount: > 0 a debit, < 0 a credit
+fn journal_from_balances(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    let users: BTreeMap<String, serde_json::Value> = serde_json::from_value(xyz["users"].clone())
+        .map_err(|error| format!("users: {error}"))?;
+
+    let mut journal = Vec::new();
+    for user in users.values() {
+        let mut lines = Vec::new();
+        let mut open = |account: serde_json::Value, house: serde_json::Value, balance: &serde_json::Value| {
+            let Some(balance) = balance.as_f64() else { return Err(format!("users: {balance} is not a balance")) };
+            if balance != 0.0 {
+                lines.push(serde_json::json!({ "account": house, "amount": balance }));
+                lines.push(serde_json::json!({ "account": account, "amount": -balance }));
+            }
+            Ok(())
+        };
+
+        open(serde_json::json!({ "UserFiat": user["id"] }), serde_json::json!("FiatFloat"), &user["fiat_balance"])?;
+
+        let cryptos: BTreeMap<String, serde_json::Value> = serde_json::from_value(user["crypto_balance"].clone())
+            .map_err(|error| format!("users: {error}"))?; // same order every time
+        for (crypto, balance) in &cryptos {
+            open(serde_json::json!({ "UserCrypto": { "user": user["id"], "crypto": crypto } }), serde_json::json!({ "HotWallet": crypto }), balance)?;
+        }
+
+        if !lines.is_empty() {
+            journal.push(serde_json::json!({ "id": journal.len(), "date": FechaHora::default(), "ledger_id": null, "lines": lines }));
+        }
+    }
+
+    xyz["journal"] = serde_json::Value::from(journal);
+    Ok(xyz)
+}
+
+// every f64 becomes the nearest amount in its currency: pesos for balances, quotes,
+// fiat operations and buys; the crypto for everything else.
+// the history and the balances are rounded separately, so they may end up a few units apart:
+// XYZ absorbs the difference in its trading account, in one last entry
+fn amounts_to_money(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    // what each user account's balance says minus what its history adds up to
+    let mut drifts: BTreeMap<V4Account, Dinero> = BTreeMap::new();
+    for user in object_mut(&mut xyz["users"], "users")?.values_mut() {
+        let Some(id) = user["id"].as_u64().and_then(|id| u32::try_from(id).ok()) else { return Err(format!("users: {} is not an id", user["id"])) };
+        drifts.insert(V4Account::UserFiat(id), number_to_money(&mut user["fiat_balance"], FIAT)?);
+        for (crypto, balance) in object_mut(&mut user["crypto_balance"], "crypto_balance")? {
+            let crypto = currency(crypto)?;
+            drifts.insert(V4Account::UserCrypto { user: id, crypto }, number_to_money(balance, crypto)?);
+        }
+    }
+
+    for quote in object_mut(&mut xyz["quotes"], "quotes")?.values_mut() {
+        quote_to_money(quote)?;
+    }
+
+    for transactions in object_mut(&mut xyz["crypto_transactions"], "crypto_transactions")?.values_mut() {
+        for transaction in array_mut(transactions, "crypto_transactions")? {
+            crypto_transaction_to_money(transaction)?;
+        }
+    }
+
+    for entry in array_mut(&mut xyz["ledger"], "ledger")? {
+        let record = object_mut(&mut entry["record"], "ledger")?;
+        if let Some(fiat) = record.get_mut("Fiat") {
+            number_to_money(&mut fiat["data"]["amount"], FIAT)?;
+        } else if let Some(crypto) = record.get_mut("Crypto") {
+            crypto_transaction_to_money(crypto)?;
+        } else if let Some(blockchain) = record.get_mut("Blockchain") {
+            let crypto = currency(blockchain["crypto"].as_str().unwrap_or_default())?;
+            number_to_money(&mut blockchain["data"]["amount"], crypto)?;
+            quote_to_money(&mut blockchain["quote"])?;
+        }
+    }
+
+    for entry in array_mut(&mut xyz["journal"], "journal")? {
+        for line in array_mut(&mut entry["lines"], "journal")? {
+            let account: V4Account = serde_json::from_value(line["account"].clone()).map_err(|error| format!("journal: {error}"))?;
+            let Some(amount) = line["amount"].as_f64() else { return Err(format!("journal: {} is not an amount", line["amount"])) };
+
+            let credit = amount < 0.0;
+            let amount = Dinero::desde_f64(amount.abs(), account.currency(), Redondeo::MitadLejosDeCero).map_err(|error| error.to_string())?;
+            // users' accounts grow with credits
+            if account.is_user_account() {
+                let drift = drifts.entry(account.clone()).or_insert(Dinero::cero(account.currency()));
+                *drift = if credit { drift.restar(amount) } else { drift.sumar(amount) }.map_err(|error| error.to_string())?;
+            }
+            *line = v4_line(&account, credit, amount);
+        }
+    }
+
+    let mut adjustment = Vec::new();
+    for (account, drift) in drifts {
+        if drift.es_cero() { continue }
+        let house = V4Account::HouseTrading(account.currency());
+        let (debited, credited, amount) = if drift.es_negativo() {
+            (account, house, drift.negar().map_err(|error| error.to_string())?)
+        } else {
+            (house, account, drift)
+        };
+        adjustment.extend([v4_line(&debited, false, amount), v4_line(&credited, true, amount)]);
+    }
+
+    let journal = array_mut(&mut xyz["journal"], "journal")?;
+    if !adjustment.is_empty() {
+        journal.push(serde_json::json!({ "id": journal.len(), "date": FechaHora::default(), "ledger_id": null, "lines": adjustment }));
+    }
+    Ok(xyz)
+}
+
+// journal accounts as v3 and v4 saved them. Account may change after v4; what a v4 file has can't
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum V4Account {
+    UserFiat(u32),
+    UserCrypto { user: u32, crypto: Moneda },
+    FiatFloat,
+    HotWallet(Moneda),
+    HouseTrading(Moneda),
+}
+
+impl V4Account {
+    fn currency(&self) -> Moneda {
+        match self {
+            V4Account::UserFiat(_) | V4Account::FiatFloat => FIAT,
+            V4Account::UserCrypto { crypto, .. } | V4Account::HotWallet(crypto) | V4Account::HouseTrading(crypto) => *crypto,
+        }
+    }
+
+    fn is_user_account(&self) -> bool {
+        matches!(self, V4Account::UserFiat(_) | V4Account::UserCrypto { .. })
+    }
+}
+
+// a journal line as v4 saved it
+fn v4_line(account: &V4Account, credit: bool, amount: Dinero) -> serde_json::Value {
+    serde_json::json!({ "account": account, "side": if credit { "Credit" } else { "Debit" }, "amount": amount.to_string() })
+}
+
+// there were no orders before v5
+fn empty_order_books(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    xyz["orders"] = serde_json::json!({ "next_id": 0, "books": {} });
+    Ok(xyz)
+}
+
+// nobody knows when the current quotes were published, so they're dated at the epoch;
+// a blockchain operation's quote was the one in effect when it was made.
+// there's no telling what quote older buys and sells were made at, and no history to start from
+fn dated_quotes(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    let epoch = serde_json::to_value(FechaHora::default()).map_err(|error| error.to_string())?;
+    for quote in object_mut(&mut xyz["quotes"], "quotes")?.values_mut() {
+        object_mut(quote, "quotes")?.insert("date".to_string(), epoch.clone());
+    }
+
+    for transactions in object_mut(&mut xyz["crypto_transactions"], "crypto_transactions")?.values_mut() {
+        for transaction in array_mut(transactions, "crypto_transactions")? {
+            object_mut(transaction, "crypto_transactions")?.insert("quote".to_string(), serde_json::Value::Null);
+        }
+    }
+
+    for entry in array_mut(&mut xyz["ledger"], "ledger")? {
+        let record = object_mut(&mut entry["record"], "ledger")?;
+        if let Some(crypto) = record.get_mut("Crypto") {
+            object_mut(crypto, "ledger")?.insert("quote".to_string(), serde_json::Value::Null);
+        } else if let Some(blockchain) = record.get_mut("Blockchain") {
+            let date = blockchain["data"]["date"].clone();
+            object_mut(&mut blockchain["quote"], "ledger")?.insert("date".to_string(), date);
+        }
+    }
+
+    xyz["quote_history"] = serde_json::json!({ "candles": {} });
+    Ok(xyz)
+}
+
+// nothing was ever charged before v7
+fn free_of_fees(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    let no_fee = |transaction: &mut serde_json::Value, currency: Moneda| -> Result<(), String> {
+        object_mut(transaction, "transaction")?.insert("fee".to_string(), serde_json::Value::from(Dinero::cero(currency).to_string()));
+        Ok(())
+    };
+
+    for transactions in object_mut(&mut xyz["crypto_transactions"], "crypto_transactions")?.values_mut() {
+        for transaction in array_mut(transactions, "crypto_transactions")? {
+            no_fee(transaction, FIAT)?;
+        }
+    }
+
+    for entry in array_mut(&mut xyz["ledger"], "ledger")? {
+        let record = object_mut(&mut entry["record"], "ledger")?;
+        if let Some(fiat) = record.get_mut("Fiat") {
+            no_fee(fiat, FIAT)?;
+        } else if let Some(crypto) = record.get_mut("Crypto") {
+            no_fee(crypto, FIAT)?;
+        } else if let Some(blockchain) = record.get_mut("Blockchain") {
+            let crypto = currency(blockchain["crypto"].as_str().unwrap_or_default())?;
+            no_fee(blockchain, crypto)?;
+        }
+    }
+
+    xyz["fees"] = serde_json::json!({ "trading": {}, "blockchain_withdrawal": {}, "fiat_withdrawal": {} });
+    Ok(xyz)
+}
+
+// users from before v8 were already operating: they're taken as verified, at the epoch
+fn grandfathered_kyc(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    let kyc = serde_json::json!({
+        "state": "Verified",
+        "documents": [],
+        "history": [{ "date": FechaHora::default(), "from": "Unverified", "to": "Verified", "note": "operating before KYC was required" }],
+    });
+    for user in object_mut(&mut xyz["users"], "users")?.values_mut() {
+        object_mut(user, "users")?.insert("kyc".to_string(), kyc.clone());
+    }
+    Ok(xyz)
+}
+
+// nobody was asked for their DNI before v9, and no account was ever closed
+fn account_lifecycle(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    let mut next_user_id = 0;
+    for user in object_mut(&mut xyz["users"], "users")?.values_mut() {
+        let Some(id) = user["id"].as_u64() else { return Err(format!("users: {} is not an id", user["id"])) };
+        next_user_id = next_user_id.max(id + 1);
+        object_mut(user, "users")?.insert("dni".to_string(), serde_json::Value::Null);
+    }
+
+    xyz["next_user_id"] = serde_json::Value::from(next_user_id);
+    xyz["closed_accounts"] = serde_json::json!({});
+    Ok(xyz)
+}
+
+// every user starts as Standard, with no rules to check them against
+fn no_aml_rules(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    for user in object_mut(&mut xyz["users"], "users")?.values_mut() {
+        object_mut(user, "users")?.insert("tier".to_string(), serde_json::json!("Standard"));
+    }
+
+    xyz["aml"] = serde_json::json!({ "limits": {}, "velocity": null, "structuring": null, "turnaround": null });
+    xyz["compliance"] = serde_json::json!({ "alerts": [], "holds": [] });
+    Ok(xyz)
+}
+
+// every chain starts empty: what was sent before v11 is only in the ledger, and was credited right away
+fn idle_chains(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    let chain = serde_json::json!({ "confirmations": 0, "nonce": 0, "mempool": [], "blocks": [], "unconfirmed_deposits": [] });
+    for blockchain in object_mut(&mut xyz["blockchains"], "blockchains")?.values_mut() {
+        object_mut(blockchain, "blockchains")?.insert("chain".to_string(), chain.clone());
+    }
+    Ok(xyz)
+}
+
+// before v12 every crypto was quoted in pesos only
+fn no_pair_quotes(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    xyz["pair_quotes"] = serde_json::json!({});
+    Ok(xyz)
+}
+
+// transfers kept no quote before v13
+fn unquoted_transfers(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    for entry in array_mut(&mut xyz["ledger"], "ledger")? {
+        if let Some(transfer) = object_mut(&mut entry["record"], "ledger")?.get_mut("Transfer") {
+            object_mut(transfer, "ledger")?.insert("quote".to_string(), serde_json::Value::Null);
+        }
+    }
+    Ok(xyz)
+}
+
+// before v14 swaps could only go through pesos
+fn swaps_through_pesos(mut xyz: serde_json::Value) -> Result<serde_json::Value, String> {
+    if !xyz.is_object() { return Err("XYZ is not an object".to_string()) }
+
+    xyz["swap_via"] = serde_json::json!(["ARS"]);
+    Ok(xyz)
+}
+
+fn object_mut<'a>(value: &'a mut serde_json::Value, name: &str) -> Result<&'a mut serde_json::Map<String, serde_json::Value>, String> {
+    value.as_object_mut().ok_or_else(|| format!("{name} is not an object"))
+}
+
+fn array_mut<'a>(value: &'a mut serde_json::Value, name: &str) -> Result<&'a mut Vec<serde_json::Value>, String> {
+    value.as_array_mut().ok_or_else(|| format!("{name} is not a list"))
+}
+
+fn currency(code: &str) -> Result<Moneda, String> {
+    code.parse().map_err(|error: ErrorDinero| error.to_string())
+}
+
+fn number_to_money(value: &mut serde_json::Value, currency: Moneda) -> Result<Dinero, String> {
+    let Some(number) = value.as_f64() else { return Err(format!("{value} is not an amount")) };
+    let money = Dinero::desde_f64(number, currency, Redondeo::MitadLejosDeCero).map_err(|error| error.to_string())?;
+    *value = serde_json::Value::from(money.to_string());
+    Ok(money)
+}
+
+fn quote_to_money(quote: &mut serde_json::Value) -> Result<(), String> {
+    number_to_money(&mut quote["buy"], FIAT)?;
+    number_to_money(&mut quote["sell"], FIAT)?;
+    Ok(())
+}
+
+fn crypto_transaction_to_money(transaction: &mut serde_json::Value) -> Result<(), String> {
+    let amount_currency = match transaction["data"]["transaction_type"].as_str() {
+        Some("CryptoBuy") => FIAT,
+        _ => currency(transaction["currency"].as_str().unwrap_or_default())?,
+    };
+    number_to_money(&mut transaction["data"]["amount"], amount_currency)?;
+    Ok(())
+}
+
+// the house holds whatever the user brings: fiat in the float, crypto in the hot wallets
+fn opening_lines(user: &User) -> Result<Vec<JournalLine>, ErrorDinero> {
+    let mut lines = Vec::new();
+
+    if !user.fiat_balance.es_cero() {
+        lines.extend(transfer(Account::FiatFloat, Account::UserFiat(user.id), user.fiat_balance)?);
+    }
+
+    let cryptos: BTreeMap<&Moneda, &Dinero> = user.crypto_balance.iter().collect(); // same order every time
+    for (crypto, balance) in cryptos {
+        if balance.es_cero() { continue }
+        lines.extend(transfer(Account::HotWallet(*crypto), Account::UserCrypto { user: user.id, crypto: *crypto }, *balance)?);
+    }
+
+    Ok(lines)
+}
+
+// amount goes to `credited` from `debited`. A negative amount goes the other way
+fn transfer(debited: Account, credited: Account, amount: Dinero) -> Result<[JournalLine; 2], ErrorDinero> {
+    if amount.es_negativo() {
+        let amount = amount.negar()?;
+        Ok([JournalLine::debit(credited, amount), JournalLine::credit(debited, amount)])
+    } else {
+        Ok([JournalLine::debit(debited, amount), JournalLine::credit(credited, amount)])
+    }
+}
+
+// an entry's lines, plus the fee XYZ keeps if there's any
+fn with_fee(mut lines: Vec<JournalLine>, fee: Dinero) -> Vec<JournalLine> {
+    if fee.es_positivo() { lines.push(JournalLine::credit(Account::FeeRevenue(fee.moneda()), fee)) }
+    lines
+}
+
+// where a user's pesos or crypto are
+fn user_account(user: u32, currency: Moneda) -> Account {
+    if currency == FIAT { Account::UserFiat(user) } else { Account::UserCrypto { user, crypto: currency } }
+}
+
+// a blockchain deposit: the crypto arrives at XYZ's hot wallet
+fn deposit_lines(transaction: &BlockchainTransaction) -> Vec<JournalLine> {
+    vec![
+        JournalLine::debit(Account::HotWallet(transaction.crypto), transaction.data.amount),
+        JournalLine::credit(Account::UserCrypto { user: transaction.data.user, crypto: transaction.crypto }, transaction.data.amount),
+    ]
+}
+
+// users' accounts whose balance isn't what the journal says: (account, balance, journal's balance).
+// an account only the journal knows about has a balance of zero
+fn balance_drifts(users: &Users, journal: &Journal) -> Result<Vec<(Account, Dinero, Dinero)>, ErrorDinero> {
+    let mut cached = BTreeMap::new();
+    for (user_id, user) in users {
+        cached.insert(Account::UserFiat(*user_id), user.fiat_balance);
+        for (crypto, balance) in &user.crypto_balance {
+            cached.insert(Account::UserCrypto { user: *user_id, crypto: *crypto }, *balance);
+        }
+    }
+
+    let posted = journal.balances()?;
+    for account in posted.keys() {
+        if account.is_user_account() && !cached.contains_key(*account) {
+            cached.insert((*account).clone(), Dinero::cero(account.currency()));
+        }
+    }
+
+    Ok(cached.into_iter()
+        .map(|(account, cached)| {
+            let posted = posted.get(&account).copied().unwrap_or(Dinero::cero(account.currency()));
+            (account, cached, posted)
+        })
+        .filter(|(_, cached, posted)| cached != posted)
+        .collect())
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct XYZ {
+    file_name: String,
+    users: Users, // open accounts. See registrar_usuario and cerrar_cuenta
+    closed_accounts: BTreeMap<u32, ClosedAccount>,
+    next_user_id: u32, // ids are never reused, not even a closed account's
+    pub blockchains: Blockchains,
+    pub quotes: Quotes, // the latest (buy, sell) prices
+    pub pair_quotes: PairQuotes, // the latest prices between cryptos, for swaps. see swaps.rs
+    pub swap_via: Vec<Moneda>, // what a swap can go through when no pair is quoted directly: pesos, stablecoins...
+    pub crypto_transactions: CryptoTransactionHistory, // <Prefix, Transactions>
+    pub ledger: Ledger, // every operation, see ledger.rs
+    pub journal: Journal, // what users' balances are made of, see journal.rs
+    pub orders: OrderBooks, // open orders hold part of their users' balances, see orderbook.rs
+    pub quote_history: QuoteHistory, // every quote ever in effect, as daily candles. see quotes.rs
+    pub fees: FeeSchedule, // what XYZ charges, see fees.rs
+    pub aml: AmlRules, // what XYZ checks on deposits and withdrawals, see aml.rs
+    pub compliance: Compliance, // the alerts raised and the withdrawals held
+    #[serde(skip)]
+    clock: RelojCompartido, // dates every *_now operation
+    #[serde(skip)]
+    store: AlmacenCompartido, // where file_name is saved
+}
+
+//
+// errors
+//
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddUserError {
+    UserAlreadyExists { user_id: u32 },
+    Money(ErrorDinero),
+    File(ErrorPersistencia)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FiatDepositError {
+    FiatTransactionError(ErrorNewTransaction),
+    UserNotFound{ user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    Money(ErrorDinero),
+    File(ErrorPersistencia)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FiatWithdrawalError {
+    FiatTransactionError(ErrorNewTransaction),
+    UserNotFound{ user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    NotEnoughBalance{ balance: Dinero, balance_needed: Dinero },
+    LimitExceeded(LimitExceeded),
+    Held{ hold_id: u64 }, // not made yet: it waits for an operator, see approve_withdrawal
+    Money(ErrorDinero),
+    File(ErrorPersistencia)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockchainDepositError {
+    BlockchainTransactionError(ErrorNewTransaction),
+    BlockchainNotFound{ blockchain: String },
+    CryptoNotQuoted{ crypto: Moneda },
+    UserNotFound{ user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    Money(ErrorDinero),
+    File(ErrorPersistencia)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockchainWithdrawalError {
+    BlockchainTransactionError(ErrorNewTransaction),
+    BlockchainNotFound{ blockchain: String },
+    CryptoNotQuoted{ crypto: Moneda },
+    UserNotFound{ user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    NotEnoughBalance{ balance: Dinero, balance_needed: Dinero },
+    LimitExceeded(LimitExceeded),
+    Held{ hold_id: u64 }, // not made yet: it waits for an operator, see approve_withdrawal
+    Money(ErrorDinero),
+    File(ErrorPersistencia)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuySellError {
+    TransactionError(ErrorNewTransaction),
+    CryptocurrencyNotQuoted { crypto: Moneda },
+    UserNotFound { user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    NotEnoughBalance { balance: Dinero, balance_needed: Dinero },
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferError {
+    TransactionError(ErrorNewTransaction),
+    UserNotFound { user_id: u32 },
+    RecipientNotFound { recipient: Recipient },
+    SameUser { user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    RecipientNotValidated(UsuarioNoValidado),
+    NotEnoughBalance { balance: Dinero, balance_needed: Dinero },
+    LimitExceeded(LimitExceeded),
+    Held { hold_id: u64 }, // nothing moves until an operator reviews it
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SwapError {
+    TransactionError(ErrorNewTransaction),
+    NoRoute { from: Moneda, to: Moneda }, // nothing quoted connects them
+    NothingReceived { amount: Dinero }, // too little to give anything back
+    UserNotFound { user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    NotEnoughBalance { balance: Dinero, balance_needed: Dinero },
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaceOrderError {
+    TransactionError(ErrorNewTransaction),
+    InvalidAmount { amount: Dinero }, // must be a positive amount of a crypto
+    InvalidPrice { price: Dinero }, // must be a positive amount of pesos
+    UserNotFound { user_id: u32 },
+    UserNotValidated(UsuarioNoValidado),
+    NotEnoughBalance { balance: Dinero, balance_needed: Dinero },
+    SelfTrade { order_id: u64 }, // the user's own resting order it would trade with
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CancelOrderError {
+    OrderNotFound { order_id: u64 }, // or it's someone else's
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CloseAccountError {
+    UserNotFound { user_id: u32 },
+    BalanceNotZero { balances: Vec<Dinero> }, // and there's no payout for them
+    UserNotValidated(UsuarioNoValidado), // to be paid out
+    OpenAlerts { alerts: Vec<u64> }, // to be reviewed before it's paid out
+    DepositsUnconfirmed { deposits: Vec<BlockchainTransactionHash> }, // to be credited before it's closed
+    TransactionError(ErrorNewTransaction),
+    BlockchainNotFound { blockchain: String },
+    NoPayoutBlockchain { crypto: Moneda }, // the payout doesn't say where it goes
+    CryptoNotQuoted { crypto: Moneda },
+    LimitExceeded(LimitExceeded), // the payout, all of it, would go over the user's tier limits
+    Held { holds: Vec<u64> }, // the payout waits for an operator: once it's approved, the empty account can be closed
+    Review(ReviewError),
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KycUpdateError {
+    UserNotFound { user_id: u32 },
+    Kyc(KycError),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComplianceUpdateError {
+    UserNotFound { user_id: u32 },
+    UserNotValidated(UsuarioNoValidado), // to be paid the withdrawal
+    Review(ReviewError),
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TickError {
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateQuotesError {
+    Money(ErrorDinero),
+    File(ErrorPersistencia),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PortfolioError {
+    UserNotFound{ user_id: u32 },
+    Money(ErrorDinero),
+}
+
+impl XYZ {
+    // starts empty if there is no file yet. An unreadable file (broken, or a version
+    // with no migration path) is an error instead of silently starting over
+    fn new(file_name: Option<&str>) -> Result<Self, ErrorPersistencia> {
+        Self::new_with_store(file_name, AlmacenCompartido::default())
+    }
+
+    // same as new, but data is loaded from and saved to `store`
+    fn new_with_store(file_name: Option<&str>, store: impl Into<AlmacenCompartido>) -> Result<Self, ErrorPersistencia> {
+        let mut xyz = Self {
+            file_name: file_name.unwrap_or("xyz").to_string(),
+            users: Users::default(),
+            closed_accounts: BTreeMap::default(),
+            next_user_id: 0,
+            blockchains: Blockchains::default(),
+            quotes: Quotes::default(),
+            pair_quotes: PairQuotes::default(),
+            swap_via: vec![FIAT],
+            crypto_transactions: CryptoTransactionHistory::default(),
+            ledger: Ledger::default(),
+            journal: Journal::default(),
+            orders: OrderBooks::default(),
+            quote_history: QuoteHistory::default(),
+            fees: FeeSchedule::default(),
+            aml: AmlRules::default(),
+            compliance: Compliance::default(),
+            clock: RelojCompartido::default(),
+            store: store.into()
+        };
+        match xyz.actualizar_datos_xyz() {
+            Err(ErrorPersistencia::NoExiste { .. }) | Ok(()) => Ok(xyz),
+            Err(error) => Err(error)
+        }
+    }
+
+    // replaces the system clock, mostly for tests
+    fn with_clock(mut self, clock: impl Into<RelojCompartido>) -> Self {
+        self.clock = clock.into();
+        self
+    }
+
+    // the store adds the .json to file_name
+    fn archivo_xyz(&self) -> Documento<XYZ> {
+        Documento::new(self.store.clone(), self.file_name.as_str()).con_migraciones(xyz_migrations())
+    }
+
+    fn sobreescribir_archivo_xyz(&self) -> Result<(), ErrorPersistencia> {
+        self.archivo_xyz().guardar(self)
+    }
+
+    fn leer_archivo_xyz(&self) -> Result<XYZ, ErrorPersistencia> {
+        self.archivo_xyz().cargar()
+    }
+
+    fn actualizar_datos_xyz(&mut self) -> Result<(), ErrorPersistencia> {
+        let data = self.leer_archivo_xyz()?;
+
+        self.users = data.users;
+        self.closed_accounts = data.closed_accounts;
+        self.next_user_id = data.next_user_id;
+        self.blockchains = data.blockchains;
+        self.quotes = data.quotes;
+        self.pair_quotes = data.pair_quotes;
+        self.swap_via = data.swap_via;
+        self.crypto_transactions = data.crypto_transactions;
+        self.ledger = data.ledger;
+        self.journal = data.journal;
+        self.orders = data.orders;
+        self.quote_history = data.quote_history;
+        self.fees = data.fees;
+        self.aml = data.aml;
+        self.compliance = data.compliance;
+
+        Ok(())
+    }
+
+    // a user's balance in one of its accounts. House accounts only have the journal's balance
+    fn cached_balance(&self, account: &Account) -> Option<Dinero> {
+        match account {
+            Account::UserFiat(user_id) => self.users.get(user_id).map(|user| user.fiat_balance),
+            Account::UserCrypto { user, crypto } => self.users.get(user)
+                .map(|user| user.crypto_balance.get(crypto).copied().unwrap_or(Dinero::cero(*crypto))),
+            _ => None,
+        }
+    }
+
+    // what the user can spend of a currency: the balance minus what open orders and held withdrawals hold
+    fn available_balance(&self, user: &User, currency: Moneda) -> Result<Dinero, ErrorDinero> {
+        let balance = if currency == FIAT {
+            user.fiat_balance
+        } else {
+            user.crypto_balance.get(&currency).copied().unwrap_or(Dinero::cero(currency))
+        };
+        balance.restar(self.orders.held(user.id, currency)?)?.restar(self.compliance.held(user.id, currency)?)
+    }
+
+    // what the user bought, sold and swapped, in pesos, during the TRAILING_VOLUME_DAYS days before `date`
+    fn traded_volume(&self, user_id: u32, date: FechaHora) -> Result<Dinero, ErrorDinero> {
+        let since = date - Duration::from_hours(24 * TRAILING_VOLUME_DAYS);
+        let mut volume = Dinero::cero(FIAT);
+        for entry in self.ledger.query(&LedgerQuery::default().for_user(user_id).between(since, date)) {
+            let value = match &entry.record {
+                LedgerRecord::Crypto(transaction) => transaction.fiat_value()?,
+                LedgerRecord::Swap(swap) => swap.value,
+                _ => None,
+            };
+            if let Some(value) = value { volume = volume.sumar(value)? }
+        }
+        Ok(volume)
+    }
+
+    // the user's rate for trading a crypto, by the tier their volume reaches
+    fn trading_rate(&self, user_id: u32, crypto: Moneda, date: FechaHora, liquidity: Liquidity) -> Result<u32, ErrorDinero> {
+        Ok(self.fees.trading_rate(crypto, self.traded_volume(user_id, date)?, liquidity))
+    }
+
+    // the user's operations during the `hours` up to `date`, both included
+    fn recent_records(&self, user_id: u32, date: FechaHora, hours: u64) -> impl Iterator<Item = &LedgerRecord> {
+        let since = date - Duration::from_hours(hours);
+        self.ledger.query(&LedgerQuery::default().for_user(user_id)).into_iter()
+            .map(|entry| &entry.record)
+            .filter(move |record| record.data().date >= since && record.data().date <= date)
+    }
+
+    // the user's deposits during the `hours` up to `date`: how many, and what they were worth in pesos
+    fn recent_deposits(&self, user_id: u32, date: FechaHora, hours: u64) -> Result<(usize, Dinero), ErrorDinero> {
+        let values = self.recent_records(user_id, date, hours)
+            .filter(|record| matches!(record.data().transaction_type, TransactionType::FiatDeposit | TransactionType::BlockchainDeposit))
+            .map(LedgerRecord::fiat_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((values.len(), Dinero::sumar_todos(FIAT, values.into_iter().flatten())?))
+    }
+
+    // the same for withdrawals, both made and held. Transfers the user sent count too:
+    // the other account could withdraw them
+    fn recent_withdrawals(&self, user_id: u32, date: FechaHora, hours: u64) -> Result<(usize, Dinero), ErrorDinero> {
+        let since = date - Duration::from_hours(hours);
+        let mut values = self.recent_records(user_id, date, hours)
+            .filter(|record| match record.data().transaction_type {
+                TransactionType::FiatWithdrawal { .. } | TransactionType::BlockchainWithdrawal => true,
+                TransactionType::InternalTransfer => record.data().user == user_id,
+                _ => false,
+            })
+            .map(LedgerRecord::fiat_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        values.extend(self.compliance.pending(user_id)
+            .filter(|hold| hold.record.data().date >= since && hold.record.data().date <= date)
+            .map(|hold| Some(hold.value)));
+        Ok((values.len(), Dinero::sumar_todos(FIAT, values.into_iter().flatten())?))
+    }
+
+    // the first of the user's tier limits a withdrawal worth `value` pesos would go over
+    fn exceeded_limit(&self, user: &User, date: FechaHora, value: Dinero) -> Result<Option<LimitExceeded>, ErrorDinero> {
+        let limits = self.aml.limits(user.tier);
+        for (period, limit) in [(LimitPeriod::Daily, limits.daily), (LimitPeriod::Monthly, limits.monthly)] {
+            let Some(limit) = limit else { continue };
+            let withdrawn = self.recent_withdrawals(user.id, date, period.hours())?.1.sumar(value)?;
+            if withdrawn > limit { return Ok(Some(LimitExceeded { period, limit, withdrawn })) }
+        }
+        Ok(None)
+    }
+
+    // raises the alerts a withdrawal worth `value` pesos calls for
+    fn flag_withdrawal(&mut self, user_id: u32, date: FechaHora, value: Dinero) -> Result<(), ErrorDinero> {
+        let mut alerts = Vec::new();
+
+        if let Some(rule) = self.aml.velocity() {
+            let withdrawals = self.recent_withdrawals(user_id, date, rule.hours)?.0 + 1;
+            if withdrawals > rule.withdrawals { alerts.push(AlertKind::Velocity { withdrawals, hours: rule.hours }) }
+        }
+
+        if let Some(rule) = self.aml.turnaround() {
+            let (_, deposited) = self.recent_deposits(user_id, date, rule.hours)?;
+            let withdrawn = self.recent_withdrawals(user_id, date, rule.hours)?.1.sumar(value)?;
+            let threshold = deposited.fraccion(i128::from(rule.rate), i128::from(FULL_RATE), Redondeo::HaciaArriba)?;
+            if deposited.es_positivo() && withdrawn >= threshold {
+                alerts.push(AlertKind::RapidTurnaround { deposited, withdrawn, hours: rule.hours });
+            }
+        }
+
+        for alert in alerts { self.compliance.raise(date, user_id, alert) }
+        Ok(())
+    }
+
+    // raises an alert if the user's fiat deposits, the one just made included, look structured
+    fn flag_deposit(&mut self, user_id: u32, date: FechaHora) -> Result<(), ErrorDinero> {
+        let Some(rule) = self.aml.structuring() else { return Ok(()) };
+        let floor = rule.threshold.restar(rule.margin)?;
+
+        let deposits = self.recent_records(user_id, date, rule.hours)
+            .filter(|record| record.data().transaction_type == TransactionType::FiatDeposit)
+            .filter(|record| (floor..rule.threshold).contains(&record.data().amount))
+            .count();
+        if deposits >= rule.deposits { self.compliance.raise(date, user_id, AlertKind::Structuring { deposits, hours: rule.hours }) }
+        Ok(())
+    }
+
+    // the only way balances change: the operation goes to the ledger, its entry to the journal,
+    // and users' balances are updated from the entry's lines.
+    // every new balance is worked out first, so an overflow leaves XYZ untouched.
+    // users must exist: every operation checks it before posting
+    fn post(&mut self, date: FechaHora, record: Option<LedgerRecord>, lines: Vec<JournalLine>) -> Result<(), ErrorDinero> {
+        let mut balances: BTreeMap<Account, Dinero> = BTreeMap::new();
+        for line in &lines {
+            let Some(balance) = balances.get(&line.account).copied().or_else(|| self.cached_balance(&line.account)) else { continue };
+            balances.insert(line.account.clone(), line.apply_to(balance)?);
+        }
+
+        for (account, balance) in balances {
+            match account {
+                Account::UserFiat(user_id) => if let Some(user) = self.users.get_mut(&user_id) {
+                    user.fiat_balance = balance;
+                },
+                Account::UserCrypto { user, crypto } => if let Some(user) = self.users.get_mut(&user) {
+                    user.crypto_balance.insert(crypto, balance);
+                },
+                _ => {}
+            }
+        }
+
+        let ledger_id = record.map(|record| self.ledger.record(record).id);
+        self.journal.post(date, ledger_id, lines);
+        Ok(())
+    }
+
+    // hands the transaction to its blockchain, which gives it its hash. Returns it as it was sent
+    fn send(&mut self, transaction: BlockchainTransaction) -> BlockchainTransaction {
+        match self.blockchains.get_mut(&transaction.blockchain) {
+            Some(blockchain) => blockchain.chain.send(transaction),
+            None => transaction,
+        }
+    }
+
+    // the user's deposits that were sent and aren't credited yet, in every blockchain
+    fn unconfirmed_deposits(&self, user_id: u32) -> Vec<BlockchainTransactionHash> {
+        self.blockchains.values()
+            .flat_map(|blockchain| blockchain.chain.unconfirmed_deposits().iter()
+                .filter(|hash| blockchain.chain.transaction(hash).is_some_and(|transaction| transaction.data.user == user_id)))
+            .cloned()
+            .collect()
+    }
+
+    // whatever balance the user comes with is brought into the journal as an opening entry
+    // it's meant for bringing in users from elsewhere, as they are: registrar_usuario is for new ones
+    fn add_user(&mut self, date: FechaHora, mut user: User) -> Result<(), AddUserError> {
+        if self.users.contains_key(&user.id) || self.closed_accounts.contains_key(&user.id) {
+            return Err(AddUserError::UserAlreadyExists { user_id: user.id })
+        }
+
+        let lines = opening_lines(&user).map_err(AddUserError::Money)?;
+        user.fiat_balance = Dinero::cero(FIAT);
+        user.crypto_balance.clear();
+        self.next_user_id = self.next_user_id.max(user.id.saturating_add(1));
+        self.users.insert(user.id, user);
+        if !lines.is_empty() { self.post(date, None, lines).map_err(AddUserError::Money)?; }
+
+        self.sobreescribir_archivo_xyz().map_err(AddUserError::File)
     }
-}
 
-//
-// xyz.rs
-//
+    pub fn user(&self, user_id: u32) -> Option<&User> {
+        self.users.get(&user_id)
+    }
 
-type Users = BTreeMap<u32, User>;
-type Blockchains = BTreeMap<String, Blockchain>;
-type Quotes = HashMap<String, Quote>;
-type CryptoTransactionHistory = BTreeMap<String, Vec<CryptoTransaction>>;
+    // an email or DNI can only belong to one open account
+    fn check_unique(&self, email: Option<&str>, dni: Option<&str>, except: Option<u32>) -> Result<(), UserDataError> {
+        for user in self.users.values().filter(|user| Some(user.id) != except) {
+            if let Some(email) = email && user.email.to_lowercase() == email {
+                return Err(UserDataError::EmailInUse { email: email.to_string(), user_id: user.id })
+            }
+            if let Some(dni) = dni && user.dni.as_deref() == Some(dni) {
+                return Err(UserDataError::DniInUse { dni: dni.to_string(), user_id: user.id })
+            }
+        }
+        Ok(())
+    }
 
-const BASE_FOLDER: &str = "";
+    // new users start unverified and with nothing. Returns the id they were given
+    fn registrar_usuario(&mut self, new_user: &NewUser) -> Result<u32, UserDataError> {
+        let first_name = valid_name(&new_user.first_name)?;
+        let last_name = valid_name(&new_user.last_name)?;
+        let email = valid_email(&new_user.email)?;
+        let dni = valid_dni(&new_user.dni)?;
+        self.check_unique(Some(&email), Some(&dni), None)?;
+
+        let id = self.next_user_id;
+        let Some(next_user_id) = id.checked_add(1) else { return Err(UserDataError::NoIdsLeft) };
+        self.next_user_id = next_user_id;
+        self.users.insert(id, User {
+            first_name, last_name, email,
+            dni: Some(dni),
+            id,
+            fiat_balance: Dinero::cero(FIAT),
+            crypto_balance: HashMap::new(),
+            kyc: Kyc::default(),
+            tier: UserTier::default(),
+        });
+
+        self.sobreescribir_archivo_xyz().map_err(UserDataError::File)?;
+        Ok(id)
+    }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-pub struct XYZ {
-    file_name: String,
-    pub users: Users,
-    pub blockchains: Blockchains,
-    pub quotes: Quotes, // (buy, sell) prices
-    pub crypto_transactions: CryptoTransactionHistory, // <Prefix, Transactions>
-}
+    fn actualizar_datos(&mut self, user_id: u32, update: &UserDataUpdate) -> Result<(), UserDataError> {
+        if !self.users.contains_key(&user_id) { return Err(UserDataError::UserNotFound { user_id }) }
 
-//
-// errors
-//
+        let first_name = update.first_name.as_deref().map(valid_name).transpose()?;
+        let last_name = update.last_name.as_deref().map(valid_name).transpose()?;
+        let email = update.email.as_deref().map(valid_email).transpose()?;
+        self.check_unique(email.as_deref(), None, Some(user_id))?;
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum FiatDepositError {
-    FiatTransactionError(ErrorNewTransaction),
-    UserNotFound{ user_id: u32 },
-    File(FileError)
-}
+        let Some(user) = self.users.get_mut(&user_id) else { return Err(UserDataError::UserNotFound { user_id }) };
+        if let Some(first_name) = first_name { user.first_name = first_name }
+        if let Some(last_name) = last_name { user.last_name = last_name }
+        if let Some(email) = email { user.email = email }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum FiatWithdrawalError {
-    FiatTransactionError(ErrorNewTransaction),
-    UserNotFound{ user_id: u32 },
-    NotEnoughBalance{ balance: f64, balance_needed: f64 },
-    File(FileError)
-}
+        self.sobreescribir_archivo_xyz().map_err(UserDataError::File)
+    }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum BlockchainDepositError {
-    BlockchainTransactionError(ErrorNewTransaction),
-    BlockchainNotFound{ blockchain: String },
-    CryptoNotQuoted{ crypto: String },
-    UserNotFound{ user_id: u32 },
-    File(FileError)
-}
+    // suspends the user's KYC: they can't operate until they're reinstated
+    fn suspender(&mut self, date: FechaHora, user_id: u32, reason: &str) -> Result<(), KycUpdateError> {
+        self.review_kyc(date, user_id, KycReview::Suspend, reason)
+    }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum BlockchainWithdrawalError {
-    BlockchainTransactionError(ErrorNewTransaction),
-    BlockchainNotFound{ blockchain: String },
-    CryptoNotQuoted{ crypto: String },
-    UserNotFound{ user_id: u32 },
-    NotEnoughBalance{ balance: f64, balance_needed: f64 },
-    File(FileError)
-}
+    // an account is only closed with nothing left in it. Whatever is left is withdrawn with the payout,
+    // if there's one: the pesos by its mean, every crypto to the blockchain it gives for it, which has to support it.
+    // it's a withdrawal like any other: its fees come out of what's paid out (a balance the fee takes whole is all fee),
+    // all of it counts against the tier's limits, and it's flagged as one withdrawal, so it can be held for an operator.
+    // open orders are cancelled, and so are pending withdrawals, but a user with open alerts isn't paid out until
+    // they're reviewed. The account is kept with the closed ones, and its id is never given again
+    fn cerrar_cuenta(&mut self, date: FechaHora, user_id: u32, payout: Option<&Payout>) -> Result<(), CloseAccountError> {
+        let Some(user) = self.users.get(&user_id) else { return Err(CloseAccountError::UserNotFound { user_id }) };
+
+        // they'd be credited to an account that's gone
+        let deposits = self.unconfirmed_deposits(user_id);
+        if !deposits.is_empty() { return Err(CloseAccountError::DepositsUnconfirmed { deposits }) }
+
+        let mut balances: Vec<Dinero> = user.crypto_balance.values().copied().filter(|balance| !balance.es_cero()).collect();
+        balances.sort_by_key(Dinero::moneda);
+        if !user.fiat_balance.es_cero() { balances.insert(0, user.fiat_balance) }
+
+        // every withdrawal is checked before any is made
+        let mut withdrawals = Vec::new();
+        let mut all_fee = Vec::new();
+        let mut value = Dinero::cero(FIAT);
+        if !balances.is_empty() {
+            let Some(payout) = payout else { return Err(CloseAccountError::BalanceNotZero { balances }) };
+            user.check_kyc(KycOperation::Withdrawal).map_err(CloseAccountError::UserNotValidated)?;
+            let alerts: Vec<u64> = self.compliance.open_alerts(user_id).map(|alert| alert.id).collect();
+            if !alerts.is_empty() { return Err(CloseAccountError::OpenAlerts { alerts }) }
+
+            for balance in balances {
+                let currency = balance.moneda();
+                let account = user_account(user_id, currency);
+                let (record, lines) = if currency == FIAT {
+                    let fee = self.fees.fiat_withdrawal_fee(payout.mean);
+                    let amount = balance.restar(fee).map_err(CloseAccountError::Money)?;
+                    if !amount.es_positivo() { all_fee.push(with_fee(vec![JournalLine::debit(account, balance)], balance)); continue }
+
+                    let transaction_data = CommonTransactionData { date, user: user_id, amount, transaction_type: TransactionType::FiatWithdrawal { mean: payout.mean } };
+                    let transaction = FiatTransaction::new(transaction_data).map_err(CloseAccountError::TransactionError)?.with_fee(fee);
+                    (LedgerRecord::Fiat(transaction), with_fee(vec![
+                        JournalLine::debit(account, balance),
+                        JournalLine::credit(Account::FiatFloat, amount),
+                    ], fee))
+                } else {
+                    let Some(name) = payout.blockchains.get(&currency) else { return Err(CloseAccountError::NoPayoutBlockchain { crypto: currency }) };
+                    let Some(blockchain) = self.blockchains.get(name) else {
+                        return Err(CloseAccountError::BlockchainNotFound { blockchain: name.clone() })
+                    };
+                    let Some(quote) = self.quotes.get(&currency) else { return Err(CloseAccountError::CryptoNotQuoted { crypto: currency }) };
+                    blockchain.check_supports(currency, name).map_err(CloseAccountError::TransactionError)?;
+                    let fee = self.fees.blockchain_withdrawal_fee(name, currency);
+                    let amount = balance.restar(fee).map_err(CloseAccountError::Money)?;
+                    if !amount.es_positivo() { all_fee.push(with_fee(vec![JournalLine::debit(account, balance)], balance)); continue }
+
+                    let transaction_data = CommonTransactionData { date, user: user_id, amount, transaction_type: TransactionType::BlockchainWithdrawal };
+                    let transaction = BlockchainTransaction::new(transaction_data, name, None, currency, quote.clone())
+                        .map_err(CloseAccountError::TransactionError)?.with_fee(fee);
+                    (LedgerRecord::Blockchain(transaction), with_fee(vec![
+                        JournalLine::debit(account, balance),
+                        JournalLine::credit(Account::HotWallet(currency), amount),
+                    ], fee))
+                };
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum BuySellError {
-    TransactionError(ErrorNewTransaction),
-    CryptocurrencyNotQuoted { crypto_prefix: String },
-    UserNotFound { user_id: u32 },
-    NotEnoughBalance { balance: f64, balance_needed: f64 },
-    File(FileError),
-    Unknown(String),
-}
+                // limits are in pesos
+                let record_value = record.fiat_value().map_err(CloseAccountError::Money)?.unwrap_or(Dinero::cero(FIAT));
+                value = value.sumar(record_value).map_err(CloseAccountError::Money)?;
+                withdrawals.push((record, lines, record_value));
+            }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum FileError {
-    Serialization,
-    Deserialization,
-    IO,
-}
+            if let Some(exceeded) = self.exceeded_limit(user, date, value).map_err(CloseAccountError::Money)? {
+                return Err(CloseAccountError::LimitExceeded(exceeded))
+            }
+        }
 
-impl XYZ {
-    fn new(file_name: Option<&str>) -> Self {
-        let mut xyz = Self {
-            file_name: file_name.unwrap_or("xyz").to_string(),
-            users: Users::default(),
-            blockchains: Blockchains::default(),
-            quotes: Quotes::default(),
-            crypto_transactions: CryptoTransactionHistory::default()
-        };
-        let _ = xyz.actualizar_datos_xyz();
-        xyz
-    }
+        self.orders.cancel_all(user_id);
+        let pending: Vec<u64> = self.compliance.pending(user_id).map(|hold| hold.id).collect();
+        for hold_id in pending {
+            self.compliance.resolve(date, hold_id, false, "account closed").map_err(CloseAccountError::Review)?;
+        }
+        for lines in all_fee {
+            self.post(date, None, lines).map_err(CloseAccountError::Money)?;
+        }
 
-    fn sobreescribir_archivo_xyz(&self) -> Result<(), FileError> {
-        let parse_to_json: String = match serde_json::to_string_pretty(self) {
-            Ok(json) => { json }
-            Err(_) => { return Err(FileError::Serialization) }
-        };
+        // if it's flagged, the whole payout waits for an operator, and the account stays open until it's approved
+        if !withdrawals.is_empty() { self.flag_withdrawal(user_id, date, value).map_err(CloseAccountError::Money)? }
+        let mut holds = Vec::new();
+        for (record, lines, record_value) in withdrawals {
+            if let Some(hold_id) = self.compliance.hold(record.clone(), lines.clone(), record_value) {
+                holds.push(hold_id);
+                continue
+            }
+            let record = match record {
+                LedgerRecord::Blockchain(transaction) => LedgerRecord::Blockchain(self.send(transaction)),
+                record => record,
+            };
+            self.post(date, Some(record), lines).map_err(CloseAccountError::Money)?;
+        }
+        if !holds.is_empty() {
+            self.sobreescribir_archivo_xyz().map_err(CloseAccountError::File)?;
+            return Err(CloseAccountError::Held { holds })
+        }
 
-        match fs::write(format!("{BASE_FOLDER}{}.json", self.file_name), parse_to_json) {
-            Ok(()) => { Ok(()) }
-            Err(_) => { Err(FileError::IO) }
+        if let Some(user) = self.users.remove(&user_id) {
+            self.closed_accounts.insert(user_id, ClosedAccount { user, date });
         }
+        self.sobreescribir_archivo_xyz().map_err(CloseAccountError::File)
     }
 
-    fn leer_archivo_xyz(&self) -> Result<XYZ, FileError> {
-        let Ok(mut file) = File::open(format!("{BASE_FOLDER}{}", self.file_name))
-        else { return Err(FileError::IO) };
+    // re-sums the journal: every line must be valid, every entry must balance per currency,
+    // and every user's balance must be what the journal says it is
+    fn verificar_integridad(&self) -> Result<(), Vec<IntegrityError>> {
+        let mut errors = Vec::new();
+
+        for entry in self.journal.entries() {
+            for (line, _) in entry.lines.iter().enumerate().filter(|(_, line)| !line.is_valid()) {
+                errors.push(IntegrityError::InvalidLine { entry: entry.id, line });
+            }
 
-        let mut contents = String::new();
-        match file.read_to_string(&mut contents) {
-            Ok(_) => {},
-            Err(_) => return Err(FileError::IO)
+            match entry.imbalances() {
+                Ok(imbalances) => errors.extend(imbalances.into_values()
+                    .map(|difference| IntegrityError::UnbalancedEntry { entry: entry.id, difference })),
+                Err(error) => errors.push(IntegrityError::Money(error)),
+            }
         }
 
-        match serde_json::from_str(&contents) {
-            Err(_) => Err(FileError::Deserialization),
-            Ok(value) => Ok(value),
+        match balance_drifts(&self.users, &self.journal) {
+            Ok(drifts) => errors.extend(drifts.into_iter()
+                .map(|(account, cached, journal)| IntegrityError::BalanceDrift { account, cached, journal })),
+            Err(error) => errors.push(IntegrityError::Money(error)),
         }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
     }
 
-    fn actualizar_datos_xyz(&mut self) -> Result<(), FileError> {
-        let data = self.leer_archivo_xyz()?;
+    fn submit_kyc(&mut self, date: FechaHora, user_id: u32, documents: Vec<KycDocument>) -> Result<(), KycUpdateError> {
+        let Some(user) = self.users.get_mut(&user_id) else { return Err(KycUpdateError::UserNotFound { user_id }) };
+        user.kyc.submit(date, documents).map_err(KycUpdateError::Kyc)?;
+        self.sobreescribir_archivo_xyz().map_err(KycUpdateError::File)
+    }
 
-        self.users = data.users;
-        self.blockchains = data.blockchains;
-        self.quotes = data.quotes;
-        self.crypto_transactions = data.crypto_transactions;
+    // a suspended user's open orders are cancelled: they can't trade anymore
+    fn review_kyc(&mut self, date: FechaHora, user_id: u32, review: KycReview, note: &str) -> Result<(), KycUpdateError> {
+        let Some(user) = self.users.get_mut(&user_id) else { return Err(KycUpdateError::UserNotFound { user_id }) };
+        user.kyc.review(date, review, note).map_err(KycUpdateError::Kyc)?;
 
-        Ok(())
+        if review == KycReview::Suspend { self.orders.cancel_all(user_id); }
+
+        self.sobreescribir_archivo_xyz().map_err(KycUpdateError::File)
+    }
+
+    fn set_tier(&mut self, user_id: u32, tier: UserTier) -> Result<(), UserDataError> {
+        let Some(user) = self.users.get_mut(&user_id) else { return Err(UserDataError::UserNotFound { user_id }) };
+        user.tier = tier;
+        self.sobreescribir_archivo_xyz().map_err(UserDataError::File)
+    }
+
+    fn review_alert(&mut self, date: FechaHora, alert_id: u64, review: AlertReview, note: &str) -> Result<(), ComplianceUpdateError> {
+        self.compliance.review_alert(date, alert_id, review, note).map_err(ComplianceUpdateError::Review)?;
+        self.sobreescribir_archivo_xyz().map_err(ComplianceUpdateError::File)
+    }
+
+    // the held withdrawal is made now, as it was asked for, and its alerts are dismissed.
+    // the journal dates it now; the ledger keeps the date it was asked for. Blockchain withdrawals are sent now,
+    // and transfers go to their recipient if they can still get it
+    fn approve_withdrawal(&mut self, date: FechaHora, hold_id: u64, note: &str) -> Result<(), ComplianceUpdateError> {
+        let hold = self.compliance.pending_hold(hold_id).map_err(ComplianceUpdateError::Review)?;
+        let user_id = hold.record.data().user;
+        let Some(user) = self.users.get(&user_id) else { return Err(ComplianceUpdateError::UserNotFound { user_id }) };
+        user.check_kyc(KycOperation::Withdrawal).map_err(ComplianceUpdateError::UserNotValidated)?;
+        if let Some(user_id) = hold.record.recipient() {
+            let Some(recipient) = self.users.get(&user_id) else { return Err(ComplianceUpdateError::UserNotFound { user_id }) };
+            recipient.check_kyc(KycOperation::Deposit).map_err(ComplianceUpdateError::UserNotValidated)?;
+        }
+
+        let (record, lines) = (hold.record.clone(), hold.lines.clone());
+        let record = match record {
+            LedgerRecord::Blockchain(transaction) => LedgerRecord::Blockchain(self.send(transaction)),
+            record => record,
+        };
+        self.post(date, Some(record), lines).map_err(ComplianceUpdateError::Money)?;
+        self.compliance.resolve(date, hold_id, true, note).map_err(ComplianceUpdateError::Review)?;
+        self.sobreescribir_archivo_xyz().map_err(ComplianceUpdateError::File)
+    }
+
+    // what the held withdrawal held goes back to the user, and its alerts are confirmed
+    fn reject_withdrawal(&mut self, date: FechaHora, hold_id: u64, note: &str) -> Result<(), ComplianceUpdateError> {
+        self.compliance.resolve(date, hold_id, false, note).map_err(ComplianceUpdateError::Review)?;
+        self.sobreescribir_archivo_xyz().map_err(ComplianceUpdateError::File)
     }
 
     // ➢ Ingresar dinero: se recibe un monto en fiat de un usuario
     //  y se acredita al balance de fiat de dicho usuario. Además se crea una transacción del hecho.
-    fn fiat_deposit(&mut self, today_date: Date, user_id: u32, amount: f64) -> Result<FiatTransaction, FiatDepositError> {
+    fn fiat_deposit(&mut self, today_date: FechaHora, user_id: u32, amount: Dinero) -> Result<FiatTransaction, FiatDepositError> {
         let data = CommonTransactionData {
             date: today_date,
             user: user_id,
@@ -753,12 +3482,19 @@ impl XYZ {
             data,
         ) {
             Ok(transaction) => {
-                // deposit
-                if let Some(user) = self.users.get_mut(&data.user) {
-                    user.fiat_balance += Balance::from(data.amount);
-                } else {
+                let Some(user) = self.users.get(&data.user) else {
                     return Err(FiatDepositError::UserNotFound{ user_id: data.user });
-                }
+                };
+                user.check_kyc(KycOperation::Deposit).map_err(FiatDepositError::UserNotValidated)?;
+
+                // deposit: the pesos arrive at XYZ's bank account
+                self.post(data.date, Some(LedgerRecord::Fiat(transaction.clone())), vec![
+                    JournalLine::debit(Account::FiatFloat, data.amount),
+                    JournalLine::credit(Account::UserFiat(data.user), data.amount),
+                ]).map_err(FiatDepositError::Money)?;
+
+                // the pesos are already here: a structured deposit only raises an alert
+                self.flag_deposit(data.user, data.date).map_err(FiatDepositError::Money)?;
 
                 // guardar en archivo
                 match self.sobreescribir_archivo_xyz() {
@@ -775,7 +3511,7 @@ impl XYZ {
     // ➢ Retirar fiat por determinado medio: dado un monto de fiat se le descuenta dicho monto del balance
     // al usuario y se genera una transacción con la siguiente información:
     // fecha, usuario, tipo: retiro fiat, monto y medio (puede ser MercadoPago o Transferencia Bancaria)
-    fn fiat_withdrawal(&mut self, today_date: Date, user_id: u32, amount: f64, mean: WithdrawalMean) -> Result<FiatTransaction, FiatWithdrawalError> {
+    fn fiat_withdrawal(&mut self, today_date: FechaHora, user_id: u32, amount: Dinero, mean: WithdrawalMean) -> Result<FiatTransaction, FiatWithdrawalError> {
         let data = CommonTransactionData {
             date: today_date,
             user: user_id,
@@ -787,19 +3523,45 @@ impl XYZ {
             data,
         ) {
             Ok(transaction) => {
+                // the fee is paid on top of the amount
+                let fee = self.fees.fiat_withdrawal_fee(mean);
+                let transaction = transaction.with_fee(fee);
+                let needed = data.amount.sumar(fee).map_err(FiatWithdrawalError::Money)?;
+
                 // does user exist/have enough balance?
-                if let Some(user) = self.users.get_mut(&data.user) {
+                if let Some(user) = self.users.get(&data.user) {
+                    user.check_kyc(KycOperation::Withdrawal).map_err(FiatWithdrawalError::UserNotValidated)?;
+
                     // not enough! abort.
-                    if user.fiat_balance < Balance(data.amount) { return Err( FiatWithdrawalError::NotEnoughBalance {
-                        balance_needed: data.amount, balance: user.fiat_balance.f64()
+                    let available = self.available_balance(user, FIAT).map_err(FiatWithdrawalError::Money)?;
+                    if available < needed { return Err( FiatWithdrawalError::NotEnoughBalance {
+                        balance_needed: needed, balance: available
                     }) }
 
-                    // enough! substract balance
-                    user.fiat_balance-= Balance(data.amount);
+                    // over the tier's limits! abort.
+                    if let Some(exceeded) = self.exceeded_limit(user, data.date, data.amount).map_err(FiatWithdrawalError::Money)? {
+                        return Err(FiatWithdrawalError::LimitExceeded(exceeded))
+                    }
                 } else {
                     return Err(FiatWithdrawalError::UserNotFound { user_id: data.user })
                 }
 
+                // enough! substract balance: the pesos leave XYZ's bank account, except for the fee
+                let record = LedgerRecord::Fiat(transaction.clone());
+                let lines = with_fee(vec![
+                    JournalLine::debit(Account::UserFiat(data.user), needed),
+                    JournalLine::credit(Account::FiatFloat, data.amount),
+                ], fee);
+
+                // unless it's flagged: then it waits for an operator
+                self.flag_withdrawal(data.user, data.date, data.amount).map_err(FiatWithdrawalError::Money)?;
+                if let Some(hold_id) = self.compliance.hold(record.clone(), lines.clone(), data.amount) {
+                    self.sobreescribir_archivo_xyz().map_err(FiatWithdrawalError::File)?;
+                    return Err(FiatWithdrawalError::Held { hold_id })
+                }
+
+                self.post(data.date, Some(record), lines).map_err(FiatWithdrawalError::Money)?;
+
                 // guardar en archivo
                 match self.sobreescribir_archivo_xyz() {
                     Ok(()) => {}
@@ -819,7 +3581,7 @@ impl XYZ {
     //  de la cripto y desacreditar en el balance de fiat.
     // Luego de ello se registra la transacción con los siguientes datos:
     //      fecha, usuario, criptomoneda, tipo: compra de cripto, monto de cripto y cotización.
-    fn crypto_buy(&mut self, today_date: Date, user_id: u32, fiat_amount: f64, crypto_prefix: &str)
+    fn crypto_buy(&mut self, today_date: FechaHora, user_id: u32, fiat_amount: Dinero, crypto: Moneda)
                   -> Result<&CryptoTransaction, BuySellError> {
         // date errors are handled by CryptoTransaction::new()
         let data = CommonTransactionData {
@@ -831,39 +3593,53 @@ impl XYZ {
 
         match CryptoTransaction::new(
             data,
-            crypto_prefix
+            crypto
         ) {
             Ok(transaction) => {
                 // process buy
 
-                // check 1: currency must be quoted AND quoted higher than 0.0
-                let currency_unitary_value = if let Some(quoting) = self.quotes.get(crypto_prefix) {
-                    if quoting.buy <= 0.0 { return Err(BuySellError::Unknown(format!("${crypto_prefix} is valued at {} FIAT (which is <= 0)", quoting.buy))) }
-                    quoting.buy
+                // check 1: currency must be quoted AND quoted higher than 0
+                let currency_value = if let Some(quoting) = self.quotes.get(&crypto) {
+                    if !quoting.buy.es_positivo() { return Err(BuySellError::Unknown(format!("${crypto} is valued at {} (which is <= 0)", quoting.buy))) }
+                    quoting.clone()
                 } else {
-                    return Err(BuySellError::CryptocurrencyNotQuoted{ crypto_prefix: crypto_prefix.to_string() });
+                    return Err(BuySellError::CryptocurrencyNotQuoted{ crypto });
                 };
 
-                let transaction_crypto_amount = data.amount / currency_unitary_value; // .0 -> buy, .1 -> sell
+                // the fee comes out of the pesos paid, and the rest buys the crypto.
+                // rounded down: XYZ never hands out a fraction of a unit that wasn't paid for
+                let rate = self.trading_rate(data.user, crypto, data.date, Liquidity::Taker).map_err(BuySellError::Money)?;
+                let fee = fee_at(data.amount, rate).map_err(BuySellError::Money)?;
+                let paid = data.amount.restar(fee).map_err(BuySellError::Money)?;
+                let transaction_crypto_amount = paid.dividir_por_precio(currency_value.buy, crypto, Redondeo::HaciaCero)
+                    .map_err(BuySellError::Money)?;
 
                 // check 2: user must exist
-                if let Some(user) = self.users.get_mut(&data.user) {
+                if let Some(user) = self.users.get(&data.user) {
+                    // check 3: user must be allowed to trade
+                    user.check_kyc(KycOperation::Trade).map_err(BuySellError::UserNotValidated)?;
+
                     // check 4: user must have enough fiat balance
-                    if user.fiat_balance < Balance::from(data.amount) {
-                        return Err(BuySellError::NotEnoughBalance{ balance: user.fiat_balance.f64(), balance_needed: data.amount })
+                    let available = self.available_balance(user, FIAT).map_err(BuySellError::Money)?;
+                    if available < data.amount {
+                        return Err(BuySellError::NotEnoughBalance{ balance: available, balance_needed: data.amount })
                     }
-
-                    // no error. execute operation
-                    user.fiat_balance-= Balance::from(data.amount);
-                    *user.crypto_balance.entry(crypto_prefix.to_string()).or_insert(Balance::from(0.0))+= Balance::from(transaction_crypto_amount);
                 } else {
                     return Err(BuySellError::UserNotFound{ user_id: data.user });
                 }
 
-                self.crypto_transactions.entry(crypto_prefix.to_string()).or_default().push(transaction);
+                // no error. execute operation: the user's pesos go to XYZ, XYZ's crypto goes to the user
+                let transaction = transaction.with_quote(currency_value).with_fee(fee);
+                self.post(data.date, Some(LedgerRecord::Crypto(transaction.clone())), with_fee(vec![
+                    JournalLine::debit(Account::UserFiat(data.user), data.amount),
+                    JournalLine::credit(Account::HouseTrading(FIAT), paid),
+                    JournalLine::debit(Account::HouseTrading(crypto), transaction_crypto_amount),
+                    JournalLine::credit(Account::UserCrypto { user: data.user, crypto }, transaction_crypto_amount),
+                ], fee)).map_err(BuySellError::Money)?;
+                self.crypto_transactions.entry(crypto).or_default().push(transaction);
 
                 // comprobar añadido y obtener préstamo
-                match self.crypto_transactions.get(crypto_prefix) {
+                match self.crypto_transactions.get(&crypto) {
                     Some(crypto_transactions) => {
                         // la ultima transaccion guarada debería estar al final
                         match crypto_transactions.last() {
@@ -894,8 +3670,10 @@ impl XYZ {
     //  y desacreditar en el balance de la criptomoneda.
     //  Luego de ello se registra la transacción con los siguientes datos:
     //  fecha, usuario, criptomoneda, tipo: venta de cripto, monto de cripto y cotización.
-    fn crypto_sell(&mut self, today_date: Date, user_id: u32, crypto_amount: f64, crypto_prefix: &str) ->
+    fn crypto_sell(&mut self, today_date: FechaHora, user_id: u32, crypto_amount: Dinero) ->
     Result<&CryptoTransaction, BuySellError> {
+        let crypto = crypto_amount.moneda();
+
         // date errors are handled by CryptoTransaction::new()
         let data = CommonTransactionData {
             date: today_date,
@@ -906,43 +3684,52 @@ impl XYZ {
 
         match CryptoTransaction::new(
             data,
-            crypto_prefix,
+            crypto,
         ) {
             Ok(transaction) => {
                 // process sell
 
-                // check 1: currency must be quoted AND quoted higher than 0.0
-                let currency_value = if let Some(quoting) = self.quotes.get(crypto_prefix) {
-                    if quoting.sell <= 0.0 { return Err(BuySellError::Unknown(format!("${crypto_prefix} is valued at {} FIAT (which is <= 0)", quoting.sell))) }
-                    quoting
+                // check 1: currency must be quoted AND quoted higher than 0
+                let currency_value = if let Some(quoting) = self.quotes.get(&crypto) {
+                    if !quoting.sell.es_positivo() { return Err(BuySellError::Unknown(format!("${crypto} is valued at {} (which is <= 0)", quoting.sell))) }
+                    quoting.clone()
                 } else {
-                    return Err(BuySellError::CryptocurrencyNotQuoted{ crypto_prefix: crypto_prefix.to_string() });
+                    return Err(BuySellError::CryptocurrencyNotQuoted{ crypto });
                 };
 
-                let transaction_fiat_value = currency_value.sell * data.amount;
+                // rounded down, like buys. The fee comes out of the pesos the user gets
+                let transaction_fiat_value = data.amount.por(currency_value.sell, Redondeo::HaciaCero)
+                    .map_err(BuySellError::Money)?;
+                let rate = self.trading_rate(data.user, crypto, data.date, Liquidity::Taker).map_err(BuySellError::Money)?;
+                let fee = fee_at(transaction_fiat_value, rate).map_err(BuySellError::Money)?;
+                let received = transaction_fiat_value.restar(fee).map_err(BuySellError::Money)?;
 
                 // check 2: user must exist
-                if let Some(user) = self.users.get_mut(&data.user) {
-                    // check 3: user must have enough $crypto_prefix balance
-                    if let Some(user_crypto_balance) = user.crypto_balance.get_mut(crypto_prefix) {
-                        if *user_crypto_balance < Balance::from(data.amount) {
-                            return Err(BuySellError::NotEnoughBalance { balance: user_crypto_balance.f64(), balance_needed: data.amount })
-                        }
-
-                        // no error. execute operation
-                        *user_crypto_balance-= Balance::from(data.amount);
-                        user.fiat_balance+= Balance::from(transaction_fiat_value);
-                    } else {
-                        return Err(BuySellError::NotEnoughBalance{ balance: 0.0, balance_needed: data.amount })
+                if let Some(user) = self.users.get(&data.user) {
+                    // check 3: user must be allowed to trade
+                    user.check_kyc(KycOperation::Trade).map_err(BuySellError::UserNotValidated)?;
+
+                    // check 4: user must have enough $crypto balance
+                    let available = self.available_balance(user, crypto).map_err(BuySellError::Money)?;
+                    if available < data.amount {
+                        return Err(BuySellError::NotEnoughBalance { balance: available, balance_needed: data.amount })
                     }
                 } else {
                     return Err(BuySellError::UserNotFound{ user_id: data.user });
                 }
 
-                self.crypto_transactions.entry(crypto_prefix.to_string()).or_default().push(transaction);
+                // no error. execute operation: the user's crypto goes to XYZ, XYZ's pesos go to the user
+                let transaction = transaction.with_quote(currency_value).with_fee(fee);
+                self.post(data.date, Some(LedgerRecord::Crypto(transaction.clone())), with_fee(vec![
+                    JournalLine::debit(Account::UserCrypto { user: data.user, crypto }, data.amount),
+                    JournalLine::credit(Account::HouseTrading(crypto), data.amount),
+                    JournalLine::debit(Account::HouseTrading(FIAT), transaction_fiat_value),
+                    JournalLine::credit(Account::UserFiat(data.user), received),
+                ], fee)).map_err(BuySellError::Money)?;
+                self.crypto_transactions.entry(crypto).or_default().push(transaction);
 
                 // comprobar añadido y obtener préstamo
-                match self.crypto_transactions.get(crypto_prefix) {
+                match self.crypto_transactions.get(&crypto) {
                     Some(crypto_transactions) => {
                         // la ultima transaccion guarada debería estar al final
                         match crypto_transactions.last() {
@@ -969,15 +3756,17 @@ impl XYZ {
     // ➢ Recibir criptomoneda de blockchain: dado un monto de una cripto y una blockchain se le acredita
     // al balancede dicha cripto al usuario el monto. Luego se genera una transacción con los siguientes datos:
     // fecha, usuario, tipo: recepción cripto, blockchain, cripto, monto, cotización.
-    fn blockchain_deposit(&mut self, today_date: Date, user_id: u32, amount: f64, blockchain: &str, crypto: &str) -> Result<BlockchainTransaction, BlockchainDepositError> {
+    fn blockchain_deposit(&mut self, today_date: FechaHora, user_id: u32, amount: Dinero, blockchain: &str) -> Result<BlockchainTransaction, BlockchainDepositError> {
+        let crypto = amount.moneda();
+
         // does blockchain exist?
         if !self.blockchains.contains_key(blockchain) {
             return Err(BlockchainDepositError::BlockchainNotFound { blockchain: blockchain.to_string() })
         }
 
         // does crypto have a quote?
-        let Some(quote) = self.quotes.get(crypto) else {
-            return Err(BlockchainDepositError::CryptoNotQuoted { crypto: crypto.to_string() })
+        let Some(quote) = self.quotes.get(&crypto) else {
+            return Err(BlockchainDepositError::CryptoNotQuoted { crypto })
         };
 
         let data = CommonTransactionData {
@@ -992,20 +3781,21 @@ impl XYZ {
             blockchain,
             None,
             crypto,
-            quote.clone() // quote should be cloned, as it changes over time. can't be copied due to containing f64
+            quote.clone() // quote should be cloned, as it changes over time
         ) {
             Ok(transaction) => {
-                // add to balance
-                // does user exist/have enough balance?
-                if let Some(user) = self.users.get_mut(&data.user) {
-                    // enough! ready to deposit
-                    if let Some(crypto_balance) = user.crypto_balance.get_mut(crypto) {
-                        *crypto_balance+= Balance::from(amount);
-                    } else {
-                        user.crypto_balance.insert(crypto.to_string(), Balance::from(amount));
-                    }
-                } else {
+                // does user exist?
+                let Some(user) = self.users.get(&data.user) else {
                     return Err(BlockchainDepositError::UserNotFound { user_id: data.user })
+                };
+                user.check_kyc(KycOperation::Deposit).map_err(BlockchainDepositError::UserNotValidated)?;
+
+                // add to balance: the crypto arrives at XYZ's hot wallet.
+                // if the blockchain asks for confirmations, it's credited once it has them, see tick
+                let transaction = self.send(transaction);
+                if self.blockchains.get(blockchain).is_some_and(|blockchain| blockchain.chain.confirmations_required() == 0) {
+                    self.post(data.date, Some(LedgerRecord::Blockchain(transaction.clone())), deposit_lines(&transaction))
+                        .map_err(BlockchainDepositError::Money)?;
                 }
 
                 // guardar en archivo, finalizar
@@ -1024,15 +3814,17 @@ impl XYZ {
     // (esto hágalo retornando el nombre de la blockchain + un número random).
     // Luego se genera una transacción con los siguientes datos:
     // fecha, usuario, tipo: retiro cripto, blockchain, hash, cripto, monto, cotización.
-    fn blockchain_withdrawal(&mut self, today_date: Date, user_id: u32, crypto_amount: f64, blockchain: &str, crypto: &str) -> Result<BlockchainTransaction, BlockchainWithdrawalError> {
+    fn blockchain_withdrawal(&mut self, today_date: FechaHora, user_id: u32, crypto_amount: Dinero, blockchain: &str) -> Result<BlockchainTransaction, BlockchainWithdrawalError> {
+        let crypto = crypto_amount.moneda();
+
         // does blockchain exist?
         if !self.blockchains.contains_key(blockchain) {
             return Err(BlockchainWithdrawalError::BlockchainNotFound { blockchain: blockchain.to_string() })
         }
 
         // does crypto have a quote?
-        let Some(quote) = self.quotes.get(crypto) else {
-            return Err(BlockchainWithdrawalError::CryptoNotQuoted { crypto: crypto.to_string() })
+        let Some(quote) = self.quotes.get(&crypto) else {
+            return Err(BlockchainWithdrawalError::CryptoNotQuoted { crypto })
         };
 
         let data = CommonTransactionData {
@@ -1047,27 +3839,51 @@ impl XYZ {
             blockchain,
             None,
             crypto,
-            quote.clone() // quote should be cloned, as it changes over time. can't be copied due to containing f64
+            quote.clone() // quote should be cloned, as it changes over time
         ) {
             Ok(transaction) => {
+                // the blockchain's fee is paid on top of the amount, in the crypto
+                let fee = self.fees.blockchain_withdrawal_fee(blockchain, crypto);
+                let transaction = transaction.with_fee(fee);
+                let needed = crypto_amount.sumar(fee).map_err(BlockchainWithdrawalError::Money)?;
+
                 // remove balance
                 // does user exist/have enough balance?
-                if let Some(user) = self.users.get_mut(&data.user) {
-                    if let Some(balance) = user.crypto_balance.get_mut(crypto) {
-
-                        if *balance >= Balance::from(crypto_amount) {
-                            *balance-= Balance::from(data.amount);
-                        } else {
-                            return Err(BlockchainWithdrawalError::NotEnoughBalance { balance_needed: crypto_amount, balance: balance.f64() });
-                        }
-
-                    } else {
-                        return Err(BlockchainWithdrawalError::NotEnoughBalance { balance: 0.0, balance_needed: data.amount } )
+                if let Some(user) = self.users.get(&data.user) {
+                    user.check_kyc(KycOperation::Withdrawal).map_err(BlockchainWithdrawalError::UserNotValidated)?;
+                    let available = self.available_balance(user, crypto).map_err(BlockchainWithdrawalError::Money)?;
+                    if available < needed {
+                        return Err(BlockchainWithdrawalError::NotEnoughBalance { balance_needed: needed, balance: available });
                     }
                 } else {
                     return Err(BlockchainWithdrawalError::UserNotFound { user_id: data.user })
                 }
 
+                // limits are in pesos
+                let record = LedgerRecord::Blockchain(transaction.clone());
+                let value = record.fiat_value().map_err(BlockchainWithdrawalError::Money)?.unwrap_or(Dinero::cero(FIAT));
+                if let Some(user) = self.users.get(&data.user)
+                    && let Some(exceeded) = self.exceeded_limit(user, data.date, value).map_err(BlockchainWithdrawalError::Money)? {
+                    return Err(BlockchainWithdrawalError::LimitExceeded(exceeded))
+                }
+
+                // the crypto leaves XYZ's hot wallet, except for the fee
+                let lines = with_fee(vec![
+                    JournalLine::debit(Account::UserCrypto { user: data.user, crypto }, needed),
+                    JournalLine::credit(Account::HotWallet(crypto), data.amount),
+                ], fee);
+
+                // unless it's flagged: then it waits for an operator
+                self.flag_withdrawal(data.user, data.date, value).map_err(BlockchainWithdrawalError::Money)?;
+                if let Some(hold_id) = self.compliance.hold(record, lines.clone(), value) {
+                    self.sobreescribir_archivo_xyz().map_err(BlockchainWithdrawalError::File)?;
+                    return Err(BlockchainWithdrawalError::Held { hold_id })
+                }
+
+                // it's on its way: it gets its hash from the blockchain
+                let transaction = self.send(transaction);
+                self.post(data.date, Some(LedgerRecord::Blockchain(transaction.clone())), lines).map_err(BlockchainWithdrawalError::Money)?;
+
                 // guardar en archivo
                 match self.sobreescribir_archivo_xyz() {
                     Ok(()) => {}
@@ -1080,9 +3896,572 @@ impl XYZ {
         }
     }
 
+    // moves pesos or crypto to another user's account in one journal entry: either both balances change or neither does.
+    // sending is like withdrawing and receiving like depositing, so a user only verified to deposit can receive but not send.
+    // nothing leaves XYZ, so there are no fees. The AML rules still see it as a withdrawal: the limits apply,
+    // and it's held while the sender has open alerts
+    fn transfer(&mut self, date: FechaHora, user_id: u32, recipient: &Recipient, amount: Dinero, memo: Option<&str>) -> Result<InternalTransfer, TransferError> {
+        let Some(sender) = self.users.get(&user_id) else { return Err(TransferError::UserNotFound { user_id }) };
+        let receiver = match recipient {
+            Recipient::Id(id) => self.users.get(id),
+            Recipient::Email(email) => self.users.values().find(|user| user.email.to_lowercase() == email.to_lowercase()),
+        }.ok_or_else(|| TransferError::RecipientNotFound { recipient: recipient.clone() })?;
+        if sender.id == receiver.id { return Err(TransferError::SameUser { user_id }) }
+
+        let transfer_data = CommonTransactionData { date, user: user_id, amount, transaction_type: TransactionType::InternalTransfer };
+        let transfer = InternalTransfer::new(transfer_data, receiver.id, memo).map_err(TransferError::TransactionError)?;
+
+        sender.check_kyc(KycOperation::Withdrawal).map_err(TransferError::UserNotValidated)?;
+        receiver.check_kyc(KycOperation::Deposit).map_err(TransferError::RecipientNotValidated)?;
+
+        let currency = amount.moneda();
+        let available = self.available_balance(sender, currency).map_err(TransferError::Money)?;
+        if available < amount {
+            return Err(TransferError::NotEnoughBalance { balance: available, balance_needed: amount })
+        }
+
+        // limits are in pesos. A crypto with no quote can't be withdrawn, so it's worth nothing here
+        let transfer = match self.quotes.get(&currency) {
+            Some(quote) if currency != FIAT => transfer.with_quote(quote.clone()),
+            _ => transfer,
+        };
+        let record = LedgerRecord::Transfer(transfer.clone());
+        let value = record.fiat_value().map_err(TransferError::Money)?.unwrap_or(Dinero::cero(FIAT));
+        if let Some(exceeded) = self.exceeded_limit(sender, date, value).map_err(TransferError::Money)? {
+            return Err(TransferError::LimitExceeded(exceeded))
+        }
+
+        let lines = vec![
+            JournalLine::debit(user_account(user_id, currency), amount),
+            JournalLine::credit(user_account(transfer.recipient, currency), amount),
+        ];
+
+        // unless it's flagged: then it waits for an operator
+        self.flag_withdrawal(user_id, date, value).map_err(TransferError::Money)?;
+        if let Some(hold_id) = self.compliance.hold(record.clone(), lines.clone(), value) {
+            self.sobreescribir_archivo_xyz().map_err(TransferError::File)?;
+            return Err(TransferError::Held { hold_id })
+        }
+
+        self.post(date, Some(record), lines).map_err(TransferError::Money)?;
+        self.sobreescribir_archivo_xyz().map_err(TransferError::File)?;
+        Ok(transfer)
+    }
+
+    // what swapping `amount` for `to` would give the user now: the route that gives the most, straight or through
+    // one of swap_via, with the fee taken. It's charged once, at the user's taker rate for the crypto given,
+    // or for the one got if pesos are given. Going through pesos still crosses both spreads, but pays one fee
+    fn swap_preview(&self, date: FechaHora, user_id: u32, amount: Dinero, to: Moneda) -> Result<SwapPreview, SwapError> {
+        let from = amount.moneda();
+        if !amount.es_positivo() { return Err(SwapError::TransactionError(ErrorNewTransaction::InvalidInputAmount { amount })) }
+        if from == to { return Err(SwapError::TransactionError(ErrorNewTransaction::InvalidCurrency { amount })) }
+        if !self.users.contains_key(&user_id) { return Err(SwapError::UserNotFound { user_id }) }
+
+        let rate = self.trading_rate(user_id, if from == FIAT { to } else { from }, date, Liquidity::Taker).map_err(SwapError::Money)?;
+        let mut best: Option<SwapPreview> = None;
+        for route in swap_routes(&self.quotes, &self.pair_quotes, &self.swap_via, from, to) {
+            let preview = SwapPreview::new(amount, route, rate, &self.quotes).map_err(SwapError::Money)?;
+            // straight routes come first, so they win ties
+            if best.as_ref().is_none_or(|best| preview.received > best.received) { best = Some(preview) }
+        }
+        best.ok_or(SwapError::NoRoute { from, to })
+    }
+
+    // ➢ Swap: trades one currency for another in one operation, at what swap_preview gives.
+    // whatever route it takes it's one record and one journal entry: the user's currency goes to XYZ and XYZ's
+    // goes to the user. What the route went through never leaves the house
+    fn swap(&mut self, date: FechaHora, user_id: u32, amount: Dinero, to: Moneda) -> Result<SwapTransaction, SwapError> {
+        let swap_data = CommonTransactionData { date, user: user_id, amount, transaction_type: TransactionType::CryptoSwap };
+        let swap = SwapTransaction::new(swap_data, to).map_err(SwapError::TransactionError)?;
+
+        let Some(user) = self.users.get(&user_id) else { return Err(SwapError::UserNotFound { user_id }) };
+        user.check_kyc(KycOperation::Trade).map_err(SwapError::UserNotValidated)?;
+
+        let from = amount.moneda();
+        let available = self.available_balance(user, from).map_err(SwapError::Money)?;
+        if available < amount {
+            return Err(SwapError::NotEnoughBalance { balance: available, balance_needed: amount })
+        }
+
+        let preview = self.swap_preview(date, user_id, amount, to)?;
+        if !preview.received.es_positivo() { return Err(SwapError::NothingReceived { amount }) }
+
+        let swap = swap.with_preview(&preview);
+        self.post(date, Some(LedgerRecord::Swap(swap.clone())), with_fee(vec![
+            JournalLine::debit(user_account(user_id, from), amount),
+            JournalLine::credit(Account::HouseTrading(from), amount),
+            JournalLine::debit(Account::HouseTrading(to), preview.gross),
+            JournalLine::credit(user_account(user_id, to), preview.received),
+        ], preview.fee)).map_err(SwapError::Money)?;
+
+        self.sobreescribir_archivo_xyz().map_err(SwapError::File)?;
+        Ok(swap)
+    }
+
+    // ➢ Operar en el libro de órdenes: the order trades with the best resting orders on the other side
+    // for as long as prices cross. Each trade is a sell for the seller and a buy for the buyer at the resting order's price,
+    // recorded like any other, and its price becomes the crypto's quote.
+    // A limit order's remainder rests in the book, holding its funds; a market order's is dropped.
+    // Market buys take as much as the user's pesos can pay for
+    fn place_order(&mut self, date: FechaHora, user_id: u32, side: OrderSide, kind: OrderKind, amount: Dinero) -> Result<OrderPlacement, PlaceOrderError> {
+        let crypto = amount.moneda();
+        if crypto == FIAT || !amount.es_positivo() { return Err(PlaceOrderError::InvalidAmount { amount }) }
+        if let OrderKind::Limit { price } = kind && (price.moneda() != FIAT || !price.es_positivo()) {
+            return Err(PlaceOrderError::InvalidPrice { price })
+        }
+        if !date.es_valida() { return Err(PlaceOrderError::TransactionError(ErrorNewTransaction::InvalidDate)) }
+
+        let Some(user) = self.users.get(&user_id) else { return Err(PlaceOrderError::UserNotFound { user_id }) };
+        user.check_kyc(KycOperation::Trade).map_err(PlaceOrderError::UserNotValidated)?;
+
+        // nobody trades with themselves: it would only pay fees and set the quote. The order that
+        // would cross one of the user's own has to wait until that one is cancelled
+        let own = self.orders.book(crypto)
+            .and_then(|book| book.side(side.opposite()).iter().find(|resting| resting.user == user_id && resting.crossed_by(kind)));
+        if let Some(own) = own { return Err(PlaceOrderError::SelfTrade { order_id: own.id }) }
+
+        // what the whole order needs, if it's known up front
+        let needed = match (side, kind) {
+            (OrderSide::Sell, _) => Some(amount),
+            (OrderSide::Buy, OrderKind::Limit { price }) => Some(amount.por(price, Redondeo::HaciaArriba).map_err(PlaceOrderError::Money)?),
+            (OrderSide::Buy, OrderKind::Market) => None,
+        };
+        if let Some(needed) = needed {
+            let available = self.available_balance(user, needed.moneda()).map_err(PlaceOrderError::Money)?;
+            if available < needed { return Err(PlaceOrderError::NotEnoughBalance { balance: available, balance_needed: needed }) }
+        }
+
+        let order_id = self.orders.next_id;
+        self.orders.next_id += 1;
+
+        let mut remaining = amount;
+        let mut trades = Vec::new();
+        while remaining.es_positivo() {
+            let Some(resting) = self.orders.book(crypto).and_then(|book| book.best(side.opposite())).cloned() else { break };
+            if let OrderKind::Limit { price } = kind && !resting.crosses(price) { break }
+
+            let mut filled = if remaining < resting.remaining { remaining } else { resting.remaining };
+            if side == OrderSide::Buy && kind == OrderKind::Market {
+                let Some(user) = self.users.get(&user_id) else { break };
+                let available = self.available_balance(user, FIAT).map_err(PlaceOrderError::Money)?;
+                let affordable = available.dividir_por_precio(resting.price, crypto, Redondeo::HaciaCero).map_err(PlaceOrderError::Money)?;
+                if affordable < filled { filled = affordable }
+                if !filled.es_positivo() { break }
+            }
+
+            let (buy_order, buyer, sell_order, seller) = match side {
+                OrderSide::Buy => (order_id, user_id, resting.id, resting.user),
+                OrderSide::Sell => (resting.id, resting.user, order_id, user_id),
+            };
+            // this order takes, the resting one makes
+            let fiat = filled.por(resting.price, Redondeo::HaciaCero).map_err(PlaceOrderError::Money)?;
+            let taker_rate = self.trading_rate(user_id, crypto, date, Liquidity::Taker).map_err(PlaceOrderError::Money)?;
+            let maker_rate = self.trading_rate(resting.user, crypto, date, Liquidity::Maker).map_err(PlaceOrderError::Money)?;
+            let (buyer_rate, seller_rate) = match side {
+                OrderSide::Buy => (taker_rate, maker_rate),
+                OrderSide::Sell => (maker_rate, taker_rate),
+            };
+            let trade = Trade {
+                buy_order, sell_order, buyer, seller,
+                price: resting.price,
+                amount: filled,
+                fiat,
+                buyer_fee: fee_at(filled, buyer_rate).map_err(PlaceOrderError::Money)?,
+                seller_fee: fee_at(fiat, seller_rate).map_err(PlaceOrderError::Money)?,
+            };
+
+            self.settle(date, &trade)?;
+            if let Some(book) = self.orders.books.get_mut(&crypto) {
+                book.fill_best(side.opposite(), filled).map_err(PlaceOrderError::Money)?;
+            }
+            remaining = remaining.restar(filled).map_err(PlaceOrderError::Money)?;
+            trades.push(trade);
+        }
+
+        let resting = match kind {
+            OrderKind::Limit { price } if remaining.es_positivo() => {
+                let order = Order { id: order_id, date, user: user_id, side, price, amount, remaining };
+                self.orders.books.entry(crypto).or_default().insert(order.clone());
+                Some(order)
+            }
+            _ => None,
+        };
+
+        self.sobreescribir_archivo_xyz().map_err(PlaceOrderError::File)?;
+        Ok(OrderPlacement { order_id, trades, resting })
+    }
+
+    // a trade between two users goes through XYZ's trading accounts, like a buy and a sell would,
+    // and leaves them as they were
+    fn settle(&mut self, date: FechaHora, trade: &Trade) -> Result<(), PlaceOrderError> {
+        let crypto = trade.amount.moneda();
+        let quote = Quote { buy: trade.price, sell: trade.price, date };
+        let transaction = |user, amount, transaction_type, fee| CryptoTransaction::new(
+            CommonTransactionData { date, user, amount, transaction_type }, crypto
+        ).map(|transaction| transaction.with_quote(quote.clone()).with_fee(fee)).map_err(PlaceOrderError::TransactionError);
+        let purchase = transaction(trade.buyer, trade.fiat, TransactionType::CryptoBuy, trade.buyer_fee)?;
+        let sale = transaction(trade.seller, trade.amount, TransactionType::CryptoSell, trade.seller_fee)?;
+
+        let money = PlaceOrderError::Money;
+        self.post(date, Some(LedgerRecord::Crypto(sale.clone())), with_fee(vec![
+            JournalLine::debit(Account::UserCrypto { user: trade.seller, crypto }, trade.amount),
+            JournalLine::credit(Account::HouseTrading(crypto), trade.amount),
+            JournalLine::debit(Account::HouseTrading(FIAT), trade.fiat),
+            JournalLine::credit(Account::UserFiat(trade.seller), trade.fiat.restar(trade.seller_fee).map_err(money)?),
+        ], trade.seller_fee)).map_err(money)?;
+        self.post(date, Some(LedgerRecord::Crypto(purchase.clone())), with_fee(vec![
+            JournalLine::debit(Account::UserFiat(trade.buyer), trade.fiat),
+            JournalLine::credit(Account::HouseTrading(FIAT), trade.fiat),
+            JournalLine::debit(Account::HouseTrading(crypto), trade.amount),
+            JournalLine::credit(Account::UserCrypto { user: trade.buyer, crypto }, trade.amount.restar(trade.buyer_fee).map_err(money)?),
+        ], trade.buyer_fee)).map_err(money)?;
+
+        let history = self.crypto_transactions.entry(crypto).or_default();
+        history.push(sale);
+        history.push(purchase);
+
+        self.record_quote(crypto, quote).map_err(PlaceOrderError::Money)
+    }
+
+    // the funds it held are free again
+    fn cancel_order(&mut self, user_id: u32, order_id: u64) -> Result<Order, CancelOrderError> {
+        let Some(book) = self.orders.books.values_mut()
+            .find(|book| book.orders().any(|order| order.id == order_id && order.user == user_id))
+        else { return Err(CancelOrderError::OrderNotFound { order_id }) };
+
+        let Some(order) = book.remove(order_id) else { return Err(CancelOrderError::OrderNotFound { order_id }) };
+        self.sobreescribir_archivo_xyz().map_err(CancelOrderError::File)?;
+        Ok(order)
+    }
+
+    // oldest first
+    fn open_orders(&self, user_id: u32) -> Vec<&Order> {
+        let mut orders: Vec<&Order> = self.orders.orders().filter(|order| order.user == user_id).collect();
+        orders.sort_by_key(|order| order.id);
+        orders
+    }
+
+    // every quote goes to the history, but only the latest becomes the crypto's quote
+    fn record_quote(&mut self, crypto: Moneda, quote: Quote) -> Result<(), ErrorDinero> {
+        self.quote_history.record(crypto, &quote)?;
+        if self.quotes.get(&crypto).is_none_or(|current| current.date <= quote.date) {
+            self.quotes.insert(crypto, quote);
+        }
+        Ok(())
+    }
+
+    // a crypto quoted in another one, like ETH in BTC. Only the latest is kept: the history is in pesos.
+    // a quote in pesos is a regular quote, and one in the crypto itself says nothing
+    fn record_pair_quote(&mut self, crypto: Moneda, quote: Quote) -> Result<(), ErrorDinero> {
+        let quoted_in = quote.buy.moneda();
+        if quote.sell.moneda() != quoted_in {
+            return Err(ErrorDinero::MonedasDistintas { esperada: quoted_in, encontrada: quote.sell.moneda() })
+        }
+        if quoted_in == FIAT { return self.record_quote(crypto, quote) }
+        if quoted_in == crypto { return Ok(()) }
+
+        let quotes = self.pair_quotes.entry(crypto).or_default();
+        match quotes.iter_mut().find(|current| current.buy.moneda() == quoted_in) {
+            Some(current) if current.date <= quote.date => *current = quote,
+            Some(_) => {}
+            None => quotes.push(quote),
+        }
+        Ok(())
+    }
+
+    // takes whatever the feed published up to `until`. Returns how many quotes it took
+    fn update_quotes(&mut self, feed: &mut impl PriceFeed, until: FechaHora) -> Result<usize, UpdateQuotesError> {
+        let quotes = feed.quotes_until(until);
+        let taken = quotes.len();
+        for (crypto, quote) in quotes {
+            self.record_quote(crypto, quote).map_err(UpdateQuotesError::Money)?;
+        }
+
+        self.sobreescribir_archivo_xyz().map_err(UpdateQuotesError::File)?;
+        Ok(taken)
+    }
+
+    //
+    // blockchains
+    // the simulated chains only move when XYZ mines them.
+    //
+
+    // mines a block on every blockchain, dated `date`, and credits the deposits that have their confirmations with it.
+    // the journal dates them now; the ledger keeps the date they were sent. Returns them, in the order they were credited
+    fn tick(&mut self, date: FechaHora) -> Result<Vec<BlockchainTransaction>, TickError> {
+        let mut confirmed = Vec::new();
+        for blockchain in self.blockchains.values_mut() {
+            blockchain.chain.mine(date);
+            confirmed.extend(blockchain.chain.confirmed_deposits());
+        }
+
+        for transaction in &confirmed {
+            self.post(date, Some(LedgerRecord::Blockchain(transaction.clone())), deposit_lines(transaction)).map_err(TickError::Money)?;
+        }
+
+        self.sobreescribir_archivo_xyz().map_err(TickError::File)?;
+        Ok(confirmed)
+    }
+
+    //
+    // clock-stamped operations
+    // same as above, but the date comes from XYZ's clock instead of the caller.
+    // the explicit-date versions are kept for backfilling old operations.
+    //
+
+    fn fiat_deposit_now(&mut self, user_id: u32, amount: Dinero) -> Result<FiatTransaction, FiatDepositError> {
+        self.fiat_deposit(self.clock.ahora(), user_id, amount)
+    }
+
+    fn fiat_withdrawal_now(&mut self, user_id: u32, amount: Dinero, mean: WithdrawalMean) -> Result<FiatTransaction, FiatWithdrawalError> {
+        self.fiat_withdrawal(self.clock.ahora(), user_id, amount, mean)
+    }
+
+    fn crypto_buy_now(&mut self, user_id: u32, fiat_amount: Dinero, crypto: Moneda) -> Result<&CryptoTransaction, BuySellError> {
+        self.crypto_buy(self.clock.ahora(), user_id, fiat_amount, crypto)
+    }
+
+    fn crypto_sell_now(&mut self, user_id: u32, crypto_amount: Dinero) -> Result<&CryptoTransaction, BuySellError> {
+        self.crypto_sell(self.clock.ahora(), user_id, crypto_amount)
+    }
+
+    fn blockchain_deposit_now(&mut self, user_id: u32, amount: Dinero, blockchain: &str) -> Result<BlockchainTransaction, BlockchainDepositError> {
+        self.blockchain_deposit(self.clock.ahora(), user_id, amount, blockchain)
+    }
+
+    fn blockchain_withdrawal_now(&mut self, user_id: u32, crypto_amount: Dinero, blockchain: &str) -> Result<BlockchainTransaction, BlockchainWithdrawalError> {
+        self.blockchain_withdrawal(self.clock.ahora(), user_id, crypto_amount, blockchain)
+    }
+
+    fn transfer_now(&mut self, user_id: u32, recipient: &Recipient, amount: Dinero, memo: Option<&str>) -> Result<InternalTransfer, TransferError> {
+        self.transfer(self.clock.ahora(), user_id, recipient, amount, memo)
+    }
+
+    fn swap_preview_now(&self, user_id: u32, amount: Dinero, to: Moneda) -> Result<SwapPreview, SwapError> {
+        self.swap_preview(self.clock.ahora(), user_id, amount, to)
+    }
+
+    fn swap_now(&mut self, user_id: u32, amount: Dinero, to: Moneda) -> Result<SwapTransaction, SwapError> {
+        self.swap(self.clock.ahora(), user_id, amount, to)
+    }
+
+    fn place_order_now(&mut self, user_id: u32, side: OrderSide, kind: OrderKind, amount: Dinero) -> Result<OrderPlacement, PlaceOrderError> {
+        self.place_order(self.clock.ahora(), user_id, side, kind, amount)
+    }
+
+    fn tick_now(&mut self) -> Result<Vec<BlockchainTransaction>, TickError> {
+        self.tick(self.clock.ahora())
+    }
+
+    fn update_quotes_now(&mut self, feed: &mut impl PriceFeed) -> Result<usize, UpdateQuotesError> {
+        self.update_quotes(feed, self.clock.ahora())
+    }
+
+    // every operation of a user during the given days, oldest first
+    fn statement(&self, user_id: u32, range: &RangoFechas) -> Vec<&LedgerEntry> {
+        self.ledger.query(&LedgerQuery::default().for_user(user_id).during(range))
+    }
+
+    // the user's portfolio as it was at `date`, from what the journal had posted by then.
+    // the quote history keeps no sell prices, so cryptos are valued at the close of their mid price that day
+    fn portfolio(&self, user_id: u32, date: FechaHora, method: CostMethod) -> Result<Portfolio, PortfolioError> {
+        self.portfolio_at(user_id, date, method, |crypto| self.quote_history.close_on(crypto, date.fecha()))
+    }
+
+    // the user's portfolio today, valued at what XYZ would pay for every crypto
+    fn portfolio_now(&self, user_id: u32, method: CostMethod) -> Result<Portfolio, PortfolioError> {
+        self.portfolio_at(user_id, self.clock.ahora(), method, |crypto| self.quotes.get(&crypto).map(|quote| quote.sell))
+    }
+
+    // closed accounts still have a portfolio: everything they had, sold or paid out
+    fn portfolio_at(&self, user_id: u32, date: FechaHora, method: CostMethod, price: impl Fn(Moneda) -> Option<Dinero>) -> Result<Portfolio, PortfolioError> {
+        if !self.users.contains_key(&user_id) && !self.closed_accounts.contains_key(&user_id) {
+            return Err(PortfolioError::UserNotFound { user_id })
+        }
+        self.replay_portfolio(user_id, date, method, price).map_err(PortfolioError::Money)
+    }
+
+    // replays the user's journal lines up to `date`. An entry that gives the user crypto acquires it,
+    // at the pesos it took from them; one that takes crypto away disposes of it. It's a sale if it gave pesos back,
+    // a withdrawal if it left through a blockchain and a swap if it was traded for another crypto with a value.
+    // Anything else only takes its cost away
+    fn replay(&self, user_id: u32, date: FechaHora, method: CostMethod) -> Result<Replay, ErrorDinero> {
+        let mut fiat = Dinero::cero(FIAT);
+        let mut held: BTreeMap<Moneda, Held> = BTreeMap::new();
+        let mut disposals = Vec::new();
+        for entry in self.journal.entries().iter().filter(|entry| entry.date <= date) {
+            let mut fiat_change = Dinero::cero(FIAT);
+            let mut crypto_changes: BTreeMap<Moneda, Dinero> = BTreeMap::new();
+            for line in &entry.lines {
+                match line.account {
+                    Account::UserFiat(user) if user == user_id => fiat_change = line.apply_to(fiat_change)?,
+                    Account::UserCrypto { user, crypto } if user == user_id => {
+                        let change = crypto_changes.entry(crypto).or_insert(Dinero::cero(crypto));
+                        *change = line.apply_to(*change)?;
+                    }
+                    _ => {}
+                }
+            }
+            fiat = fiat.sumar(fiat_change)?;
+
+            let record = entry.ledger_id.and_then(|id| usize::try_from(id).ok())
+                .and_then(|id| self.ledger.entries().get(id)).map(|entry| &entry.record);
+            for (crypto, change) in crypto_changes {
+                let crypto_held = held.entry(crypto)
+                    .or_insert(Held { holding: Dinero::cero(crypto), basis: CostBasis::new(method), realized: Dinero::cero(FIAT) });
+                crypto_held.holding = crypto_held.holding.sumar(change)?;
+
+                if change.es_positivo() {
+                    let cost = match record {
+                        _ if fiat_change.es_negativo() => fiat_change.negar()?,
+                        Some(LedgerRecord::Blockchain(transaction)) => change.por(transaction.quote.sell, Redondeo::HaciaCero)?,
+                        Some(LedgerRecord::Swap(SwapTransaction { value: Some(value), .. })) => *value,
+                        // what it was worth when it was sent. Unquoted ones, from before v13, cost nothing
+                        Some(record @ LedgerRecord::Transfer(_)) => record.fiat_value()?.unwrap_or(Dinero::cero(FIAT)),
+                        _ => Dinero::cero(FIAT),
+                    };
+                    crypto_held.basis.acquire(entry.date, change, cost)?;
+                    continue
+                }
+                if !change.es_negativo() { continue }
+
+                let amount = change.negar()?;
+                let lots = crypto_held.basis.dispose(amount)?;
+                let (kind, proceeds) = match record {
+                    _ if fiat_change.es_positivo() => (DisposalKind::Sale, fiat_change),
+                    Some(LedgerRecord::Blockchain(transaction)) if transaction.data.transaction_type == TransactionType::BlockchainWithdrawal =>
+                        (DisposalKind::Withdrawal, amount.por(transaction.quote.sell, Redondeo::HaciaCero)?),
+                    Some(LedgerRecord::Swap(SwapTransaction { value: Some(value), .. })) => (DisposalKind::Swap, *value),
+                    Some(record @ LedgerRecord::Transfer(_)) => (DisposalKind::Transfer, record.fiat_value()?.unwrap_or(Dinero::cero(FIAT))),
+                    _ => continue,
+                };
+                // what leaves XYZ or goes to someone else isn't sold: nothing is realized
+                if !matches!(kind, DisposalKind::Withdrawal | DisposalKind::Transfer) {
+                    let cost = Dinero::sumar_todos(FIAT, lots.iter().map(|lot| lot.cost))?;
+                    crypto_held.realized = crypto_held.realized.sumar(proceeds.restar(cost)?)?;
+                }
+
+                // the proceeds are shared by amount, and the last lot gets what rounding left
+                let taken = Dinero::sumar_todos(crypto, lots.iter().map(|lot| lot.amount))?;
+                let mut left = proceeds;
+                for (position, lot) in lots.iter().enumerate() {
+                    let lot_proceeds = if position + 1 == lots.len() { left }
+                        else { proceeds.fraccion(lot.amount.unidades(), taken.unidades(), Redondeo::MitadPar)? };
+                    left = left.restar(lot_proceeds)?;
+                    disposals.push(Disposal {
+                        date: entry.date,
+                        crypto,
+                        kind,
+                        amount: lot.amount,
+                        acquired: lot.acquired,
+                        holding_days: lot.acquired.fecha().dias_hasta(&entry.date.fecha()),
+                        cost: lot.cost,
+                        proceeds: lot_proceeds,
+                        gain: lot_proceeds.restar(lot.cost)?,
+                    });
+                }
+            }
+        }
+
+        Ok(Replay { fiat, held, disposals })
+    }
+
+    fn replay_portfolio(&self, user_id: u32, date: FechaHora, method: CostMethod, price: impl Fn(Moneda) -> Option<Dinero>) -> Result<Portfolio, ErrorDinero> {
+        let Replay { fiat, held, .. } = self.replay(user_id, date, method)?;
+
+        let mut positions = Vec::new();
+        for (crypto, Held { holding, basis, realized }) in held {
+            if holding.es_cero() && realized.es_cero() { continue }
+            let price = price(crypto);
+            let value = price.map(|price| holding.por(price, Redondeo::HaciaCero)).transpose()?;
+            let cost_basis = basis.cost()?;
+            let unrealized = value.map(|value| value.restar(cost_basis)).transpose()?;
+            positions.push(Position { crypto, holding, price, value, cost_basis, realized, unrealized, allocation: None });
+        }
+
+        let value = Dinero::sumar_todos(FIAT, positions.iter().filter_map(|position| position.value))?.sumar(fiat)?;
+        for position in &mut positions {
+            position.allocation = position.value.and_then(|part| allocation(part, value));
+        }
+
+        Ok(Portfolio {
+            user_id,
+            date,
+            method,
+            fiat,
+            fiat_allocation: allocation(fiat, value),
+            realized: Dinero::sumar_todos(FIAT, positions.iter().map(|position| position.realized))?,
+            unrealized: Dinero::sumar_todos(FIAT, positions.iter().filter_map(|position| position.unrealized))?,
+            positions,
+            value,
+        })
+    }
+
+    // the year's disposals, and what the user held at the end of Dec 31, valued at that day's close
+    fn tax_report(&self, user_id: u32, year: i64) -> Result<TaxReport, PortfolioError> {
+        let start = FechaHora::from(Fecha { dia: 1, mes: 1, ano: year });
+        let end = FechaHora::from(Fecha { dia: 1, mes: 1, ano: year + 1 }) - Duration::from_millis(1);
+        let holdings = self.portfolio(user_id, end, CostMethod::Fifo)?;
+        let replay = self.replay(user_id, end, CostMethod::Fifo).map_err(PortfolioError::Money)?;
+        let disposals: Vec<Disposal> = replay.disposals.into_iter().filter(|disposal| disposal.date >= start).collect();
+
+        let mut totals: Vec<AssetTotal> = Vec::new();
+        for disposal in &disposals {
+            let total = match totals.binary_search_by(|total| total.crypto.cmp(&disposal.crypto)) {
+                Ok(position) => &mut totals[position],
+                Err(position) => {
+                    let zero = Dinero::cero(FIAT);
+                    totals.insert(position, AssetTotal { crypto: disposal.crypto, amount: Dinero::cero(disposal.crypto), cost: zero, proceeds: zero, gain: zero });
+                    &mut totals[position]
+                }
+            };
+            let add = |total: Dinero, amount: Dinero| total.sumar(amount).map_err(PortfolioError::Money);
+            total.amount = add(total.amount, disposal.amount)?;
+            total.cost = add(total.cost, disposal.cost)?;
+            total.proceeds = add(total.proceeds, disposal.proceeds)?;
+            total.gain = add(total.gain, disposal.gain)?;
+        }
+
+        let pesos = YearEndHolding { currency: FIAT, amount: holdings.fiat, price: None, value: Some(holdings.fiat) };
+        let cryptos = holdings.positions.iter().filter(|position| !position.holding.es_cero())
+            .map(|position| YearEndHolding { currency: position.crypto, amount: position.holding, price: position.price, value: position.value });
+
+        Ok(TaxReport {
+            user_id,
+            year,
+            gain: Dinero::sumar_todos(FIAT, totals.iter().map(|total| total.gain)).map_err(PortfolioError::Money)?,
+            totals,
+            disposals,
+            holdings: std::iter::once(pesos).chain(cryptos).collect(),
+            holdings_value: holdings.value,
+        })
+    }
+
+    // every buy, sell and swap made during `range`, see MarketOperation
+    fn crypto_operations(&self, range: &RangoFechas) -> Result<Vec<MarketOperation>, ErrorDinero> {
+        let mut operations = Vec::new();
+        for entry in self.ledger.query(&LedgerQuery::default().during(range)) {
+            operations.extend(MarketOperation::from_record(&entry.record)?);
+        }
+        Ok(operations)
+    }
+
+    // every crypto bought, sold or swapped during `range`, ranked by `metric`
+    fn market_ranking(&self, range: &RangoFechas, metric: MarketMetric) -> Result<Vec<AssetStats>, ErrorDinero> {
+        market_ranking(self.crypto_operations(range)?, metric)
+    }
+
+    // the same ranking, period by period. A period with no operations has an empty ranking
+    fn market_series(&self, window: &Ventana, metric: MarketMetric) -> Result<Vec<MarketPeriod>, ErrorDinero> {
+        let operations = self.crypto_operations(&window.rango())?.into_iter().map(|operation| (operation.date.fecha(), operation));
+        window.agrupar(operations).into_iter()
+            .map(|(period, operations)| Ok(MarketPeriod { period, ranking: market_ranking(operations, metric)? }))
+            .collect()
+    }
+
     // ➢ Saber cual es la criptomoneda que más cantidad de ventas tiene
-    fn most_times_sold_cryptocurrency(&self) -> Option<(&str, usize)> {
-        let mut telemetry = ("", 0usize);
+    fn most_times_sold_cryptocurrency(&self) -> Option<(Moneda, usize)> {
+        let mut telemetry: Option<(Moneda, usize)> = None;
 
         for (prefix, transactions) in &self.crypto_transactions {
             let mut times_sold = 0usize;
@@ -1093,22 +4472,17 @@ impl XYZ {
                 }
             }
 
-            if times_sold > telemetry.1 {
-                telemetry.0 = prefix.as_str();
-                telemetry.1 = times_sold;
+            if times_sold > telemetry.map_or(0, |(_, most)| most) {
+                telemetry = Some((*prefix, times_sold));
             }
         }
 
-        if telemetry.1 > 0 {
-            Some(telemetry)
-        } else {
-            None
-        }
+        telemetry
     }
 
     // ➢ Saber cual es la criptomoneda que más cantidad de compras tiene
-    fn most_times_bought_cryptocurrency(&self) -> Option<(&str, usize)> {
-        let mut telemetry = ("", 0usize);
+    fn most_times_bought_cryptocurrency(&self) -> Option<(Moneda, usize)> {
+        let mut telemetry: Option<(Moneda, usize)> = None;
 
         for (prefix, transactions) in &self.crypto_transactions {
             let mut times_sold = 0usize;
@@ -1119,69 +4493,50 @@ impl XYZ {
                 }
             }
 
-            if times_sold > telemetry.1 {
-                telemetry.0 = prefix.as_str();
-                telemetry.1 = times_sold;
+            if times_sold > telemetry.map_or(0, |(_, most)| most) {
+                telemetry = Some((*prefix, times_sold));
             }
         }
 
-        if telemetry.1 > 0 {
-            Some(telemetry)
-        } else {
-            None
-        }
+        telemetry
     }
 
     // ➢ Saber cual es la criptomoneda que más volumen de ventas tiene
-    fn highest_sold_volume_cryptocurrency(&self) -> Option<(&str, f64)> {
-        let mut telemetry = ("", 0.0f64);
+    // sells are in each crypto's own units, so volumes of different cryptos are compared as plain numbers
+    fn highest_sold_volume_cryptocurrency(&self) -> Option<(Moneda, Dinero)> {
+        let mut telemetry: Option<(Moneda, Dinero)> = None;
 
         for (prefix, transactions) in &self.crypto_transactions {
-            let mut amount = 0.0f64;
-
-            for transaction in transactions {
-                if transaction.data.transaction_type == TransactionType::CryptoSell {
-                    amount+= transaction.data.amount;
-                }
-            }
+            let sells = transactions.iter()
+                .filter(|transaction| transaction.data.transaction_type == TransactionType::CryptoSell)
+                .map(|transaction| transaction.data.amount);
+            let Ok(amount) = Dinero::sumar_todos(*prefix, sells) else { continue };
 
-            if amount > telemetry.1 {
-                telemetry.0 = prefix.as_str();
-                telemetry.1 = amount;
+            if amount.es_positivo() && telemetry.is_none_or(|(_, highest)| amount.a_f64() > highest.a_f64()) {
+                telemetry = Some((*prefix, amount));
             }
         }
 
-        if telemetry.1 > 0.0 {
-            Some(telemetry)
-        } else {
-            None
-        }
+        telemetry
     }
 
     // ➢ Saber cual es la criptomoneda que más volumen de compras tiene
-    fn highest_buy_volume_cryptocurrency(&self) -> Option<(&str, f64)> {
-        let mut telemetry = ("", 0.0f64);
+    // buys are paid in pesos, so this is the crypto users spent the most pesos on
+    fn highest_buy_volume_cryptocurrency(&self) -> Option<(Moneda, Dinero)> {
+        let mut telemetry: Option<(Moneda, Dinero)> = None;
 
         for (prefix, transactions) in &self.crypto_transactions {
-            let mut amount = 0.0f64;
-
-            for transaction in transactions {
-                if transaction.data.transaction_type == TransactionType::CryptoBuy {
-                    amount+= transaction.data.amount;
-                }
-            }
+            let buys = transactions.iter()
+                .filter(|transaction| transaction.data.transaction_type == TransactionType::CryptoBuy)
+                .map(|transaction| transaction.data.amount);
+            let Ok(amount) = Dinero::sumar_todos(FIAT, buys) else { continue };
 
-            if amount > telemetry.1 {
-                telemetry.0 = prefix.as_str();
-                telemetry.1 = amount;
+            if amount.es_positivo() && telemetry.is_none_or(|(_, highest)| amount > highest) {
+                telemetry = Some((*prefix, amount));
             }
         }
 
-        if telemetry.1 > 0.0 {
-            Some(telemetry)
-        } else {
-            None
-        }
+        telemetry
     }
 
     // Nota: Tanto para comprar. vender, retirar el usuario debe estar validado.
@@ -1191,128 +4546,244 @@ impl XYZ {
 
 #[cfg(test)]
 mod test_xyz {
-    use std::fs;
+    use std::time::Duration;
+    use calendario::{Fecha, RelojFijo};
+    use persistencia::{Almacen, AlmacenMemoria};
     use super::*;
 
-    fn delete_xyz_mock_json() -> bool {
-        fs::remove_file(
-            format!("{BASE_FOLDER}test_xyz.json")
-        ).is_ok()
+    fn ars(pesos: i64) -> Dinero { Dinero::enteros(pesos, Moneda::ARS) }
+    fn btc(bitcoins: i64) -> Dinero { Dinero::enteros(bitcoins, Moneda::BTC) }
+    fn eth(ethers: i64) -> Dinero { Dinero::enteros(ethers, Moneda::ETH) }
+
+    // the XYZ as it was saved in v13: swaps only through pesos
+    fn v13_document(xyz: &XYZ) -> serde_json::Value {
+        let Ok(mut document) = serde_json::to_value(xyz) else { panic!() };
+        let Some(document_object) = document.as_object_mut() else { panic!() };
+        document_object.remove("swap_via");
+        document
+    }
+
+    // the XYZ as it was saved in v12: transfers with no quote
+    fn v12_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v13_document(xyz);
+        let Some(entries) = document["ledger"].as_array_mut() else { panic!() };
+        entries.iter_mut().filter_map(|entry| entry.pointer_mut("/record/Transfer").and_then(|transfer| transfer.as_object_mut()))
+            .for_each(|transfer| { transfer.remove("quote"); });
+        document
+    }
+
+    // the XYZ as it was saved in v11: cryptos only quoted in pesos
+    fn v11_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v12_document(xyz);
+        let Some(document_object) = document.as_object_mut() else { panic!() };
+        document_object.remove("pair_quotes");
+        document
+    }
+
+    // the XYZ as it was saved in v10: no simulated chains
+    fn v10_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v11_document(xyz);
+        let Some(blockchains) = document["blockchains"].as_object_mut() else { panic!() };
+        blockchains.values_mut().filter_map(serde_json::Value::as_object_mut).for_each(|blockchain| { blockchain.remove("chain"); });
+        document
+    }
+
+    // the XYZ as it was saved in v9: no tiers, no AML
+    fn v9_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v10_document(xyz);
+        let Some(document_object) = document.as_object_mut() else { panic!() };
+        document_object.remove("aml");
+        document_object.remove("compliance");
+        let Some(users) = document["users"].as_object_mut() else { panic!() };
+        users.values_mut().filter_map(serde_json::Value::as_object_mut).for_each(|user| { user.remove("tier"); });
+        document
+    }
+
+    // the XYZ as it was saved in v8: no DNIs, no closed accounts, ids given by hand
+    fn v8_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v9_document(xyz);
+        let Some(document_object) = document.as_object_mut() else { panic!() };
+        document_object.remove("next_user_id");
+        document_object.remove("closed_accounts");
+        let Some(users) = document["users"].as_object_mut() else { panic!() };
+        users.values_mut().filter_map(serde_json::Value::as_object_mut).for_each(|user| { user.remove("dni"); });
+        document
+    }
+
+    // the XYZ as it was saved in v7: no KYC
+    fn v7_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v8_document(xyz);
+        let Some(users) = document["users"].as_object_mut() else { panic!() };
+        users.values_mut().filter_map(serde_json::Value::as_object_mut).for_each(|user| { user.remove("kyc"); });
+        document
+    }
+
+    // the XYZ as it was saved in v6: no fees
+    fn v6_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v7_document(xyz);
+        let Some(document_object) = document.as_object_mut() else { panic!() };
+        document_object.remove("fees");
+
+        let Some(history) = document["crypto_transactions"].as_object_mut() else { panic!() };
+        for transaction in history.values_mut().filter_map(serde_json::Value::as_array_mut).flatten() {
+            let Some(transaction) = transaction.as_object_mut() else { panic!() };
+            transaction.remove("fee");
+        }
+        let Some(ledger) = document["ledger"].as_array_mut() else { panic!() };
+        for record in ledger.iter_mut().filter_map(|entry| entry["record"].as_object_mut()) {
+            record.values_mut().filter_map(serde_json::Value::as_object_mut).for_each(|transaction| { transaction.remove("fee"); });
+        }
+
+        document
+    }
+
+    // the XYZ as it was saved in v5: quotes weren't dated or snapshotted
+    fn v5_document(xyz: &XYZ) -> serde_json::Value {
+        let mut document = v6_document(xyz);
+        let Some(document_object) = document.as_object_mut() else { panic!() };
+        document_object.remove("quote_history");
+
+        let Some(quotes) = document["quotes"].as_object_mut() else { panic!() };
+        quotes.values_mut().filter_map(serde_json::Value::as_object_mut).for_each(|quote| { quote.remove("date"); });
+        let Some(history) = document["crypto_transactions"].as_object_mut() else { panic!() };
+        for transaction in history.values_mut().filter_map(serde_json::Value::as_array_mut).flatten() {
+            let Some(transaction) = transaction.as_object_mut() else { panic!() };
+            transaction.remove("quote");
+        }
+        let Some(ledger) = document["ledger"].as_array_mut() else { panic!() };
+        for record in ledger.iter_mut().filter_map(|entry| entry["record"].as_object_mut()) {
+            if let Some(crypto) = record.get_mut("Crypto").and_then(serde_json::Value::as_object_mut) { crypto.remove("quote"); }
+            if let Some(quote) = record.get_mut("Blockchain").and_then(|blockchain| blockchain["quote"].as_object_mut()) { quote.remove("date"); }
+        }
+
+        document
     }
 
-    // fiat_balance: 13548.0
-    // crypto balance: BTC 3.0, ETH 5000.0
+    // the XYZ as it was saved in v3: every amount a number, journal lines a signed amount
+    fn v3_document(xyz: &XYZ) -> serde_json::Value {
+        fn as_numbers(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::String(text) => if let Ok(money) = text.parse::<Dinero>() {
+                    *value = serde_json::Value::from(money.a_f64());
+                },
+                serde_json::Value::Array(values) => values.iter_mut().for_each(as_numbers),
+                serde_json::Value::Object(values) => values.values_mut().for_each(as_numbers),
+                _ => {}
+            }
+        }
+
+        let mut document = v5_document(xyz);
+        let Some(entries) = document["journal"].as_array_mut() else { panic!() };
+        for line in entries.iter_mut().flat_map(|entry| entry["lines"].as_array_mut().into_iter().flatten()) {
+            let Some(line) = line.as_object_mut() else { panic!() };
+            let Ok(amount) = serde_json::from_value::<Dinero>(line["amount"].clone()) else { panic!() };
+            let sign = if line.remove("side") == Some(serde_json::json!("Credit")) { -1.0 } else { 1.0 };
+            line.insert("amount".to_string(), serde_json::Value::from(sign * amount.a_f64()));
+        }
+
+        as_numbers(&mut document);
+        document
+    }
+
+    // what a file from before v6 can tell about the quotes operations were made at
+    fn forget_quotes(xyz: &mut XYZ) {
+        xyz.crypto_transactions.values_mut().flatten().for_each(|transaction| transaction.quote = None);
+        let mut ledger = Ledger::default();
+        for entry in xyz.ledger.entries() {
+            let mut record = entry.record.clone();
+            if let LedgerRecord::Crypto(transaction) = &mut record { transaction.quote = None }
+            ledger.record(record);
+        }
+        xyz.ledger = ledger;
+    }
+
+    // fiat_balance: $13548
+    // crypto balance: BTC 3, ETH 5000
     fn mock_user_0() -> User {
         User {
             first_name: "a".to_string(),
             last_name: "sd".to_string(),
             email: "asd@asd.asd".to_string(),
+            dni: None,
             id: 0,
-            fiat_balance: Balance(13548.0),
+            fiat_balance: ars(13548),
             crypto_balance: HashMap::from([
-                (String::from("BTC"), Balance::from(3.0)),
-                (String::from("ETH"), Balance::from(5000.0))
+                (Moneda::BTC, btc(3)),
+                (Moneda::ETH, eth(5000))
             ]),
+            kyc: verified_kyc(),
+            tier: UserTier::Standard,
+        }
+    }
+
+    // users as a file from before v8 brings them in
+    fn grandfather(xyz: &mut XYZ) {
+        xyz.users.values_mut().for_each(|user| user.kyc = grandfathered());
+    }
+
+    // what users from before KYC was required get
+    fn grandfathered() -> Kyc {
+        Kyc {
+            state: KycState::Verified,
+            documents: Vec::new(),
+            history: vec![KycTransition {
+                date: FechaHora::default(),
+                from: KycState::Unverified,
+                to: KycState::Verified,
+                note: Some("operating before KYC was required".to_string()),
+            }],
         }
     }
 
-    // 3 crypto transactions each for buy/sell: BTC 1, BTC 2, ETH 5000
+    fn verified_kyc() -> Kyc {
+        let mut kyc = Kyc::default();
+        let dni = KycDocument { kind: DocumentKind::Dni, reference: "12345678".to_string(), submitted: FechaHora::default() };
+        let Ok(()) = kyc.submit(FechaHora::default(), vec![dni]) else { panic!("Should be Unverified") };
+        let Ok(()) = kyc.review(FechaHora::default(), KycReview::Approve, "ok") else { panic!("Should be Pending") };
+        kyc
+    }
+
+    // 4 crypto transactions each for buy/sell
+    // sells: BTC 1, BTC 2, ETH 5000, ETH 1000. buys: BTC $1000, BTC $2000, ETH $50000, ETH $10000
     // btc buy: $1000 sell: $900
     // eth buy: $10   sell: $9
     fn mock_test_xyz() -> XYZ {
-        let mut xyz = XYZ::new(Some("test_xyz"));
+        let mut xyz = XYZ::new_with_store(Some("test_xyz"), AlmacenMemoria::new()).expect("Should be Ok");
+
+        // every one made by user 0 at the epoch, and free
+        let transaction = |amount: Dinero, transaction_type, currency| CryptoTransaction {
+            data: CommonTransactionData { date: FechaHora::default(), user: 0, amount, transaction_type },
+            currency,
+            quote: None,
+            fee: ars(0),
+        };
 
-        // 3 crypto transactions each for buy/sell: BTC 1, BTC 2, ETH 5000
         // sell:
-        let trans1_sell = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 1.0,
-                transaction_type: TransactionType::CryptoSell,
-            },
-            currency: "BTC".to_string(),
-        };
-        let trans2_sell = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 2.0,
-                transaction_type: TransactionType::CryptoSell,
-            },
-            currency: "BTC".to_string(),
-        };
-        let trans3_sell = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 5000.0,
-                transaction_type: TransactionType::CryptoSell,
-            },
-            currency: "ETH".to_string(),
-        };
-        let trans4_sell = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 1000.0,
-                transaction_type: TransactionType::CryptoSell,
-            },
-            currency: "ETH".to_string(),
-        };
+        let trans1_sell = transaction(btc(1), TransactionType::CryptoSell, Moneda::BTC);
+        let trans2_sell = transaction(btc(2), TransactionType::CryptoSell, Moneda::BTC);
+        let trans3_sell = transaction(eth(5000), TransactionType::CryptoSell, Moneda::ETH);
+        let trans4_sell = transaction(eth(1000), TransactionType::CryptoSell, Moneda::ETH);
 
         //
         // buy
         //
 
-        let trans1_buy = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 1.0,
-                transaction_type: TransactionType::CryptoBuy,
-            },
-            currency: "BTC".to_string(),
-        };
-        let trans2_buy = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 2.0,
-                transaction_type: TransactionType::CryptoBuy,
-            },
-            currency: "BTC".to_string(),
-        };
-        let trans3_buy = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 5000.0,
-                transaction_type: TransactionType::CryptoBuy,
-            },
-            currency: "ETH".to_string(),
-        };
-        let trans4_buy = CryptoTransaction {
-            data: CommonTransactionData {
-                date: Date::default(),
-                user: 0,
-                amount: 1000.0,
-                transaction_type: TransactionType::CryptoBuy,
-            },
-            currency: "ETH".to_string(),
-        };
+        let trans1_buy = transaction(ars(1000), TransactionType::CryptoBuy, Moneda::BTC);
+        let trans2_buy = transaction(ars(2000), TransactionType::CryptoBuy, Moneda::BTC);
+        let trans3_buy = transaction(ars(50000), TransactionType::CryptoBuy, Moneda::ETH);
+        let trans4_buy = transaction(ars(10000), TransactionType::CryptoBuy, Moneda::ETH);
 
         let trans_vec_btc = vec![trans1_sell, trans2_sell, trans1_buy, trans2_buy];
         let trans_vec_eth = vec![trans3_sell, trans4_sell, trans3_buy, trans4_buy];
-        xyz.crypto_transactions.insert(String::from("BTC"), trans_vec_btc);
-        xyz.crypto_transactions.insert(String::from("ETH"), trans_vec_eth);
+        xyz.crypto_transactions.insert(Moneda::BTC, trans_vec_btc);
+        xyz.crypto_transactions.insert(Moneda::ETH, trans_vec_eth);
 
         //
         // quotes
         //
 
-        xyz.quotes.insert(String::from("BTC"), Quote { buy: 1000.0, sell: 900.0 });
-        xyz.quotes.insert(String::from("ETH"), Quote { buy: 10.0, sell: 9.0 });
+        xyz.quotes.insert(Moneda::BTC, Quote { buy: ars(1000), sell: ars(900), date: FechaHora::default() });
+        xyz.quotes.insert(Moneda::ETH, Quote { buy: ars(10), sell: ars(9), date: FechaHora::default() });
 
         //
         // blockchains
@@ -1322,6 +4793,7 @@ mod test_xyz {
             name: "MARITO".to_string(),
             prefix: "MTO".to_string(),
             supported_cryptos: vec![String::from("BTC"), String::from("LIBRA")],
+            chain: SimulatedChain::default(),
         };
         xyz.blockchains.insert(blockchain1.prefix.to_string(), blockchain1);
 
@@ -1329,7 +4801,7 @@ mod test_xyz {
         // user 0
         //
 
-        xyz.users.insert(0, mock_user_0());
+        let Ok(()) = xyz.add_user(FechaHora::default(), mock_user_0()) else { panic!("Should be a new user") };
 
         xyz
     }
@@ -1339,327 +4811,1657 @@ mod test_xyz {
         let xyz = XYZ {
             file_name: "test_xyz".to_string(),
             users: Users::default(),
+            closed_accounts: BTreeMap::default(),
+            next_user_id: 0,
             blockchains: Blockchains::default(),
             quotes: Quotes::default(),
-            crypto_transactions: CryptoTransactionHistory::default()
+            pair_quotes: PairQuotes::default(),
+            swap_via: vec![FIAT],
+            crypto_transactions: CryptoTransactionHistory::default(),
+            ledger: Ledger::default(),
+            journal: Journal::default(),
+            orders: OrderBooks::default(),
+            quote_history: QuoteHistory::default(),
+            fees: FeeSchedule::default(),
+            aml: AmlRules::default(),
+            compliance: Compliance::default(),
+            clock: RelojCompartido::default(),
+            store: AlmacenMemoria::new().into()
+        };
+
+        assert_eq!(Ok(xyz), XYZ::new_with_store(Some("test_xyz"), AlmacenMemoria::new()));
+        assert!(XYZ::new(Some("test_xyz")).is_ok());
+    }
+
+    #[test]
+    fn test_store() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = XYZ::new_with_store(Some("test_xyz"), store.clone()).expect("Should be Ok");
+        let Ok(()) = xyz.add_user(FechaHora::default(), mock_user_0()) else { panic!("Should be a new user") };
+
+        let Ok(_) = xyz.fiat_deposit(FechaHora::from(Fecha { dia: 1, mes: 1, ano: 2024 }), 0, ars(10)) else { panic!("Should be Ok") };
+        assert_eq!(store.claves(), vec!["test_xyz".to_string()]);
+
+        // a new XYZ on the same store picks up what was saved
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store.clone()).expect("Should load the file");
+        assert_eq!(loaded.users, xyz.users);
+        assert_eq!(loaded.users[&0].fiat_balance, ars(13558));
+
+        // a file from a newer version can't be read: that's an error, not an empty XYZ
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 15, "data": {} })) else { panic!() };
+        let Err(ErrorPersistencia::Version { encontrada: 15, esperada: 14, .. }) = XYZ::new_with_store(Some("test_xyz"), store)
+        else { panic!("Should fail loudly") };
+    }
+
+    #[test]
+    fn test_ledger() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, ..mock_user_0() }) else { panic!("Should be a new user") };
+
+        let day = |dia| FechaHora::from(Fecha { dia, mes: 3, ano: 2024 });
+        let Ok(_) = xyz.fiat_deposit(day(1), 0, ars(100)) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.fiat_withdrawal(day(2), 0, ars(50), WithdrawalMean::MercadoPago) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.crypto_buy(day(3), 0, ars(2000), Moneda::BTC) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.crypto_sell(day(4), 1, eth(100)) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.blockchain_deposit(day(5), 1, btc(1), "MTO") else { panic!("Should be Ok") };
+        let Ok(hash) = xyz.blockchain_withdrawal(day(6), 0, btc(1), "MTO").map(|transaction| transaction.hash) else { panic!("Should be Ok") };
+
+        // failed operations leave no trace
+        let Err(_) = xyz.fiat_withdrawal(day(7), 0, ars(1_000_000_000), WithdrawalMean::BankTansfer) else { panic!("Not enough balance") };
+        let Err(_) = xyz.fiat_deposit(day(7), 5, ars(10)) else { panic!("User doesn't exist") };
+
+        assert_eq!(xyz.ledger.len(), 6);
+        assert!(xyz.ledger.entries().iter().enumerate().all(|(i, entry)| entry.id == i as u64));
+        let Some(LedgerEntry { record: LedgerRecord::Blockchain(withdrawal), .. }) = xyz.ledger.entries().last() else { panic!("Should be the withdrawal") };
+        assert_eq!(withdrawal.hash, hash);
+
+        // queries
+        let ids = |entries: Vec<&LedgerEntry>| entries.iter().map(|entry| entry.id).collect::<Vec<_>>();
+        assert_eq!(ids(xyz.ledger.query(&LedgerQuery::default())), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(ids(xyz.ledger.query(&LedgerQuery::default().for_user(1))), vec![3, 4]);
+        assert_eq!(ids(xyz.ledger.query(&LedgerQuery::default().with_crypto(Moneda::BTC))), vec![2, 4, 5]);
+        assert_eq!(ids(xyz.ledger.query(&LedgerQuery::default().of_type(TransactionType::FiatWithdrawal { mean: WithdrawalMean::BankTansfer }))), vec![1]);
+        assert_eq!(ids(xyz.ledger.query(&LedgerQuery::default().between(day(2), day(4)))), vec![1, 2]);
+        assert_eq!(ids(xyz.ledger.query(&LedgerQuery::default().for_user(0).with_crypto(Moneda::BTC).of_type(TransactionType::BlockchainWithdrawal))), vec![5]);
+
+        let Some(march) = RangoFechas::new(Fecha { dia: 3, mes: 3, ano: 2024 }, Fecha { dia: 6, mes: 3, ano: 2024 }) else { panic!() };
+        assert_eq!(ids(xyz.statement(0, &march)), vec![2, 5]);
+
+        // saved with everything else
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!(loaded.ledger, xyz.ledger);
+    }
+
+    #[test]
+    fn test_ledger_migration() {
+        // a v1 file: no ledger, only crypto_transactions
+        let mut old = mock_test_xyz();
+        old.crypto_transactions.get_mut(&Moneda::BTC).expect("BTC")[0].data.date = FechaHora::from(Fecha { dia: 1, mes: 1, ano: 2020 });
+        let mut v1 = v3_document(&old);
+        let Some(v1_object) = v1.as_object_mut() else { panic!() };
+        v1_object.remove("ledger");
+        v1_object.remove("journal");
+
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 1, "data": v1 })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        // the old buys and sells are the start of the ledger, oldest first
+        assert_eq!(loaded.crypto_transactions, old.crypto_transactions);
+        assert_eq!(loaded.ledger.len(), 8);
+        let Some(LedgerEntry { id: 0, record: LedgerRecord::Crypto(first) }) = loaded.ledger.entries().first() else { panic!("Should be a crypto transaction") };
+        assert_eq!(first, &old.crypto_transactions[&Moneda::BTC][1]);
+        let Some(LedgerEntry { id: 7, record: LedgerRecord::Crypto(last) }) = loaded.ledger.entries().last() else { panic!("Should be a crypto transaction") };
+        assert_eq!(last, &old.crypto_transactions[&Moneda::BTC][0]);
+    }
+
+    #[test]
+    fn test_journal() {
+        let mut xyz = mock_test_xyz();
+        assert_eq!(xyz.add_user(FechaHora::default(), mock_user_0()), Err(AddUserError::UserAlreadyExists { user_id: 0 }));
+
+        // the opening entry
+        assert_eq!(xyz.journal.entries().len(), 1);
+        assert_eq!(xyz.journal.balance(&Account::FiatFloat), Ok(ars(13548)));
+        assert_eq!(xyz.journal.balance(&Account::HotWallet(Moneda::ETH)), Ok(eth(5000)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let Ok(_) = xyz.fiat_deposit(day, 0, ars(100)) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.fiat_withdrawal(day, 0, ars(50), WithdrawalMean::BankTansfer) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.crypto_buy(day, 0, ars(2000), Moneda::BTC) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.crypto_sell(day, 0, btc(1)) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.blockchain_deposit(day, 0, btc(4), "MTO") else { panic!("Should be Ok") };
+        let Ok(_) = xyz.blockchain_withdrawal(day, 0, btc(1), "MTO") else { panic!("Should be Ok") };
+        let Err(_) = xyz.crypto_sell(day, 0, btc(1_000_000)) else { panic!("Not enough balance") };
+
+        // one entry per operation, each tied to its ledger entry, each balanced
+        assert_eq!(xyz.journal.entries().len(), 7);
+        assert!(xyz.journal.entries()[1..].iter().zip(xyz.ledger.entries()).all(|(posted, recorded)| posted.ledger_id == Some(recorded.id)));
+        assert!(xyz.journal.entries().iter().all(|entry| entry.imbalances() == Ok(BTreeMap::new())));
+        assert!(xyz.journal.entries().iter().flat_map(|entry| &entry.lines).all(JournalLine::is_valid));
+
+        // balances are the journal's
+        let user = &xyz.users[&0];
+        assert_eq!(user.fiat_balance, ars(13548 + 100 - 50 - 2000 + 900));
+        assert_eq!(Ok(user.fiat_balance), xyz.journal.balance(&Account::UserFiat(0)));
+        assert_eq!(user.crypto_balance[&Moneda::BTC], btc(3 + 2 - 1 + 4 - 1));
+        assert_eq!(xyz.journal.balance(&Account::HotWallet(Moneda::BTC)), Ok(btc(3 + 4 - 1)));
+        assert_eq!(xyz.journal.balance(&Account::HouseTrading(FIAT)), Ok(ars(2000 - 900)));
+        assert_eq!(xyz.journal.balance(&Account::HouseTrading(Moneda::BTC)), Ok(btc(-2 + 1)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+
+        // a balance changed behind the journal's back
+        xyz.users.get_mut(&0).expect("user 0").fiat_balance = ars(12499);
+        let Err(errors) = xyz.verificar_integridad() else { panic!("Should find the drift") };
+        assert_eq!(errors, vec![IntegrityError::BalanceDrift { account: Account::UserFiat(0), cached: ars(12499), journal: ars(12498) }]);
+
+        // a user removed behind the journal's back, and an unbalanced entry
+        xyz.users.clear();
+        xyz.journal.post(day, None, vec![JournalLine::debit(Account::FiatFloat, ars(10))]);
+        let Err(errors) = xyz.verificar_integridad() else { panic!("Should find both") };
+        assert!(errors.contains(&IntegrityError::UnbalancedEntry { entry: 7, difference: ars(10) }));
+        assert!(errors.contains(&IntegrityError::BalanceDrift { account: Account::UserFiat(0), cached: ars(0), journal: ars(12498) }));
+
+        // a line in the wrong currency: the journal can't be added up anymore
+        xyz.journal.post(day, None, vec![JournalLine::debit(Account::FiatFloat, btc(1)), JournalLine::credit(Account::HotWallet(Moneda::BTC), btc(1))]);
+        let Err(errors) = xyz.verificar_integridad() else { panic!("Should find it") };
+        assert!(errors.contains(&IntegrityError::InvalidLine { entry: 8, line: 0 }));
+        assert!(errors.iter().any(|error| matches!(error, IntegrityError::Money(ErrorDinero::MonedasDistintas { .. }))));
+    }
+
+    #[test]
+    fn test_journal_migration() {
+        // a v2 file: balances, but no journal
+        let mut old = mock_test_xyz();
+        let mut v2 = v3_document(&old);
+        let Some(v2_object) = v2.as_object_mut() else { panic!() };
+        v2_object.remove("journal");
+
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 2, "data": v2 })) else { panic!() };
+        let mut loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        grandfather(&mut old);
+        assert_eq!(loaded.users, old.users);
+        assert_eq!(loaded.journal, old.journal, "Same opening entry as add_user");
+        assert_eq!(loaded.verificar_integridad(), Ok(()));
+
+        let Ok(_) = loaded.fiat_deposit_now(0, ars(10)) else { panic!("Should be Ok") };
+        assert_eq!(loaded.verificar_integridad(), Ok(()));
+    }
+
+    #[test]
+    fn test_money_migration() {
+        // a v3 file: every amount a number
+        let mut old = mock_test_xyz();
+        let Ok(_) = old.crypto_buy(FechaHora::default(), 0, ars(2000), Moneda::BTC) else { panic!("Should be Ok") };
+        let mut v3 = v3_document(&old);
+        v3["quotes"]["ETH"]["buy"] = serde_json::json!(10.125);
+        forget_quotes(&mut old); // the buy's quote isn't in a v3 file
+
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 3, "data": v3.clone() })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store.clone()).expect("Should migrate");
+
+        // nothing lost, and cents are rounded half away from zero
+        grandfather(&mut old);
+        assert_eq!(loaded.users, old.users);
+        assert_eq!(loaded.crypto_transactions, old.crypto_transactions);
+        assert_eq!(loaded.ledger, old.ledger);
+        assert_eq!(loaded.journal, old.journal);
+        assert_eq!(loaded.quotes[&Moneda::ETH].buy, Dinero::new(1013, Moneda::ARS));
+
+        // a balance that doesn't round to what its history does: XYZ covers the difference
+        v3["users"]["0"]["fiat_balance"] = serde_json::json!(11548.006);
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 3, "data": v3 })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.users[&0].fiat_balance, Dinero::new(1_154_801, Moneda::ARS));
+        assert_eq!(loaded.journal.entries().len(), old.journal.entries().len() + 1);
+        let Some(adjustment) = loaded.journal.entries().last() else { panic!() };
+        assert_eq!(adjustment.ledger_id, None);
+        assert_eq!(adjustment.lines, vec![
+            JournalLine::debit(Account::HouseTrading(FIAT), Dinero::new(1, Moneda::ARS)),
+            JournalLine::credit(Account::UserFiat(0), Dinero::new(1, Moneda::ARS)),
+        ]);
+        assert_eq!(loaded.verificar_integridad(), Ok(()));
+    }
+
+    #[test]
+    fn test_registered_currency_migration() {
+        // a v3 file with a crypto other than BTC and ETH: it's read as any other once it's registered
+        let Ok(usdt) = Moneda::registrar("USDT", 6) else { panic!("Should be registered") };
+        let mut old = mock_test_xyz();
+        old.quotes.insert(usdt, Quote { buy: Dinero::new(125, Moneda::ARS), sell: Dinero::new(120, Moneda::ARS), date: FechaHora::default() });
+        let Ok(_) = old.crypto_buy(FechaHora::default(), 0, ars(1000), usdt) else { panic!("Should be Ok") };
+        let mut v3 = v3_document(&old);
+        forget_quotes(&mut old);
+
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 3, "data": v3.clone() })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store.clone()).expect("Should migrate");
+
+        grandfather(&mut old);
+        assert_eq!(loaded.users, old.users);
+        assert_eq!(loaded.users[&0].crypto_balance[&usdt], Dinero::enteros(800, usdt));
+        assert_eq!(loaded.crypto_transactions, old.crypto_transactions);
+        assert_eq!(loaded.ledger, old.ledger);
+        assert_eq!(loaded.journal, old.journal);
+        assert_eq!(loaded.verificar_integridad(), Ok(()));
+
+        // one that isn't: there's no telling how many decimals its amounts had
+        v3["users"]["0"]["crypto_balance"]["DOGE"] = serde_json::json!(5.5);
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 3, "data": v3 })) else { panic!() };
+        let Err(ErrorPersistencia::Migracion { desde: 3, mensaje, .. }) = XYZ::new_with_store(Some("test_xyz"), store) else { panic!("Shouldn't migrate") };
+        assert_eq!(mensaje, "'DOGE' no es una moneda conocida");
+    }
+
+    #[test]
+    fn test_swap_through_stablecoin() {
+        let Ok(usdt) = Moneda::registrar("USDT", 6) else { panic!("Should be registered") };
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let usdts = |amount| Dinero::enteros(amount, usdt);
+
+        // BTC and ETH are quoted in USDT, and it pays better than pesos: 2 BTC are 2000 USDT, that buy 200 ETH
+        let Ok(()) = xyz.record_pair_quote(Moneda::BTC, Quote { buy: usdts(1100), sell: usdts(1000), date: day }) else { panic!() };
+        let Ok(()) = xyz.record_pair_quote(Moneda::ETH, Quote { buy: usdts(10), sell: usdts(9), date: day }) else { panic!() };
+        let Ok(preview) = xyz.swap_preview(day, 0, btc(2), Moneda::ETH) else { panic!("Should have a route") };
+        assert_eq!((preview.route[0].to, preview.received), (Moneda::ARS, eth(180)), "Only through pesos until it's allowed");
+
+        xyz.swap_via.push(usdt);
+        let Ok(swap) = xyz.swap(day, 0, btc(2), Moneda::ETH) else { panic!("Should be a valid swap") };
+        assert_eq!(swap.route, vec![
+            SwapLeg { from: Moneda::BTC, to: usdt, side: OrderSide::Sell, price: usdts(1000) },
+            SwapLeg { from: usdt, to: Moneda::ETH, side: OrderSide::Buy, price: usdts(10) },
+        ]);
+        assert_eq!(swap.received, eth(200));
+        assert_eq!(xyz.user(0).map(|user| user.crypto_balance[&Moneda::ETH]), Some(eth(5200)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!(loaded.swap_via, vec![FIAT, usdt]);
+    }
+
+    #[test]
+    fn test_swap_via_migration() {
+        let old = mock_test_xyz();
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 13, "data": v13_document(&old) })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.swap_via, vec![FIAT]);
+    }
+
+    #[test]
+    fn test_order_book() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, ..mock_user_0() }) else { panic!("Should be a new user") };
+        let half_btc = Dinero::new(50_000_000, Moneda::BTC);
+        let limit = |pesos| OrderKind::Limit { price: ars(pesos) };
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+
+        // two asks: the cheapest goes first
+        let Ok(OrderPlacement { order_id: 0, trades, resting: Some(_) }) = xyz.place_order(day, 0, OrderSide::Sell, limit(1100), btc(1)) else { panic!("Should rest") };
+        assert!(trades.is_empty());
+        let Ok(OrderPlacement { order_id: 1, .. }) = xyz.place_order(day, 0, OrderSide::Sell, limit(1000), btc(1)) else { panic!("Should rest") };
+        let Some(book) = xyz.orders.book(Moneda::BTC) else { panic!("Should have a book") };
+        assert_eq!(book.asks().iter().map(|order| order.id).collect::<Vec<_>>(), vec![1, 0]);
+
+        // what the asks hold can't be spent anywhere else
+        assert_eq!(xyz.blockchain_withdrawal(day, 0, btc(2), "MTO"), Err(BlockchainWithdrawalError::NotEnoughBalance { balance: btc(1), balance_needed: btc(2) }));
+
+        // a bid that crosses the cheapest ask: filled at the ask's price, the rest waits in the book
+        let Ok(placement) = xyz.place_order(day, 1, OrderSide::Buy, limit(1050), Dinero::new(150_000_000, Moneda::BTC)) else { panic!("Should trade") };
+        assert_eq!(placement.trades, vec![Trade { buy_order: 2, sell_order: 1, buyer: 1, seller: 0, price: ars(1000), amount: btc(1), fiat: ars(1000), buyer_fee: btc(0), seller_fee: ars(0) }]);
+        let Some(resting) = placement.resting else { panic!("Should rest") };
+        assert_eq!(resting.remaining, half_btc);
+        assert_eq!(xyz.quotes[&Moneda::BTC], Quote { buy: ars(1000), sell: ars(1000), date: day });
+        assert_eq!((xyz.users[&0].fiat_balance, xyz.users[&0].crypto_balance[&Moneda::BTC]), (ars(14548), btc(2)));
+        assert_eq!((xyz.users[&1].fiat_balance, xyz.users[&1].crypto_balance[&Moneda::BTC]), (ars(12548), btc(4)));
+        assert_eq!(xyz.orders.held(1, FIAT), Ok(ars(525)));
+
+        // same price: the oldest bid is filled first
+        let Ok(_) = xyz.place_order(day, 1, OrderSide::Buy, limit(1050), btc(1)) else { panic!("Should rest") };
+        let Ok(placement) = xyz.place_order(day, 0, OrderSide::Sell, OrderKind::Market, btc(1)) else { panic!("Should trade") };
+        assert_eq!(placement.trades.iter().map(|trade| (trade.buy_order, trade.amount)).collect::<Vec<_>>(), vec![(2, half_btc), (3, half_btc)]);
+        assert_eq!(placement.resting, None);
+        assert_eq!(xyz.open_orders(1).iter().map(|order| (order.id, order.remaining)).collect::<Vec<_>>(), vec![(3, half_btc)]);
+
+        // only its owner can cancel an order
+        assert_eq!(xyz.cancel_order(0, 3), Err(CancelOrderError::OrderNotFound { order_id: 3 }));
+        let Ok(cancelled) = xyz.cancel_order(1, 3) else { panic!("Should be cancelled") };
+        assert_eq!(cancelled.remaining, half_btc);
+        assert_eq!(xyz.orders.held(1, FIAT), Ok(ars(0)));
+
+        // a market buy takes what it can pay for
+        let Ok(_) = xyz.place_order(day, 1, OrderSide::Buy, OrderKind::Market, btc(100)) else { panic!("Should trade") };
+        assert_eq!(xyz.users[&1].crypto_balance[&Moneda::BTC], btc(6));
+        let Ok(_) = xyz.place_order(day, 1, OrderSide::Sell, limit(10000), btc(6)) else { panic!("Should rest") };
+        let Ok(placement) = xyz.place_order(day, 0, OrderSide::Buy, OrderKind::Market, btc(6)) else { panic!("Should trade") };
+        assert_eq!(placement.trades.iter().map(|trade| (trade.amount, trade.fiat)).collect::<Vec<_>>(), vec![(Dinero::new(166_980_000, Moneda::BTC), ars(16698))]);
+        assert_eq!(xyz.users[&0].fiat_balance, ars(0));
+
+        // every trade is a sell and a buy, balanced through XYZ's trading accounts
+        assert_eq!(xyz.ledger.len(), 10);
+        assert_eq!(xyz.crypto_transactions[&Moneda::BTC].len(), 4 + 10);
+        assert_eq!(xyz.journal.balance(&Account::HouseTrading(Moneda::BTC)), Ok(btc(0)));
+        assert_eq!(xyz.journal.balance(&Account::HouseTrading(FIAT)), Ok(ars(0)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+
+        // errors
+        assert_eq!(xyz.place_order(day, 1, OrderSide::Buy, limit(10), ars(1)), Err(PlaceOrderError::InvalidAmount { amount: ars(1) }));
+        assert_eq!(xyz.place_order(day, 1, OrderSide::Buy, OrderKind::Limit { price: btc(1) }, btc(1)), Err(PlaceOrderError::InvalidPrice { price: btc(1) }));
+        assert_eq!(xyz.place_order(day, 5, OrderSide::Buy, limit(10), btc(1)), Err(PlaceOrderError::UserNotFound { user_id: 5 }));
+        assert!(matches!(xyz.place_order(day, 1, OrderSide::Buy, limit(1000), btc(1000)), Err(PlaceOrderError::NotEnoughBalance { .. })));
+
+        // saved with everything else
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!(loaded.orders, xyz.orders);
+    }
+
+    #[test]
+    fn test_self_trade() {
+        let mut xyz = mock_test_xyz();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, ..mock_user_0() }) else { panic!("Should be a new user") };
+        let limit = |pesos| OrderKind::Limit { price: ars(pesos) };
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let Ok(OrderPlacement { order_id: 0, .. }) = xyz.place_order(day, 0, OrderSide::Sell, limit(1000), btc(1)) else { panic!("Should rest") };
+        let Ok(OrderPlacement { order_id: 1, .. }) = xyz.place_order(day, 1, OrderSide::Sell, limit(1100), btc(1)) else { panic!("Should rest") };
+
+        // a bid that would reach the user's own ask isn't placed, not even the part that would trade with others
+        assert_eq!(xyz.place_order(day, 0, OrderSide::Buy, limit(1100), btc(2)), Err(PlaceOrderError::SelfTrade { order_id: 0 }));
+        assert_eq!(xyz.place_order(day, 0, OrderSide::Buy, OrderKind::Market, btc(1)), Err(PlaceOrderError::SelfTrade { order_id: 0 }));
+        assert_eq!(xyz.ledger.len(), 0);
+        assert_eq!(xyz.quotes[&Moneda::BTC], Quote { buy: ars(1000), sell: ars(900), date: FechaHora::default() });
+
+        // one that doesn't cross it rests, and the other user can still trade with it
+        let Ok(OrderPlacement { trades, resting: Some(_), .. }) = xyz.place_order(day, 0, OrderSide::Buy, limit(900), btc(1)) else { panic!("Should rest") };
+        assert!(trades.is_empty());
+        let Ok(OrderPlacement { trades, .. }) = xyz.place_order(day, 1, OrderSide::Buy, limit(1000), btc(1)) else { panic!("Should trade") };
+        assert_eq!(trades.iter().map(|trade| (trade.buyer, trade.seller)).collect::<Vec<_>>(), vec![(1, 0)]);
+
+        // once its own ask is gone, the user's order goes through
+        let Ok(OrderPlacement { trades, .. }) = xyz.place_order(day, 0, OrderSide::Buy, limit(1100), btc(1)) else { panic!("Should trade") };
+        assert_eq!(trades.iter().map(|trade| (trade.buyer, trade.seller)).collect::<Vec<_>>(), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_quote_history() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let day = |dia| FechaHora::from(Fecha { dia, mes: 3, ano: 2024 });
+
+        let csv = "fecha,cripto,compra,venta\n\
+                   # marzo\n\
+                   2024-03-02, BTC, 1100, 1000\n\
+                   \n\
+                   2024-03-01, BTC, 1000, 900\n\
+                   1709294400000, ETH, 10.50, 9.50\n";
+        let Ok(mut feed) = ReplayFeed::from_csv(csv) else { panic!("Should be a valid CSV") };
+
+        // oldest first, and only what was published by then: ETH is published at noon
+        assert_eq!(xyz.update_quotes(&mut feed, day(1)), Ok(1));
+        assert_eq!(xyz.quotes[&Moneda::BTC], Quote { buy: ars(1000), sell: ars(900), date: day(1) });
+        assert_eq!(xyz.update_quotes(&mut feed, day(2)), Ok(2));
+        assert_eq!(xyz.quotes[&Moneda::ETH].buy, Dinero::new(1050, Moneda::ARS));
+        assert_eq!(xyz.update_quotes(&mut feed, day(30)), Ok(0));
+
+        // a late quote goes to the history, but doesn't replace a newer one
+        let Ok(()) = xyz.record_quote(Moneda::BTC, Quote { buy: ars(1300), sell: ars(1300), date: day(1) + Duration::from_hours(20) }) else { panic!() };
+        assert_eq!(xyz.quotes[&Moneda::BTC].date, day(2));
+        assert_eq!(xyz.quote_history.candles(Moneda::BTC), [
+            Candle { day: Fecha { dia: 1, mes: 3, ano: 2024 }, open: ars(950), high: ars(1300), low: ars(950), close: ars(1300) },
+            Candle::new(Fecha { dia: 2, mes: 3, ano: 2024 }, ars(1050)),
+        ]);
+        assert!(xyz.quote_history.candles(Moneda::ARS).is_empty());
+
+        // every buy, sell and trade keeps the quote it was made at
+        let Ok(buy) = xyz.crypto_buy(day(3), 0, ars(2200), Moneda::BTC) else { panic!("Should be Ok") };
+        assert_eq!(buy.quote.as_ref().map(|quote| quote.date), Some(day(2)));
+        let Ok(sell) = xyz.crypto_sell(day(3), 0, eth(10)) else { panic!("Should be Ok") };
+        assert_eq!(sell.quote.as_ref().map(|quote| quote.sell), Some(Dinero::new(950, Moneda::ARS)));
+        let Ok(_) = xyz.place_order(day(4), 0, OrderSide::Sell, OrderKind::Limit { price: ars(1200) }, btc(1)) else { panic!("Should rest") };
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, ..mock_user_0() }) else { panic!("Should be a new user") };
+        let Ok(_) = xyz.place_order(day(5), 1, OrderSide::Buy, OrderKind::Market, btc(1)) else { panic!("Should trade") };
+        let traded = Quote { buy: ars(1200), sell: ars(1200), date: day(5) };
+        assert!(xyz.crypto_transactions[&Moneda::BTC].iter().rev().take(2).all(|transaction| transaction.quote == Some(traded.clone())));
+        assert_eq!(xyz.quotes[&Moneda::BTC], traded);
+        assert_eq!(xyz.quote_history.candles(Moneda::BTC).len(), 3);
+
+        // saved with everything else
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!(loaded.quote_history, xyz.quote_history);
+
+        // broken lines say where they are
+        assert_eq!(ReplayFeed::from_csv("2024-03-01,BTC,1000").map(|_| ()), Err(FeedError::Line { line: 1, error: "expected 4 columns: 2024-03-01,BTC,1000".to_string() }));
+        assert!(matches!(ReplayFeed::from_csv("# precios\n2024-03-01,BTC,mil,900"), Err(FeedError::Line { line: 2, .. })));
+        assert!(matches!(ReplayFeed::from_file("no_existe.csv"), Err(FeedError::Io { .. })));
+    }
+
+    #[test]
+    fn test_random_walk_feed() {
+        let xyz = mock_test_xyz();
+        let start = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let walk = |seed| RandomWalkFeed::new(seed, start, Duration::from_hours(1), &xyz.quotes).quotes_until(start + Duration::from_hours(3));
+
+        // the starting quotes, then a step an hour for each crypto
+        let quotes = walk(42);
+        assert_eq!(quotes.len(), 8);
+        assert_eq!(quotes[0], (Moneda::BTC, Quote { date: start, ..xyz.quotes[&Moneda::BTC].clone() }));
+        assert!(quotes.windows(2).all(|pair| pair[0].1.date <= pair[1].1.date));
+
+        // at most 1% each step
+        let btc_quotes: Vec<&Quote> = quotes.iter().filter(|(crypto, _)| *crypto == Moneda::BTC).map(|(_, quote)| quote).collect();
+        for pair in btc_quotes.windows(2) {
+            let Ok(change) = pair[1].buy.restar(pair[0].buy) else { panic!() };
+            assert!(change.unidades().abs() <= pair[0].buy.unidades() / 100);
+        }
+
+        // the same seed walks the same way
+        assert_eq!(walk(42), quotes);
+        assert_ne!(walk(7), quotes);
+
+        // picks up where it left off
+        let mut feed = RandomWalkFeed::new(42, start, Duration::from_hours(1), &xyz.quotes);
+        assert_eq!(feed.quotes_until(start + Duration::from_hours(1)), quotes[..4]);
+        assert_eq!(feed.quotes_until(start + Duration::from_hours(3)), quotes[4..]);
+        assert!(feed.quotes_until(start).is_empty());
+
+        // no volatility, no change
+        let mut still = RandomWalkFeed::new(42, start, Duration::ZERO, &xyz.quotes).with_volatility(0);
+        let quotes = still.quotes_until(start + Duration::from_millis(2));
+        assert_eq!(quotes.len(), 6);
+        assert!(quotes.iter().all(|(crypto, quote)| quote.buy == xyz.quotes[crypto].buy));
+    }
+
+    #[test]
+    fn test_quote_migration() {
+        // a v5 file: undated quotes and no snapshots
+        let mut old = mock_test_xyz();
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let Ok(_) = old.crypto_buy(day, 0, ars(2000), Moneda::BTC) else { panic!("Should be Ok") };
+        let Ok(_) = old.blockchain_deposit(day, 0, btc(1), "MTO") else { panic!("Should be Ok") };
+
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 5, "data": v5_document(&old) })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.quotes, old.quotes);
+        assert_eq!(loaded.quote_history, QuoteHistory::default());
+        forget_quotes(&mut old);
+        assert_eq!(loaded.crypto_transactions, old.crypto_transactions);
+        let Some(LedgerEntry { record: LedgerRecord::Blockchain(deposit), .. }) = loaded.ledger.entries().last() else { panic!("Should be the deposit") };
+        assert_eq!(deposit.quote.date, day);
+    }
+
+    #[test]
+    fn test_fees() {
+        let mut xyz = mock_test_xyz();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, ..mock_user_0() }) else { panic!("Should be a new user") };
+        let day = |dia, mes| FechaHora::from(Fecha { dia, mes, ano: 2024 });
+
+        // BTC: 0.10% to make and 0.20% to take, free from $5000 a month. ETH is free
+        xyz.fees = FeeSchedule::default()
+            .with_trading(Moneda::BTC, vec![
+                FeeTier { min_volume: ars(5000), fee: TradingFee::Flat(0) },
+                FeeTier { min_volume: ars(0), fee: TradingFee::MakerTaker { maker: 10, taker: 20 } },
+            ])
+            .with_fiat_withdrawal(WithdrawalMean::MercadoPago, ars(100))
+            .with_blockchain_withdrawal("MTO", Dinero::new(10_000, Moneda::BTC));
+        assert_eq!(xyz.fees.trading_rate(Moneda::BTC, ars(4999), Liquidity::Maker), 10);
+        assert_eq!(FeeSchedule::default().with_trading(Moneda::ETH, vec![FeeTier { min_volume: ars(0), fee: TradingFee::Flat(20_000) }])
+            .trading_rate(Moneda::ETH, ars(0), Liquidity::Taker), FULL_RATE);
+
+        // buys pay out of the pesos, sells out of what they get
+        let Ok(buy) = xyz.crypto_buy(day(1, 3), 0, ars(2000), Moneda::BTC).cloned() else { panic!("Should be Ok") };
+        assert_eq!(buy.fee, ars(4));
+        assert_eq!(xyz.users[&0].crypto_balance[&Moneda::BTC], Dinero::new(499_600_000, Moneda::BTC));
+        let Ok(sell) = xyz.crypto_sell(day(1, 3), 0, btc(1)).cloned() else { panic!("Should be Ok") };
+        assert_eq!(sell.fee, Dinero::new(180, Moneda::ARS));
+        let Ok(sell) = xyz.crypto_sell(day(2, 3), 0, eth(10)).cloned() else { panic!("Should be Ok") };
+        assert_eq!(sell.fee, ars(0));
+        assert_eq!(xyz.users[&0].fiat_balance, Dinero::new(1_253_620, Moneda::ARS));
+
+        // withdrawals pay on top of the amount
+        assert_eq!(xyz.fiat_withdrawal(day(2, 3), 0, ars(12_536), WithdrawalMean::MercadoPago),
+            Err(FiatWithdrawalError::NotEnoughBalance { balance: Dinero::new(1_253_620, Moneda::ARS), balance_needed: ars(12_636) }));
+        let Ok(withdrawal) = xyz.fiat_withdrawal(day(2, 3), 0, ars(1000), WithdrawalMean::MercadoPago) else { panic!("Should be Ok") };
+        assert_eq!(withdrawal.fee, ars(100));
+        let Ok(withdrawal) = xyz.fiat_withdrawal(day(2, 3), 0, ars(1000), WithdrawalMean::BankTansfer) else { panic!("Should be Ok") };
+        assert_eq!(withdrawal.fee, ars(0));
+        let Ok(withdrawal) = xyz.blockchain_withdrawal(day(2, 3), 0, btc(1), "MTO") else { panic!("Should be Ok") };
+        assert_eq!(withdrawal.fee, Dinero::new(10_000, Moneda::BTC));
+        assert_eq!(xyz.users[&0].crypto_balance[&Moneda::BTC], Dinero::new(299_590_000, Moneda::BTC));
+
+        // in the book, the resting order makes and the new one takes
+        let Ok(_) = xyz.place_order(day(3, 3), 1, OrderSide::Sell, OrderKind::Limit { price: ars(1000) }, btc(1)) else { panic!("Should rest") };
+        let Ok(placement) = xyz.place_order(day(3, 3), 0, OrderSide::Buy, OrderKind::Market, btc(1)) else { panic!("Should trade") };
+        assert_eq!((placement.trades[0].buyer_fee, placement.trades[0].seller_fee), (Dinero::new(200_000, Moneda::BTC), ars(1)));
+        assert_eq!(xyz.users[&1].fiat_balance, ars(13548 + 999));
+
+        // the tier comes from the last 30 days: $2990 + $1000, then $3000 more, then nothing
+        let Ok(buy) = xyz.crypto_buy(day(4, 3), 0, ars(3000), Moneda::BTC).cloned() else { panic!("Should be Ok") };
+        assert_eq!(buy.fee, ars(6));
+        let Ok(buy) = xyz.crypto_buy(day(5, 3), 0, ars(1000), Moneda::BTC).cloned() else { panic!("Should be Ok") };
+        assert_eq!(buy.fee, ars(0));
+        let Ok(buy) = xyz.crypto_buy(day(10, 4), 0, ars(1000), Moneda::BTC).cloned() else { panic!("Should be Ok") };
+        assert_eq!(buy.fee, ars(2));
+
+        // every fee ends up in XYZ's revenue
+        assert_eq!(xyz.journal.balance(&Account::FeeRevenue(FIAT)), Ok(Dinero::new(11_480, Moneda::ARS)));
+        assert_eq!(xyz.journal.balance(&Account::FeeRevenue(Moneda::BTC)), Ok(Dinero::new(210_000, Moneda::BTC)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+    }
+
+    #[test]
+    fn test_fee_migration() {
+        // a v6 file: no fee schedule, no fees
+        let old = mock_test_xyz();
+        let mut with_history = mock_test_xyz();
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let Ok(_) = with_history.fiat_deposit(day, 0, ars(10)) else { panic!("Should be Ok") };
+        let Ok(_) = with_history.crypto_buy(day, 0, ars(2000), Moneda::BTC) else { panic!("Should be Ok") };
+        let Ok(_) = with_history.blockchain_deposit(day, 0, btc(1), "MTO") else { panic!("Should be Ok") };
+
+        for old in [old, with_history] {
+            let store = AlmacenMemoria::new();
+            let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 6, "data": v6_document(&old) })) else { panic!() };
+            let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+            assert_eq!(loaded.fees, FeeSchedule::default());
+            assert_eq!(loaded.crypto_transactions, old.crypto_transactions);
+            assert_eq!(loaded.ledger, old.ledger);
+        }
+    }
+
+    #[test]
+    fn test_kyc() {
+        let mut xyz = mock_test_xyz();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, kyc: Kyc::default(), ..mock_user_0() }) else { panic!("Should be a new user") };
+        let day = |dia| FechaHora::from(Fecha { dia, mes: 3, ano: 2024 });
+        let not_validated = |state, operation| UsuarioNoValidado { user_id: 1, state, operation };
+
+        // unverified users may only deposit
+        let Ok(_) = xyz.fiat_deposit(day(1), 1, ars(10)) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.blockchain_deposit(day(1), 1, btc(1), "MTO") else { panic!("Should be Ok") };
+        assert_eq!(xyz.fiat_withdrawal(day(1), 1, ars(10), WithdrawalMean::MercadoPago),
+            Err(FiatWithdrawalError::UserNotValidated(not_validated(KycState::Unverified, KycOperation::Withdrawal))));
+        assert_eq!(xyz.blockchain_withdrawal(day(1), 1, btc(1), "MTO"),
+            Err(BlockchainWithdrawalError::UserNotValidated(not_validated(KycState::Unverified, KycOperation::Withdrawal))));
+        assert_eq!(xyz.crypto_buy(day(1), 1, ars(10), Moneda::BTC).cloned(),
+            Err(BuySellError::UserNotValidated(not_validated(KycState::Unverified, KycOperation::Trade))));
+        assert_eq!(xyz.crypto_sell(day(1), 1, btc(1)).cloned(),
+            Err(BuySellError::UserNotValidated(not_validated(KycState::Unverified, KycOperation::Trade))));
+        assert_eq!(xyz.place_order(day(1), 1, OrderSide::Sell, OrderKind::Market, btc(1)),
+            Err(PlaceOrderError::UserNotValidated(not_validated(KycState::Unverified, KycOperation::Trade))));
+
+        // reviews only go one way
+        assert_eq!(xyz.review_kyc(day(2), 1, KycReview::Approve, "?"), Err(KycUpdateError::Kyc(KycError::InvalidTransition { from: KycState::Unverified, to: KycState::Verified })));
+        assert_eq!(xyz.submit_kyc(day(2), 1, Vec::new()), Err(KycUpdateError::Kyc(KycError::NoDocuments)));
+        assert_eq!(xyz.submit_kyc(day(2), 5, Vec::new()), Err(KycUpdateError::UserNotFound { user_id: 5 }));
+        let dni = KycDocument { kind: DocumentKind::Dni, reference: "12345678".to_string(), submitted: day(2) };
+        let Ok(()) = xyz.submit_kyc(day(2), 1, vec![dni.clone()]) else { panic!("Should be Ok") };
+        assert_eq!(xyz.crypto_buy(day(2), 1, ars(10), Moneda::BTC).cloned(),
+            Err(BuySellError::UserNotValidated(not_validated(KycState::Pending, KycOperation::Trade))));
+        let Ok(()) = xyz.review_kyc(day(3), 1, KycReview::Reject, "blurry") else { panic!("Should be Ok") };
+
+        // a rejected user may try again
+        let selfie = KycDocument { kind: DocumentKind::Selfie, reference: "selfie.jpg".to_string(), submitted: day(4) };
+        let Ok(()) = xyz.submit_kyc(day(4), 1, vec![selfie.clone()]) else { panic!("Should be Ok") };
+        let Ok(()) = xyz.review_kyc(day(5), 1, KycReview::Approve, "ok") else { panic!("Should be Ok") };
+        let Ok(_) = xyz.crypto_buy(day(5), 1, ars(10), Moneda::BTC) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.place_order(day(5), 1, OrderSide::Sell, OrderKind::Limit { price: ars(5000) }, btc(1)) else { panic!("Should rest") };
+
+        // a suspended user can't do anything, and their orders are gone
+        let Ok(()) = xyz.review_kyc(day(6), 1, KycReview::Suspend, "fraud?") else { panic!("Should be Ok") };
+        assert!(xyz.open_orders(1).is_empty());
+        assert_eq!(xyz.fiat_deposit(day(6), 1, ars(10)),
+            Err(FiatDepositError::UserNotValidated(not_validated(KycState::Suspended, KycOperation::Deposit))));
+        let Ok(()) = xyz.review_kyc(day(7), 1, KycReview::Reinstate, "no fraud") else { panic!("Should be Ok") };
+        let Ok(_) = xyz.fiat_withdrawal(day(7), 1, ars(10), WithdrawalMean::MercadoPago) else { panic!("Should be Ok") };
+
+        // everything is kept
+        let kyc = &xyz.users[&1].kyc;
+        assert_eq!(kyc.state(), KycState::Verified);
+        assert_eq!(kyc.documents(), [dni, selfie]);
+        assert_eq!(kyc.history().iter().map(|transition| (transition.date, transition.to)).collect::<Vec<_>>(), vec![
+            (day(2), KycState::Pending), (day(3), KycState::Rejected), (day(4), KycState::Pending),
+            (day(5), KycState::Verified), (day(6), KycState::Suspended), (day(7), KycState::Verified),
+        ]);
+        assert_eq!(kyc.history()[1].note.as_deref(), Some("blurry"));
+    }
+
+    #[test]
+    fn test_kyc_migration() {
+        // a v7 file: no KYC. Its users were already operating
+        let old = mock_test_xyz();
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 7, "data": v7_document(&old) })) else { panic!() };
+        let mut loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.users[&0].kyc, grandfathered());
+        let Ok(_) = loaded.crypto_sell(FechaHora::default(), 0, btc(1)) else { panic!("Should be verified") };
+    }
+
+    #[test]
+    fn test_user_lifecycle() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let day = |dia| FechaHora::from(Fecha { dia, mes: 3, ano: 2024 });
+        let new_user = |email: &str, dni: &str| NewUser { first_name: " Ana ".to_string(), last_name: "Gómez".to_string(), email: email.to_string(), dni: dni.to_string() };
+
+        // ids follow the ones already taken
+        assert_eq!(xyz.registrar_usuario(&new_user("Ana@Mail.com", "30.123.456")), Ok(1));
+        let Some(ana) = xyz.user(1) else { panic!("Should be registered") };
+        assert_eq!((ana.first_name.as_str(), ana.email.as_str(), ana.dni.as_deref()), ("Ana", "ana@mail.com", Some("30123456")));
+        assert_eq!(ana.kyc.state(), KycState::Unverified);
+        assert_eq!(ana.fiat_balance, ars(0));
+
+        // checked for format and uniqueness
+        assert_eq!(xyz.registrar_usuario(&new_user("ana@mail", "30123457")), Err(UserDataError::InvalidEmail { email: "ana@mail".to_string() }));
+        assert_eq!(xyz.registrar_usuario(&new_user("a na@mail.com", "30123457")), Err(UserDataError::InvalidEmail { email: "a na@mail.com".to_string() }));
+        assert_eq!(xyz.registrar_usuario(&new_user("ana2@mail.com", "3012345X")), Err(UserDataError::InvalidDni { dni: "3012345X".to_string() }));
+        assert_eq!(xyz.registrar_usuario(&new_user("ANA@mail.com", "30123457")), Err(UserDataError::EmailInUse { email: "ana@mail.com".to_string(), user_id: 1 }));
+        assert_eq!(xyz.registrar_usuario(&new_user("ana2@mail.com", "30123456")), Err(UserDataError::DniInUse { dni: "30123456".to_string(), user_id: 1 }));
+        assert_eq!(xyz.registrar_usuario(&NewUser { first_name: "  ".to_string(), ..new_user("ana2@mail.com", "1234567") }), Err(UserDataError::InvalidName { name: "  ".to_string() }));
+        assert_eq!(xyz.registrar_usuario(&new_user("ana2@mail.com", "1234567")), Ok(2));
+
+        // updates are checked the same way
+        assert_eq!(xyz.actualizar_datos(2, &UserDataUpdate { email: Some("asd@asd.asd".to_string()), ..UserDataUpdate::default() }),
+            Err(UserDataError::EmailInUse { email: "asd@asd.asd".to_string(), user_id: 0 }));
+        assert_eq!(xyz.actualizar_datos(7, &UserDataUpdate::default()), Err(UserDataError::UserNotFound { user_id: 7 }));
+        let Ok(()) = xyz.actualizar_datos(2, &UserDataUpdate { last_name: Some("Pérez".to_string()), email: Some("ana@otro.com.ar".to_string()), ..UserDataUpdate::default() }) else { panic!("Should be Ok") };
+        assert_eq!(xyz.user(2).map(|user| (user.last_name.as_str(), user.email.as_str())), Some(("Pérez", "ana@otro.com.ar")));
+
+        // suspended users can't operate
+        let Ok(()) = xyz.suspender(day(1), 0, "chargeback") else { panic!("Should be Ok") };
+        assert!(matches!(xyz.crypto_sell(day(1), 0, btc(1)), Err(BuySellError::UserNotValidated(_))));
+        let Ok(()) = xyz.review_kyc(day(2), 0, KycReview::Reinstate, "resolved") else { panic!("Should be Ok") };
+
+        // empty accounts close right away, the rest need a payout
+        let Ok(()) = xyz.cerrar_cuenta(day(3), 2, None) else { panic!("Should be empty") };
+        let Ok(_) = xyz.place_order(day(3), 0, OrderSide::Sell, OrderKind::Limit { price: ars(5000) }, btc(1)) else { panic!("Should rest") };
+        assert_eq!(xyz.cerrar_cuenta(day(3), 0, None), Err(CloseAccountError::BalanceNotZero { balances: vec![ars(13548), btc(3), eth(5000)] }));
+
+        // every crypto goes out on a blockchain that supports it: MTO doesn't take ETH. Nothing is paid out until they all can be
+        let mut payout = Payout { mean: WithdrawalMean::BankTansfer, blockchains: BTreeMap::from([(Moneda::BTC, "MTO".to_string())]) };
+        assert_eq!(xyz.cerrar_cuenta(day(3), 0, Some(&payout)), Err(CloseAccountError::NoPayoutBlockchain { crypto: Moneda::ETH }));
+        payout.blockchains.insert(Moneda::ETH, "MTO".to_string());
+        assert_eq!(xyz.cerrar_cuenta(day(3), 0, Some(&payout)),
+            Err(CloseAccountError::TransactionError(ErrorNewTransaction::CryptoNotSupportedByBlockchain { crypto: "ETH".to_string(), blockchain: "MTO".to_string() })));
+        assert_eq!((xyz.open_orders(0).len(), xyz.user(0).map(|user| user.fiat_balance)), (1, Some(ars(13548))));
+
+        xyz.blockchains.insert("ETH".to_string(), Blockchain::new("Ethereum", "ETH", vec!["ETH".to_string()]));
+        payout.blockchains.insert(Moneda::ETH, "ETH".to_string());
+        let Ok(()) = xyz.cerrar_cuenta(day(3), 0, Some(&payout)) else { panic!("Should pay out") };
+        let Some(LedgerEntry { record: LedgerRecord::Blockchain(eth_payout), .. }) = xyz.ledger.entries().last() else { panic!("Should be paid out") };
+        assert_eq!((eth_payout.blockchain.as_str(), eth_payout.data.amount), ("ETH", eth(5000)));
+        assert_eq!(xyz.ledger.query(&LedgerQuery::default().for_user(0)).len(), 3);
+        assert!(xyz.open_orders(0).is_empty());
+        assert_eq!(xyz.journal.balance(&Account::UserFiat(0)), Ok(ars(0)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+
+        // closed accounts can't operate, and their ids, email and DNI aren't given again
+        assert_eq!(xyz.fiat_deposit(day(4), 0, ars(10)), Err(FiatDepositError::UserNotFound { user_id: 0 }));
+        assert_eq!(xyz.cerrar_cuenta(day(4), 0, None), Err(CloseAccountError::UserNotFound { user_id: 0 }));
+        assert_eq!(xyz.add_user(day(4), mock_user_0()), Err(AddUserError::UserAlreadyExists { user_id: 0 }));
+        assert_eq!(xyz.registrar_usuario(&new_user("ana2@mail.com", "1234567")), Ok(3));
+
+        // saved with everything else
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!((loaded.users, loaded.closed_accounts, loaded.next_user_id), (xyz.users, xyz.closed_accounts, 4));
+    }
+
+    #[test]
+    fn test_lifecycle_migration() {
+        // a v8 file: no DNIs, and ids given by hand
+        let mut old = mock_test_xyz();
+        let Ok(()) = old.add_user(FechaHora::default(), User { id: 7, ..mock_user_0() }) else { panic!("Should be a new user") };
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 8, "data": v8_document(&old) })) else { panic!() };
+        let mut loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.users, old.users);
+        assert!(loaded.closed_accounts.is_empty());
+        assert_eq!(loaded.registrar_usuario(&NewUser { first_name: "a".to_string(), last_name: "b".to_string(), email: "c@d.ef".to_string(), dni: "1234567".to_string() }), Ok(8));
+    }
+
+    #[test]
+    fn test_withdrawal_limits() {
+        let mut xyz = mock_test_xyz();
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        xyz.aml = AmlRules::default()
+            .with_limits(UserTier::Standard, WithdrawalLimits { daily: Some(ars(5000)), monthly: Some(ars(8000)) })
+            .with_limits(UserTier::Premium, WithdrawalLimits { daily: Some(ars(20000)), monthly: None });
+
+        // pesos count as they are, crypto at its sell quote: a BTC is 900
+        let Ok(_) = xyz.fiat_withdrawal(day, 0, ars(3000), WithdrawalMean::MercadoPago) else { panic!("Should be under the limits") };
+        let Ok(_) = xyz.blockchain_withdrawal(day + Duration::from_hours(1), 0, btc(2), "MTO") else { panic!("Should be under the limits") };
+        assert_eq!(xyz.fiat_withdrawal(day + Duration::from_hours(2), 0, ars(201), WithdrawalMean::MercadoPago),
+            Err(FiatWithdrawalError::LimitExceeded(LimitExceeded { period: LimitPeriod::Daily, limit: ars(5000), withdrawn: ars(5001) })));
+        assert_eq!(xyz.blockchain_withdrawal(day + Duration::from_hours(2), 0, btc(1), "MTO"),
+            Err(BlockchainWithdrawalError::LimitExceeded(LimitExceeded { period: LimitPeriod::Daily, limit: ars(5000), withdrawn: ars(5700) })));
+
+        // a day later the daily limit is clear, but not the monthly one
+        let next_day = day + Duration::from_hours(26);
+        assert_eq!(xyz.fiat_withdrawal(next_day, 0, ars(3201), WithdrawalMean::BankTansfer),
+            Err(FiatWithdrawalError::LimitExceeded(LimitExceeded { period: LimitPeriod::Monthly, limit: ars(8000), withdrawn: ars(8001) })));
+        let Ok(_) = xyz.fiat_withdrawal(next_day, 0, ars(3200), WithdrawalMean::BankTansfer) else { panic!("Should be on the limit") };
+
+        // other tiers, other limits
+        assert_eq!(xyz.set_tier(7, UserTier::Premium), Err(UserDataError::UserNotFound { user_id: 7 }));
+        let Ok(()) = xyz.set_tier(0, UserTier::Premium) else { panic!("Should be Ok") };
+        let Ok(_) = xyz.fiat_withdrawal(next_day, 0, ars(5000), WithdrawalMean::BankTansfer) else { panic!("Should have no monthly limit") };
+
+        assert_eq!(xyz.user(0).map(|user| user.fiat_balance), Some(ars(2348)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+    }
+
+    #[test]
+    fn test_aml_holds() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let at = |hours| day + Duration::from_hours(hours);
+        xyz.aml = AmlRules::default()
+            .with_velocity(VelocityRule { withdrawals: 2, hours: 24 })
+            .with_structuring(StructuringRule { threshold: ars(1000), margin: ars(100), deposits: 3, hours: 48 })
+            .with_turnaround(TurnaroundRule { hours: 24, rate: 8000 });
+
+        // velocity: the third withdrawal in a day is held
+        let Ok(_) = xyz.fiat_withdrawal(at(0), 0, ars(100), WithdrawalMean::MercadoPago) else { panic!("Should be made") };
+        let Ok(_) = xyz.fiat_withdrawal(at(1), 0, ars(100), WithdrawalMean::MercadoPago) else { panic!("Should be made") };
+        assert_eq!(xyz.fiat_withdrawal(at(2), 0, ars(1000), WithdrawalMean::MercadoPago), Err(FiatWithdrawalError::Held { hold_id: 0 }));
+        assert_eq!(xyz.compliance.alerts()[0].kind, AlertKind::Velocity { withdrawals: 3, hours: 24 });
+
+        // what's held can't be spent, and the next withdrawals wait while the alert is open
+        assert_eq!(xyz.fiat_withdrawal(at(3), 0, ars(12349), WithdrawalMean::MercadoPago),
+            Err(FiatWithdrawalError::NotEnoughBalance { balance: ars(12348), balance_needed: ars(12349) }));
+        assert_eq!(xyz.blockchain_withdrawal(at(3), 0, btc(1), "MTO"), Err(BlockchainWithdrawalError::Held { hold_id: 1 }));
+        assert_eq!(xyz.compliance.holds()[1].alerts, vec![0]);
+        assert_eq!(xyz.journal.balance(&Account::UserFiat(0)), Ok(ars(13348)));
+
+        // approved, it's made; rejected, what it held is back
+        let Ok(()) = xyz.approve_withdrawal(at(4), 0, "known customer") else { panic!("Should be made") };
+        assert_eq!(xyz.user(0).map(|user| user.fiat_balance), Some(ars(12348)));
+        assert_eq!(xyz.compliance.alerts()[0].status, AlertStatus::Dismissed);
+        let Ok(()) = xyz.reject_withdrawal(at(4), 1, "wrong address") else { panic!("Should be released") };
+        let Some(user) = xyz.user(0) else { panic!() };
+        assert_eq!(xyz.available_balance(user, Moneda::BTC), Ok(btc(3)));
+        assert_eq!(xyz.reject_withdrawal(at(4), 1, "again"),
+            Err(ComplianceUpdateError::Review(ReviewError::HoldAlreadyReviewed { hold_id: 1, status: HoldStatus::Rejected })));
+        assert_eq!(xyz.approve_withdrawal(at(4), 7, ""), Err(ComplianceUpdateError::Review(ReviewError::HoldNotFound { hold_id: 7 })));
+
+        // structuring: deposits just under the threshold only raise an alert, but it holds the next withdrawal
+        for (hour, pesos) in [(72, 1000), (73, 950), (74, 901), (75, 999)] {
+            let Ok(_) = xyz.fiat_deposit(at(hour), 0, ars(pesos)) else { panic!("Should be deposited") };
+        }
+        assert_eq!(xyz.compliance.alerts()[1].kind, AlertKind::Structuring { deposits: 3, hours: 48 });
+        assert_eq!(xyz.fiat_withdrawal(at(76), 0, ars(100), WithdrawalMean::MercadoPago), Err(FiatWithdrawalError::Held { hold_id: 2 }));
+        let Ok(()) = xyz.review_alert(at(77), 1, AlertReview::Confirm, "reported") else { panic!("Should be reviewed") };
+        assert_eq!(xyz.review_alert(at(77), 1, AlertReview::Dismiss, ""),
+            Err(ComplianceUpdateError::Review(ReviewError::AlertAlreadyReviewed { alert_id: 1, status: AlertStatus::Confirmed })));
+        let Ok(()) = xyz.reject_withdrawal(at(77), 2, "reported") else { panic!("Should be released") };
+
+        // rapid turnaround: withdrawing most of what was just deposited
+        assert_eq!(xyz.fiat_withdrawal(at(78), 0, ars(3100), WithdrawalMean::BankTansfer), Err(FiatWithdrawalError::Held { hold_id: 3 }));
+        assert_eq!(xyz.compliance.alerts()[2].kind, AlertKind::RapidTurnaround { deposited: ars(3850), withdrawn: ars(3100), hours: 24 });
+
+        // a user with open alerts isn't paid out
+        let payout = Payout { mean: WithdrawalMean::BankTansfer, blockchains: BTreeMap::from([(Moneda::BTC, "MTO".to_string()), (Moneda::ETH, "MTO".to_string())]) };
+        assert_eq!(xyz.cerrar_cuenta(at(79), 0, Some(&payout)), Err(CloseAccountError::OpenAlerts { alerts: vec![2] }));
+
+        // saved with everything else
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!((loaded.aml, loaded.compliance), (xyz.aml, xyz.compliance));
+    }
+
+    #[test]
+    fn test_aml_migration() {
+        let old = mock_test_xyz();
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 9, "data": v9_document(&old) })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.users, old.users);
+        assert_eq!(loaded.aml, AmlRules::default());
+        assert_eq!(loaded.compliance, Compliance::default());
+
+        // only the migration gives a tier: a file as it's saved now can't be missing one
+        let Ok(mut current) = serde_json::to_value(&old) else { panic!() };
+        let Some(user) = current.pointer_mut("/users/0").and_then(serde_json::Value::as_object_mut) else { panic!() };
+        user.remove("tier");
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 14, "data": current })) else { panic!() };
+        assert!(XYZ::new_with_store(Some("test_xyz"), store).is_err());
+    }
+
+    #[test]
+    fn test_simulated_chain() {
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let at = |hours| day + Duration::from_hours(hours);
+        let simulate = || {
+            let mut xyz = mock_test_xyz();
+            xyz.blockchains.insert("CNF".to_string(), Blockchain::new("CONFIRMED", "CNF", vec!["BTC".to_string()]).with_confirmations(2));
+            let Ok(withdrawal) = xyz.blockchain_withdrawal(at(0), 0, btc(1), "MTO") else { panic!("Should be sent") };
+            let Ok(deposit) = xyz.blockchain_deposit(at(0), 0, btc(2), "CNF") else { panic!("Should be sent") };
+            (xyz, withdrawal, deposit)
         };
+        let store = AlmacenMemoria::new();
+        let (mut xyz, withdrawal, deposit) = simulate();
+        xyz.store = store.clone().into();
+        let btc_balance = |xyz: &XYZ| xyz.user(0).map(|user| user.crypto_balance[&Moneda::BTC]);
+
+        // withdrawals are debited right away and wait in the mempool
+        assert_eq!(btc_balance(&xyz), Some(btc(2)));
+        assert_eq!(xyz.blockchains["MTO"].chain.mempool(), std::slice::from_ref(&withdrawal));
+        assert_eq!(xyz.blockchains["MTO"].chain.confirmations(&withdrawal.hash), Some(0));
+        assert_eq!(xyz.ledger.entries().last().map(|entry| &entry.record), Some(&LedgerRecord::Blockchain(withdrawal.clone())));
+        assert!(withdrawal.hash.as_str().starts_with("MTO-"));
+
+        // deposits on a chain that asks for confirmations are credited once they have them
+        assert_eq!(xyz.blockchains["CNF"].chain.unconfirmed_deposits(), std::slice::from_ref(&deposit.hash));
+        assert_eq!(xyz.tick(at(1)), Ok(vec![]));
+        assert_eq!(xyz.blockchains["MTO"].chain.confirmations(&withdrawal.hash), Some(1));
+        assert_eq!(xyz.blockchains["CNF"].chain.confirmations(&deposit.hash), Some(1));
+        assert_eq!(btc_balance(&xyz), Some(btc(2)));
+        assert_eq!(xyz.tick(at(2)), Ok(vec![deposit.clone()]));
+        assert_eq!(btc_balance(&xyz), Some(btc(4)));
+        assert_eq!(xyz.tick(at(3)), Ok(vec![]));
+        assert!(xyz.blockchains["CNF"].chain.unconfirmed_deposits().is_empty());
+
+        // every block points to the one before it
+        let blocks = xyz.blockchains["CNF"].chain.blocks();
+        assert_eq!(blocks.iter().map(|block| block.height).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(blocks[0].previous, None);
+        assert_eq!(blocks[2].previous.as_ref(), Some(&blocks[1].hash));
+        assert_eq!(blocks[0].transactions, vec![deposit]);
+
+        // nothing is random: the same operations make the same chains
+        let (mut again, _, _) = simulate();
+        for hour in 1..=3 { let Ok(_) = again.tick(at(hour)) else { panic!("Should be mined") }; }
+        assert_eq!(again.blockchains, xyz.blockchains);
+
+        // an account with deposits on their way can't be closed
+        let Ok(pending) = xyz.blockchain_deposit(at(4), 0, btc(1), "CNF") else { panic!("Should be sent") };
+        assert_eq!(xyz.cerrar_cuenta(at(4), 0, None), Err(CloseAccountError::DepositsUnconfirmed { deposits: vec![pending.hash] }));
+
+        // saved with everything else
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!(loaded.blockchains, xyz.blockchains);
+    }
+
+    #[test]
+    fn test_chain_migration() {
+        let old = mock_test_xyz();
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 10, "data": v10_document(&old) })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.blockchains, old.blockchains);
+        assert_eq!(loaded.blockchains["MTO"].chain, SimulatedChain::default());
+    }
+
+    #[test]
+    fn test_portfolio() {
+        let clock = RelojFijo::from(Fecha { dia: 4, mes: 3, ano: 2024 });
+        let mut xyz = mock_test_xyz().with_clock(clock);
+        let day = |dia| FechaHora::from(Fecha { dia, mes: 3, ano: 2024 });
+
+        // 3 BTC from the opening balances, 2 bought at $1000 and 2 at $2000, then 4 sold at $1800
+        let Ok(()) = xyz.record_quote(Moneda::BTC, Quote { buy: ars(1000), sell: ars(900), date: day(1) }) else { panic!() };
+        let Ok(()) = xyz.record_quote(Moneda::ETH, Quote { buy: ars(10), sell: ars(9), date: day(1) }) else { panic!() };
+        let Ok(_) = xyz.crypto_buy(day(1), 0, ars(2000), Moneda::BTC) else { panic!("Should be a valid buy") };
+        let Ok(()) = xyz.record_quote(Moneda::BTC, Quote { buy: ars(2000), sell: ars(1800), date: day(2) }) else { panic!() };
+        let Ok(_) = xyz.crypto_buy(day(2), 0, ars(4000), Moneda::BTC) else { panic!("Should be a valid buy") };
+        let Ok(_) = xyz.crypto_sell(day(3), 0, btc(4)) else { panic!("Should be a valid sell") };
+
+        // first in, first out: the 3 that cost nothing and 1 of the $1000 ones were sold
+        let Ok(fifo) = xyz.portfolio_now(0, CostMethod::Fifo) else { panic!("Should be Ok") };
+        assert_eq!(fifo.positions[0], Position {
+            crypto: Moneda::BTC,
+            holding: btc(3),
+            price: Some(ars(1800)),
+            value: Some(ars(5400)),
+            cost_basis: ars(5000),
+            realized: ars(6200),
+            unrealized: Some(ars(400)),
+            allocation: Some(829),
+        });
+        assert_eq!(fifo.positions[1].unrealized, Some(ars(45000)));
+        assert_eq!((fifo.fiat, fifo.value, fifo.realized, fifo.unrealized), (ars(14748), ars(65148), ars(6200), ars(45400)));
+        let allocations: Vec<u32> = fifo.positions.iter().filter_map(|position| position.allocation).chain(fifo.fiat_allocation).collect();
+        assert_eq!(allocations, vec![829, 6907, 2264]);
+        assert_eq!(allocations.iter().sum::<u32>(), FULL_RATE);
+
+        // averaged, every BTC cost $6000 / 7. What's left keeps the rest of the cost
+        let Ok(average) = xyz.portfolio_now(0, CostMethod::WeightedAverage) else { panic!("Should be Ok") };
+        assert_eq!(average.positions[0].cost_basis, Dinero::new(257_143, Moneda::ARS));
+        assert_eq!(average.positions[0].realized, Dinero::new(377_143, Moneda::ARS));
+        assert_eq!(average.positions[0].unrealized, Some(Dinero::new(282_857, Moneda::ARS)));
+        assert_eq!(average.value, fifo.value);
+
+        // as it was at the end of the first day, at that day's close
+        let Ok(then) = xyz.portfolio(0, day(1) + Duration::from_hours(23), CostMethod::Fifo) else { panic!("Should be Ok") };
+        assert_eq!(then.positions[0].holding, btc(5));
+        assert_eq!(then.positions[0].price, Some(ars(950)));
+        assert_eq!(then.positions[0].unrealized, Some(ars(2750)));
+        assert_eq!(then.positions[1].value, Some(ars(47500)));
+        assert_eq!((then.fiat, then.value, then.realized), (ars(11548), ars(63798), ars(0)));
+
+        // nothing quoted before the first day
+        let Ok(opening) = xyz.portfolio(0, FechaHora::default(), CostMethod::Fifo) else { panic!("Should be Ok") };
+        assert!(opening.positions.iter().all(|position| position.value.is_none() && position.allocation.is_none()));
+        assert_eq!(opening.fiat_allocation, Some(FULL_RATE));
+
+        assert_eq!(xyz.portfolio_now(9, CostMethod::Fifo), Err(PortfolioError::UserNotFound { user_id: 9 }));
+    }
+
+    #[test]
+    fn test_tax_report() {
+        let mut xyz = mock_test_xyz();
+        let day = |dia, mes, ano| FechaHora::from(Fecha { dia, mes, ano });
+
+        // 3 BTC from the opening balances and 2 bought in March: 4 sold, 1 withdrawn
+        let Ok(()) = xyz.record_quote(Moneda::BTC, Quote { buy: ars(1000), sell: ars(900), date: day(1, 3, 2024) }) else { panic!() };
+        let Ok(()) = xyz.record_quote(Moneda::ETH, Quote { buy: ars(10), sell: ars(9), date: day(1, 3, 2024) }) else { panic!() };
+        let Ok(_) = xyz.crypto_buy(day(1, 3, 2024), 0, ars(2000), Moneda::BTC) else { panic!("Should be a valid buy") };
+        let Ok(()) = xyz.record_quote(Moneda::BTC, Quote { buy: ars(2000), sell: ars(1800), date: day(2, 3, 2024) }) else { panic!() };
+        let Ok(_) = xyz.crypto_sell(day(3, 3, 2024), 0, btc(4)) else { panic!("Should be a valid sell") };
+        let Ok(_) = xyz.blockchain_withdrawal(day(4, 3, 2024), 0, btc(1), "MTO") else { panic!("Should be a valid withdrawal") };
+        let Ok(_) = xyz.crypto_sell(day(10, 1, 2025), 0, eth(1000)) else { panic!("Should be a valid sell") };
+
+        let Ok(report) = xyz.tax_report(0, 2024) else { panic!("Should be Ok") };
+
+        // the sale took from two lots, so it's two disposals, and the proceeds are split between them
+        let opening_days = Fecha { dia: 1, mes: 1, ano: 1970 }.dias_hasta(&Fecha { dia: 3, mes: 3, ano: 2024 });
+        assert_eq!(report.disposals, vec![
+            Disposal { date: day(3, 3, 2024), crypto: Moneda::BTC, kind: DisposalKind::Sale, amount: btc(3), acquired: FechaHora::default(),
+                       holding_days: opening_days, cost: ars(0), proceeds: ars(5400), gain: ars(5400) },
+            Disposal { date: day(3, 3, 2024), crypto: Moneda::BTC, kind: DisposalKind::Sale, amount: btc(1), acquired: day(1, 3, 2024),
+                       holding_days: 2, cost: ars(1000), proceeds: ars(1800), gain: ars(800) },
+            Disposal { date: day(4, 3, 2024), crypto: Moneda::BTC, kind: DisposalKind::Withdrawal, amount: btc(1), acquired: day(1, 3, 2024),
+                       holding_days: 3, cost: ars(1000), proceeds: ars(1800), gain: ars(800) },
+        ]);
+        assert_eq!(report.totals, vec![AssetTotal { crypto: Moneda::BTC, amount: btc(5), cost: ars(2000), proceeds: ars(9000), gain: ars(7000) }]);
+        assert_eq!(report.gain, ars(7000));
+
+        // on Dec 31 there was no BTC left, and ETH closed at its mid price
+        assert_eq!(report.holdings, vec![
+            YearEndHolding { currency: Moneda::ARS, amount: ars(18748), price: None, value: Some(ars(18748)) },
+            YearEndHolding { currency: Moneda::ETH, amount: eth(5000), price: Some(Dinero::new(950, Moneda::ARS)), value: Some(ars(47500)) },
+        ]);
+        assert_eq!(report.holdings_value, ars(66248));
+
+        // the next year only has the ETH sale
+        let Ok(next) = xyz.tax_report(0, 2025) else { panic!("Should be Ok") };
+        assert_eq!(next.totals.iter().map(|total| total.crypto).collect::<Vec<_>>(), vec![Moneda::ETH]);
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("# operaciones del 2024\nfecha,cripto,tipo,cantidad,adquirida,dias,costo,ingreso,ganancia\n"));
+        assert!(csv.contains("\n2024-03-04,BTC,retiro,1.00000000,2024-03-01,3,1000.00,1800.00,800.00\n"));
+        assert!(csv.contains("\nBTC,5.00000000,2000.00,9000.00,7000.00\ntotal,,,,7000.00\n"));
+        assert!(csv.ends_with("# bienes personales al 2024-12-31\nmoneda,cantidad,cotizacion,valor\nARS,18748.00,,18748.00\nETH,5000.000000000000000000,9.50,47500.00\ntotal,,,66248.00\n"));
+
+        let Ok(json) = report.to_json() else { panic!("Should serialize") };
+        assert_eq!(serde_json::from_str::<TaxReport>(&json).ok(), Some(report));
+        assert_eq!(xyz.tax_report(9, 2024).map(|_| ()), Err(PortfolioError::UserNotFound { user_id: 9 }));
+    }
+
+    #[test]
+    fn test_market_analytics() {
+        let mut xyz = mock_test_xyz();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, ..mock_user_0() }) else { panic!("Should be a new user") };
+        let day = |dia| FechaHora::from(Fecha { dia, mes: 3, ano: 2024 });
+        let range = |inicio, fin| RangoFechas::new(Fecha { dia: inicio, mes: 3, ano: 2024 }, Fecha { dia: fin, mes: 3, ano: 2024 }).expect("Should be a range");
+
+        let Ok(_) = xyz.crypto_buy(day(1), 0, ars(2000), Moneda::BTC) else { panic!("Should be a valid buy") };
+        let Ok(_) = xyz.crypto_buy(day(2), 0, ars(100), Moneda::ETH) else { panic!("Should be a valid buy") };
+        let Ok(_) = xyz.crypto_buy(day(2), 1, ars(300), Moneda::ETH) else { panic!("Should be a valid buy") };
+        let Ok(_) = xyz.crypto_sell(day(8), 1, eth(100)) else { panic!("Should be a valid sell") };
+        let Ok(_) = xyz.crypto_sell(day(8), 0, btc(1)) else { panic!("Should be a valid sell") };
+
+        // sells are valued at their quote, so BTC and ETH volumes can be compared
+        let Ok(by_trades) = xyz.market_ranking(&range(1, 31), MarketMetric::Trades) else { panic!("Should be Ok") };
+        assert_eq!(by_trades, vec![
+            AssetStats { crypto: Moneda::ETH, buys: 2, sells: 1, bought: ars(400), sold: ars(900), volume: ars(1300), traders: 2 },
+            AssetStats { crypto: Moneda::BTC, buys: 1, sells: 1, bought: ars(2000), sold: ars(900), volume: ars(2900), traders: 1 },
+        ]);
+        let ranked = |metric| xyz.market_ranking(&range(1, 31), metric).map(|ranking| ranking.iter().map(|asset| asset.crypto).collect::<Vec<_>>());
+        assert_eq!(ranked(MarketMetric::Volume), Ok(vec![Moneda::BTC, Moneda::ETH]));
+        assert_eq!(ranked(MarketMetric::Traders), Ok(vec![Moneda::ETH, Moneda::BTC]));
+        assert_eq!(xyz.market_ranking(&range(3, 7), MarketMetric::Volume), Ok(vec![]));
+
+        // week by week. The second week is a tie, so it goes by crypto
+        let Ok(weeks) = xyz.market_series(&Ventana::semanal(range(1, 21)), MarketMetric::Volume) else { panic!("Should be Ok") };
+        assert_eq!(weeks.iter().map(|week| week.period).collect::<Vec<_>>(), vec![range(1, 7), range(8, 14), range(15, 21)]);
+        assert_eq!(weeks[0].ranking.iter().map(|asset| (asset.crypto, asset.volume)).collect::<Vec<_>>(), vec![(Moneda::BTC, ars(2000)), (Moneda::ETH, ars(400))]);
+        assert_eq!(weeks[1].ranking.iter().map(|asset| (asset.crypto, asset.sells)).collect::<Vec<_>>(), vec![(Moneda::BTC, 1), (Moneda::ETH, 1)]);
+        assert!(weeks[2].ranking.is_empty());
+
+        let Ok(days) = xyz.market_series(&Ventana::diaria(range(1, 2)), MarketMetric::Trades) else { panic!("Should be Ok") };
+        assert_eq!(days.iter().map(|day| day.ranking.iter().map(AssetStats::trades).sum::<usize>()).collect::<Vec<_>>(), vec![1, 2]);
+
+        // a swap sells one crypto and buys the other, both at what the swap was worth. Pesos aren't ranked
+        let Ok(swap) = xyz.swap(day(22), 1, eth(100), Moneda::BTC) else { panic!("Should be a valid swap") };
+        let Ok(_) = xyz.swap(day(23), 0, ars(1000), Moneda::BTC) else { panic!("Should be a valid swap") };
+        assert_eq!(swap.value, Some(ars(900)));
+        assert_eq!(xyz.market_ranking(&range(22, 28), MarketMetric::Trades), Ok(vec![
+            AssetStats { crypto: Moneda::BTC, buys: 2, sells: 0, bought: ars(1900), sold: ars(0), volume: ars(1900), traders: 2 },
+            AssetStats { crypto: Moneda::ETH, buys: 0, sells: 1, bought: ars(0), sold: ars(900), volume: ars(900), traders: 1 },
+        ]));
+    }
+
+    #[test]
+    fn test_transfer_cost_basis() {
+        let mut xyz = mock_test_xyz();
+        let day = |dia| FechaHora::from(Fecha { dia, mes: 3, ano: 2024 });
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, email: "otro@mail.com".to_string(), crypto_balance: HashMap::new(), ..mock_user_0() }) else { panic!("Should be a new user") };
+        let Ok(()) = xyz.record_quote(Moneda::BTC, Quote { buy: ars(1000), sell: ars(900), date: day(1) }) else { panic!() };
+        let Ok(_) = xyz.crypto_buy(day(1), 0, ars(2000), Moneda::BTC) else { panic!("Should be a valid buy") };
+        let Ok(_) = xyz.transfer(day(2), 0, &Recipient::Id(1), btc(1), None) else { panic!("Should be sent") };
+        let Ok(_) = xyz.crypto_sell(day(3), 1, btc(1)) else { panic!("Should be a valid sell") };
+
+        // the sender gave it away at what it was worth, without selling it
+        let Ok(sender) = xyz.tax_report(0, 2024) else { panic!("Should be Ok") };
+        assert_eq!(sender.disposals.iter().map(|disposal| (disposal.kind, disposal.proceeds)).collect::<Vec<_>>(), vec![(DisposalKind::Transfer, ars(900))]);
+        let Ok(portfolio) = xyz.portfolio(0, day(3), CostMethod::Fifo) else { panic!("Should be Ok") };
+        assert_eq!(portfolio.realized, ars(0));
+
+        // and the recipient got it at that, so selling it at the same price gains nothing
+        let Ok(recipient) = xyz.tax_report(1, 2024) else { panic!("Should be Ok") };
+        assert_eq!(recipient.disposals.iter().map(|disposal| (disposal.kind, disposal.cost, disposal.gain)).collect::<Vec<_>>(), vec![(DisposalKind::Sale, ars(900), ars(0))]);
+    }
+
+    #[test]
+    fn test_internal_transfer() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, email: "Otro@Mail.com".to_string(), ..mock_user_0() }) else { panic!("Should be a new user") };
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let balances = |xyz: &XYZ, user_id| xyz.user(user_id).map(|user| (user.fiat_balance, user.crypto_balance[&Moneda::BTC]));
+
+        // by id or by email, pesos or crypto
+        let Ok(pesos) = xyz.transfer(day, 0, &Recipient::Id(1), ars(500), Some("la cena")) else { panic!("Should be a valid transfer") };
+        let Ok(bitcoins) = xyz.transfer(day, 0, &Recipient::Email("otro@mail.com".to_string()), btc(1), None) else { panic!("Should be a valid transfer") };
+        assert_eq!(pesos.memo.as_deref(), Some("la cena"));
+        assert_eq!(pesos.data.transaction_type, TransactionType::InternalTransfer);
+        assert_eq!(bitcoins, InternalTransfer { data: bitcoins.data, recipient: 1, memo: None, quote: Some(xyz.quotes[&Moneda::BTC].clone()) });
+        assert_eq!(balances(&xyz, 0), Some((ars(13048), btc(2))));
+        assert_eq!(balances(&xyz, 1), Some((ars(14048), btc(4))));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+
+        // it's in both users' histories
+        let march = RangoFechas::dia(day.fecha()).expect("Should be a range");
+        assert_eq!(xyz.statement(0, &march).len(), 2);
+        assert_eq!(xyz.statement(1, &march).len(), 2);
+        assert_eq!(xyz.ledger.query(&LedgerQuery::default().for_user(1).with_crypto(Moneda::BTC)).len(), 1);
+
+        // the same checks as any other operation, and nothing moves when one fails
+        assert_eq!(xyz.transfer(day, 0, &Recipient::Id(0), ars(1), None), Err(TransferError::SameUser { user_id: 0 }));
+        assert_eq!(xyz.transfer(day, 0, &Recipient::Id(1), btc(3), None), Err(TransferError::NotEnoughBalance { balance: btc(2), balance_needed: btc(3) }));
+        assert_eq!(xyz.transfer(day, 0, &Recipient::Id(1), ars(0), None), Err(TransferError::TransactionError(ErrorNewTransaction::InvalidInputAmount { amount: ars(0) })));
+        assert_eq!(xyz.transfer(day, 9, &Recipient::Id(1), ars(1), None), Err(TransferError::UserNotFound { user_id: 9 }));
+        let nobody = Recipient::Email("nadie@mail.com".to_string());
+        assert_eq!(xyz.transfer(day, 0, &nobody, ars(1), None), Err(TransferError::RecipientNotFound { recipient: nobody }));
+
+        let Ok(()) = xyz.suspender(day, 1, "revisión") else { panic!("Should be suspended") };
+        assert!(matches!(xyz.transfer(day, 0, &Recipient::Id(1), ars(1), None), Err(TransferError::RecipientNotValidated(_))));
+        assert!(matches!(xyz.transfer(day, 1, &Recipient::Id(0), ars(1), None), Err(TransferError::UserNotValidated(_))));
+        assert_eq!(balances(&xyz, 0), Some((ars(13048), btc(2))));
+
+        // saved with everything else
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!(loaded.ledger, xyz.ledger);
+        assert_eq!(loaded.user(1).map(|user| user.fiat_balance), Some(ars(14048)));
+    }
+
+    #[test]
+    fn test_swap() {
+        let store = AlmacenMemoria::new();
+        let mut xyz = mock_test_xyz();
+        xyz.store = store.clone().into();
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let one_percent = vec![FeeTier { min_volume: ars(0), fee: TradingFee::Flat(100) }];
+        xyz.fees = FeeSchedule::default().with_trading(Moneda::BTC, one_percent.clone()).with_trading(Moneda::ETH, one_percent);
+        let balances = |xyz: &XYZ| xyz.user(0).map(|user| (user.crypto_balance[&Moneda::BTC], user.crypto_balance[&Moneda::ETH]));
+        let eth_units = |units| Dinero::new(units, Moneda::ETH);
+
+        // nothing quotes ETH in BTC, so it goes through pesos: 2 BTC sell for $1800, that buy 180 ETH, and 1% of that is the fee
+        let Ok(preview) = xyz.swap_preview(day, 0, btc(2), Moneda::ETH) else { panic!("Should have a route") };
+        assert_eq!(preview.route, vec![
+            SwapLeg { from: Moneda::BTC, to: Moneda::ARS, side: OrderSide::Sell, price: ars(900) },
+            SwapLeg { from: Moneda::ARS, to: Moneda::ETH, side: OrderSide::Buy, price: ars(10) },
+        ]);
+        assert_eq!((preview.gross, preview.fee, preview.received), (eth(180), eth_units(1_800_000_000_000_000_000), eth_units(178_200_000_000_000_000_000)));
+        assert_eq!((preview.rate, preview.value), (eth_units(89_100_000_000_000_000_000), Some(ars(1800))));
+
+        // made at what the preview said, as one record found by either crypto
+        let Ok(swap) = xyz.swap(day, 0, btc(2), Moneda::ETH) else { panic!("Should be a valid swap") };
+        assert_eq!((swap.received, swap.fee, &swap.route, swap.value), (preview.received, preview.fee, &preview.route, preview.value));
+        assert_eq!(balances(&xyz), Some((btc(1), eth(5000).sumar(preview.received).expect("Should add"))));
+        let swaps = LedgerQuery::default().for_user(0).of_type(TransactionType::CryptoSwap);
+        assert_eq!(xyz.ledger.query(&swaps.clone().with_crypto(Moneda::BTC)).len(), 1);
+        assert_eq!(xyz.ledger.query(&swaps.with_crypto(Moneda::ETH)).len(), 1);
+        assert_eq!(xyz.ledger.entries().last().map(|entry| &entry.record), Some(&LedgerRecord::Swap(swap)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+
+        // selling and buying instead pays the fee twice: $891 after the sell's, 88.209 ETH after the buy's
+        let Some((_, before)) = balances(&xyz) else { panic!() };
+        let Ok(_) = xyz.crypto_sell(day, 0, btc(1)) else { panic!("Should be a valid sell") };
+        let Ok(_) = xyz.crypto_buy(day, 0, Dinero::new(89_100, Moneda::ARS), Moneda::ETH) else { panic!("Should be a valid buy") };
+        assert_eq!(balances(&xyz).map(|(_, after)| after.restar(before)), Some(Ok(eth_units(88_209_000_000_000_000_000))));
+
+        // a quoted pair is taken straight when it gives as much or more: 100 ETH sell for 0.9 BTC either way
+        let Ok(()) = xyz.record_pair_quote(Moneda::ETH, Quote { buy: Dinero::new(1_000_000, Moneda::BTC), sell: Dinero::new(900_000, Moneda::BTC), date: day }) else { panic!() };
+        let Ok(swap) = xyz.swap(day, 0, eth(100), Moneda::BTC) else { panic!("Should be a valid swap") };
+        assert_eq!(swap.route, vec![SwapLeg { from: Moneda::ETH, to: Moneda::BTC, side: OrderSide::Sell, price: Dinero::new(900_000, Moneda::BTC) }]);
+        assert_eq!((swap.received, swap.value), (Dinero::new(89_100_000, Moneda::BTC), Some(ars(900))));
+        let Ok(preview) = xyz.swap_preview(day, 0, btc(1), Moneda::ETH) else { panic!("Should have a route") };
+        assert_eq!((preview.route.len(), preview.received), (1, eth(99)));
+
+        // the BTC swapped away was disposed of at what the swap was worth
+        let Ok(report) = xyz.tax_report(0, 2024) else { panic!("Should be Ok") };
+        assert_eq!((report.disposals[0].kind, report.disposals[0].amount, report.disposals[0].proceeds), (DisposalKind::Swap, btc(2), ars(1800)));
+
+        // the same checks as any other trade, and nothing moves when one fails
+        assert_eq!(xyz.swap(day, 0, btc(1), Moneda::ETH), Err(SwapError::NotEnoughBalance { balance: Dinero::new(89_100_000, Moneda::BTC), balance_needed: btc(1) }));
+        assert_eq!(xyz.swap(day, 0, eth(1), Moneda::ETH), Err(SwapError::TransactionError(ErrorNewTransaction::InvalidCurrency { amount: eth(1) })));
+        assert_eq!(xyz.swap(day, 0, Dinero::new(1, Moneda::BTC), Moneda::ARS), Err(SwapError::NothingReceived { amount: Dinero::new(1, Moneda::BTC) }));
+        assert_eq!(xyz.swap(day, 9, btc(1), Moneda::ETH), Err(SwapError::UserNotFound { user_id: 9 }));
+        xyz.quotes.remove(&Moneda::ETH);
+        assert_eq!(xyz.swap_preview(day, 0, ars(100), Moneda::ETH), Err(SwapError::NoRoute { from: Moneda::ARS, to: Moneda::ETH }));
+
+        // saved with everything else
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should load the file");
+        assert_eq!(loaded.ledger, xyz.ledger);
+        assert_eq!(loaded.pair_quotes, xyz.pair_quotes);
+    }
+
+    #[test]
+    fn test_pair_quotes_migration() {
+        let old = mock_test_xyz();
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 11, "data": v11_document(&old) })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        assert_eq!(loaded.quotes, old.quotes);
+        assert_eq!(loaded.pair_quotes, PairQuotes::default());
+    }
+
+    #[test]
+    fn test_close_account_aml() {
+        let mut xyz = mock_test_xyz();
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let at = |hours| day + Duration::from_hours(hours);
+        xyz.blockchains.insert("ETH".to_string(), Blockchain::new("Ethereum", "ETH", vec!["ETH".to_string()]));
+        xyz.fees = FeeSchedule::default()
+            .with_fiat_withdrawal(WithdrawalMean::BankTansfer, ars(48))
+            .with_blockchain_withdrawal("MTO", btc(1))
+            .with_blockchain_withdrawal("ETH", eth(5000));
+        let payout = Payout { mean: WithdrawalMean::BankTansfer, blockchains: BTreeMap::from([(Moneda::BTC, "MTO".to_string()), (Moneda::ETH, "ETH".to_string())]) };
+
+        // fees come out of the payout, and all of it counts against the limits: $13500 and 2 BTC at $900. The ETH is all fee
+        xyz.aml = AmlRules::default().with_limits(UserTier::Standard, WithdrawalLimits { daily: Some(ars(15000)), monthly: None });
+        assert_eq!(xyz.cerrar_cuenta(at(0), 0, Some(&payout)),
+            Err(CloseAccountError::LimitExceeded(LimitExceeded { period: LimitPeriod::Daily, limit: ars(15000), withdrawn: ars(15300) })));
+        assert_eq!(xyz.user(0).map(|user| user.fiat_balance), Some(ars(13548)));
+
+        // it's flagged as one withdrawal, and held whole
+        xyz.aml = AmlRules::default().with_velocity(VelocityRule { withdrawals: 0, hours: 24 });
+        assert_eq!(xyz.cerrar_cuenta(at(0), 0, Some(&payout)), Err(CloseAccountError::Held { holds: vec![0, 1] }));
+        assert_eq!(xyz.compliance.alerts()[0].kind, AlertKind::Velocity { withdrawals: 1, hours: 24 });
+        assert_eq!(xyz.cerrar_cuenta(at(1), 0, Some(&payout)), Err(CloseAccountError::OpenAlerts { alerts: vec![0] }));
+        assert_eq!(xyz.user(0).map(|user| user.crypto_balance[&Moneda::ETH]), Some(eth(0)));
+
+        // once it's approved the account is empty, and closes
+        for hold_id in [0, 1] {
+            let Ok(()) = xyz.approve_withdrawal(at(2), hold_id, "closing") else { panic!("Should be paid out") };
+        }
+        let Some(LedgerEntry { record: LedgerRecord::Fiat(pesos), .. }) = xyz.ledger.entries().iter().rev().nth(1) else { panic!("Should be paid out") };
+        assert_eq!((pesos.data.amount, pesos.fee), (ars(13500), ars(48)));
+        let Ok(()) = xyz.cerrar_cuenta(at(3), 0, None) else { panic!("Should be empty") };
+        assert!(xyz.user(0).is_none());
+        assert_eq!(xyz.journal.balance(&Account::FeeRevenue(Moneda::ETH)), Ok(eth(5000)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+    }
+
+    #[test]
+    fn test_transfer_aml() {
+        let mut xyz = mock_test_xyz();
+        let Ok(()) = xyz.add_user(FechaHora::default(), User { id: 1, email: "otro@mail.com".to_string(), ..mock_user_0() }) else { panic!("Should be a new user") };
+        let day = FechaHora::from(Fecha { dia: 1, mes: 3, ano: 2024 });
+        let at = |hours| day + Duration::from_hours(hours);
+        xyz.aml = AmlRules::default()
+            .with_limits(UserTier::Standard, WithdrawalLimits { daily: Some(ars(2000)), monthly: None })
+            .with_velocity(VelocityRule { withdrawals: 2, hours: 24 });
+        let over_the_limit = LimitExceeded { period: LimitPeriod::Daily, limit: ars(2000), withdrawn: ars(2100) };
+
+        // what's sent counts against the sender's limits, crypto at its quote's sell price, but not against the recipient's
+        let Ok(_) = xyz.transfer(at(0), 0, &Recipient::Id(1), btc(1), None) else { panic!("Should be sent") };
+        assert_eq!(xyz.fiat_withdrawal(at(1), 0, ars(1200), WithdrawalMean::MercadoPago), Err(FiatWithdrawalError::LimitExceeded(over_the_limit.clone())));
+        assert_eq!(xyz.transfer(at(1), 0, &Recipient::Id(1), ars(1200), None), Err(TransferError::LimitExceeded(over_the_limit)));
+        let Ok(_) = xyz.fiat_withdrawal(at(1), 1, ars(2000), WithdrawalMean::MercadoPago) else { panic!("Should be on the limit") };
+
+        // flagged like a withdrawal: the third one in a day is held, and so is everything after it while the alert is open
+        let Ok(_) = xyz.fiat_withdrawal(at(2), 0, ars(100), WithdrawalMean::MercadoPago) else { panic!("Should be made") };
+        assert_eq!(xyz.transfer(at(3), 0, &Recipient::Id(1), ars(500), None), Err(TransferError::Held { hold_id: 0 }));
+        assert_eq!(xyz.compliance.alerts()[0].kind, AlertKind::Velocity { withdrawals: 3, hours: 24 });
+        assert_eq!(xyz.transfer(at(4), 0, &Recipient::Id(1), ars(1), None), Err(TransferError::Held { hold_id: 1 }));
+        let Some(user) = xyz.user(0) else { panic!() };
+        assert_eq!(xyz.available_balance(user, FIAT), Ok(ars(12947)));
+        assert_eq!(xyz.user(1).map(|user| user.fiat_balance), Some(ars(11548)));
+
+        // approved, it reaches the recipient, if they can still get it
+        let Ok(()) = xyz.approve_withdrawal(at(5), 0, "family") else { panic!("Should be made") };
+        assert_eq!(xyz.user(1).map(|user| user.fiat_balance), Some(ars(12048)));
+        let Ok(()) = xyz.suspender(at(5), 1, "revisión") else { panic!("Should be suspended") };
+        assert!(matches!(xyz.approve_withdrawal(at(5), 1, ""), Err(ComplianceUpdateError::UserNotValidated(_))));
+        let Ok(()) = xyz.reject_withdrawal(at(5), 1, "recipient suspended") else { panic!("Should be released") };
+
+        assert_eq!(xyz.user(0).map(|user| user.fiat_balance), Some(ars(12948)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
+    }
+
+    #[test]
+    fn test_transfer_migration() {
+        let mut old = mock_test_xyz();
+        let Ok(()) = old.add_user(FechaHora::default(), User { id: 1, email: "otro@mail.com".to_string(), ..mock_user_0() }) else { panic!("Should be a new user") };
+        let Ok(_) = old.transfer(FechaHora::default(), 0, &Recipient::Id(1), btc(1), None) else { panic!("Should be sent") };
+        let store = AlmacenMemoria::new();
+        let Ok(()) = store.escribir("test_xyz", &serde_json::json!({ "version": 12, "data": v12_document(&old) })) else { panic!() };
+        let loaded = XYZ::new_with_store(Some("test_xyz"), store).expect("Should migrate");
+
+        let Some(LedgerEntry { record: LedgerRecord::Transfer(transfer), .. }) = loaded.ledger.entries().last() else { panic!("Should be the transfer") };
+        assert_eq!(transfer.quote, None);
+        assert_eq!(loaded.ledger.entries().last().map(|entry| entry.record.fiat_value()), Some(Ok(None)));
+    }
+
+    #[test]
+    fn test_operations_now() {
+        let clock = RelojFijo::from(Fecha { dia: 15, mes: 3, ano: 2024 });
+        let mut xyz = mock_test_xyz().with_clock(clock.clone());
+
+        let Ok(deposit) = xyz.fiat_deposit_now(0, ars(10)) else { panic!("Should be a valid deposit") };
+        assert_eq!(deposit.data.date, clock.ahora());
+
+        clock.avanzar(Duration::from_mins(90));
+        let Ok(withdrawal) = xyz.fiat_withdrawal_now(0, ars(10), WithdrawalMean::MercadoPago) else { panic!("Should be a valid withdrawal") };
+        assert_eq!(withdrawal.data.date, clock.ahora());
+        assert!(deposit.data.date < withdrawal.data.date);
+
+        clock.avanzar_dias(1);
+        let Ok(buy) = xyz.crypto_buy_now(0, ars(2000), Moneda::BTC) else { panic!("Should be a valid buy") };
+        assert_eq!(buy.data.date.fecha(), Fecha { dia: 16, mes: 3, ano: 2024 });
+
+        let Ok(sell) = xyz.crypto_sell_now(0, btc(1)) else { panic!("Should be a valid sell") };
+        assert_eq!(sell.data.date, clock.ahora());
+
+        let Ok(deposit) = xyz.blockchain_deposit_now(0, btc(1), "MTO") else { panic!("Should be a valid deposit") };
+        assert_eq!(deposit.data.date, clock.ahora());
+
+        let Ok(withdrawal) = xyz.blockchain_withdrawal_now(0, btc(1), "MTO") else { panic!("Should be a valid withdrawal") };
+        assert_eq!(withdrawal.data.date, clock.ahora());
 
-        assert_eq!(xyz, XYZ::new(Some("test_xyz")));
     }
 
     #[test]
     fn test_fiat_deposit() {
-        delete_xyz_mock_json();
         let mut xyz = mock_test_xyz();
 
-        let date_today = Date { day: 1, month: 1, year: 1 };
+        let date_today = FechaHora::from(Fecha { dia: 1, mes: 1, ano: 1 });
 
         //
         // good deposit
         //
 
-        let fiat_deposit_id0 = xyz.fiat_deposit(date_today, 0, 10.0);
+        let fiat_deposit_id0 = xyz.fiat_deposit(date_today, 0, ars(10));
         let Ok(fiat_deposit_id0) = fiat_deposit_id0 else { panic!("Should be Ok"); };
         assert_eq!(fiat_deposit_id0.data.transaction_type, TransactionType::FiatDeposit, "Should be fiat deposit");
 
-        // default fiat balance is 13548.0, should now be 13558.0
+        // default fiat balance is 13548, should now be 13558
         let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist") };
-        assert_eq!(user.fiat_balance, Balance::from(13558.0));
+        assert_eq!(user.fiat_balance, ars(13558));
 
         //
         // deposit error: user not found
         //
 
-        let fiat_deposit_id1 = xyz.fiat_deposit(date_today, 1, 10.0);
+        let fiat_deposit_id1 = xyz.fiat_deposit(date_today, 1, ars(10));
         assert_eq!(fiat_deposit_id1, Err(FiatDepositError::UserNotFound { user_id: 1 }));
 
         //
-        // deposit error: transaction error (negative amount)
+        // deposit error: transaction error (negative amount, not pesos)
         //
 
-        let fiat_deposit_id0 = xyz.fiat_deposit(date_today, 1, -10.0);
-        assert_eq!(fiat_deposit_id0, Err(FiatDepositError::FiatTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: -10.0 })));
+        let fiat_deposit_id0 = xyz.fiat_deposit(date_today, 1, ars(-10));
+        assert_eq!(fiat_deposit_id0, Err(FiatDepositError::FiatTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: ars(-10) })));
+
+        let fiat_deposit_id0 = xyz.fiat_deposit(date_today, 0, btc(10));
+        assert_eq!(fiat_deposit_id0, Err(FiatDepositError::FiatTransactionError(ErrorNewTransaction::InvalidCurrency { amount: btc(10) })));
 
         //
         // good deposit, file error
         //
 
         xyz.file_name = "/ASDASD/ASD/ASDAASD/AD/ASAD/".to_string();
-        let fiat_deposit_id0 = xyz.fiat_deposit(date_today, 0, 10.0);
-        assert_eq!(fiat_deposit_id0, Err(FiatDepositError::File(FileError::IO)));
+        let fiat_deposit_id0 = xyz.fiat_deposit(date_today, 0, ars(10));
+        assert!(matches!(fiat_deposit_id0, Err(FiatDepositError::File(ErrorPersistencia::Ruta { .. }))));
     }
 
     #[test]
     fn test_fiat_withdrawal() {
-        delete_xyz_mock_json();
         let mut xyz = mock_test_xyz();
 
-        let date_today = Date { day: 1, month: 1, year: 1 };
+        let date_today = FechaHora::from(Fecha { dia: 1, mes: 1, ano: 1 });
 
         //
         // good withdrawal
         //
 
-        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, 10.0, WithdrawalMean::MercadoPago);
+        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, ars(10), WithdrawalMean::MercadoPago);
         let Ok(fiat_withdrawal_id0) = fiat_withdrawal_id0 else { panic!("Should be Ok"); };
         assert_eq!(fiat_withdrawal_id0.data.transaction_type, TransactionType::FiatWithdrawal { mean: WithdrawalMean::MercadoPago }, "Should be fiat deposit");
 
-        // default balance is 13548.0, should now be 13538.0
+        // default balance is 13548, should now be 13538
         let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist") };
-        assert_eq!(user.fiat_balance, Balance::from(13538.0));
+        assert_eq!(user.fiat_balance, ars(13538));
 
         //
         // withdrawal error: user not found
         //
 
-        let fiat_withdrawal_id1 = xyz.fiat_withdrawal(date_today, 1, 10.0, WithdrawalMean::MercadoPago);
+        let fiat_withdrawal_id1 = xyz.fiat_withdrawal(date_today, 1, ars(10), WithdrawalMean::MercadoPago);
         assert_eq!(fiat_withdrawal_id1, Err(FiatWithdrawalError::UserNotFound { user_id: 1 }));
 
         //
         // withdrawal error: transaction error (negative amount)
         //
 
-        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, -10.0, WithdrawalMean::MercadoPago);
-        assert_eq!(fiat_withdrawal_id0, Err(FiatWithdrawalError::FiatTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: -10.0 })));
+        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, ars(-10), WithdrawalMean::MercadoPago);
+        assert_eq!(fiat_withdrawal_id0, Err(FiatWithdrawalError::FiatTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: ars(-10) })));
 
         //
         // withdrawal error: not enough balance
         //
 
-        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, 1_000_000.0, WithdrawalMean::BankTansfer);
-        assert_eq!(fiat_withdrawal_id0, Err(FiatWithdrawalError::NotEnoughBalance { balance: 13538.0, balance_needed: 1_000_000.0 }));
+        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, ars(1_000_000), WithdrawalMean::BankTansfer);
+        assert_eq!(fiat_withdrawal_id0, Err(FiatWithdrawalError::NotEnoughBalance { balance: ars(13538), balance_needed: ars(1_000_000) }));
 
         //
         // good withdrawal, file error
         //
 
         xyz.file_name = "/ASDASD/ASD/ASDAASD/AD/ASAD/".to_string();
-        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, 10.0, WithdrawalMean::MercadoPago);
-        assert_eq!(fiat_withdrawal_id0, Err(FiatWithdrawalError::File(FileError::IO)));
+        let fiat_withdrawal_id0 = xyz.fiat_withdrawal(date_today, 0, ars(10), WithdrawalMean::MercadoPago);
+        assert!(matches!(fiat_withdrawal_id0, Err(FiatWithdrawalError::File(ErrorPersistencia::Ruta { .. }))));
     }
 
-    // xyz.quotes.insert(String::from("BTC"), Quote { buy: 1000.0, sell: 900.0 });
-    // xyz.quotes.insert(String::from("ETH"), Quote { buy: 10.0, sell: 9.0 });
+    // xyz.quotes.insert(Moneda::BTC, Quote { buy: ars(1000), sell: ars(900) });
+    // xyz.quotes.insert(Moneda::ETH, Quote { buy: ars(10), sell: ars(9) });
 
     #[test]
     fn test_crypto_buy() {
-        delete_xyz_mock_json();
         let mut xyz = mock_test_xyz();
 
-        let date_today = Date { day: 1, month: 1, year: 1 };
+        let date_today = FechaHora::from(Fecha { dia: 1, mes: 1, ano: 1 });
 
         //
         // good buy
         //
 
-        let id0_buy1 = xyz.crypto_buy(date_today, 0, 2000.0, "BTC");
+        let id0_buy1 = xyz.crypto_buy(date_today, 0, ars(2000), Moneda::BTC);
         let Ok(id0_buy1) = id0_buy1 else { panic!("Should be Ok"); };
 
         // default fiat balance is 13548. now it should be 11548. defalt BTC balance is 3. it should now be 5
         let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist") };
-        assert_eq!(user.fiat_balance, Balance::from(11548.0)); // 13548 - 2000
-        assert_eq!(user.crypto_balance.get("BTC"), Some(&Balance::from(5.0)));
+        assert_eq!(user.fiat_balance, ars(11548)); // 13548 - 2000
+        assert_eq!(user.crypto_balance.get(&Moneda::BTC), Some(&btc(5)));
+
+        //
+        // good buy, rounded down: $1 buys 0.001 BTC and not a satoshi more
+        //
+
+        let Ok(_) = xyz.crypto_buy(date_today, 0, ars(1), Moneda::BTC) else { panic!("Should be Ok") };
+        let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist") };
+        assert_eq!(user.crypto_balance.get(&Moneda::BTC), Some(&Dinero::new(500_100_000, Moneda::BTC)));
+        let Ok(_) = xyz.crypto_buy(date_today, 0, Dinero::new(1, Moneda::ARS), Moneda::ETH) else { panic!("Should be Ok") };
+        let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist") };
+        assert_eq!(user.crypto_balance.get(&Moneda::ETH), Some(&Dinero::new(5_000_001_000_000_000_000_000, Moneda::ETH)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
 
         //
         // buy error: user does not exist
         //
 
-        let id1_buy1 = xyz.crypto_buy(date_today, 1, 2000.0, "BTC");
+        let id1_buy1 = xyz.crypto_buy(date_today, 1, ars(2000), Moneda::BTC);
         assert_eq!(id1_buy1, Err(BuySellError::UserNotFound { user_id: 1 }));
 
         //
         // buy error: currency not quoted
         //
 
-        let id0_buy1 = xyz.crypto_buy(date_today, 0, 2.0, "LIBRA");
-        assert_eq!(id0_buy1, Err(BuySellError::CryptocurrencyNotQuoted { crypto_prefix: "LIBRA".to_string() }));
+        xyz.quotes.remove(&Moneda::ETH);
+        let id0_buy1 = xyz.crypto_buy(date_today, 0, ars(2), Moneda::ETH);
+        assert_eq!(id0_buy1, Err(BuySellError::CryptocurrencyNotQuoted { crypto: Moneda::ETH }));
 
         //
         // buy error: not enough balance
         //
 
-        let id0_buy1 = xyz.crypto_buy(date_today, 0, 1_000_000.0, "BTC");
-        assert_eq!(id0_buy1, Err(BuySellError::NotEnoughBalance { balance_needed: 1_000_000.0, balance: 11548.0 }));
+        let id0_buy1 = xyz.crypto_buy(date_today, 0, ars(1_000_000), Moneda::BTC);
+        assert_eq!(id0_buy1, Err(BuySellError::NotEnoughBalance { balance_needed: ars(1_000_000), balance: Dinero::new(1_154_699, Moneda::ARS) }));
+
+        //
+        // buy error: transaction error (paid in crypto)
+        //
+
+        let id0_buy1 = xyz.crypto_buy(date_today, 0, btc(1), Moneda::BTC);
+        assert_eq!(id0_buy1, Err(BuySellError::TransactionError(ErrorNewTransaction::InvalidCurrency { amount: btc(1) })));
 
         //
         // good buy, file error
         //
 
         xyz.file_name = "/ASDASD/ASD/ASDAASD/AD/ASAD/".to_string();
-        let id0_buy1 = xyz.crypto_buy(date_today, 0, 2000.0, "BTC");
-        assert_eq!(id0_buy1, Err(BuySellError::File(FileError::IO)));
+        let id0_buy1 = xyz.crypto_buy(date_today, 0, ars(2000), Moneda::BTC);
+        assert!(matches!(id0_buy1, Err(BuySellError::File(ErrorPersistencia::Ruta { .. }))));
     }
 
     #[test]
     fn test_crypto_sell() {
-        delete_xyz_mock_json();
         let mut xyz = mock_test_xyz();
 
-        let date_today = Date { day: 1, month: 1, year: 1 };
+        let date_today = FechaHora::from(Fecha { dia: 1, mes: 1, ano: 1 });
 
         //
         // good sell
         //
 
-        let id0_sell1 = xyz.crypto_sell(date_today, 0, 2.0, "BTC");
+        let id0_sell1 = xyz.crypto_sell(date_today, 0, btc(2));
         let Ok(id0_sell1) = id0_sell1 else { panic!("Should be Ok"); };
 
         // default fiat balance is 13548. now it should now be 15348. defalt BTC balance is 3. it should now be 1
         let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist") };
-        assert_eq!(user.fiat_balance, Balance::from(15348.0)); // 13548 + 1800
-        assert_eq!(user.crypto_balance.get("BTC"), Some(&Balance::from(1.0)));
+        assert_eq!(user.fiat_balance, ars(15348)); // 13548 + 1800
+        assert_eq!(user.crypto_balance.get(&Moneda::BTC), Some(&btc(1)));
+
+        //
+        // good sell, rounded down: a satoshi is worth $0.000009, which is no cents
+        //
+
+        let Ok(_) = xyz.crypto_sell(date_today, 0, Dinero::new(1, Moneda::BTC)) else { panic!("Should be Ok") };
+        let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist") };
+        assert_eq!(user.fiat_balance, ars(15348));
+        assert_eq!(user.crypto_balance.get(&Moneda::BTC), Some(&Dinero::new(99_999_999, Moneda::BTC)));
+        assert_eq!(xyz.verificar_integridad(), Ok(()));
 
         //
         // sell error: user does not exist
         //
 
-        let id1_sell1 = xyz.crypto_sell(date_today, 1, 2000.0, "BTC");
+        let id1_sell1 = xyz.crypto_sell(date_today, 1, btc(2000));
         assert_eq!(id1_sell1, Err(BuySellError::UserNotFound { user_id: 1 }));
 
         //
         // sell error: currency not quoted
         //
 
-        let id0_sell1 = xyz.crypto_sell(date_today, 0, 2.0, "LIBRA");
-        assert_eq!(id0_sell1, Err(BuySellError::CryptocurrencyNotQuoted { crypto_prefix: "LIBRA".to_string() }));
+        xyz.quotes.remove(&Moneda::ETH);
+        let id0_sell1 = xyz.crypto_sell(date_today, 0, eth(2));
+        assert_eq!(id0_sell1, Err(BuySellError::CryptocurrencyNotQuoted { crypto: Moneda::ETH }));
 
         //
         // sell error: not enough balance
         //
 
-        let id0_sell1 = xyz.crypto_sell(date_today, 0, 1_000_000.0, "BTC");
-        assert_eq!(id0_sell1, Err(BuySellError::NotEnoughBalance { balance_needed: 1_000_000.0, balance: 1.0 }));
+        let id0_sell1 = xyz.crypto_sell(date_today, 0, btc(1_000_000));
+        assert_eq!(id0_sell1, Err(BuySellError::NotEnoughBalance { balance_needed: btc(1_000_000), balance: Dinero::new(99_999_999, Moneda::BTC) }));
 
         //
         // sell error: transaction error
         //
 
-        let id0_sell1 = xyz.crypto_sell(date_today, 0, -10.0, "BTC");
-        assert_eq!(id0_sell1, Err(BuySellError::TransactionError(ErrorNewTransaction::InvalidInputAmount { amount: -10.0 })));
+        let id0_sell1 = xyz.crypto_sell(date_today, 0, btc(-10));
+        assert_eq!(id0_sell1, Err(BuySellError::TransactionError(ErrorNewTransaction::InvalidInputAmount { amount: btc(-10) })));
 
         //
         // good sell, file error
         //
 
         xyz.file_name = "/ASDASD/ASD/ASDAASD/AD/ASAD/".to_string();
-        let id0_sell1 = xyz.crypto_sell(date_today, 0, 1.0, "BTC");
-        assert_eq!(id0_sell1, Err(BuySellError::File(FileError::IO)));
+        let id0_sell1 = xyz.crypto_sell(date_today, 0, Dinero::new(1, Moneda::BTC));
+        assert!(matches!(id0_sell1, Err(BuySellError::File(ErrorPersistencia::Ruta { .. }))));
     }
 
     #[test]
     fn test_blockchain_deposit() {
-        delete_xyz_mock_json();
         let mut xyz = mock_test_xyz();
 
-        let date_today = Date { day: 1, month: 1, year: 1 };
+        let date_today = FechaHora::from(Fecha { dia: 1, mes: 1, ano: 1 });
 
         //
         // good deposit
         //
 
-        let id0 = xyz.blockchain_deposit(date_today, 0, 10.0, "MTO", "BTC");
+        let id0 = xyz.blockchain_deposit(date_today, 0, btc(10), "MTO");
         let Ok(id0) = id0 else { panic!("Should be Ok"); };
-        // original btc balance is 3.0, should now be 13.0
+        // original btc balance is 3, should now be 13
         let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist"); };
-        assert_eq!(user.crypto_balance.get("BTC"), Some(&Balance::from(13.0)));
+        assert_eq!(user.crypto_balance.get(&Moneda::BTC), Some(&btc(13)));
 
         //
         // deposit error: blockchain not found
         //
 
-        let id0 = xyz.blockchain_deposit(date_today, 0, 10.0, "ASD", "BTC");
+        let id0 = xyz.blockchain_deposit(date_today, 0, btc(10), "ASD");
         assert_eq!(id0, Err(BlockchainDepositError::BlockchainNotFound { blockchain: "ASD".to_string() }));
 
         //
-        // deposit error: crypto not quoted
+        // deposit error: user not found
         //
 
-        let id0 = xyz.blockchain_deposit(date_today, 0, 10.0, "MTO", "LIBRA");
-        assert_eq!(id0, Err(BlockchainDepositError::CryptoNotQuoted { crypto: "LIBRA".to_string() }));
+        let id1 = xyz.blockchain_deposit(date_today, 1, btc(10), "MTO");
+        assert_eq!(id1, Err(BlockchainDepositError::UserNotFound { user_id: 1 }));
 
         //
-        // deposit error: user not found
+        // deposit error: transaction error
         //
 
-        let id1 = xyz.blockchain_deposit(date_today, 1, 10.0, "MTO", "BTC");
-        assert_eq!(id1, Err(BlockchainDepositError::UserNotFound { user_id: 1 }));
+        let id0 = xyz.blockchain_deposit(date_today, 0, btc(-10), "MTO");
+        assert_eq!(id0, Err(BlockchainDepositError::BlockchainTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: btc(-10) })));
 
         //
-        // deposit error: transaction error
+        // deposit error: crypto not quoted
         //
 
-        let id0 = xyz.blockchain_deposit(date_today, 0, -10.0, "MTO", "BTC");
-        assert_eq!(id0, Err(BlockchainDepositError::BlockchainTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: -10.0 })));
+        xyz.quotes.remove(&Moneda::ETH);
+        let id0 = xyz.blockchain_deposit(date_today, 0, eth(10), "MTO");
+        assert_eq!(id0, Err(BlockchainDepositError::CryptoNotQuoted { crypto: Moneda::ETH }));
 
         //
         // good deposit, file error
         //
 
         xyz.file_name = "/ASDASD/ASD/ASDAASD/AD/ASAD/".to_string();
-        let id0 = xyz.blockchain_deposit(date_today, 0, 10.0, "MTO", "BTC");
-        assert_eq!(id0, Err(BlockchainDepositError::File(FileError::IO)));
+        let id0 = xyz.blockchain_deposit(date_today, 0, btc(10), "MTO");
+        assert!(matches!(id0, Err(BlockchainDepositError::File(ErrorPersistencia::Ruta { .. }))));
     }
 
     #[test]
     fn test_blockchain_withdrawal() {
-        delete_xyz_mock_json();
         let mut xyz = mock_test_xyz();
 
-        let date_today = Date { day: 1, month: 1, year: 1 };
+        let date_today = FechaHora::from(Fecha { dia: 1, mes: 1, ano: 1 });
 
         //
         // good withdrawal
         //
 
-        let id0 = xyz.blockchain_withdrawal(date_today, 0, 1.0, "MTO", "BTC");
+        let id0 = xyz.blockchain_withdrawal(date_today, 0, btc(1), "MTO");
         println!("{id0:?}");
         let Ok(id0) = id0 else { panic!("Should be Ok"); };
-        // original btc balance is 3.0, should now be 2.0
+        // original btc balance is 3, should now be 2
         let Some(user) = xyz.users.get(&0) else { panic!("User 0 should exist"); };
-        assert_eq!(user.crypto_balance.get("BTC"), Some(&Balance::from(2.0)));
+        assert_eq!(user.crypto_balance.get(&Moneda::BTC), Some(&btc(2)));
 
         //
         // withdrawal error: not enough balance
         //
 
-        let id0 = xyz.blockchain_withdrawal(date_today, 0, 100.0, "MTO", "BTC");
-        assert_eq!(id0, Err(BlockchainWithdrawalError::NotEnoughBalance { balance_needed: 100.0, balance: 2.0 }));
+        let id0 = xyz.blockchain_withdrawal(date_today, 0, btc(100), "MTO");
+        assert_eq!(id0, Err(BlockchainWithdrawalError::NotEnoughBalance { balance_needed: btc(100), balance: btc(2) }));
 
         //
         // withdrawal error: blockchain not found
         //
 
-        let id0 = xyz.blockchain_withdrawal(date_today, 0, 1.0, "ASD", "BTC");
+        let id0 = xyz.blockchain_withdrawal(date_today, 0, btc(1), "ASD");
         assert_eq!(id0, Err(BlockchainWithdrawalError::BlockchainNotFound { blockchain: "ASD".to_string() }));
 
         //
-        // withdrawal error: crypto not quoted
+        // withdrawal error: user not found
         //
 
-        let id0 = xyz.blockchain_withdrawal(date_today, 0, 1.0, "MTO", "LIBRA");
-        assert_eq!(id0, Err(BlockchainWithdrawalError::CryptoNotQuoted { crypto: "LIBRA".to_string() }));
+        let id1 = xyz.blockchain_withdrawal(date_today, 1, btc(10), "MTO");
+        assert_eq!(id1, Err(BlockchainWithdrawalError::UserNotFound { user_id: 1 }));
 
         //
-        // withdrawal error: user not found
+        // withdrawal error: transaction error
         //
 
-        let id1 = xyz.blockchain_withdrawal(date_today, 1, 10.0, "MTO", "BTC");
-        assert_eq!(id1, Err(BlockchainWithdrawalError::UserNotFound { user_id: 1 }));
+        let id0 = xyz.blockchain_withdrawal(date_today, 0, btc(-10), "MTO");
+        assert_eq!(id0, Err(BlockchainWithdrawalError::BlockchainTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: btc(-10) })));
 
         //
-        // withdrawal error: transaction error
+        // withdrawal error: crypto not quoted
         //
 
-        let id0 = xyz.blockchain_withdrawal(date_today, 0, -10.0, "MTO", "BTC");
-        assert_eq!(id0, Err(BlockchainWithdrawalError::BlockchainTransactionError(ErrorNewTransaction::InvalidInputAmount { amount: -10.0 })));
+        xyz.quotes.remove(&Moneda::ETH);
+        let id0 = xyz.blockchain_withdrawal(date_today, 0, eth(1), "MTO");
+        assert_eq!(id0, Err(BlockchainWithdrawalError::CryptoNotQuoted { crypto: Moneda::ETH }));
 
         //
         // good withdrawal, file error
         //
 
         xyz.file_name = "/ASDASD/ASD/ASDAASD/AD/ASAD/".to_string();
-        let id0 = xyz.blockchain_withdrawal(date_today, 0, 1.0, "MTO", "BTC");
-        assert_eq!(id0, Err(BlockchainWithdrawalError::File(FileError::IO)));
+        let id0 = xyz.blockchain_withdrawal(date_today, 0, btc(1), "MTO");
+        assert!(matches!(id0, Err(BlockchainWithdrawalError::File(ErrorPersistencia::Ruta { .. }))));
     }
 
     //
@@ -1668,53 +6470,49 @@ mod test_xyz {
 
     #[test]
     fn test_most_times_sold_cryptocurrency() {
-        delete_xyz_mock_json();
         let xyz = mock_test_xyz();
 
         let data = xyz.most_times_sold_cryptocurrency();
         assert!(data.is_some(), "Should be Some");
         let data = data.unwrap();
 
-        assert_eq!(data.0, "BTC");
+        assert_eq!(data.0, Moneda::BTC);
         assert_eq!(data.1, 2usize);
     }
 
     #[test]
     fn test_highest_sold_volume_cryptocurrency() {
-        delete_xyz_mock_json();
         let xyz = mock_test_xyz();
 
         let data = xyz.highest_sold_volume_cryptocurrency();
         assert!(data.is_some(), "Should be Some");
         let data = data.unwrap();
 
-        assert_eq!(data.0, "ETH");
-        assert_eq!(data.1.as_balance(), 6000.0.as_balance());
+        assert_eq!(data.0, Moneda::ETH);
+        assert_eq!(data.1, eth(6000));
     }
 
     #[test]
     fn test_most_times_bought_cryptocurrency() {
-        delete_xyz_mock_json();
         let xyz = mock_test_xyz();
 
         let data = xyz.most_times_bought_cryptocurrency();
         assert!(data.is_some(), "Should be Some");
         let data = data.unwrap();
 
-        assert_eq!(data.0, "BTC");
+        assert_eq!(data.0, Moneda::BTC);
         assert_eq!(data.1, 2usize);
     }
 
     #[test]
     fn test_highest_buy_volume_cryptocurrency() {
-        delete_xyz_mock_json();
         let xyz = mock_test_xyz();
 
         let data = xyz.highest_buy_volume_cryptocurrency();
         assert!(data.is_some(), "Should be Some");
         let data = data.unwrap();
 
-        assert_eq!(data.0, "ETH");
-        assert_eq!(data.1.as_balance(), 6000.0.as_balance());
+        assert_eq!(data.0, Moneda::ETH);
+        assert_eq!(data.1, ars(60000));
     }
-}
\ No newline at end of file
+}